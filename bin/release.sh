@@ -78,6 +78,8 @@ main() {
 
   # We need to publish one crate at a time...
 
+  cargo publish $args --target wasm32-unknown-unknown -p substreams-core
+  maybe_wait_publish
   cargo publish $args --target wasm32-unknown-unknown -p substreams-macro
   maybe_wait_publish
   cargo publish $args --target wasm32-unknown-unknown -p substreams
@@ -0,0 +1,48 @@
+//! Encoding of messages, see `substreams::codec`.
+
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display};
+
+/// Encodes a message to bytes and decodes it back.
+pub trait Codec: Sized {
+    type EncodeError: Debug + Display;
+    type DecodeError: Debug + Display;
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError>;
+
+    /// The size of the encoded message when it can be computed without encoding it.
+    fn encoded_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(feature = "prost-codec")]
+impl<T: prost::Message + Default> Codec for T {
+    type EncodeError = prost::EncodeError;
+    type DecodeError = prost::DecodeError;
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError> {
+        prost_encode(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        prost_decode(bytes)
+    }
+
+    fn encoded_len(&self) -> Option<usize> {
+        Some(prost::Message::encoded_len(self))
+    }
+}
+
+/// Encodes `msg` with `prost`, used by the [Codec] implementation of `prost` messages.
+pub fn prost_encode<M: prost::Message>(msg: &M) -> Result<Vec<u8>, prost::EncodeError> {
+    let mut buf = Vec::with_capacity(msg.encoded_len());
+    msg.encode(&mut buf)?;
+    Ok(buf)
+}
+
+/// Decodes `bytes` with `prost`, used by the [Codec] implementation of `prost` messages.
+pub fn prost_decode<M: prost::Message + Default>(bytes: &[u8]) -> Result<M, prost::DecodeError> {
+    M::decode(bytes)
+}
@@ -0,0 +1,94 @@
+//! Extraction of the segments of keys, see `substreams::key`.
+//!
+//! Keys are made of segments joined by the `:` [SEPARATOR], like `user:0x1234:balance`.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+/// The separator of key segments.
+pub const SEPARATOR: char = ':';
+
+// The functions take `&String` instead of `&str` as they are called with delta keys
+
+pub fn segment_at(key: &String, index: usize) -> &str {
+    try_segment_at(key, index).unwrap_or_else(|| {
+        panic!(
+            "Unable to extract segment index {} out of key {}",
+            index, key
+        )
+    })
+}
+
+pub fn first_segment(key: &String) -> &str {
+    segment_at(key, 0)
+}
+
+pub fn last_segment(key: &String) -> &str {
+    try_last_segment(key)
+        .unwrap_or_else(|| panic!("Unable to extract last segment out of key {}", key))
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn try_segment_at(key: &String, index: usize) -> Option<&str> {
+    key.split(SEPARATOR).nth(index)
+}
+
+pub fn try_first_segment(key: &String) -> Option<&str> {
+    try_segment_at(key, 0)
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn try_last_segment(key: &String) -> Option<&str> {
+    key.split(SEPARATOR).next_back()
+}
+
+/// Returns `key` without its leading `namespace` segment(s), `None` if the key isn't in the
+/// namespace. The reverse of the keys written through `substreams::store::Scoped`.
+pub fn strip_namespace<'a>(key: &'a str, namespace: &str) -> Option<&'a str> {
+    key.strip_prefix(namespace)?.strip_prefix(SEPARATOR)
+}
+
+/// Escapes `segment` so it can hold any string, `%` becoming `%25` and the `:` separator
+/// `%3A`. Segments without those characters are returned as is, without allocating.
+pub fn escape_segment(segment: &str) -> Cow<'_, str> {
+    if !segment.contains(['%', SEPARATOR]) {
+        return Cow::Borrowed(segment);
+    }
+
+    let mut escaped = String::with_capacity(segment.len() + 4);
+    for c in segment.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            SEPARATOR => escaped.push_str("%3A"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Reverts [escape_segment], panics if `segment` contains a `%` that doesn't start a `%25`
+/// or `%3A` escape sequence.
+pub fn unescape_segment(segment: &str) -> Cow<'_, str> {
+    try_unescape_segment(segment)
+        .unwrap_or_else(|| panic!("Invalid escape sequence in key segment {}", segment))
+}
+
+pub fn try_unescape_segment(segment: &str) -> Option<Cow<'_, str>> {
+    if !segment.contains('%') {
+        return Some(Cow::Borrowed(segment));
+    }
+
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut parts = segment.split('%');
+    unescaped.push_str(parts.next().unwrap_or_default());
+    for part in parts {
+        let (c, rest) = match part.get(..2) {
+            Some("25") => ('%', &part[2..]),
+            Some("3A") | Some("3a") => (SEPARATOR, &part[2..]),
+            _ => return None,
+        };
+        unescaped.push(c);
+        unescaped.push_str(rest);
+    }
+    Some(Cow::Owned(unescaped))
+}
@@ -0,0 +1,16 @@
+//! The `no_std` core of the Substreams SDK.
+//!
+//! Holds the parts of the [substreams](https://docs.rs/substreams) crate that only need
+//! `alloc`, for runtimes embedding them without `std`: the [codec] used to encode and decode
+//! messages and the [key] segment helpers. The `substreams` crate re-exports them, handlers
+//! keep depending on `substreams` only.
+//!
+//! Scalars (`BigInt`, `BigDecimal`) and the store traits, which call the host functions of
+//! the Substreams runtime, remain in the `substreams` crate.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod codec;
+pub mod key;
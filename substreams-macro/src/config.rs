@@ -1,108 +1,204 @@
-// use proc_macro2::{Span};
-// type AttributeArgs = syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>;
+type AttributeArgs = Vec<syn::NestedMeta>;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum ModuleType {
     Store,
     Map,
+    Index,
 }
 
 impl ModuleType {
-    // fn from_str(s: &str) -> Result<ModuleType, String> {
-    //     match s {
-    //         "store" => Ok(ModuleType::Store),
-    //         "map" => Ok(ModuleType::Map),
-    //         _ => Err(format!("No such module type `{}`. The modules types are `store` and `map`.", s)),
-    //     }
-    // }
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModuleType::Store => "store",
+            ModuleType::Map => "map",
+            ModuleType::Index => "index",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Limits {
+    pub max_keys_written: Option<usize>,
+    pub max_value_bytes: Option<usize>,
+    pub max_deltas_consumed: Option<usize>,
+}
+
+impl Limits {
+    pub fn is_empty(&self) -> bool {
+        self.max_keys_written.is_none()
+            && self.max_value_bytes.is_none()
+            && self.max_deltas_consumed.is_none()
+    }
 }
 
 pub struct FinalConfiguration {
     pub module_type: ModuleType,
+    pub keep_empty_output: bool,
+    pub skip_identical_output: bool,
+    pub no_panic_hook: bool,
+    pub multi_writable: bool,
+    /// Emits a native `<handler>::entrypoint` function calling the handler with Rust types.
+    pub test_entrypoint: bool,
+    pub limits: Limits,
+    /// Types replacing the handler's generic type parameters, in declaration order.
+    pub instantiate: Vec<syn::Type>,
+    /// Message a store handler can emit with `substreams::emit`.
+    pub emit: Option<syn::Type>,
+    /// The map handler's body returns a `substreams::pipeline::Pipeline` run on its first input.
+    pub pipeline: bool,
+}
+
+impl FinalConfiguration {
+    pub fn new(module_type: ModuleType) -> Self {
+        FinalConfiguration {
+            module_type,
+            keep_empty_output: false,
+            skip_identical_output: false,
+            no_panic_hook: false,
+            multi_writable: false,
+            test_entrypoint: false,
+            limits: Limits::default(),
+            instantiate: Vec::new(),
+            emit: None,
+            pipeline: false,
+        }
+    }
 }
 
-// struct Configuration {
-//     module_type: Option<ModuleType>
-// }
-//
-// impl Configuration {
-//     fn new() -> Self {
-//         Configuration {
-//             module_type: None,
-//         }
-//     }
-//
-//     fn set_module_type(&mut self, runtime: syn::Lit, span: Span) -> Result<(), syn::Error> {
-//         if self.module_type.is_some() {
-//             return Err(syn::Error::new(span, "`type` set multiple times."));
-//         }
-//
-//         let runtime_str = parse_string(runtime, span, "type")?;
-//         let mod_type =
-//             ModuleType::from_str(&runtime_str).map_err(|err| syn::Error::new(span, err))?;
-//         self.module_type = Some(mod_type);
-//         Ok(())
-//     }
-//
-//     fn build(&self) -> Result<FinalConfiguration, syn::Error> {
-//         let mod_type = self.module_type.unwrap_or(ModuleType::Map);
-//         Ok(FinalConfiguration {
-//             module_type: mod_type,
-//         })
-//     }
-// }
+const EXPECTED_ARGUMENTS: &str = "`keep_empty_output`, `skip_empty_output`, `skip_identical_output`, `no_panic_hook`, `multi_writable`, `test_entrypoint`, `pipeline`, `instantiate = \"<types>\"`, `emit = \"<type>\"`, `max_keys_written = <usize>`, `max_value_bytes = <usize>`, `max_deltas_consumed = <usize>`";
 
-// fn parse_string(int: syn::Lit, span: Span, field: &str) -> Result<String, syn::Error> {
-//     match int {
-//         syn::Lit::Str(s) => Ok(s.value()),
-//         syn::Lit::Verbatim(s) => Ok(s.to_string()),
-//         _ => Err(syn::Error::new(
-//             span,
-//             format!("Failed to parse value of `{}` as string.", field),
-//         )),
-//     }
-// }
+pub fn build_config(
+    module_type: ModuleType,
+    args: AttributeArgs,
+) -> Result<FinalConfiguration, syn::Error> {
+    let mut config = FinalConfiguration::new(module_type);
+    let mut skip_empty_output = false;
 
-// fn build_config(
-//     args: AttributeArgs,
-// ) -> Result<FinalConfiguration, syn::Error> {
-//
-//     let mut config = Configuration::new();
-//
-//     for arg in args {
-//         match arg {
-//             syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue)) => {
-//                 let ident = namevalue
-//                     .path
-//                     .get_ident()
-//                     .ok_or_else(|| {
-//                         syn::Error::new_spanned(&namevalue, "Must have specified ident")
-//                     })?
-//                     .to_string()
-//                     .to_lowercase();
-//                 match ident.as_str() {
-//                     "type" => {
-//                         config.set_module_type(
-//                             namevalue.lit.clone(),
-//                             syn::spanned::Spanned::span(&namevalue.lit),
-//                         )?;
-//                     }
-//                     name => {
-//                         let msg = format!(
-//                             "Unknown attribute {} is specified; expected one of: `type`",
-//                             name,
-//                         );
-//                         return Err(syn::Error::new_spanned(namevalue, msg));
-//                     }
-//                 }
-//             }
-//             other => {
-//                 return Err(syn::Error::new_spanned(
-//                     other,
-//                     "Unknown attribute inside the macro",
-//                 ));
-//             }
-//         }
-//     }
-//     config.build()
-// }
+    for arg in args {
+        match arg {
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                let ident = path
+                    .get_ident()
+                    .ok_or_else(|| syn::Error::new_spanned(&path, "Must have specified ident"))?
+                    .to_string();
+
+                match ident.as_str() {
+                    "keep_empty_output" | "skip_empty_output" => {
+                        if config.keep_empty_output || skip_empty_output {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "only one of `keep_empty_output` and `skip_empty_output` can be used",
+                            ));
+                        }
+                        config.keep_empty_output = ident == "keep_empty_output";
+                        skip_empty_output = ident == "skip_empty_output";
+                    }
+                    "skip_identical_output" => {
+                        if module_type != ModuleType::Map {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "`skip_identical_output` is only supported on map handlers",
+                            ));
+                        }
+                        config.skip_identical_output = true
+                    }
+                    "no_panic_hook" => config.no_panic_hook = true,
+                    "multi_writable" => {
+                        if module_type != ModuleType::Store {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "`multi_writable` is only supported on store handlers",
+                            ));
+                        }
+                        config.multi_writable = true
+                    }
+                    "test_entrypoint" => config.test_entrypoint = true,
+                    "pipeline" => {
+                        if module_type != ModuleType::Map {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "`pipeline` is only supported on map handlers",
+                            ));
+                        }
+                        config.pipeline = true
+                    }
+                    name => {
+                        let msg = format!(
+                            "Invalid argument '{}' for {} macro; expected one of: {}",
+                            name,
+                            module_type.name(),
+                            EXPECTED_ARGUMENTS,
+                        );
+                        return Err(syn::Error::new_spanned(path, msg));
+                    }
+                }
+            }
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("instantiate") => {
+                let types = match &nv.lit {
+                    syn::Lit::Str(types) => types.parse_with(
+                        syn::punctuated::Punctuated::<syn::Type, syn::Token![,]>::parse_terminated,
+                    )?,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "`instantiate` must be a string literal listing types, like `instantiate = \"eth::Block\"`",
+                        ))
+                    }
+                };
+                config.instantiate = types.into_iter().collect();
+            }
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("emit") => {
+                if module_type != ModuleType::Store {
+                    return Err(syn::Error::new_spanned(
+                        nv.path,
+                        "`emit` is only supported on store handlers, map handlers return their output",
+                    ));
+                }
+
+                config.emit = match &nv.lit {
+                    syn::Lit::Str(ty) => Some(ty.parse::<syn::Type>()?),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "`emit` must be a string literal naming the emitted message, like `emit = \"pb::Summary\"`",
+                        ))
+                    }
+                };
+            }
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) => {
+                let limit = match nv.path.get_ident().map(|x| x.to_string()).as_deref() {
+                    Some("max_keys_written") => &mut config.limits.max_keys_written,
+                    Some("max_value_bytes") => &mut config.limits.max_value_bytes,
+                    Some("max_deltas_consumed") => &mut config.limits.max_deltas_consumed,
+                    _ => {
+                        let msg = format!(
+                            "Invalid argument for {} macro; expected one of: {}",
+                            module_type.name(),
+                            EXPECTED_ARGUMENTS,
+                        );
+                        return Err(syn::Error::new_spanned(nv.path, msg));
+                    }
+                };
+
+                match &nv.lit {
+                    syn::Lit::Int(v) => *limit = Some(v.base10_parse::<usize>()?),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "guardrail limit must be an integer literal",
+                        ))
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    format!("Invalid argument for {} macro", module_type.name()),
+                ));
+            }
+        }
+    }
+
+    Ok(config)
+}
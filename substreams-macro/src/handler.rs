@@ -1,15 +1,40 @@
 use crate::config::{FinalConfiguration, ModuleType};
-use crate::errors;
+use crate::metadata::HandlerMetadata;
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote, ToTokens};
 use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
 
-pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool) -> TokenStream {
-
+pub fn main(item: TokenStream, final_config: FinalConfiguration) -> TokenStream {
     let original = item.clone();
 
-    let final_config = FinalConfiguration { module_type };
-    let input = syn::parse2::<syn::ItemFn>(item).expect("Proc macro input should be a function");
+    let mut input =
+        syn::parse2::<syn::ItemFn>(item).expect("Proc macro input should be a function");
+    if let Err(e) = instantiate(&mut input, &final_config.instantiate) {
+        return token_stream_with_error(original, e);
+    }
+
+    if let Some(asyncness) = &input.sig.asyncness {
+        return token_stream_with_error(
+            original,
+            syn::Error::new(
+                asyncness.span(),
+                "handlers can't be `async`, Substreams calls them synchronously once per block; remove `async`",
+            ),
+        );
+    }
+
+    if final_config.pipeline {
+        if let Err(e) = run_pipeline(&mut input) {
+            return token_stream_with_error(original, e);
+        }
+    }
+
+    let entrypoint = if final_config.test_entrypoint {
+        build_test_entrypoint(&input)
+    } else {
+        quote! {}
+    };
 
     let output_result = parse_func_output(&final_config, input.sig.output.clone());
     let output_type;
@@ -20,12 +45,21 @@ pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool)
         Err(e) => return token_stream_with_error(original, e),
     }
     let mut has_seen_writable_store = false;
+    // Name of the first store input of the manifest, params inputs can't follow it
+    let mut first_store_input: Option<syn::Ident> = None;
     let mut args: Vec<proc_macro2::TokenStream> = Vec::with_capacity(input.sig.inputs.len() * 2);
     let mut proto_decodings: Vec<proc_macro2::TokenStream> =
         Vec::with_capacity(input.sig.inputs.len());
     let mut read_only_stores: Vec<proc_macro2::TokenStream> =
         Vec::with_capacity(input.sig.inputs.len());
     let mut writable_store: proc_macro2::TokenStream = quote! {};
+    // First `Rc<T>` message input of each type, later ones holding the same bytes share it
+    let mut shared_inputs: Vec<(String, syn::Ident)> = Vec::new();
+    let mut metadata = HandlerMetadata::new(
+        final_config.module_type,
+        input.sig.ident.to_string(),
+        &input.sig.output,
+    );
 
     for i in (&input.sig.inputs).into_iter() {
         match i {
@@ -43,46 +77,114 @@ pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool)
                     let var_name = v.ident.clone();
 
                     let argument_type = &*pat_type.ty;
-                    let input_obj = match parse_input_type(argument_type) {
+                    let (borrow, decoded_type) = parse_borrow(argument_type);
+                    if let syn::Type::ImplTrait(_) = decoded_type {
+                        return token_stream_with_error(
+                            original,
+                            syn::Error::new(
+                                pat_type.ty.span(),
+                                "`impl Trait` inputs are only supported as `impl Into<T>`, `T` being the decoded type",
+                            ),
+                        );
+                    }
+                    let optional_store = optional_store(decoded_type);
+                    let input_obj = match parse_input_type(optional_store.unwrap_or(decoded_type)) {
                         Ok(t) => t,
-                        Err(e) => {
+                        Err(e) => return token_stream_with_error(original, e),
+                    };
+
+                    if final_config.module_type == ModuleType::Index
+                        && (input_obj.is_writable_store
+                            || input_obj.is_readable_store
+                            || input_obj.is_deltas)
+                    {
+                        return token_stream_with_error(
+                            original,
+                            syn::Error::new(
+                                pat_type.span(),
+                                "index handler cannot have store inputs",
+                            ),
+                        );
+                    }
+
+                    if (borrow != Borrow::Owned || decoded_type != argument_type)
+                        && (input_obj.is_writable_store || input_obj.is_readable_store)
+                    {
+                        return token_stream_with_error(
+                            original,
+                            syn::Error::new(pat_type.span(), "store inputs must be taken by value"),
+                        );
+                    }
+
+                    if input_obj.is_string || input_obj.is_params || input_obj.is_bytes {
+                        if let Some(store) = &first_store_input {
                             return token_stream_with_error(
                                 original,
                                 syn::Error::new(
                                     pat_type.span(),
-                                    format!("failed to parse input {:?}", e),
+                                    format!(
+                                        "params input `{}` must be declared before store input `{}`, handler inputs follow the order of the manifest inputs where `params` come before stores",
+                                        var_name, store
+                                    ),
                                 ),
-                            )
+                            );
                         }
-                    };
+                    }
+                    if (input_obj.is_readable_store || input_obj.is_deltas)
+                        && first_store_input.is_none()
+                    {
+                        first_store_input = Some(var_name.clone());
+                    }
 
                     if input_obj.is_writable_store {
+                        metadata.add_input("store", &var_name, argument_type);
+
+                        // Each writable store of a `multi_writable` handler is bound to the
+                        // output store index passed by the host at its position
+                        if final_config.multi_writable {
+                            let var_idx = format_ident!("{}_idx", var_name);
+                            args.push(quote! { #var_idx: u32 });
+                            writable_store.extend(quote! {
+                                let #var_name: #argument_type = substreams::store::StoreNewOutput::new_output(#var_idx);
+                            });
+                            continue;
+                        }
+
                         if has_seen_writable_store {
                             return token_stream_with_error(
                                     original,
-                                    syn::Error::new(pat_type.span(), format!("handler cannot have more then one writable store as an input"))
+                                    syn::Error::new(pat_type.span(), "handler cannot have more then one writable store as an input, use `#[substreams::handlers::store(multi_writable)]` to write to several stores")
                                 );
                         }
                         has_seen_writable_store = true;
-                        let store_type = format_ident!("{}", input_obj.store_type);
+                        // The store is built through its full type so stores imported through
+                        // a module path (`set::StoreAddInt64`) work too
                         writable_store =
-                            quote! { let #var_name: #argument_type = #store_type::new(); };
+                            quote! { let #var_name: #argument_type = <#argument_type>::new(); };
                         continue;
                     }
 
                     if input_obj.is_readable_store {
+                        metadata.add_input("get", &var_name, argument_type);
                         let var_idx = format_ident!("{}_idx", var_name);
-                        let store_type = format_ident!("{}", input_obj.store_type);
                         args.push(quote! { #var_idx: u32 });
-                        read_only_stores.push(
-                            quote! { let #var_name: #argument_type = #store_type::new(#var_idx); },
-                        );
+                        read_only_stores.push(match optional_store {
+                            Some(store_type) => quote! {
+                                let #var_name: #argument_type = if #var_idx == substreams::store::MISSING_STORE {
+                                    None
+                                } else {
+                                    Some(<#store_type>::new(#var_idx))
+                                };
+                            },
+                            None => quote! { let #var_name: #argument_type = <#argument_type>::new(#var_idx); },
+                        });
                         continue;
                     }
 
                     if final_config.module_type == ModuleType::Store
                         && var_name.to_string().ends_with("_idx")
                     {
+                        metadata.add_input("idx", &var_name, argument_type);
                         args.push(quote! { #pat_type });
                         continue;
                     }
@@ -91,23 +193,130 @@ pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool)
                     args.push(quote! { #var_ptr: *mut u8 });
                     args.push(quote! { #var_len: usize });
 
-                    if input_obj.is_deltas {
+                    // `impl Into<T>` inputs are recorded with their decoded type
+                    let recorded_type = match argument_type {
+                        syn::Type::ImplTrait(_) => decoded_type,
+                        _ => argument_type,
+                    };
+
+                    let mutability = if v.mutability.is_some() {
+                        quote! { mut }
+                    } else {
+                        quote! {}
+                    };
+
+                    let is_message = !(input_obj.is_deltas
+                        || input_obj.is_params
+                        || input_obj.is_bytes
+                        || input_obj.is_raw_bytes
+                        || input_obj.is_lazy
+                        || input_obj.is_string);
+
+                    let (setup, value, owned_type) = if input_obj.is_deltas {
+                        metadata.add_input("deltas", &var_name, recorded_type);
                         let raw = format_ident!("raw_{}", var_name);
-                        proto_decodings.push(quote! {
-                                let #raw = substreams::proto::decode_ptr::<substreams::pb::substreams::StoreDeltas>(#var_ptr, #var_len).unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to 'substreams::pb::substreams::StoreDeltas' message's struct", #var_len)).deltas;
-                                let #var_name: #argument_type = substreams::store::Deltas::new(#raw);
-                            })
+                        (
+                            quote! { let #raw = substreams::proto::decode_ptr::<substreams::pb::substreams::StoreDeltas>(#var_ptr, #var_len).unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to 'substreams::pb::substreams::StoreDeltas' message's struct", #var_len)).deltas; },
+                            quote! { substreams::store::Deltas::new(#raw) },
+                            quote! { #decoded_type },
+                        )
+                    } else if input_obj.is_params {
+                        metadata.add_input("params", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::Params::parse(substreams::trace_input(std::mem::ManuallyDrop::new(unsafe {String::from_raw_parts(#var_ptr, #var_len, #var_len)}).to_string())) },
+                            quote! { #decoded_type },
+                        )
+                    } else if input_obj.is_bytes {
+                        metadata.add_input("bytes", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::trace_input(std::mem::ManuallyDrop::new(unsafe { Vec::from_raw_parts(#var_ptr, #var_len, #var_len) }).to_vec()) },
+                            quote! { Vec<u8> },
+                        )
+                    } else if input_obj.is_raw_bytes {
+                        metadata.add_input("proto", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::input::RawBytes::from_ptr(#var_ptr, #var_len) },
+                            quote! { #decoded_type },
+                        )
+                    } else if input_obj.is_lazy {
+                        metadata.add_input("proto", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::input::LazyInput::from_ptr(#var_ptr, #var_len) },
+                            quote! { #decoded_type },
+                        )
                     } else if input_obj.is_string {
-                        proto_decodings.push(quote! { let #var_name: String = std::mem::ManuallyDrop::new(unsafe {String::from_raw_parts(#var_ptr, #var_len, #var_len)}).to_string(); });
+                        metadata.add_input("string", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::trace_input(std::mem::ManuallyDrop::new(unsafe {String::from_raw_parts(#var_ptr, #var_len, #var_len)}).to_string()) },
+                            quote! { String },
+                        )
                     } else {
-                        let mutability = if v.mutability.is_some() {
-                            quote! { mut }
-                        } else {
-                            quote! {}
-                        };
+                        metadata.add_input("proto", &var_name, recorded_type);
+                        (
+                            quote! {},
+                            quote! { substreams::proto::decode_ptr(#var_ptr, #var_len).unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", #var_len, stringify!(#decoded_type))) },
+                            quote! { #decoded_type },
+                        )
+                    };
 
-                        proto_decodings.push(quote! { let #mutability #var_name: #argument_type = substreams::proto::decode_ptr(#var_ptr, #var_len).unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", #var_len, stringify!(#argument_type))); })
+                    // Borrowed inputs are decoded once into a hidden owned binding living for
+                    // the whole handler body, the declared name only holds the reference.
+                    let mut decoding = match borrow {
+                        Borrow::Owned => quote! {
+                            #setup
+                            let #mutability #var_name: #owned_type = #value;
+                        },
+                        Borrow::Ref(ref_mutability) => {
+                            let owned = format_ident!("owned_{}", var_name);
+                            quote! {
+                                #setup
+                                let #ref_mutability #owned: #owned_type = #value;
+                                let #mutability #var_name: #argument_type = &#ref_mutability #owned;
+                            }
+                        }
+                        Borrow::Cow => quote! {
+                            #setup
+                            let #mutability #var_name: #argument_type = std::borrow::Cow::Owned(#value);
+                        },
+                        // Message inputs of a type already received, like the same upstream
+                        // module wired twice, are only decoded when their bytes differ
+                        Borrow::Rc if is_message => {
+                            let type_name = decoded_type.to_token_stream().to_string();
+                            let shared = shared_inputs.iter().find(|(t, _)| *t == type_name);
+                            let decoding = match shared {
+                                Some((_, first)) => {
+                                    let first_ptr = format_ident!("{}_ptr", first);
+                                    let first_len = format_ident!("{}_len", first);
+                                    quote! {
+                                        let #mutability #var_name: #argument_type = unsafe { substreams::input::share_input(&#first, #first_ptr, #first_len, #var_ptr, #var_len) }
+                                            .unwrap_or_else(|| std::rc::Rc::new(#value));
+                                    }
+                                }
+                                None => quote! {
+                                    let #mutability #var_name: #argument_type = std::rc::Rc::new(#value);
+                                },
+                            };
+                            if shared.is_none() {
+                                shared_inputs.push((type_name, var_name.clone()));
+                            }
+                            decoding
+                        }
+                        Borrow::Rc => quote! {
+                            #setup
+                            let #mutability #var_name: #argument_type = std::rc::Rc::new(#value);
+                        },
+                    };
+
+                    // Records the clock so `substreams::clock()` returns it anywhere in the module
+                    if input_obj.is_clock {
+                        decoding.extend(quote! { substreams::clock::set(&#var_name); });
                     }
+                    proto_decodings.push(decoding);
                 }
                 _ => {
                     return token_stream_with_error(
@@ -119,22 +328,27 @@ pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool)
         }
     }
 
-    match final_config.module_type {
+    let component_export = build_component_export(&input.sig.ident, &args);
+    let handler = match final_config.module_type {
         ModuleType::Store => build_store_handler(
             input,
+            output_type,
             args,
             proto_decodings,
             read_only_stores,
             writable_store,
-            keep_empty_output,
+            &final_config,
         ),
-        ModuleType::Map => {
+        ModuleType::Map | ModuleType::Index => {
             if output_type == OutputType::Void {
                 return token_stream_with_error(
                     original,
                     syn::Error::new(
                         input.sig.output.span(),
-                        format!("map handler must return a value"),
+                        format!(
+                            "{} handler must return a value",
+                            final_config.module_type.name()
+                        ),
                     ),
                 );
             }
@@ -146,13 +360,119 @@ pub fn main(item: TokenStream, module_type: ModuleType, keep_empty_output: bool)
                 proto_decodings,
                 read_only_stores,
                 writable_store,
-                keep_empty_output,
+                &final_config,
             )
         }
+    };
+
+    let metadata_section = metadata.to_section_static();
+    let registration = metadata.to_registration(entrypoint);
+    quote! {
+        #handler
+        #metadata_section
+        #registration
+        #component_export
     }
 }
 
-const WRITABLE_STORE: [&'static str; 31] = [
+/// Maximum number of flat parameters of a component model function, more are passed through
+/// memory by the canonical ABI.
+const MAX_COMPONENT_FLAT_PARAMS: usize = 16;
+
+/// Renders the `substreams::__component_export!` call exporting the handler as a component
+/// model function named in kebab-case, it expands to nothing without the `component` feature.
+/// The raw arguments (pointer and length of each input, index of each store) are already the
+/// flattened canonical ABI parameters of `list<u8>` and `u32` inputs.
+fn build_component_export(func_name: &syn::Ident, args: &[TokenStream]) -> TokenStream {
+    let export_name = func_name.to_string().replace('_', "-");
+    let post_return_name = format!("cabi_post_{}", export_name);
+    let wrapper = format_ident!("__substreams_component_{}", func_name);
+
+    if args.len() > MAX_COMPONENT_FLAT_PARAMS {
+        let msg = format!(
+            "handler `{}` has {} raw parameters, component exports support at most {}",
+            func_name,
+            args.len(),
+            MAX_COMPONENT_FLAT_PARAMS
+        );
+        return quote! {
+            substreams::__component_export!(unsupported #msg);
+        };
+    }
+
+    quote! {
+        substreams::__component_export!(#func_name, #wrapper, #export_name, #post_return_name, (#(#args),*));
+    }
+}
+
+/// Replaces the body of a `pipeline` handler, an expression building a
+/// `substreams::pipeline::Pipeline`, by a call running the pipeline on the handler's first
+/// input, the error of a failing stage being converted into the handler's error type.
+fn run_pipeline(input: &mut syn::ItemFn) -> Result<(), syn::Error> {
+    match parse_value_output(input.sig.output.clone(), MAP_WRONG_TYPE_ERR)? {
+        OutputType::Result | OutputType::ResultOption => {}
+        _ => {
+            return Err(syn::Error::new(
+                input.sig.output.span(),
+                "`pipeline` handlers must return `Result<T, E>`, the error of a failing stage being returned as `E`",
+            ))
+        }
+    }
+
+    let first = match input.sig.inputs.first() {
+        Some(syn::FnArg::Typed(pat_type)) => match &*pat_type.pat {
+            syn::Pat::Ident(v) => {
+                let (_, decoded_type) = parse_borrow(&pat_type.ty);
+                let input_obj = parse_input_type(decoded_type)?;
+                if input_obj.is_writable_store || input_obj.is_readable_store {
+                    return Err(syn::Error::new(
+                        pat_type.span(),
+                        "the first input of a `pipeline` handler is passed to the pipeline and can't be a store",
+                    ));
+                }
+                v.ident.clone()
+            }
+            _ => return Err(syn::Error::new(pat_type.span(), "unknown argument type")),
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.sig.ident.span(),
+                "`pipeline` handlers must have an input, passed to the pipeline",
+            ))
+        }
+    };
+
+    let block = &input.block;
+    *input.block = syn::parse_quote! {{
+        substreams::pipeline::Pipeline::run(&#block, #first).map_err(::std::convert::From::from)
+    }};
+    Ok(())
+}
+
+/// Renders `entrypoint`, a copy of the handler taking and returning its Rust types, so native
+/// test crates can call it as `<handler>::entrypoint(...)` without going through the wasm ABI.
+fn build_test_entrypoint(input: &syn::ItemFn) -> TokenStream {
+    let (generics, _, where_clause) = input.sig.generics.split_for_impl();
+    let inputs = &input.sig.inputs;
+    let output = &input.sig.output;
+    let block = &input.block;
+    let doc = format!(
+        "Native copy of the `{}` handler, calling it directly with decoded inputs.",
+        input.sig.ident
+    );
+
+    quote! {
+        #[cfg(not(target_arch = "wasm32"))]
+        #[allow(unused_imports)]
+        use super::*;
+
+        #[doc = #doc]
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn entrypoint #generics (#inputs) #output #where_clause #block
+    }
+}
+
+pub(crate) const WRITABLE_STORE: [&str; 48] = [
     "StoreSetRaw",
     "StoreSetString",
     "StoreSetBigInt",
@@ -160,13 +480,24 @@ const WRITABLE_STORE: [&'static str; 31] = [
     "StoreSetProto",
     "StoreSetInt64",
     "StoreSetFloat64",
+    "StoreSetInt32",
+    "StoreSetUint64",
+    "StoreSetBool",
+    "StoreSetEnum",
     "StoreSetIfNotExistsRaw",
     "StoreSetIfNotExistsString",
     "StoreSetIfNotExistsBigDecimal",
     "StoreSetIfNotExistsBigInt",
     "StoreSetIfNotExistsInt64",
     "StoreSetIfNotExistsFloat64",
+    "StoreSetIfNotExistsInt32",
+    "StoreSetIfNotExistsUint64",
+    "StoreSetIfNotExistsBool",
     "StoreSetIfNotExistsProto",
+    "StoreSetOnceRaw",
+    "StoreSetOnceString",
+    "StoreSetOnceBigInt",
+    "StoreSetOnceProto",
     "StoreAddInt64",
     "StoreAddFloat64",
     "StoreAddBigDecimal",
@@ -175,39 +506,222 @@ const WRITABLE_STORE: [&'static str; 31] = [
     "StoreMaxBigInt",
     "StoreMaxFloat64",
     "StoreMaxBigDecimal",
+    "StoreMaxString",
+    "StoreMaxBytes",
     "StoreMinInt64",
     "StoreMinBigInt",
     "StoreMinFloat64",
     "StoreMinBigDecimal",
+    "StoreMinString",
+    "StoreMinBytes",
     "StoreAppend",
+    "StoreAppendProto",
+    "StoreAppendBytes",
     "StoreSetSumInt64",
     "StoreSetSumFloat64",
     "StoreSetSumBigInt",
     "StoreSetSumBigDecimal",
 ];
 
-const READABLE_STORE: [&'static str; 8] = [
+pub(crate) const READABLE_STORE: [&str; 14] = [
     "StoreGetInt64",
     "StoreGetFloat64",
+    "StoreGetInt32",
+    "StoreGetUint64",
+    "StoreGetBool",
+    "StoreGetEnum",
     "StoreGetBigDecimal",
     "StoreGetBigInt",
     "StoreGetProto",
     "StoreGetRaw",
     "StoreGetString",
     "StoreGetArray",
+    "StoreGetProtoArray",
+    "StoreGetBytesArray",
 ];
 
+#[derive(PartialEq)]
+pub(crate) enum Borrow {
+    Owned,
+    Ref(Option<syn::token::Mut>),
+    Cow,
+    Rc,
+}
+
+/// Splits `&T`, `&mut T`, `Cow<T>` and `Rc<T>` inputs into how they are borrowed and the
+/// type that must be decoded, `&str` and `Cow<str>` being decoded as `String`. `impl Into<T>`
+/// inputs are decoded as an owned `T`.
+pub(crate) fn parse_borrow(ty: &syn::Type) -> (Borrow, &syn::Type) {
+    match ty {
+        syn::Type::Reference(r) => (Borrow::Ref(r.mutability), &*r.elem),
+        syn::Type::ImplTrait(t) => (Borrow::Owned, into_target(t).unwrap_or(ty)),
+        syn::Type::Path(p) => {
+            let (borrow, last) = match p.path.segments.last() {
+                Some(last) if last.ident == "Cow" => (Borrow::Cow, last),
+                Some(last) if last.ident == "Rc" => (Borrow::Rc, last),
+                _ => return (Borrow::Owned, ty),
+            };
+
+            if let syn::PathArguments::AngleBracketed(args) = &last.arguments {
+                for arg in args.args.iter() {
+                    if let syn::GenericArgument::Type(inner) = arg {
+                        return (borrow, inner);
+                    }
+                }
+            }
+
+            (Borrow::Owned, ty)
+        }
+        _ => (Borrow::Owned, ty),
+    }
+}
+
+/// Returns `T` for `Option<T>` inputs, `T` being a store in get mode: the store is `None`
+/// when the host passes `substreams::store::MISSING_STORE` as its index.
+pub(crate) fn optional_store(ty: &syn::Type) -> Option<&syn::Type> {
+    let last = match ty {
+        syn::Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .filter(|last| last.ident == "Option")?,
+        _ => return None,
+    };
+
+    let inner = match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(inner) => inner,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match inner {
+        syn::Type::Path(p) => {
+            let name = p.path.segments.last()?.ident.to_string();
+            READABLE_STORE.contains(&name.as_str()).then(|| inner)
+        }
+        _ => None,
+    }
+}
+
+/// Returns `T` for `impl Into<T>`.
+fn into_target(t: &syn::TypeImplTrait) -> Option<&syn::Type> {
+    let mut bounds = t.bounds.iter();
+    let path = match (bounds.next(), bounds.next()) {
+        (Some(syn::TypeParamBound::Trait(bound)), None) => &bound.path,
+        _ => return None,
+    };
+
+    let last = path.segments.last().filter(|last| last.ident == "Into")?;
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(target) => Some(target),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replaces the generic type parameters of `input` by the `instantiate` types, in the
+/// signature and through type aliases at the start of the body, since the exported
+/// function can't be generic.
+pub(crate) fn instantiate(input: &mut syn::ItemFn, types: &[syn::Type]) -> Result<(), syn::Error> {
+    let generics = &input.sig.generics;
+    if generics.params.is_empty() {
+        return match types.first() {
+            Some(ty) => Err(syn::Error::new(
+                ty.span(),
+                "`instantiate` is only supported on handlers with generic type parameters",
+            )),
+            None => Ok(()),
+        };
+    }
+
+    let mut params = Vec::with_capacity(generics.params.len());
+    for param in generics.params.iter() {
+        match param {
+            syn::GenericParam::Type(param) => params.push(param.ident.clone()),
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "handler can only be generic over types, lifetimes and const parameters are not supported",
+                ))
+            }
+        }
+    }
+
+    if params.len() != types.len() {
+        return Err(syn::Error::new(
+            generics.span(),
+            format!(
+                "generic handler must be instantiated with one type per generic parameter through `instantiate = \"<types>\"` (like `instantiate = \"eth::Block\"`), found {} type(s) for {} parameter(s); alternatively call the generic function from a non-generic handler",
+                types.len(),
+                params.len()
+            ),
+        ));
+    }
+
+    let mut substitution = Instantiate {
+        params: params.iter().cloned().zip(types.iter().cloned()).collect(),
+    };
+    for arg in input.sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            substitution.visit_type_mut(&mut pat_type.ty);
+        }
+    }
+    substitution.visit_return_type_mut(&mut input.sig.output);
+    input.sig.generics = syn::Generics::default();
+
+    for (param, ty) in params.iter().zip(types.iter()).rev() {
+        let alias: syn::Stmt = syn::parse_quote! {
+            #[allow(dead_code)]
+            type #param = #ty;
+        };
+        input.block.stmts.insert(0, alias);
+    }
+
+    Ok(())
+}
+
+struct Instantiate {
+    params: Vec<(syn::Ident, syn::Type)>,
+}
+
+impl syn::visit_mut::VisitMut for Instantiate {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        let target = match ty {
+            syn::Type::Path(p) if p.qself.is_none() => p.path.get_ident().and_then(|ident| {
+                self.params
+                    .iter()
+                    .find(|(param, _)| param == ident)
+                    .map(|(_, target)| target.clone())
+            }),
+            _ => None,
+        };
+
+        match target {
+            Some(target) => *ty = target,
+            None => syn::visit_mut::visit_type_mut(self, ty),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Input {
     is_writable_store: bool,
     is_readable_store: bool,
     is_deltas: bool,
     is_string: bool,
+    is_params: bool,
+    is_clock: bool,
+    is_raw_bytes: bool,
+    /// `Vec<u8>` params, passed as-is
+    is_bytes: bool,
+    is_lazy: bool,
     resolved_ty: String,
-    store_type: String,
 }
 
-fn parse_input_type(ty: &syn::Type) -> Result<Input, errors::SubstreamMacroError> {
+fn parse_input_type(ty: &syn::Type) -> Result<Input, syn::Error> {
     match ty {
         syn::Type::Path(p) => {
             let mut input = Input {
@@ -215,41 +729,153 @@ fn parse_input_type(ty: &syn::Type) -> Result<Input, errors::SubstreamMacroError
                 is_readable_store: false,
                 is_deltas: false,
                 is_string: false,
+                is_params: false,
+                is_clock: false,
+                is_raw_bytes: false,
+                is_bytes: false,
+                is_lazy: false,
                 resolved_ty: "".to_owned(),
-                store_type: "".to_string(),
             };
             let mut last_type = "".to_owned();
             for segment in p.path.segments.iter() {
                 last_type = segment.ident.to_string();
             }
             input.resolved_ty = last_type.clone();
-            if last_type == "String".to_owned() {
+            if last_type == "String" || last_type == "str" {
                 input.is_string = true;
             }
+            if last_type == "Params" {
+                input.is_params = true;
+            }
+            if last_type == "Clock" {
+                input.is_clock = true;
+            }
+            if last_type == "Vec" && is_vec_u8(p) {
+                input.is_bytes = true;
+            }
+            if last_type == "RawBytes" {
+                input.is_raw_bytes = true;
+            }
+            if last_type == "LazyInput" {
+                input.is_lazy = true;
+            }
             for t in WRITABLE_STORE {
                 if last_type == t.to_owned() {
                     input.is_writable_store = true;
-                    input.store_type = last_type.clone();
                 }
             }
             for t in READABLE_STORE {
                 if last_type == t.to_owned() {
                     input.is_readable_store = true;
-                    input.store_type = last_type.clone();
                 }
             }
             if last_type == "Deltas".to_owned() {
                 // todo: should check that it's fully qualified to be our `store::Deltas`
                 input.is_deltas = true;
             }
+            if !input.is_writable_store && !input.is_readable_store {
+                if let Some(err) = unknown_store_error(p, &last_type) {
+                    return Err(err);
+                }
+            }
             Ok(input)
         }
-        _ => Err(errors::SubstreamMacroError::UnknownInputType(
-            "unable to parse input type".to_owned(),
+        _ => Err(syn::Error::new(
+            ty.span(),
+            "unsupported input type, expected a Protobuf message, a store, `Deltas<...>`, `String`, `Vec<u8>` or `Params`",
         )),
     }
 }
 
+/// Prefixes of the store types, a type starting with one of them must be a known store.
+const STORE_PREFIXES: [&str; 6] = [
+    "StoreSet",
+    "StoreGet",
+    "StoreAdd",
+    "StoreMax",
+    "StoreMin",
+    "StoreAppend",
+];
+
+/// Reports a type named like a store but which isn't one, suggesting the closest stores.
+fn unknown_store_error(p: &syn::TypePath, name: &str) -> Option<syn::Error> {
+    let prefix = STORE_PREFIXES
+        .iter()
+        .filter(|prefix| name.starts_with(*prefix))
+        .max_by_key(|prefix| prefix.len())?;
+
+    let (kind, known): (&str, &[&str]) = match *prefix {
+        "StoreGet" => ("readable", &READABLE_STORE),
+        _ => ("writable", &WRITABLE_STORE),
+    };
+
+    let mut candidates: Vec<(usize, &str)> = known
+        .iter()
+        .map(|store| (edit_distance(name, store), *store))
+        .filter(|(distance, _)| *distance <= 3)
+        .collect();
+    candidates.sort();
+    // Only the closest stores, a single one is an unambiguous suggestion
+    if let Some(best) = candidates.first().map(|(distance, _)| *distance) {
+        candidates.retain(|(distance, _)| *distance == best);
+    }
+
+    let suggestions: Vec<String> = match candidates.is_empty() {
+        true => known
+            .iter()
+            .filter(|store| store.starts_with(*prefix))
+            .map(|store| format!("`{}`", store))
+            .collect(),
+        false => candidates
+            .iter()
+            .take(3)
+            .map(|(_, store)| format!("`{}`", store))
+            .collect(),
+    };
+
+    let help = match (candidates.len(), suggestions.len()) {
+        (_, 0) => format!("known {} stores are: {}", kind, known.join(", ")),
+        (1, _) => format!("did you mean {}?", suggestions[0]),
+        (0, _) => format!("known `{}*` stores are: {}", prefix, suggestions.join(", ")),
+        _ => format!("did you mean one of {}?", suggestions.join(", ")),
+    };
+
+    let ident = &p.path.segments.last()?.ident;
+    Some(syn::Error::new(
+        ident.span(),
+        format!(
+            "`{}` is not a known {} store; {} (store type aliases are not supported, use the store type directly)",
+            name, kind, help
+        ),
+    ))
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + (ca != *cb) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn is_vec_u8(p: &syn::TypePath) -> bool {
+    match p.path.segments.last().map(|last| &last.arguments) {
+        Some(syn::PathArguments::AngleBracketed(args)) if args.args.len() == 1 => {
+            matches!(&args.args[0], syn::GenericArgument::Type(syn::Type::Path(inner)) if inner.path.is_ident("u8"))
+        }
+        _ => false,
+    }
+}
+
 #[derive(PartialEq)]
 enum OutputType {
     Result,
@@ -260,40 +886,64 @@ enum OutputType {
 }
 
 const MAP_WRONG_TYPE_ERR: &str = "Module of type Map should return a 'Result<T, Error>', 'Result<Option<T>, Error>', 'Option<T>' or 'T' where 'T' is your output type";
+const INDEX_WRONG_TYPE_ERR: &str = "Module of type Index should return a 'Result<Keys, Error>', 'Result<Option<Keys>, Error>', 'Option<Keys>' or 'Keys' where 'Keys' is 'substreams::index::Keys'";
 
 fn parse_func_output(
     final_config: &FinalConfiguration,
     output: syn::ReturnType,
 ) -> Result<OutputType, syn::Error> {
     match final_config.module_type {
-        ModuleType::Map => {
-            if output == syn::ReturnType::Default {
-                return Err(syn::Error::new(Span::call_site(), MAP_WRONG_TYPE_ERR));
+        ModuleType::Map => parse_value_output(output, MAP_WRONG_TYPE_ERR),
+        ModuleType::Index => {
+            let returns_keys = output
+                .to_token_stream()
+                .into_iter()
+                .any(|x| x.to_string() == "Keys");
+            if !returns_keys {
+                return Err(syn::Error::new(Span::call_site(), INDEX_WRONG_TYPE_ERR));
             }
 
+            parse_value_output(output, INDEX_WRONG_TYPE_ERR)
+        }
+        ModuleType::Store => {
             let tokens = output
-                .into_token_stream()
+                .to_token_stream()
                 .into_iter()
                 .map(|x| x.to_string())
                 .collect::<Vec<_>>();
             let tokens: Vec<&str> = tokens.iter().map(|x| x.as_str()).collect::<Vec<_>>();
 
             match tokens[..] {
-                ["-", ">", "Result", "<", "Option", "<", ..] => Ok(OutputType::ResultOption),
-                ["-", ">", "Result", "<", ..] => Ok(OutputType::Result),
-                ["-", ">", "Option", "<", ..] => Ok(OutputType::Option),
-                ["-", ">", ..] => Ok(OutputType::Value),
                 [] => Ok(OutputType::Void),
-                _ => Err(syn::Error::new(Span::call_site(), MAP_WRONG_TYPE_ERR)),
+                ["-", ">", "Result", "<", "()", ",", ..] => Ok(OutputType::Result),
+                _ => Err(syn::Error::new(
+                    Span::call_site(),
+                    "Module of type Store should not have a return statement or return 'Result<(), Error>'",
+                )),
             }
         }
-        ModuleType::Store => match output {
-            syn::ReturnType::Default => Ok(OutputType::Void),
-            _ => Err(syn::Error::new(
-                Span::call_site(),
-                "Module of type Store should not have a return statement",
-            )),
-        },
+    }
+}
+
+fn parse_value_output(output: syn::ReturnType, err: &str) -> Result<OutputType, syn::Error> {
+    if output == syn::ReturnType::Default {
+        return Err(syn::Error::new(Span::call_site(), err));
+    }
+
+    let tokens = output
+        .into_token_stream()
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    let tokens: Vec<&str> = tokens.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+
+    match tokens[..] {
+        ["-", ">", "Result", "<", "Option", "<", ..] => Ok(OutputType::ResultOption),
+        ["-", ">", "Result", "<", ..] => Ok(OutputType::Result),
+        ["-", ">", "Option", "<", ..] => Ok(OutputType::Option),
+        ["-", ">", ..] => Ok(OutputType::Value),
+        [] => Ok(OutputType::Void),
+        _ => Err(syn::Error::new(Span::call_site(), err)),
     }
 }
 
@@ -304,7 +954,7 @@ fn build_map_handler(
     decodings: Vec<proc_macro2::TokenStream>,
     read_only_stores: Vec<proc_macro2::TokenStream>,
     writable_store: proc_macro2::TokenStream,
-    keep_empty_output: bool,
+    config: &FinalConfiguration,
 ) -> TokenStream {
     let body = &input.block;
     let header = quote! {
@@ -322,37 +972,52 @@ fn build_map_handler(
         };
     };
 
+    let (output_state, emit_output) = match config.skip_identical_output {
+        false => (quote! {}, quote! { substreams::output }),
+        true => (
+            quote! {
+                thread_local! {
+                    static LAST_OUTPUT: substreams::IdenticalOutputFilter = substreams::IdenticalOutputFilter::new();
+                }
+                let emit_output = |value| LAST_OUTPUT.with(|last| last.output(value));
+            },
+            quote! { emit_output },
+        ),
+    };
+
     let output_handler = match output_type {
         OutputType::Result => {
             quote! {
                 if result.is_err() {
-                    panic!("{:?}", result.unwrap_err())
+                    substreams::finish_handler_trace();
+                    panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
                 }
 
-                substreams::output(result.expect("already checked that result is not an error"));
+                #emit_output(result.expect("already checked that result is not an error"));
             }
         }
         OutputType::ResultOption => {
             quote! {
                 if result.is_err() {
-                    panic!("{:?}", result.unwrap_err())
+                    substreams::finish_handler_trace();
+                    panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
                 }
 
                 if let Some(inner) = result.expect("already checked that result is not an error") {
-                    substreams::output(inner);
+                    #emit_output(inner);
                 }
             }
         }
         OutputType::Option => {
             quote! {
                 if let Some(value) = result {
-                    substreams::output(value);
+                    #emit_output(value);
                 }
             }
         }
         OutputType::Value => {
             quote! {
-                substreams::output(result);
+                #emit_output(result);
             }
         }
         OutputType::Void => {
@@ -360,21 +1025,23 @@ fn build_map_handler(
         }
     };
 
-    let skip_empty_output = match keep_empty_output {
-        true => quote! {},
-        false => quote! {
-            substreams::skip_empty_output();
-        },
-    };
+    let output_policy = build_output_policy(config);
+    let panic_hook = build_panic_hook(&func_name, config);
+    let guardrails = build_guardrails(config);
 
     let result = quote! {
         #header
         pub extern "C" fn #func_name(#(#collected_args),*){
-            substreams::register_panic_hook();
+            #output_state
+            #panic_hook
+            #guardrails
+            substreams::begin_handler_trace();
             #lambda
-            #skip_empty_output
+            #output_policy
             let result = func();
             #output_handler
+            substreams::apply_output_policy();
+            substreams::finish_handler_trace();
         }
     };
     result.into()
@@ -382,38 +1049,126 @@ fn build_map_handler(
 
 fn build_store_handler(
     input: syn::ItemFn,
+    output_type: OutputType,
     collected_args: Vec<proc_macro2::TokenStream>,
     decodings: Vec<proc_macro2::TokenStream>,
     read_only_stores: Vec<proc_macro2::TokenStream>,
     writable_store: proc_macro2::TokenStream,
-    keep_empty_output: bool,
+    config: &FinalConfiguration,
 ) -> TokenStream {
     let body = &input.block;
     let header = quote! {
         #[no_mangle]
     };
     let func_name = input.sig.ident.clone();
-    let skip_empty_output = match keep_empty_output {
-        true => quote! {},
-        false => quote! {
-            substreams::skip_empty_output();
+    let output_policy = build_output_policy(config);
+    let panic_hook = build_panic_hook(&func_name, config);
+    let guardrails = build_guardrails(config);
+    let (emit_begin, emit_finish) = match &config.emit {
+        Some(ty) => (
+            quote! { substreams::emit::begin::<#ty>(); },
+            quote! { substreams::emit::finish(); },
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    // `?` in a handler returning a `Result` must return from the handler's body, not from
+    // the generated function, so the body is wrapped in a closure
+    let run = match output_type {
+        OutputType::Result => {
+            let lambda_return = input.sig.output.clone();
+            quote! {
+                let func = || #lambda_return #body;
+                let result = func();
+                if result.is_err() {
+                    substreams::finish_handler_trace();
+                    panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
+                }
+                #emit_finish
+                substreams::apply_output_policy();
+                substreams::finish_handler_trace();
+            }
+        }
+        _ => quote! {
+            let result = #body;
+            #emit_finish
+            substreams::apply_output_policy();
+            substreams::finish_handler_trace();
+            result
         },
     };
+
     let result = quote! {
         #header
         pub extern "C" fn #func_name(#(#collected_args),*){
-            substreams::register_panic_hook();
+            #panic_hook
+            #guardrails
+            substreams::begin_handler_trace();
             #(#decodings)*
             #(#read_only_stores)*
             #writable_store
-            #skip_empty_output
-            let result = #body;
-            result
+            #output_policy
+            #emit_begin
+            #run
         }
     };
     result.into()
 }
 
+/// The policy is set before running the handler so the handler can override it, it's
+/// applied by `substreams::apply_output_policy` once the handler returned.
+fn build_output_policy(config: &FinalConfiguration) -> TokenStream {
+    match config.keep_empty_output {
+        true => quote! {},
+        false => quote! {
+            substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+        },
+    }
+}
+
+/// The handler name is recorded even without the panic hook so custom hooks can read it
+/// through `substreams::context::handler`.
+fn build_panic_hook(func_name: &syn::Ident, config: &FinalConfiguration) -> TokenStream {
+    let handler_name = func_name.to_string();
+    let handler_context = quote! {
+        substreams::context::set_handler(#handler_name);
+    };
+
+    match config.no_panic_hook {
+        true => handler_context,
+        false => quote! {
+            substreams::register_panic_hook();
+            #handler_context
+        },
+    }
+}
+
+fn build_guardrails(config: &FinalConfiguration) -> TokenStream {
+    // Limits are kept per thread, handlers without limits reset them too so they don't
+    // inherit the limits and counters of the handler run before them.
+    if config.limits.is_empty() {
+        return quote! {
+            substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+        };
+    }
+
+    let to_tokens = |limit: Option<usize>| match limit {
+        Some(v) => quote! { Some(#v) },
+        None => quote! { None },
+    };
+    let max_keys_written = to_tokens(config.limits.max_keys_written);
+    let max_value_bytes = to_tokens(config.limits.max_value_bytes);
+    let max_deltas_consumed = to_tokens(config.limits.max_deltas_consumed);
+
+    quote! {
+        substreams::guardrails::set_limits(substreams::guardrails::Limits {
+            max_keys_written: #max_keys_written,
+            max_value_bytes: #max_value_bytes,
+            max_deltas_consumed: #max_deltas_consumed,
+        });
+    }
+}
+
 fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
     tokens.extend(TokenStream::from(error.into_compile_error()));
     tokens
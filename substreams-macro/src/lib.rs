@@ -2,30 +2,54 @@ use proc_macro::TokenStream;
 
 mod assertions;
 mod config;
-mod errors;
 mod handler;
+mod manifest;
+mod memoize;
+mod metadata;
 mod store;
 
 #[proc_macro_attribute]
 pub fn map(args: TokenStream, item: TokenStream) -> TokenStream {
-    let mut keep_empty_output = false;
-    match args.to_string().as_str() {
-        "" => {},
-        "keep_empty_output" => {keep_empty_output = true},
-        _ => panic!("Invalid argument '{}' for map macro", args)
-    }
-    handler::main(item.into(), config::ModuleType::Map, keep_empty_output).into()
+    handler_macro(config::ModuleType::Map, args, item)
 }
 
 #[proc_macro_attribute]
 pub fn store(args: TokenStream, item: TokenStream) -> TokenStream {
-    let mut keep_empty_output = false;
-    match args.to_string().as_str() {
-        "" => {},
-        "keep_empty_output" => {keep_empty_output = true},
-        _ => panic!("Invalid argument '{}' for store macro", args)
+    handler_macro(config::ModuleType::Store, args, item)
+}
+
+fn handler_macro(
+    module_type: config::ModuleType,
+    args: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+
+    match config::build_config(module_type, args) {
+        Ok(config) => handler::main(item.into(), config).into(),
+        Err(e) => {
+            let mut tokens: proc_macro2::TokenStream = item.into();
+            tokens.extend(e.into_compile_error());
+            tokens.into()
+        }
     }
-    handler::main(item.into(), config::ModuleType::Store, keep_empty_output).into()
+}
+
+#[proc_macro_attribute]
+pub fn index(args: TokenStream, item: TokenStream) -> TokenStream {
+    handler_macro(config::ModuleType::Index, args, item)
+}
+
+#[proc_macro_attribute]
+pub fn memoize(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+
+    memoize::main(args, item.into()).into()
+}
+
+#[proc_macro]
+pub fn manifest(item: TokenStream) -> TokenStream {
+    manifest::main(item.into()).into()
 }
 
 // todo: remove this once satisfied with implementation of StoreDelete
@@ -36,9 +60,24 @@ pub fn derive(input: TokenStream) -> TokenStream {
 
 #[cfg(test)]
 mod test {
-    use crate::{assertions::assert_ast_eq, config::ModuleType, handler::main};
+    use crate::{
+        assertions::assert_ast_eq,
+        config::{build_config, FinalConfiguration, ModuleType},
+        handler,
+    };
     use quote::quote;
 
+    fn main(
+        item: proc_macro2::TokenStream,
+        module_type: ModuleType,
+        keep_empty_output: bool,
+    ) -> proc_macro2::TokenStream {
+        let mut config = FinalConfiguration::new(module_type);
+        config.keep_empty_output = keep_empty_output;
+
+        handler::main(item, config)
+    }
+
     #[test]
     fn test_map_plain() {
         let item = quote! {
@@ -53,6 +92,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> pb::Custom {
                         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -63,7 +105,19 @@ mod test {
                     };
                     let result = func();
                     substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
                 }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 52usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tpb::Custom\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
             },
         );
     }
@@ -82,6 +136,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> pb::Custom {
                         let mut blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -92,11 +149,287 @@ mod test {
                     };
                     let result = func();
                     substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 52usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tpb::Custom\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_borrowed_inputs() {
+        let item = quote! {
+            fn map_transfers(blk: &eth::Block, pools: Cow<pb::Pools>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Map, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize, pools_ptr: *mut u8, pools_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let owned_blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let blk: &eth::Block = &owned_blk;
+                        let pools: Cow<pb::Pools> = std::borrow::Cow::Owned(substreams::proto::decode_ptr(pools_ptr, pools_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", pools_len, stringify!(pb::Pools))));
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 80usize] = *b"1\tmap\tmap_transfers\tproto:blk:&eth::Block;proto:pools:Cow<pb::Pools>\tpb::Custom\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 2usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize, pools_ptr: *mut u8, pools_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_shared_inputs() {
+        let item = quote! {
+            fn map_pools(all: Rc<pb::Pools>, filtered: Rc<pb::Pools>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Map, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_pools(all_ptr: *mut u8, all_len: usize, filtered_ptr: *mut u8, filtered_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_pools");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let all: Rc<pb::Pools> = std::rc::Rc::new(substreams::proto::decode_ptr(all_ptr, all_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", all_len, stringify!(pb::Pools))));
+                        let filtered: Rc<pb::Pools> = unsafe { substreams::input::share_input(&all, all_ptr, all_len, filtered_ptr, filtered_len) }
+                            .unwrap_or_else(|| std::rc::Rc::new(substreams::proto::decode_ptr(filtered_ptr, filtered_len)
+                                .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", filtered_len, stringify!(pb::Pools)))));
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_POOLS: [u8; 80usize] = *b"1\tmap\tmap_pools\tproto:all:Rc<pb::Pools>;proto:filtered:Rc<pb::Pools>\tpb::Custom\n";
+                pub mod map_pools {
+                    pub const MODULE_NAME: &str = "map_pools";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_POOLS, "map_pools", Map, 2usize);
+                substreams::__component_export!(map_pools, __substreams_component_map_pools, "map-pools", "cabi_post_map-pools", (all_ptr: *mut u8, all_len: usize, filtered_ptr: *mut u8, filtered_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_clock_input() {
+        let item = quote! {
+            fn map_volumes(clock: Clock, blk: eth::Block) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Map, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_volumes(clock_ptr: *mut u8, clock_len: usize, blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_volumes");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let clock: Clock = substreams::proto::decode_ptr(clock_ptr, clock_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", clock_len, stringify!(Clock)));
+                        substreams::clock::set(&clock);
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_VOLUMES: [u8; 68usize] = *b"1\tmap\tmap_volumes\tproto:clock:Clock;proto:blk:eth::Block\tpb::Custom\n";
+                pub mod map_volumes {
+                    pub const MODULE_NAME: &str = "map_volumes";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_VOLUMES, "map_volumes", Map, 2usize);
+                substreams::__component_export!(map_volumes, __substreams_component_map_volumes, "map-volumes", "cabi_post_map-volumes", (clock_ptr: *mut u8, clock_len: usize, blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_raw_and_lazy_inputs() {
+        let item = quote! {
+            fn map_sample(raw: RawBytes, blk: LazyInput<eth::Block>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Map, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_sample(raw_ptr: *mut u8, raw_len: usize, blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_sample");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let raw: RawBytes = substreams::input::RawBytes::from_ptr(raw_ptr, raw_len);
+                        let blk: LazyInput<eth::Block> = substreams::input::LazyInput::from_ptr(blk_ptr, blk_len);
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_SAMPLE: [u8; 79usize] = *b"1\tmap\tmap_sample\tproto:raw:RawBytes;proto:blk:LazyInput<eth::Block>\tpb::Custom\n";
+                pub mod map_sample {
+                    pub const MODULE_NAME: &str = "map_sample";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_SAMPLE, "map_sample", Map, 2usize);
+                substreams::__component_export!(map_sample, __substreams_component_map_sample, "map-sample", "cabi_post_map-sample", (raw_ptr: *mut u8, raw_len: usize, blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_optional_store() {
+        let item = quote! {
+            fn map_pool(pools: Option<StoreGetInt64>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Map, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_pool(pools_idx: u32) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_pool");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let pools: Option<StoreGetInt64> = if pools_idx == substreams::store::MISSING_STORE {
+                            None
+                        } else {
+                            Some(<StoreGetInt64>::new(pools_idx))
+                        };
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_POOL: [u8; 58usize] = *b"1\tmap\tmap_pool\tget:pools:Option<StoreGetInt64>\tpb::Custom\n";
+                pub mod map_pool {
+                    pub const MODULE_NAME: &str = "map_pool";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_POOL, "map_pool", Map, 1usize);
+                substreams::__component_export!(map_pool, __substreams_component_map_pool, "map-pool", "cabi_post_map-pool", (pools_idx: u32));
             },
         );
     }
 
+    #[test]
+    fn test_map_borrowed_store_rejected() {
+        let item = quote! {
+            fn map_transfers(store: &StoreGetInt64) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        let output = main(item, ModuleType::Map, true).to_string();
+        assert!(output.contains("store inputs must be taken by value"));
+    }
+
+    #[test]
+    fn test_unknown_store_suggestions() {
+        let output = |item| main(item, ModuleType::Store, true).to_string();
+
+        assert!(output(quote! {
+            fn store_counts(blk: eth::Block, store: StoreSetInt65) {}
+        })
+        .contains("`StoreSetInt65` is not a known writable store; did you mean `StoreSetInt64`?"));
+
+        assert!(output(quote! {
+            fn store_counts(blk: eth::Block, store: StoreAddCounter) {}
+        })
+        .contains("known `StoreAdd*` stores are: `StoreAddInt64`, `StoreAddFloat64`, `StoreAddBigDecimal`, `StoreAddBigInt`"));
+
+        assert!(output(quote! {
+            fn store_counts(prices: StoreGetInt, store: StoreSetInt64) {}
+        })
+        .contains("`StoreGetInt` is not a known readable store; did you mean one of `StoreGetInt32`, `StoreGetInt64`"));
+    }
+
     #[test]
     fn test_map_option() {
         let item = quote! {
@@ -111,6 +444,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> Option<pb::Custom> {
                         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -122,7 +458,19 @@ mod test {
                     if let Some(value) = result {
                         substreams::output(value);
                     }
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 60usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tOption<pb::Custom>\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
             },
         );
     }
@@ -141,6 +489,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> Result<pb::Custom> {
                         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -150,10 +501,23 @@ mod test {
 
                     let result = func();
                     if result.is_err() {
-                        panic!("{:?}", result.unwrap_err())
+                        substreams::finish_handler_trace();
+                        panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
                     }
                     substreams::output(result.expect("already checked that result is not an error"));
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 60usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tResult<pb::Custom>\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
             },
         );
     }
@@ -172,6 +536,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> Result<Option<pb::Custom> > {
                         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -181,12 +548,25 @@ mod test {
 
                     let result = func();
                     if result.is_err() {
-                        panic!("{:?}", result.unwrap_err())
+                        substreams::finish_handler_trace();
+                        panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
                     }
                     if let Some(inner) = result.expect("already checked that result is not an error") {
                         substreams::output(inner);
                     }
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 68usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tResult<Option<pb::Custom>>\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
             },
         );
 
@@ -196,6 +576,9 @@ mod test {
                 #[no_mangle]
                 pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
                     let func = || -> Result<Option<pb::Custom> > {
                         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
                             .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
@@ -203,72 +586,946 @@ mod test {
                         result
                     };
 
-                    substreams :: skip_empty_output () ; 
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
                     let result = func();
                     if result.is_err() {
-                        panic!("{:?}", result.unwrap_err())
+                        substreams::finish_handler_trace();
+                        panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
                     }
                     if let Some(inner) = result.expect("already checked that result is not an error") {
                         substreams::output(inner);
                     }
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 68usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tResult<Option<pb::Custom>>\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
             },
         );
-
     }
 
     #[test]
-    fn test_store_result_option() {
+    fn test_map_params() {
         let item = quote! {
-            fn store_values(blk: eth::Block, store: StoreAddInt64) {
+            fn map_pools(params: Params, blk: eth::Block) -> pb::Pools {
                 unimplemented!("do something");
             }
         };
 
         assert_ast_eq(
-            main(item.clone(), ModuleType::Store, true).into(),
+            main(item, ModuleType::Map, true),
             quote! {
                 #[no_mangle]
-                pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                pub extern "C" fn map_pools(params_ptr: *mut u8, params_len: usize, blk_ptr: *mut u8, blk_len: usize) {
                     substreams::register_panic_hook();
-                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
-                        .unwrap_or_else(|_|
-                            panic!(
-                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
-                                blk_len, stringify!(eth::Block)
-                            )
-                        );
-                    let store: StoreAddInt64 = StoreAddInt64::new();
-                    let result = {
-                        unimplemented!("do something");
+                    substreams::context::set_handler("map_pools");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Pools {
+                        let params: Params = substreams::Params::parse(substreams::trace_input(std::mem::ManuallyDrop::new(unsafe { String::from_raw_parts(params_ptr, params_len, params_len) }).to_string()));
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let result = { unimplemented!("do something"); };
+                        result
                     };
-                    result
+
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_POOLS: [u8; 68usize] = *b"1\tmap\tmap_pools\tparams:params:Params;proto:blk:eth::Block\tpb::Pools\n";
+                pub mod map_pools {
+                    pub const MODULE_NAME: &str = "map_pools";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_POOLS, "map_pools", Map, 2usize);
+                substreams::__component_export!(map_pools, __substreams_component_map_pools, "map-pools", "cabi_post_map-pools", (params_ptr: *mut u8, params_len: usize, blk_ptr: *mut u8, blk_len: usize));
             },
         );
+    }
+
+    #[test]
+    fn test_map_multiple_params() {
+        let item = quote! {
+            fn map_pools(factory: String, salt: Vec<u8>, pools: StoreGetInt64) -> pb::Pools {
+                unimplemented!("do something");
+            }
+        };
 
         assert_ast_eq(
-            main(item, ModuleType::Store, false).into(),
+            main(item, ModuleType::Map, true),
             quote! {
                 #[no_mangle]
-                    pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                pub extern "C" fn map_pools(factory_ptr: *mut u8, factory_len: usize, salt_ptr: *mut u8, salt_len: usize, pools_idx: u32) {
                     substreams::register_panic_hook();
-                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
-                        .unwrap_or_else(|_|
-                            panic!(
-                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
-                                blk_len, stringify!(eth::Block)
-                            )
-                        );
-                    let store: StoreAddInt64 = StoreAddInt64::new();
-                    substreams :: skip_empty_output () ; 
-                    let result = {
-                        unimplemented!("do something");
+                    substreams::context::set_handler("map_pools");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Pools {
+                        let factory: String = substreams::trace_input(std::mem::ManuallyDrop::new(unsafe {String::from_raw_parts(factory_ptr, factory_len, factory_len)}).to_string());
+                        let salt: Vec<u8> = substreams::trace_input(std::mem::ManuallyDrop::new(unsafe { Vec::from_raw_parts(salt_ptr, salt_len, salt_len) }).to_vec());
+                        let pools: StoreGetInt64 = <StoreGetInt64>::new(pools_idx);
+                        let result = { unimplemented!("do something"); };
+                        result
                     };
-                    result
+
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_POOLS: [u8; 91usize] = *b"1\tmap\tmap_pools\tstring:factory:String;bytes:salt:Vec<u8>;get:pools:StoreGetInt64\tpb::Pools\n";
+                pub mod map_pools {
+                    pub const MODULE_NAME: &str = "map_pools";
                 }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_POOLS, "map_pools", Map, 3usize);
+                substreams::__component_export!(map_pools, __substreams_component_map_pools, "map-pools", "cabi_post_map-pools", (factory_ptr: *mut u8, factory_len: usize, salt_ptr: *mut u8, salt_len: usize, pools_idx: u32));
             },
         );
+    }
+
+    #[test]
+    fn test_map_params_after_store_rejected() {
+        let item = quote! {
+            fn map_pools(pools: StoreGetInt64, salt: Vec<u8>) -> pb::Pools {
+                unimplemented!("do something");
+            }
+        };
+
+        let output = main(item, ModuleType::Map, true).to_string();
+        assert!(output.contains("params input `salt` must be declared before store input `pools`"));
+    }
+
+    #[test]
+    fn test_map_async_rejected() {
+        let item = quote! {
+            async fn map_transfers(blk: eth::Block) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        let output = main(item, ModuleType::Map, true).to_string();
+        assert!(output.contains("handlers can't be `async`"));
+    }
+
+    #[test]
+    fn test_map_test_entrypoint() {
+        let item = quote! {
+            fn map_transfers(blk: eth::Block, pools: StoreGetInt64) -> Result<pb::Custom, Error> {
+                unimplemented!("do something");
+            }
+        };
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(test_entrypoint)];
+        let config = build_config(ModuleType::Map, args).unwrap();
+
+        let output = handler::main(item, config).to_string();
+        let expected = quote! {
+            pub mod map_transfers {
+                pub const MODULE_NAME: &str = "map_transfers";
+                #[cfg(not(target_arch = "wasm32"))]
+                #[allow(unused_imports)]
+                use super::*;
+                #[doc = "Native copy of the `map_transfers` handler, calling it directly with decoded inputs."]
+                #[cfg(not(target_arch = "wasm32"))]
+                pub fn entrypoint(blk: eth::Block, pools: StoreGetInt64) -> Result<pb::Custom, Error> {
+                    unimplemented!("do something");
+                }
+            }
+        };
+        assert!(output.contains(&expected.to_string()));
+    }
+
+    #[test]
+    fn test_index() {
+        let item = quote! {
+            fn index_events(blk: eth::Block) -> Keys {
+                unimplemented!("do something");
+            }
+        };
 
+        assert_ast_eq(
+            main(item, ModuleType::Index, false),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn index_events(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("index_events");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> Keys {
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let result = { unimplemented!("do something"); };
+                        result
+                    };
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_INDEX_EVENTS: [u8; 47usize] = *b"1\tindex\tindex_events\tproto:blk:eth::Block\tKeys\n";
+                pub mod index_events {
+                    pub const MODULE_NAME: &str = "index_events";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_INDEX_EVENTS, "index_events", Index, 1usize);
+                substreams::__component_export!(index_events, __substreams_component_index_events, "index-events", "cabi_post_index-events", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+
+        let item = quote! {
+            fn index_events(blk: eth::Block, pools: StoreGetProto<Pool>) -> Keys {
+                unimplemented!("do something");
+            }
+        };
+        assert!(main(item, ModuleType::Index, false)
+            .to_string()
+            .contains("compile_error"));
+
+        let item = quote! {
+            fn index_events(blk: eth::Block) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+        assert!(main(item, ModuleType::Index, false)
+            .to_string()
+            .contains("compile_error"));
+    }
+
+    #[test]
+    fn test_map_skip_identical_output() {
+        let item = quote! {
+            fn map_transfers(blk: eth::Block) -> Option<pb::Custom> {
+                unimplemented!("do something");
+            }
+        };
+
+        let mut config = FinalConfiguration::new(ModuleType::Map);
+        config.keep_empty_output = true;
+        config.skip_identical_output = true;
+
+        assert_ast_eq(
+            handler::main(item, config),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
+                    thread_local! {
+                        static LAST_OUTPUT: substreams::IdenticalOutputFilter = substreams::IdenticalOutputFilter::new();
+                    }
+                    let emit_output = |value| LAST_OUTPUT.with(|last| last.output(value));
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> Option<pb::Custom> {
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let result = { unimplemented!("do something"); };
+                        result
+                    };
+
+                    let result = func();
+                    if let Some(value) = result {
+                        emit_output(value);
+                    }
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 60usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tOption<pb::Custom>\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_generic_instantiate() {
+        let item = quote! {
+            fn map_transfers<B: HasLogs>(blk: B, params: impl Into<String>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(instantiate = "eth::Block")];
+        let mut config = build_config(ModuleType::Map, args).unwrap();
+        config.keep_empty_output = true;
+
+        assert_ast_eq(
+            handler::main(item.clone(), config),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize, params_ptr: *mut u8, params_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let params: String = substreams::trace_input(std::mem::ManuallyDrop::new(unsafe {String::from_raw_parts(params_ptr, params_len, params_len)}).to_string());
+                        let result = {
+                            #[allow(dead_code)]
+                            type B = eth::Block;
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 73usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block;string:params:String\tpb::Custom\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 2usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize, params_ptr: *mut u8, params_len: usize));
+            },
+        );
+
+        // Generic handlers must be instantiated
+        assert!(main(item, ModuleType::Map, true)
+            .to_string()
+            .contains("compile_error"));
+
+        let item = quote! {
+            fn map_transfers(blk: impl AsRef<eth::Block>) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+        assert!(main(item, ModuleType::Map, true)
+            .to_string()
+            .contains("compile_error"));
+    }
+
+    #[test]
+    fn test_config_options_list() {
+        let args: syn::AttributeArgs = vec![
+            syn::parse_quote!(skip_empty_output),
+            syn::parse_quote!(no_panic_hook),
+        ];
+        let config = build_config(ModuleType::Map, args).unwrap();
+        assert!(!config.keep_empty_output);
+        assert!(config.no_panic_hook);
+
+        let args: syn::AttributeArgs = vec![
+            syn::parse_quote!(keep_empty_output),
+            syn::parse_quote!(skip_empty_output),
+        ];
+        assert!(build_config(ModuleType::Store, args).is_err());
+    }
+
+    #[test]
+    fn test_map_no_panic_hook() {
+        let item = quote! {
+            fn map_transfers(blk: eth::Block) -> pb::Custom {
+                unimplemented!("do something");
+            }
+        };
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(no_panic_hook)];
+        let config = build_config(ModuleType::Map, args).unwrap();
+
+        assert_ast_eq(
+            handler::main(item, config),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_transfers(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::context::set_handler("map_transfers");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> pb::Custom {
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let result = {
+                            unimplemented!("do something");
+                        };
+                        result
+                    };
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = func();
+                    substreams::output(result);
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_TRANSFERS: [u8; 52usize] = *b"1\tmap\tmap_transfers\tproto:blk:eth::Block\tpb::Custom\n";
+                pub mod map_transfers {
+                    pub const MODULE_NAME: &str = "map_transfers";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_TRANSFERS, "map_transfers", Map, 1usize);
+                substreams::__component_export!(map_transfers, __substreams_component_map_transfers, "map-transfers", "cabi_post_map-transfers", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_pipeline() {
+        let item = quote! {
+            fn map_swaps(blk: eth::Block, fees: StoreGetInt64) -> Result<pb::Swaps, Error> {
+                Pipeline::new(extract_swaps).then(|swaps| enrich(swaps, &fees))
+            }
+        };
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(pipeline)];
+        let config = build_config(ModuleType::Map, args).unwrap();
+
+        assert_ast_eq(
+            handler::main(item, config),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn map_swaps(blk_ptr: *mut u8, blk_len: usize, fees_idx: u32) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("map_swaps");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let func = || -> Result<pb::Swaps, Error> {
+                        let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                            .unwrap_or_else(|_| panic!("Unable to decode Protobuf data ({} bytes) to '{}' message's struct", blk_len, stringify!(eth::Block)));
+                        let fees: StoreGetInt64 = <StoreGetInt64>::new(fees_idx);
+                        let result = {
+                            substreams::pipeline::Pipeline::run(&{
+                                Pipeline::new(extract_swaps).then(|swaps| enrich(swaps, &fees))
+                            }, blk).map_err(::std::convert::From::from)
+                        };
+                        result
+                    };
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = func();
+                    if result.is_err() {
+                        substreams::finish_handler_trace();
+                        panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
+                    }
+                    substreams::output(result.expect("already checked that result is not an error"));
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_MAP_SWAPS: [u8; 84usize] = *b"1\tmap\tmap_swaps\tproto:blk:eth::Block;get:fees:StoreGetInt64\tResult<pb::Swaps,Error>\n";
+                pub mod map_swaps {
+                    pub const MODULE_NAME: &str = "map_swaps";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_MAP_SWAPS, "map_swaps", Map, 2usize);
+                substreams::__component_export!(map_swaps, __substreams_component_map_swaps, "map-swaps", "cabi_post_map-swaps", (blk_ptr: *mut u8, blk_len: usize, fees_idx: u32));
+            },
+        );
+    }
+
+    #[test]
+    fn test_map_pipeline_errors() {
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(pipeline)];
+        assert!(build_config(ModuleType::Store, args.clone()).is_err());
+
+        for item in [
+            quote! { fn map_swaps(blk: eth::Block) -> pb::Swaps { Pipeline::new(extract_swaps) } },
+            quote! { fn map_swaps(fees: StoreGetInt64, blk: eth::Block) -> Result<pb::Swaps, Error> { Pipeline::new(extract_swaps) } },
+            quote! { fn map_swaps() -> Result<pb::Swaps, Error> { Pipeline::new(extract_swaps) } },
+        ] {
+            let config = build_config(ModuleType::Map, args.clone()).unwrap();
+            assert!(handler::main(item, config)
+                .to_string()
+                .contains("compile_error"));
+        }
+    }
+
+    #[test]
+    fn test_config_skip_identical_output_map_only() {
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(skip_identical_output)];
+
+        assert!(build_config(ModuleType::Map, args.clone()).is_ok());
+        assert!(build_config(ModuleType::Store, args).is_err());
+    }
+
+    #[test]
+    fn test_store_guardrails() {
+        let item = quote! {
+            fn store_values(blk: eth::Block, store: StoreAddInt64) {
+                unimplemented!("do something");
+            }
+        };
+
+        let args: syn::AttributeArgs = vec![
+            syn::parse_quote!(keep_empty_output),
+            syn::parse_quote!(max_keys_written = 1000),
+            syn::parse_quote!(max_deltas_consumed = 50),
+        ];
+
+        assert_ast_eq(
+            handler::main(item, build_config(ModuleType::Store, args).unwrap()),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_values");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits {
+                        max_keys_written: Some(1000usize),
+                        max_value_bytes: None,
+                        max_deltas_consumed: Some(50usize),
+                    });
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let store: StoreAddInt64 = <StoreAddInt64>::new();
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_VALUES: [u8; 69usize] = *b"1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+                pub mod store_values {
+                    pub const MODULE_NAME: &str = "store_values";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_VALUES, "store_values", Store, 2usize);
+                substreams::__component_export!(store_values, __substreams_component_store_values, "store-values", "cabi_post_store-values", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+
+        let invalid: syn::AttributeArgs = vec![syn::parse_quote!(max_keys_written = "many")];
+        assert!(build_config(ModuleType::Store, invalid).is_err());
+    }
+
+    #[test]
+    fn test_store_set_once() {
+        let item = quote! {
+            fn store_pools(pools: pb::Pools, store: StoreSetOnceProto<pb::Pool>) {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            handler::main(item, build_config(ModuleType::Store, vec![]).unwrap()),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_pools(pools_ptr: *mut u8, pools_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_pools");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let pools: pb::Pools = substreams::proto::decode_ptr(pools_ptr, pools_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                pools_len, stringify!(pb::Pools)
+                            )
+                        );
+                    let store: StoreSetOnceProto<pb::Pool> = <StoreSetOnceProto<pb::Pool> >::new();
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_POOLS: [u8; 83usize] = *b"1\tstore\tstore_pools\tproto:pools:pb::Pools;store:store:StoreSetOnceProto<pb::Pool>\t\n";
+                pub mod store_pools {
+                    pub const MODULE_NAME: &str = "store_pools";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_POOLS, "store_pools", Store, 2usize);
+                substreams::__component_export!(store_pools, __substreams_component_store_pools, "store-pools", "cabi_post_store-pools", (pools_ptr: *mut u8, pools_len: usize));
+            },
+        );
+    }
+
+    #[test]
+    fn test_store_multi_writable() {
+        let item = quote! {
+            fn store_pools(blk: eth::Block, counts: StoreAddInt64, pools: StoreSetProto<pb::Pool>) {
+                unimplemented!("do something");
+            }
+        };
+
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(multi_writable)];
+
+        assert_ast_eq(
+            handler::main(item.clone(), build_config(ModuleType::Store, args).unwrap()),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_pools(blk_ptr: *mut u8, blk_len: usize, counts_idx: u32, pools_idx: u32) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_pools");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let counts: StoreAddInt64 = substreams::store::StoreNewOutput::new_output(counts_idx);
+                    let pools: StoreSetProto<pb::Pool> = substreams::store::StoreNewOutput::new_output(pools_idx);
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_POOLS: [u8; 105usize] = *b"1\tstore\tstore_pools\tproto:blk:eth::Block;store:counts:StoreAddInt64;store:pools:StoreSetProto<pb::Pool>\t\n";
+                pub mod store_pools {
+                    pub const MODULE_NAME: &str = "store_pools";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_POOLS, "store_pools", Store, 3usize);
+                substreams::__component_export!(store_pools, __substreams_component_store_pools, "store-pools", "cabi_post_store-pools", (blk_ptr: *mut u8, blk_len: usize, counts_idx: u32, pools_idx: u32));
+            },
+        );
+
+        let output = handler::main(item, build_config(ModuleType::Store, vec![]).unwrap());
+        assert!(output
+            .to_string()
+            .contains("handler cannot have more then one writable store as an input"));
+
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(multi_writable)];
+        assert!(build_config(ModuleType::Map, args).is_err());
+    }
+
+    #[test]
+    fn test_store_emit() {
+        let item = quote! {
+            fn store_values(blk: eth::Block, store: StoreAddInt64) {
+                unimplemented!("do something");
+            }
+        };
+
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(emit = "pb::Summary")];
+
+        assert_ast_eq(
+            handler::main(item, build_config(ModuleType::Store, args).unwrap()),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_values");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let store: StoreAddInt64 = <StoreAddInt64>::new();
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    substreams::emit::begin::<pb::Summary>();
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::emit::finish();
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_VALUES: [u8; 69usize] = *b"1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+                pub mod store_values {
+                    pub const MODULE_NAME: &str = "store_values";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_VALUES, "store_values", Store, 2usize);
+                substreams::__component_export!(store_values, __substreams_component_store_values, "store-values", "cabi_post_store-values", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(emit = "pb::Summary")];
+        assert!(build_config(ModuleType::Map, args).is_err());
+        let args: syn::AttributeArgs = vec![syn::parse_quote!(emit = 1)];
+        assert!(build_config(ModuleType::Store, args).is_err());
+    }
+
+    #[test]
+    fn test_store_result() {
+        let item = quote! {
+            fn store_values(blk: eth::Block, store: StoreAddInt64) -> Result<(), Error> {
+                let value = parse(&blk)?;
+                store.add(0, "total", value);
+                Ok(())
+            }
+        };
+
+        assert_ast_eq(
+            main(item, ModuleType::Store, true),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_values");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let store: StoreAddInt64 = <StoreAddInt64>::new();
+                    let func = || -> Result<(), Error> {
+                        let value = parse(&blk)?;
+                        store.add(0, "total", value);
+                        Ok(())
+                    };
+                    let result = func();
+                    if result.is_err() {
+                        substreams::finish_handler_trace();
+                        panic!("{}", substreams::errors::panic_payload(&result.unwrap_err()))
+                    }
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_VALUES: [u8; 69usize] = *b"1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+                pub mod store_values {
+                    pub const MODULE_NAME: &str = "store_values";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_VALUES, "store_values", Store, 2usize);
+                substreams::__component_export!(store_values, __substreams_component_store_values, "store-values", "cabi_post_store-values", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+
+        let item = quote! {
+            fn store_values(blk: eth::Block, store: StoreAddInt64) -> Option<()> {}
+        };
+        let output = main(item, ModuleType::Store, true).to_string();
+        assert!(output.contains(
+            "Module of type Store should not have a return statement or return 'Result<(), Error>'"
+        ));
+    }
+
+    #[test]
+    fn test_store_result_option() {
+        let item = quote! {
+            fn store_values(blk: eth::Block, store: StoreAddInt64) {
+                unimplemented!("do something");
+            }
+        };
+
+        assert_ast_eq(
+            main(item.clone(), ModuleType::Store, true).into(),
+            quote! {
+                #[no_mangle]
+                pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_values");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let store: StoreAddInt64 = <StoreAddInt64>::new();
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_VALUES: [u8; 69usize] = *b"1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+                pub mod store_values {
+                    pub const MODULE_NAME: &str = "store_values";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_VALUES, "store_values", Store, 2usize);
+                substreams::__component_export!(store_values, __substreams_component_store_values, "store-values", "cabi_post_store-values", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+
+        assert_ast_eq(
+            main(item, ModuleType::Store, false).into(),
+            quote! {
+                #[no_mangle]
+                    pub extern "C" fn store_values(blk_ptr: *mut u8, blk_len: usize) {
+                    substreams::register_panic_hook();
+                    substreams::context::set_handler("store_values");
+                    substreams::guardrails::set_limits(substreams::guardrails::Limits::default());
+                    substreams::begin_handler_trace();
+                    let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len)
+                        .unwrap_or_else(|_|
+                            panic!(
+                                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct",
+                                blk_len, stringify!(eth::Block)
+                            )
+                        );
+                    let store: StoreAddInt64 = <StoreAddInt64>::new();
+                    substreams::output_policy(substreams::OutputPolicy::SkipEmpty);
+                    let result = {
+                        unimplemented!("do something");
+                    };
+                    substreams::apply_output_policy();
+                    substreams::finish_handler_trace();
+                    result
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                #[link_section = "substreams_metadata"]
+                #[used]
+                static __SUBSTREAMS_METADATA_STORE_VALUES: [u8; 69usize] = *b"1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+                pub mod store_values {
+                    pub const MODULE_NAME: &str = "store_values";
+                }
+                substreams::__register_handler!(__SUBSTREAMS_HANDLER_STORE_VALUES, "store_values", Store, 2usize);
+                substreams::__component_export!(store_values, __substreams_component_store_values, "store-values", "cabi_post_store-values", (blk_ptr: *mut u8, blk_len: usize));
+            },
+        );
+    }
+
+    const MANIFEST: &str = r#"
+specVersion: v0.1.0
+modules:
+  - name: map_pools
+    kind: map
+    inputs:
+      - params: string
+      - source: sf.ethereum.type.v2.Block
+    output:
+      type: proto:uniswap.v1.Pools
+
+  - name: store_pools
+    kind: store
+    updatePolicy: set
+    valueType: proto:uniswap.v1.Pool
+    inputs:
+      - map: map_pools
+
+  - name: store_volumes
+    kind: store
+    updatePolicy: add
+    valueType: bigint
+    inputs:
+      - map: map_pools
+      - store: store_pools
+      - store: store_pools
+        mode: deltas
+"#;
+
+    fn check_manifest(source: proc_macro2::TokenStream) -> Vec<String> {
+        let file = syn::parse2::<syn::File>(source).unwrap();
+        crate::manifest::check(MANIFEST, &[("src/lib.rs".to_string(), file)]).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_matching_handlers() {
+        let errors = check_manifest(quote! {
+            #[substreams::handlers::map]
+            fn map_pools(params: String, blk: eth::Block) -> Result<pb::Pools, Error> {}
+
+            mod stores {
+                #[substreams::handlers::store]
+                fn store_pools(pools: &pb::Pools, store: StoreSetProto<pb::Pool>) {}
+
+                #[substreams::handlers::store]
+                fn store_volumes(
+                    pools: pb::Pools,
+                    pool_store: StoreGetProto<pb::Pool>,
+                    deltas: Deltas<LazyDeltaProto<pb::Pool>>,
+                    store: StoreAddBigInt,
+                ) {}
+            }
+        });
+
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_manifest_mismatches() {
+        let errors = check_manifest(quote! {
+            #[substreams::handlers::map]
+            fn map_pools(blk: eth::Block) -> Option<pb::Pool> {}
+
+            #[substreams::handlers::store]
+            fn store_pools(pools: pb::Pools, store: StoreSetInt64) {}
+
+            #[substreams::handlers::store]
+            fn store_volumes(
+                pools: pb::Pools,
+                pool_store: Deltas<DeltaBigInt>,
+                deltas: Deltas<DeltaBigInt>,
+                store: StoreAddBigInt,
+            ) {}
+
+            #[substreams::handlers::map]
+            fn map_renamed(blk: eth::Block) -> pb::Pools {}
+        });
+
+        assert_eq!(
+            errors,
+            vec![
+                "handler `map_pools` takes 1 input(s) but the manifest declares 2",
+                "handler `map_pools` outputs `Pool` but the manifest declares output type 'proto:uniswap.v1.Pools'",
+                "handler `store_pools` writes to `StoreSetInt64` but the manifest declares updatePolicy 'set' and valueType 'proto:uniswap.v1.Pool', expected `StoreSetProto`",
+                "handler `store_volumes` input #2: expected store 'store_pools' in get mode, found `Deltas`",
+                "handler `store_volumes` input #3: expected deltas of store 'store_pools' (`Deltas<DeltaProto>`), found `Deltas<DeltaBigInt>`",
+                "handler `map_renamed` (src/lib.rs) is not declared in the manifest modules",
+            ]
+        );
     }
 }
\ No newline at end of file
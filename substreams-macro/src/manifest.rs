@@ -0,0 +1,569 @@
+use crate::config::{build_config, ModuleType};
+use crate::handler::{instantiate, optional_store, parse_borrow, READABLE_STORE, WRITABLE_STORE};
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use yaml_rust::{Yaml, YamlLoader};
+
+pub(crate) fn main(item: TokenStream) -> TokenStream {
+    match expand(item) {
+        Ok(tokens) => tokens,
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+fn expand(item: TokenStream) -> Result<TokenStream, syn::Error> {
+    let path = syn::parse2::<syn::LitStr>(item)?;
+    let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let manifest_path = root.join(path.value());
+
+    let manifest = std::fs::read_to_string(&manifest_path).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("unable to read {}: {}", manifest_path.display(), e),
+        )
+    })?;
+
+    let mut sources = Vec::new();
+    collect_sources(&root.join("src"), &mut sources);
+
+    let files: Vec<(String, syn::File)> = sources
+        .iter()
+        .filter_map(|source| {
+            let content = std::fs::read_to_string(source).ok()?;
+            let file = syn::parse_file(&content).ok()?;
+            let name = source.strip_prefix(&root).unwrap_or(source);
+            Some((name.display().to_string(), file))
+        })
+        .collect();
+
+    let mut errors = check(&manifest, &files)
+        .map_err(|e| syn::Error::new(path.span(), e))?
+        .into_iter()
+        .map(|e| syn::Error::new(path.span(), e));
+
+    if let Some(mut error) = errors.next() {
+        errors.for_each(|e| error.combine(e));
+        return Err(error);
+    }
+
+    // Makes the crate rebuild, and the manifest checked again, when the manifest changes
+    let manifest_path = manifest_path.display().to_string();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#manifest_path);
+    })
+}
+
+fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect_sources(&path, out);
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            out.push(path);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ModuleKind {
+    Map,
+    Store,
+    Other,
+}
+
+#[derive(Debug)]
+enum ModuleInput {
+    Source(String),
+    Map(String),
+    Store { name: String, deltas: bool },
+    Params,
+}
+
+#[derive(Debug)]
+struct Module {
+    name: String,
+    kind: ModuleKind,
+    update_policy: String,
+    value_type: String,
+    inputs: Vec<ModuleInput>,
+    output_type: String,
+}
+
+struct Handler<'a> {
+    file: &'a str,
+    kind: ModuleKind,
+    attr: &'a syn::Attribute,
+    func: &'a syn::ItemFn,
+}
+
+/// Checks the map and store handlers found in `files` against the modules declared in
+/// `manifest`, returning one message per mismatch.
+pub(crate) fn check(manifest: &str, files: &[(String, syn::File)]) -> Result<Vec<String>, String> {
+    let modules = parse_manifest(manifest)?;
+
+    let mut handlers = Vec::new();
+    for (file, content) in files {
+        collect_handlers(file, &content.items, &mut handlers);
+    }
+
+    let mut errors = Vec::new();
+    for handler in handlers {
+        let name = handler.func.sig.ident.to_string();
+        match modules.iter().find(|module| module.name == name) {
+            Some(module) => check_handler(&handler, module, &modules, &mut errors),
+            None => errors.push(format!(
+                "handler `{}` ({}) is not declared in the manifest modules",
+                name, handler.file
+            )),
+        }
+    }
+    Ok(errors)
+}
+
+fn parse_manifest(manifest: &str) -> Result<Vec<Module>, String> {
+    let docs =
+        YamlLoader::load_from_str(manifest).map_err(|e| format!("invalid manifest: {}", e))?;
+    let doc = docs.first().ok_or("invalid manifest: empty document")?;
+
+    let modules = match &doc["modules"] {
+        Yaml::Array(modules) => modules,
+        Yaml::BadValue => return Ok(vec![]),
+        _ => return Err("invalid manifest: 'modules' must be a list".to_string()),
+    };
+
+    modules
+        .iter()
+        .map(|module| {
+            let name = module["name"]
+                .as_str()
+                .ok_or("invalid manifest: module without 'name'")?;
+
+            let inputs = match &module["inputs"] {
+                Yaml::Array(inputs) => inputs.iter().map(parse_module_input).collect(),
+                _ => Ok(vec![]),
+            }
+            .map_err(|e| format!("invalid manifest: module '{}' {}", name, e))?;
+
+            Ok(Module {
+                name: name.to_string(),
+                kind: match module["kind"].as_str() {
+                    Some("map") => ModuleKind::Map,
+                    Some("store") => ModuleKind::Store,
+                    _ => ModuleKind::Other,
+                },
+                update_policy: yaml_string(&module["updatePolicy"]),
+                value_type: yaml_string(&module["valueType"]),
+                inputs,
+                output_type: yaml_string(&module["output"]["type"]),
+            })
+        })
+        .collect()
+}
+
+fn parse_module_input(input: &Yaml) -> Result<ModuleInput, String> {
+    if let Some(source) = input["source"].as_str() {
+        return Ok(ModuleInput::Source(source.to_string()));
+    }
+    if let Some(map) = input["map"].as_str() {
+        return Ok(ModuleInput::Map(map.to_string()));
+    }
+    if let Some(store) = input["store"].as_str() {
+        return Ok(ModuleInput::Store {
+            name: store.to_string(),
+            deltas: input["mode"].as_str() == Some("deltas"),
+        });
+    }
+    if !input["params"].is_badvalue() {
+        return Ok(ModuleInput::Params);
+    }
+
+    Err("has an input that is not one of 'source', 'map', 'store' or 'params'".to_string())
+}
+
+fn yaml_string(value: &Yaml) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn collect_handlers<'a>(file: &'a str, items: &'a [syn::Item], out: &mut Vec<Handler<'a>>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(func) => {
+                let handler = func.attrs.iter().find_map(|attr| {
+                    let segments: Vec<String> = attr
+                        .path
+                        .segments
+                        .iter()
+                        .map(|s| s.ident.to_string())
+                        .collect();
+
+                    let kind = match segments.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                        ["substreams", "handlers", "map"] | ["handlers", "map"] | ["map"] => {
+                            ModuleKind::Map
+                        }
+                        ["substreams", "handlers", "store"] | ["handlers", "store"] | ["store"] => {
+                            ModuleKind::Store
+                        }
+                        _ => return None,
+                    };
+                    Some((kind, attr))
+                });
+
+                if let Some((kind, attr)) = handler {
+                    out.push(Handler {
+                        file,
+                        kind,
+                        attr,
+                        func,
+                    });
+                }
+            }
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_handlers(file, items, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the handler's function with its generic parameters replaced by the types of its
+/// `instantiate` argument, invalid arguments being reported by the handler macro itself.
+fn instantiated<'a>(handler: &Handler<'a>) -> Cow<'a, syn::ItemFn> {
+    if handler.func.sig.generics.params.is_empty() {
+        return Cow::Borrowed(handler.func);
+    }
+
+    let module_type = match handler.kind {
+        ModuleKind::Map => ModuleType::Map,
+        _ => ModuleType::Store,
+    };
+    let types = match handler.attr.parse_meta() {
+        Ok(syn::Meta::List(list)) => build_config(module_type, list.nested.into_iter().collect())
+            .map(|config| config.instantiate)
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    let mut func = handler.func.clone();
+    match instantiate(&mut func, &types) {
+        Ok(()) => Cow::Owned(func),
+        Err(_) => Cow::Borrowed(handler.func),
+    }
+}
+
+fn check_handler(handler: &Handler, module: &Module, modules: &[Module], errors: &mut Vec<String>) {
+    let name = &module.name;
+    let func = instantiated(handler);
+    if handler.kind != module.kind {
+        errors.push(format!(
+            "handler `{}` is a {} but the manifest declares it with kind '{}'",
+            name,
+            kind_name(&handler.kind),
+            kind_name(&module.kind)
+        ));
+        return;
+    }
+
+    let mut inputs = Vec::new();
+    let mut writable_store = None;
+    for arg in func.sig.inputs.iter() {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            let ty = parse_borrow(&pat_type.ty).1;
+            match type_name(ty) {
+                Some(t) if WRITABLE_STORE.contains(&t.as_str()) => writable_store = Some(t),
+                _ => inputs.push(ty),
+            }
+        }
+    }
+
+    if inputs.len() != module.inputs.len() {
+        errors.push(format!(
+            "handler `{}` takes {} input(s) but the manifest declares {}",
+            name,
+            inputs.len(),
+            module.inputs.len()
+        ));
+    } else {
+        for (i, (ty, input)) in inputs.iter().zip(module.inputs.iter()).enumerate() {
+            if let Some(error) = check_input(ty, input, modules) {
+                errors.push(format!("handler `{}` input #{}: {}", name, i + 1, error));
+            }
+        }
+    }
+
+    match module.kind {
+        ModuleKind::Map => {
+            let expected = proto_name(&module.output_type);
+            let actual = match &func.sig.output {
+                syn::ReturnType::Type(_, ty) => output_type_name(ty),
+                syn::ReturnType::Default => None,
+            };
+
+            if let (Some(expected), Some(actual)) = (expected, actual) {
+                if expected != actual {
+                    errors.push(format!(
+                        "handler `{}` outputs `{}` but the manifest declares output type '{}'",
+                        name, actual, module.output_type
+                    ));
+                }
+            }
+        }
+        ModuleKind::Store => {
+            let expected = store_types(&module.update_policy, &module.value_type);
+            match (writable_store, expected.first()) {
+                (None, _) => errors.push(format!(
+                    "handler `{}` takes no writable store but the manifest declares a store module",
+                    name
+                )),
+                (Some(actual), Some(preferred)) if !expected.contains(&actual) => errors.push(format!(
+                    "handler `{}` writes to `{}` but the manifest declares updatePolicy '{}' and valueType '{}', expected `{}`",
+                    name, actual, module.update_policy, module.value_type, preferred
+                )),
+                _ => {}
+            }
+        }
+        ModuleKind::Other => {}
+    }
+}
+
+fn check_input(ty: &syn::Type, input: &ModuleInput, modules: &[Module]) -> Option<String> {
+    let ty = optional_store(ty).unwrap_or(ty);
+    let actual = type_name(ty).unwrap_or_default();
+    let is_store = READABLE_STORE.contains(&actual.as_str()) || actual == "Deltas";
+
+    match input {
+        ModuleInput::Source(source) => match (is_store, proto_name(source)) {
+            (true, _) => Some(format!(
+                "expected source '{}', found store `{}`",
+                source, actual
+            )),
+            (false, Some(expected)) if expected != actual => {
+                Some(format!("expected source '{}', found `{}`", source, actual))
+            }
+            _ => None,
+        },
+        ModuleInput::Map(map) => {
+            let expected = modules
+                .iter()
+                .find(|module| &module.name == map)
+                .and_then(|module| proto_name(&module.output_type));
+
+            match (is_store, expected) {
+                (true, _) => Some(format!(
+                    "expected output of map '{}', found store `{}`",
+                    map, actual
+                )),
+                (false, Some(expected)) if expected != actual => Some(format!(
+                    "expected output of map '{}' (`{}`), found `{}`",
+                    map, expected, actual
+                )),
+                _ => None,
+            }
+        }
+        ModuleInput::Params => match is_store {
+            true => Some(format!("expected params, found store `{}`", actual)),
+            false => None,
+        },
+        ModuleInput::Store { name, deltas } => {
+            let store = modules.iter().find(|module| &module.name == name);
+            let (policy, value_type) = store
+                .map(|m| (m.update_policy.as_str(), m.value_type.as_str()))
+                .unwrap_or_default();
+
+            if *deltas {
+                let delta = match actual.as_str() {
+                    "Deltas" => generic_type_name(ty),
+                    _ => {
+                        return Some(format!(
+                            "expected deltas of store '{}', found `{}`",
+                            name, actual
+                        ))
+                    }
+                };
+
+                let expected = delta_types(policy, value_type);
+                match delta {
+                    Some(delta) if !expected.is_empty() && !expected.contains(&delta) => {
+                        Some(format!(
+                            "expected deltas of store '{}' (`Deltas<{}>`), found `Deltas<{}>`",
+                            name, expected[0], delta
+                        ))
+                    }
+                    _ => None,
+                }
+            } else {
+                if !actual.starts_with("StoreGet") {
+                    return Some(format!(
+                        "expected store '{}' in get mode, found `{}`",
+                        name, actual
+                    ));
+                }
+
+                let expected = get_types(policy, value_type);
+                match !expected.is_empty() && !expected.contains(&actual) {
+                    true => Some(format!(
+                        "expected store '{}' (`{}`), found `{}`",
+                        name, expected[0], actual
+                    )),
+                    false => None,
+                }
+            }
+        }
+    }
+}
+
+fn kind_name(kind: &ModuleKind) -> &'static str {
+    match kind {
+        ModuleKind::Map => "map",
+        ModuleKind::Store => "store",
+        ModuleKind::Other => "other",
+    }
+}
+
+/// Returns the message name of a `proto:package.Message` or `package.Message` type.
+fn proto_name(ty: &str) -> Option<String> {
+    let ty = ty.strip_prefix("proto:").unwrap_or(ty);
+    ty.rsplit('.')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+fn value_suffix(value_type: &str) -> Option<&'static str> {
+    Some(match value_type {
+        "bigint" => "BigInt",
+        "bigdecimal" | "bigfloat" => "BigDecimal",
+        "int64" => "Int64",
+        "int32" => "Int32",
+        "uint64" => "Uint64",
+        "float64" => "Float64",
+        "bool" => "Bool",
+        "string" => "String",
+        "bytes" => "Raw",
+        t if t.starts_with("proto:") => "Proto",
+        _ => return None,
+    })
+}
+
+/// Writable store types matching a store, the first one being the preferred one.
+fn store_types(update_policy: &str, value_type: &str) -> Vec<String> {
+    let prefix = match update_policy {
+        "set" => "StoreSet",
+        "set_if_not_exists" => "StoreSetIfNotExists",
+        "set_once" => "StoreSetOnce",
+        "add" => "StoreAdd",
+        "min" if value_type == "bytes" => return vec!["StoreMinBytes".to_string()],
+        "max" if value_type == "bytes" => return vec!["StoreMaxBytes".to_string()],
+        "min" => "StoreMin",
+        "max" => "StoreMax",
+        "set_sum" => "StoreSetSum",
+        "append" if value_type.starts_with("proto:") => return vec!["StoreAppendProto".to_string()],
+        "append" if value_type == "bytes" => {
+            return vec!["StoreAppendBytes".to_string(), "StoreAppend".to_string()]
+        }
+        "append" => return vec!["StoreAppend".to_string()],
+        _ => return vec![],
+    };
+
+    let mut types: Vec<String> = value_suffix(value_type)
+        .map(|suffix| vec![format!("{}{}", prefix, suffix)])
+        .unwrap_or_default();
+    if update_policy == "set" && value_type == "string" {
+        types.push("StoreSetEnum".to_string());
+    }
+    types
+}
+
+/// Readable store types matching a store, the first one being the preferred one.
+fn get_types(update_policy: &str, value_type: &str) -> Vec<String> {
+    let mut types = match (update_policy, value_suffix(value_type)) {
+        ("append", Some("Proto")) => vec!["StoreGetProtoArray".to_string()],
+        ("append", Some("Raw")) => vec![
+            "StoreGetBytesArray".to_string(),
+            "StoreGetArray".to_string(),
+        ],
+        ("append", _) => vec!["StoreGetArray".to_string()],
+        (_, Some("String")) => vec!["StoreGetString".to_string(), "StoreGetEnum".to_string()],
+        (_, Some(suffix)) => vec![format!("StoreGet{}", suffix)],
+        (_, None) => return vec![],
+    };
+
+    types.push("StoreGetRaw".to_string());
+    types
+}
+
+/// Delta types matching a store, the first one being the preferred one.
+fn delta_types(update_policy: &str, value_type: &str) -> Vec<String> {
+    let suffix = match value_suffix(value_type) {
+        Some(suffix) => suffix,
+        None => return vec![],
+    };
+
+    let mut types = match (update_policy, suffix) {
+        ("append", "Proto") => vec!["DeltaProtoArray".to_string()],
+        ("append", "Raw") => vec!["DeltaBytesArray".to_string(), "DeltaArray".to_string()],
+        ("append", _) => vec!["DeltaArray".to_string()],
+        ("set_sum", _) => vec![format!("DeltaSetSum{}", suffix)],
+        (_, "Proto") => vec!["DeltaProto".to_string(), "LazyDeltaProto".to_string()],
+        (_, "Raw") => vec![],
+        (_, "String") => vec!["DeltaString".to_string(), "DeltaEnum".to_string()],
+        (_, _) => vec![format!("Delta{}", suffix)],
+    };
+
+    types.push("DeltaBytes".to_string());
+    types
+}
+
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+        syn::Type::Reference(r) => type_name(&r.elem),
+        _ => None,
+    }
+}
+
+fn generic_type_name(ty: &syn::Type) -> Option<String> {
+    let segment = match ty {
+        syn::Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => type_name(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Unwraps `Result<T, E>` and `Option<T>` outputs to the name of `T`.
+fn output_type_name(ty: &syn::Type) -> Option<String> {
+    let name = type_name(ty)?;
+    if name != "Result" && name != "Option" {
+        return Some(name);
+    }
+
+    let segment = match ty {
+        syn::Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => output_type_name(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,173 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+type AttributeArgs = Vec<syn::NestedMeta>;
+
+pub(crate) fn main(args: AttributeArgs, item: TokenStream) -> TokenStream {
+    let original = item.clone();
+
+    match expand(args, item) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let mut tokens = original;
+            tokens.extend(e.into_compile_error());
+            tokens
+        }
+    }
+}
+
+fn expand(args: AttributeArgs, item: TokenStream) -> Result<TokenStream, syn::Error> {
+    let capacity = parse_capacity(args)?;
+    let input = syn::parse2::<syn::ItemFn>(item)?;
+
+    if !input.sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.sig.generics.span(),
+            "memoized function cannot have generic parameters",
+        ));
+    }
+
+    let output_type = match &input.sig.output {
+        syn::ReturnType::Type(_, ty) => ty.clone(),
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new(
+                input.sig.span(),
+                "memoized function must return a value",
+            ))
+        }
+    };
+
+    let mut key_types = Vec::with_capacity(input.sig.inputs.len());
+    let mut key_values = Vec::with_capacity(input.sig.inputs.len());
+    for arg in input.sig.inputs.iter() {
+        match arg {
+            syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(v) => {
+                    let name = &v.ident;
+                    // Borrowed arguments are kept in the cache key as their owned form
+                    match &*pat_type.ty {
+                        syn::Type::Reference(r) => {
+                            let elem = &r.elem;
+                            key_types.push(quote! { <#elem as std::borrow::ToOwned>::Owned });
+                            key_values.push(quote! { std::borrow::ToOwned::to_owned(#name) });
+                        }
+                        ty => {
+                            key_types.push(quote! { #ty });
+                            key_values.push(quote! { std::clone::Clone::clone(&#name) });
+                        }
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        pat_type.span(),
+                        "memoized function arguments must be plain identifiers",
+                    ))
+                }
+            },
+            syn::FnArg::Receiver(_) => {
+                return Err(syn::Error::new(
+                    arg.span(),
+                    "memoized function does not support 'self' receiver",
+                ))
+            }
+        }
+    }
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let sig = &input.sig;
+    let body = &input.block;
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            thread_local! {
+                static CACHE: std::cell::RefCell<substreams::memo::Cache<(#(#key_types,)*), #output_type>> =
+                    std::cell::RefCell::new(substreams::memo::Cache::new(#capacity));
+            }
+
+            let key = (#(#key_values,)*);
+            if let Some(cached) = CACHE.with(|cache| cache.borrow().get(&key)) {
+                return cached;
+            }
+
+            #[allow(clippy::redundant_closure_call)]
+            let result: #output_type = (move || #body)();
+            CACHE.with(|cache| cache.borrow_mut().insert(key, result.clone()));
+            result
+        }
+    })
+}
+
+fn parse_capacity(args: AttributeArgs) -> Result<TokenStream, syn::Error> {
+    let mut capacity = quote! { substreams::memo::DEFAULT_CAPACITY };
+
+    for arg in args {
+        match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("capacity") => {
+                match &nv.lit {
+                    syn::Lit::Int(v) => {
+                        let value = v.base10_parse::<usize>()?;
+                        capacity = quote! { #value };
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "`capacity` must be an integer literal",
+                        ))
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "Invalid argument for memoize macro; expected `capacity = <usize>`",
+                ))
+            }
+        }
+    }
+
+    Ok(capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assertions::assert_ast_eq;
+    use quote::quote;
+
+    #[test]
+    fn test_memoize() {
+        let item = quote! {
+            pub fn price(token: String, depth: u32, chain: &str) -> u64 {
+                depth as u64
+            }
+        };
+
+        assert_ast_eq(
+            super::main(vec![syn::parse_quote!(capacity = 16)], item),
+            quote! {
+                pub fn price(token: String, depth: u32, chain: &str) -> u64 {
+                    thread_local! {
+                        static CACHE: std::cell::RefCell<substreams::memo::Cache<(String, u32, <str as std::borrow::ToOwned>::Owned,), u64>> =
+                            std::cell::RefCell::new(substreams::memo::Cache::new(16usize));
+                    }
+
+                    let key = (
+                        std::clone::Clone::clone(&token),
+                        std::clone::Clone::clone(&depth),
+                        std::borrow::ToOwned::to_owned(chain),
+                    );
+                    if let Some(cached) = CACHE.with(|cache| cache.borrow().get(&key)) {
+                        return cached;
+                    }
+
+                    #[allow(clippy::redundant_closure_call)]
+                    let result: u64 = (move || { depth as u64 })();
+                    CACHE.with(|cache| cache.borrow_mut().insert(key, result.clone()));
+                    result
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::config::ModuleType;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+
+/// Name of the custom wasm section holding handlers metadata, must be kept in sync
+/// with `substreams::metadata::SECTION_NAME`.
+const SECTION_NAME: &str = "substreams_metadata";
+
+/// Version of the record format, must be kept in sync with `substreams::metadata::FORMAT_VERSION`.
+const FORMAT_VERSION: &str = "1";
+
+pub(crate) struct HandlerMetadata {
+    kind: &'static str,
+    name: String,
+    inputs: Vec<String>,
+    output: String,
+}
+
+impl HandlerMetadata {
+    pub(crate) fn new(module_type: ModuleType, name: String, output: &syn::ReturnType) -> Self {
+        let kind = match module_type {
+            ModuleType::Map => "map",
+            ModuleType::Store => "store",
+            ModuleType::Index => "index",
+        };
+
+        // A store handler only returns a `Result` to report its errors, it has no output
+        let output = match output {
+            syn::ReturnType::Type(_, ty) if module_type != ModuleType::Store => type_to_string(ty),
+            _ => "".to_string(),
+        };
+
+        HandlerMetadata {
+            kind,
+            name,
+            inputs: vec![],
+            output,
+        }
+    }
+
+    pub(crate) fn add_input(&mut self, role: &str, name: &syn::Ident, ty: &syn::Type) {
+        self.inputs
+            .push(format!("{}:{}:{}", role, name, type_to_string(ty)));
+    }
+
+    /// Renders the record as a `static` placed in the metadata custom section, the linker
+    /// concatenates all statics of the same section so each handler contributes one line.
+    pub(crate) fn to_section_static(&self) -> TokenStream {
+        let record = format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            FORMAT_VERSION,
+            self.kind,
+            self.name,
+            self.inputs.join(";"),
+            self.output
+        );
+
+        let bytes = syn::LitByteStr::new(record.as_bytes(), Span::call_site());
+        let len = record.len();
+        let static_name = format_ident!("__SUBSTREAMS_METADATA_{}", self.name.to_uppercase());
+
+        quote! {
+            #[cfg(target_arch = "wasm32")]
+            #[link_section = #SECTION_NAME]
+            #[used]
+            static #static_name: [u8; #len] = *#bytes;
+        }
+    }
+
+    /// Renders the module named after the handler holding its `MODULE_NAME` and the
+    /// registration of the handler in `substreams::registry()`, `items` are added to the module.
+    pub(crate) fn to_registration(&self, items: TokenStream) -> TokenStream {
+        let module = format_ident!("{}", self.name);
+        let name = &self.name;
+        let static_name = format_ident!("__SUBSTREAMS_HANDLER_{}", self.name.to_uppercase());
+        let kind = match self.kind {
+            "map" => format_ident!("Map"),
+            "store" => format_ident!("Store"),
+            _ => format_ident!("Index"),
+        };
+        let inputs = self.inputs.len();
+
+        quote! {
+            pub mod #module {
+                pub const MODULE_NAME: &str = #name;
+                #items
+            }
+            substreams::__register_handler!(#static_name, #name, #kind, #inputs);
+        }
+    }
+}
+
+/// Renders a type as compact Rust source, only keeping whitespace where it's
+/// required to separate two identifiers (e.g. `&'a mut Block`).
+fn type_to_string(ty: &syn::Type) -> String {
+    let raw = ty.to_token_stream().to_string();
+    let chars: Vec<char> = raw.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut out = String::with_capacity(raw.len());
+    for (i, c) in chars.iter().enumerate() {
+        if *c == ' ' {
+            let prev = out.chars().last();
+            let next = chars.get(i + 1);
+            if let (Some(prev), Some(next)) = (prev, next) {
+                if is_word(prev) && is_word(*next) {
+                    out.push(' ');
+                }
+            }
+            continue;
+        }
+        out.push(*c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::type_to_string;
+
+    #[test]
+    fn test_type_to_string() {
+        let ty: syn::Type = syn::parse_quote!(StoreGetProto<pb::Pairs>);
+        assert_eq!(type_to_string(&ty), "StoreGetProto<pb::Pairs>");
+
+        let ty: syn::Type = syn::parse_quote!(&'a mut eth::Block);
+        assert_eq!(type_to_string(&ty), "&'a mut eth::Block");
+    }
+}
@@ -0,0 +1,73 @@
+//! Compares matching keys against an expression parsed for every candidate with
+//! `matches_keys_in_parsed_expr` and compiled once with `expr_matcher`, counting the
+//! allocations made by each.
+//!
+//! Run natively with `cargo bench --bench expr_matcher`, or inside wasm by building it for
+//! `wasm32-wasip1` and running the produced `.wasm` file with a WASI runtime.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use substreams::{expr_matcher, matches_keys_in_parsed_expr};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const CANDIDATES: usize = 200_000;
+const EXPRESSION: &str = "(type:swap || type:burn) && amount > 1000.5 && !pool:0x00";
+
+fn run<F: FnMut(&[String]) -> bool>(name: &str, candidates: &[Vec<String>], mut matches: F) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let matched = candidates.iter().filter(|keys| matches(keys)).count();
+
+    println!(
+        "{:<10} {:>8} candidates  {:>10.2?}  {:>10} allocations  {:>12} bytes  ({} matched)",
+        name,
+        candidates.len(),
+        start.elapsed(),
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+        matched
+    );
+}
+
+fn main() {
+    let kinds = ["type:swap", "type:mint", "type:burn"];
+    let candidates: Vec<Vec<String>> = (0..CANDIDATES)
+        .map(|i| {
+            vec![
+                kinds[i % kinds.len()].to_string(),
+                format!("amount:{}.{}", i % 2000, i % 10),
+                format!("pool:0x{:02x}", i % 256),
+            ]
+        })
+        .collect();
+
+    run("parsed", &candidates, |keys| {
+        matches_keys_in_parsed_expr(keys, EXPRESSION).unwrap()
+    });
+
+    let matcher = expr_matcher(EXPRESSION);
+    run("compiled", &candidates, |keys| matcher.matches_keys(keys));
+}
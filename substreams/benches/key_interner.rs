@@ -0,0 +1,71 @@
+//! Compares formatting store keys with `format!` and with `format_key!` when the same keys
+//! are written for many blocks, counting the allocations made by each.
+//!
+//! Run natively with `cargo bench --bench key_interner`, or inside wasm by building it for
+//! `wasm32-wasip1` and running the produced `.wasm` file with a WASI runtime.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use substreams::format_key;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const BLOCKS: usize = 1_000;
+const POOLS: usize = 500;
+
+fn run<F: FnMut(&str) -> usize>(name: &str, pools: &[String], mut write: F) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let mut total = 0;
+    for _ in 0..BLOCKS {
+        for pool in pools {
+            total += write(pool);
+        }
+    }
+
+    println!(
+        "{:<12} {:>8} keys  {:>10.2?}  {:>8} allocations  {:>10} bytes  (checksum {})",
+        name,
+        BLOCKS * POOLS,
+        start.elapsed(),
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+        total
+    );
+}
+
+fn main() {
+    let pools: Vec<String> = (0..POOLS).map(|i| format!("0x{:040x}", i)).collect();
+
+    run("format!", &pools, |pool| {
+        let key = format!("pool:{}:token0", pool);
+        key.len()
+    });
+
+    run("format_key!", &pools, |pool| {
+        let key = format_key!("pool:{}:token0", pool);
+        key.len()
+    });
+}
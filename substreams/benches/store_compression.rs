@@ -0,0 +1,89 @@
+//! Compares writing and reading `StoreSetProto` values uncompressed and compressed with each
+//! `store::Compression`, printing the stored size and the time spent per value for small
+//! messages, large snapshots repeating similar entries and values that don't compress.
+//!
+//! Run natively with `cargo bench --bench store_compression --features zstd,lz4`, or inside
+//! wasm by building it for `wasm32-wasip1` and running the produced `.wasm` file with a WASI
+//! runtime, which is closer to the cost paid by modules.
+
+use std::time::Instant;
+
+use substreams::store::{decompress, Compression};
+
+const ROUNDS: usize = 2_000;
+
+/// A pool snapshot: an address, token pair and tick liquidity entries, encoded like protobuf
+/// messages with many similar fields.
+fn snapshot(ticks: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for field in 1..=3u8 {
+        bytes.push(field << 3 | 2);
+        bytes.push(42);
+        bytes.extend_from_slice(format!("0x{:040x}", field as u64 * 0x1f98_431c).as_bytes());
+    }
+
+    for tick in 0..ticks {
+        bytes.push(4 << 3 | 2);
+        let entry = format!("{}:{}", tick as i64 * 60 - 887_220, 1_000_000 + tick * 17);
+        bytes.push(entry.len() as u8);
+        bytes.extend_from_slice(entry.as_bytes());
+    }
+
+    bytes
+}
+
+/// High entropy bytes, like hashes, that compression can't shrink.
+fn random(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn run(name: &str, compression: Compression, value: &[u8]) {
+    let start = Instant::now();
+    let mut stored = Vec::new();
+    for _ in 0..ROUNDS {
+        stored = compression.compress(value.to_vec());
+    }
+    let write = start.elapsed() / ROUNDS as u32;
+
+    let start = Instant::now();
+    let mut total = 0;
+    for _ in 0..ROUNDS {
+        total += decompress(&stored)
+            .expect("stored value decompresses")
+            .len();
+    }
+    let read = start.elapsed() / ROUNDS as u32;
+
+    println!(
+        "{:<10} {:>8} bytes -> {:>8} bytes  write {:>10.2?}  read {:>10.2?}  (checksum {})",
+        name,
+        value.len(),
+        stored.len(),
+        write,
+        read,
+        total
+    );
+}
+
+fn main() {
+    let values = [
+        ("small", snapshot(0)),
+        ("snapshot", snapshot(200)),
+        ("random", random(4_096)),
+    ];
+
+    for (value_name, value) in values.iter() {
+        println!("{}:", value_name);
+        run("none", Compression::None, value);
+        run("zstd", Compression::Zstd, value);
+        run("lz4", Compression::Lz4, value);
+    }
+}
@@ -0,0 +1,157 @@
+//! ABI versioning for Substreams WASM modules.
+//!
+//! Every module compiled against this crate exports a `substreams_abi_version` function
+//! returning [ABI_VERSION]. The Substreams server can call it to detect which intrinsics
+//! and calling conventions the module expects before invoking any handler. Modules built
+//! before this export existed do not have it, servers should assume version `0` for them.
+//!
+//! The version is bumped each time the set of imported intrinsics or the handler calling
+//! convention changes in a way that is not backward compatible. Intrinsics added on top of
+//! version `1` are also listed by [CAPABILITIES], exported as `substreams_abi_capabilities`:
+//! a bit is set for each [capability] this build of the crate can import, so the server can
+//! tell which intrinsics a module may need and reject it upfront if it doesn't provide them.
+//! Wasm linkers drop unused imports, a module only imports the intrinsics of the APIs it calls.
+
+/// The ABI version implemented by this crate.
+pub const ABI_VERSION: u32 = 11;
+
+/// The oldest ABI version a host can speak and still run modules built with this crate.
+pub const MIN_SUPPORTED_ABI_VERSION: u32 = 1;
+
+/// Intrinsics imported on top of ABI version `1`, one bit each in [CAPABILITIES].
+pub mod capability {
+    /// `state.get_many_last` and `state.get_many_at`, batched reads.
+    pub const GET_MANY: u64 = 1 << 0;
+    /// `state.get_last_into`, reading a value into a reused buffer.
+    pub const GET_LAST_INTO: u64 = 1 << 1;
+    /// `state.scan_prefix`, paginated prefix scans.
+    pub const SCAN_PREFIX: u64 = 1 << 2;
+    /// `state.set_once`, the `set_once` update policy.
+    pub const SET_ONCE: u64 = 1 << 3;
+    /// `state.write_batch`, with the `batched-writes` feature.
+    pub const WRITE_BATCH: u64 = 1 << 4;
+    /// `state.select_output`, with the `multi-writable-stores` feature.
+    pub const SELECT_OUTPUT: u64 = 1 << 5;
+    /// `state.delete_key`, with the `delete-key` feature.
+    pub const DELETE_KEY: u64 = 1 << 6;
+    /// `env.emit`, auxiliary outputs of store handlers.
+    pub const EMIT: u64 = 1 << 7;
+    /// `config.get`, with the `runtime-config` feature.
+    pub const CONFIG_GET: u64 = 1 << 8;
+    /// `state.set_max_bytes` and `state.set_min_bytes`, with the `lexicographic-min-max` feature.
+    pub const MIN_MAX_BYTES: u64 = 1 << 9;
+}
+
+/// The [capability] bits of the intrinsics this build of the crate can import.
+pub const CAPABILITIES: u64 = capability::GET_MANY
+    | capability::GET_LAST_INTO
+    | capability::SCAN_PREFIX
+    | capability::SET_ONCE
+    | capability::EMIT
+    | if cfg!(feature = "batched-writes") {
+        capability::WRITE_BATCH
+    } else {
+        0
+    }
+    | if cfg!(feature = "multi-writable-stores") {
+        capability::SELECT_OUTPUT
+    } else {
+        0
+    }
+    | if cfg!(feature = "delete-key") {
+        capability::DELETE_KEY
+    } else {
+        0
+    }
+    | if cfg!(feature = "runtime-config") {
+        capability::CONFIG_GET
+    } else {
+        0
+    }
+    | if cfg!(feature = "lexicographic-min-max") {
+        capability::MIN_MAX_BYTES
+    } else {
+        0
+    };
+
+/// Exported to the host so it can discover the ABI version this module was compiled with.
+#[no_mangle]
+pub extern "C" fn substreams_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Exported to the host so it can discover the intrinsics this module may import, see
+/// [CAPABILITIES].
+#[no_mangle]
+pub extern "C" fn substreams_abi_capabilities() -> u64 {
+    CAPABILITIES
+}
+
+/// Returns the ABI version this module was compiled with, same as [ABI_VERSION].
+pub fn version() -> u32 {
+    ABI_VERSION
+}
+
+/// Returns `true` if a host advertising `host_version` is able to run this module.
+pub fn is_supported(host_version: u32) -> bool {
+    host_version >= MIN_SUPPORTED_ABI_VERSION
+}
+
+/// Negotiates the ABI version to use with a host advertising `host_version`, this is the
+/// highest version both sides understand. Returns `None` if the host is too old to run
+/// this module.
+pub fn negotiate(host_version: u32) -> Option<u32> {
+    if !is_supported(host_version) {
+        return None;
+    }
+
+    Some(host_version.min(ABI_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_exports_current_version() {
+        assert_eq!(substreams_abi_version(), ABI_VERSION);
+        assert_eq!(version(), ABI_VERSION);
+    }
+
+    #[test]
+    fn it_exports_capabilities() {
+        assert_eq!(substreams_abi_capabilities(), CAPABILITIES);
+        assert_ne!(CAPABILITIES & capability::GET_MANY, 0);
+        assert_ne!(CAPABILITIES & capability::GET_LAST_INTO, 0);
+        assert_ne!(CAPABILITIES & capability::SCAN_PREFIX, 0);
+        assert_ne!(CAPABILITIES & capability::SET_ONCE, 0);
+        assert_ne!(CAPABILITIES & capability::EMIT, 0);
+        assert_eq!(
+            CAPABILITIES & capability::WRITE_BATCH != 0,
+            cfg!(feature = "batched-writes")
+        );
+        assert_eq!(
+            CAPABILITIES & capability::SELECT_OUTPUT != 0,
+            cfg!(feature = "multi-writable-stores")
+        );
+        assert_eq!(
+            CAPABILITIES & capability::DELETE_KEY != 0,
+            cfg!(feature = "delete-key")
+        );
+        assert_eq!(
+            CAPABILITIES & capability::CONFIG_GET != 0,
+            cfg!(feature = "runtime-config")
+        );
+        assert_eq!(
+            CAPABILITIES & capability::MIN_MAX_BYTES != 0,
+            cfg!(feature = "lexicographic-min-max")
+        );
+    }
+
+    #[test]
+    fn it_negotiates_version() {
+        assert_eq!(negotiate(0), None);
+        assert_eq!(negotiate(ABI_VERSION), Some(ABI_VERSION));
+        assert_eq!(negotiate(ABI_VERSION + 1), Some(ABI_VERSION));
+    }
+}
@@ -0,0 +1,197 @@
+//! Buffering of store writes, see [crate::store::batch].
+
+use std::cell::RefCell;
+
+#[cfg(target_arch = "wasm32")]
+use crate::externs;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::testing;
+
+/// A buffered store write, `value` holds the bytes recorded for the write by the `replay`
+/// feature, numeric values being formatted as decimal strings.
+struct Write {
+    op: &'static str,
+    ord: i64,
+    key: String,
+    value: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Buffer {
+    depth: usize,
+    writes: Vec<Write>,
+}
+
+thread_local! {
+    static BUFFER: RefCell<Buffer> = RefCell::new(Buffer::default());
+}
+
+/// Runs `f` buffering its store writes, they are flushed when the outermost batch returns.
+pub(crate) fn run<R, F: FnOnce() -> R>(f: F) -> R {
+    BUFFER.with(|buffer| buffer.borrow_mut().depth += 1);
+
+    let result = f();
+
+    let writes = BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.depth -= 1;
+        match buffer.depth {
+            0 => std::mem::take(&mut buffer.writes),
+            _ => Vec::new(),
+        }
+    });
+    flush(writes);
+
+    result
+}
+
+/// Buffers the write `op` if a batch is running, returns `false` if the write must be
+/// sent to the host right away. `value` is only evaluated when the write is buffered.
+pub(crate) fn push<F: FnOnce() -> Vec<u8>>(
+    op: &'static str,
+    ord: i64,
+    key: &str,
+    value: F,
+) -> bool {
+    BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        if buffer.depth == 0 {
+            return false;
+        }
+
+        buffer.writes.push(Write {
+            op,
+            ord,
+            key: key.to_string(),
+            value: value(),
+        });
+        true
+    })
+}
+
+fn flush(writes: Vec<Write>) {
+    if writes.is_empty() {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    for write in writes.iter() {
+        testing::write(write.op, &write.key, &write.value);
+    }
+
+    #[cfg(all(target_arch = "wasm32", feature = "batched-writes"))]
+    {
+        let frame = encode(&writes);
+        unsafe { externs::state::write_batch(frame.as_ptr(), frame.len() as u32) }
+    }
+
+    #[cfg(all(target_arch = "wasm32", not(feature = "batched-writes")))]
+    for write in writes.iter() {
+        unsafe { host_write(write) }
+    }
+}
+
+/// Encodes `writes` as the frame passed to `state.write_batch`, each write being laid out
+/// as `op_len: u8, op, ord: i64, key_len: u32, key, value_len: u32, value`, little endian.
+#[cfg_attr(
+    not(all(target_arch = "wasm32", feature = "batched-writes")),
+    allow(dead_code)
+)]
+fn encode(writes: &[Write]) -> Vec<u8> {
+    let size = writes
+        .iter()
+        .map(|write| 1 + write.op.len() + 8 + 4 + write.key.len() + 4 + write.value.len())
+        .sum();
+
+    let mut out = Vec::with_capacity(size);
+    for write in writes {
+        out.push(write.op.len() as u8);
+        out.extend_from_slice(write.op.as_bytes());
+        out.extend_from_slice(&write.ord.to_le_bytes());
+        out.extend_from_slice(&(write.key.len() as u32).to_le_bytes());
+        out.extend_from_slice(write.key.as_bytes());
+        out.extend_from_slice(&(write.value.len() as u32).to_le_bytes());
+        out.extend_from_slice(&write.value);
+    }
+    out
+}
+
+/// Sends `write` with its individual host call, used when the module is built without
+/// the `batched-writes` feature.
+#[cfg(all(target_arch = "wasm32", not(feature = "batched-writes")))]
+unsafe fn host_write(write: &Write) {
+    use externs::state;
+
+    let ord = write.ord;
+    let (key_ptr, key_len) = (write.key.as_ptr(), write.key.len() as u32);
+    let (value_ptr, value_len) = (write.value.as_ptr(), write.value.len() as u32);
+
+    match write.op {
+        "set" => state::set(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_if_not_exists" => {
+            state::set_if_not_exists(ord, key_ptr, key_len, value_ptr, value_len)
+        }
+        "set_once" => state::set_once(ord, key_ptr, key_len, value_ptr, value_len),
+        "append" => state::append(ord, key_ptr, key_len, value_ptr, value_len),
+        "delete_prefix" => state::delete_prefix(ord, key_ptr, key_len),
+        #[cfg(feature = "delete-key")]
+        "delete_key" => state::delete_key(ord, key_ptr, key_len),
+        "add_bigint" => state::add_bigint(ord, key_ptr, key_len, value_ptr, value_len),
+        "add_int64" => state::add_int64(ord, key_ptr, key_len, parse(write)),
+        "add_float64" => state::add_float64(ord, key_ptr, key_len, parse(write)),
+        "add_bigdecimal" => state::add_bigdecimal(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_min_int64" => state::set_min_int64(ord, key_ptr, key_len, parse(write)),
+        "set_min_bigint" => state::set_min_bigint(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_min_float64" => state::set_min_float64(ord, key_ptr, key_len, parse(write)),
+        "set_min_bigdecimal" => {
+            state::set_min_bigdecimal(ord, key_ptr, key_len, value_ptr, value_len)
+        }
+        "set_max_int64" => state::set_max_int64(ord, key_ptr, key_len, parse(write)),
+        "set_max_bigint" => state::set_max_bigint(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_max_float64" => state::set_max_float64(ord, key_ptr, key_len, parse(write)),
+        "set_max_bigdecimal" => {
+            state::set_max_bigdecimal(ord, key_ptr, key_len, value_ptr, value_len)
+        }
+        #[cfg(feature = "lexicographic-min-max")]
+        "set_max_bytes" => state::set_max_bytes(ord, key_ptr, key_len, value_ptr, value_len),
+        #[cfg(feature = "lexicographic-min-max")]
+        "set_min_bytes" => state::set_min_bytes(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_sum_bigint" => state::set_sum_bigint(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_sum_bigdecimal" => {
+            state::set_sum_bigdecimal(ord, key_ptr, key_len, value_ptr, value_len)
+        }
+        "set_sum_int64" => state::set_sum_int64(ord, key_ptr, key_len, value_ptr, value_len),
+        "set_sum_float64" => state::set_sum_float64(ord, key_ptr, key_len, value_ptr, value_len),
+        #[cfg(feature = "multi-writable-stores")]
+        "select_output" => state::select_output(parse(write)),
+        op => unreachable!("unknown batched store write {:?}", op),
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "batched-writes")))]
+fn parse<T: std::str::FromStr>(write: &Write) -> T {
+    std::str::from_utf8(&write.value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("invalid batched {} value {:?}", write.op, write.value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Write};
+
+    #[test]
+    fn it_encodes_batch_frame() {
+        let writes = vec![Write {
+            op: "set",
+            ord: 1,
+            key: "k".to_string(),
+            value: b"v".to_vec(),
+        }];
+
+        assert_eq!(
+            encode(&writes),
+            vec![3, b's', b'e', b't', 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, b'k', 1, 0, 0, 0, b'v']
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Chain agnostic block input.
+//!
+//! An [AnyBlock] handler input carries the raw block bytes along with the type URL of
+//! the block message, so a single module crate can handle blocks of multiple chains,
+//! the concrete block type being selected by the manifest.
+//!
+//! ```rust
+//! use substreams::block::AnyBlock;
+//! use substreams::errors::Error;
+//! # mod eth { pub type Block = prost_types::Timestamp; }
+//! # mod sol { pub type Block = prost_types::Duration; }
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transfers {}
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_transfers(blk: AnyBlock) -> Result<pb::Transfers, Error> {
+//!     if blk.is::<eth::Block>() {
+//!         let blk: eth::Block = blk.decode_as()?;
+//!         // extract Ethereum transfers
+//!     } else if blk.is::<sol::Block>() {
+//!         let blk: sol::Block = blk.decode_as()?;
+//!         // extract Solana transfers
+//!     }
+//!
+//!     Ok(pb::Transfers {})
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Error};
+
+/// A block of any chain, encoded as a `google.protobuf.Any` message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnyBlock {
+    #[prost(string, tag = "1")]
+    pub type_url: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: ::prost::alloc::vec::Vec<u8>,
+}
+
+impl AnyBlock {
+    pub fn new<S: Into<String>>(type_url: S, value: Vec<u8>) -> Self {
+        AnyBlock {
+            type_url: type_url.into(),
+            value,
+        }
+    }
+
+    /// Fully qualified Protobuf name of the block message, the type URL without
+    /// its host part, for example `sf.ethereum.type.v2.Block`.
+    pub fn type_name(&self) -> &str {
+        match self.type_url.rfind('/') {
+            Some(i) => &self.type_url[i + 1..],
+            None => &self.type_url,
+        }
+    }
+
+    /// Returns `true` if the block is a `T` message.
+    pub fn is<T: prost::Name>(&self) -> bool {
+        self.type_name() == T::full_name()
+    }
+
+    /// Decodes the block as a `T` message, failing if the type URL refers to
+    /// another message.
+    pub fn decode_as<T: prost::Message + prost::Name + Default>(&self) -> Result<T, Error> {
+        if !self.is::<T>() {
+            return Err(anyhow!(
+                "block is of type {}, not {}",
+                self.type_name(),
+                T::full_name()
+            ));
+        }
+
+        T::decode(self.value.as_slice())
+            .with_context(|| format!("unable to decode block as {}", T::full_name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnyBlock;
+    use prost::Message;
+    use prost_types::{Duration, Timestamp};
+
+    #[test]
+    fn it_decodes_matching_type() {
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 0,
+        };
+        let block = AnyBlock::new(
+            "type.googleapis.com/google.protobuf.Timestamp",
+            timestamp.encode_to_vec(),
+        );
+
+        assert_eq!(block.type_name(), "google.protobuf.Timestamp");
+        assert!(block.is::<Timestamp>());
+        assert!(!block.is::<Duration>());
+        assert_eq!(block.decode_as::<Timestamp>().unwrap(), timestamp);
+        assert_eq!(
+            block.decode_as::<Duration>().unwrap_err().to_string(),
+            "block is of type google.protobuf.Timestamp, not google.protobuf.Duration"
+        );
+    }
+}
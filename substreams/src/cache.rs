@@ -0,0 +1,124 @@
+//! Block-scoped memoization for Substreams handlers.
+//!
+//! A [BlockCache] is created by the handler and dropped when it returns, so cached values
+//! only live for the duration of one handler invocation (one block). It fits values derived
+//! from the block or from stores, which can change from one block to the next, unlike
+//! [crate::memoize] results that survive for the whole WASM instance:
+//!
+//! ```rust
+//! use substreams::cache::BlockCache;
+//! use substreams::store::{StoreGet, StoreGetProto};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Token { #[prost(uint32, tag = "1")] pub decimals: u32 }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Swaps { #[prost(string, repeated, tag = "1")] pub tokens: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_decimals(swaps: pb::Swaps, tokens: StoreGetProto<pb::Token>) -> pb::Swaps {
+//!     let cache = BlockCache::new();
+//!     for token in swaps.tokens.iter() {
+//!         // Only the first lookup of each token reads and decodes the store value
+//!         let decimals = tokens
+//!             .get_last_cached(&cache, token)
+//!             .map_or(18, |token| token.decimals);
+//!     }
+//!     swaps
+//! }
+//! ```
+//!
+//! The cache isn't thread-safe, WASM modules run on a single thread.
+
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A cache of values computed once per block, see the [module](crate::cache) documentation.
+///
+/// Values are cloned out of the cache, wrap large values in an `Rc` to share them.
+pub struct BlockCache<K, V> {
+    entries: RefCell<HashMap<K, V>>,
+}
+
+impl<K: Eq + Hash, V: Clone> BlockCache<K, V> {
+    pub fn new() -> Self {
+        BlockCache {
+            entries: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the value cached for `key`, computing it with `f` on the first call.
+    ///
+    /// `f` may itself use the cache, a value computed for the same key while `f` runs is
+    /// replaced by the one `f` returns.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key: K, f: F) -> V {
+        if let Some(value) = self.entries.borrow().get(&key) {
+            return value.clone();
+        }
+
+        let value = f();
+        self.entries.borrow_mut().insert(key, value.clone());
+        value
+    }
+
+    /// Returns the value cached for `key`, if any.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.borrow().get(key).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear()
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Default for BlockCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockCache;
+    use std::cell::Cell;
+
+    #[test]
+    fn it_computes_values_once() {
+        let cache = BlockCache::new();
+        let calls = Cell::new(0);
+        let compute = |value: u32| {
+            calls.set(calls.get() + 1);
+            value
+        };
+
+        assert_eq!(cache.get_or_insert_with("a".to_string(), || compute(1)), 1);
+        assert_eq!(cache.get_or_insert_with("a".to_string(), || compute(2)), 1);
+        assert_eq!(cache.get_or_insert_with("b".to_string(), || compute(3)), 3);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.len(), 2);
+
+        let nested = cache.get_or_insert_with("c".to_string(), || {
+            cache.get_or_insert_with("d".to_string(), || compute(4)) + 1
+        });
+        assert_eq!(nested, 5);
+        assert_eq!(cache.get("d"), Some(4));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
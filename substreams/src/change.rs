@@ -0,0 +1,43 @@
+use crate::store::NumericDelta;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Change {
+    Changed,
+    Increased,
+    Decreased,
+}
+
+pub struct ValueChange<I: Iterator> {
+    change: Change,
+    underlying: I,
+}
+
+impl<I> ValueChange<I>
+where
+    I: Iterator,
+    I::Item: NumericDelta,
+{
+    pub(crate) fn new(change: Change, underlying: I) -> Self {
+        Self { change, underlying }
+    }
+}
+
+impl<I> Iterator for ValueChange<I>
+where
+    I: Iterator,
+    I::Item: NumericDelta,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let change = self.change;
+        self.underlying.find(|x| {
+            let (old, new) = (x.old_value(), x.new_value());
+            match change {
+                Change::Changed => old != new,
+                Change::Increased => new > old,
+                Change::Decreased => new < old,
+            }
+        })
+    }
+}
@@ -0,0 +1,143 @@
+//! Incremental encoding of outputs made of a repeated message field, see [ChunkedOutput].
+//!
+//! **Experimental**, the API may change in minor releases.
+
+use prost::encoding::{encode_key, encode_varint, WireType};
+
+use crate::codec::Codec;
+
+/// Encodes the output of a handler one item at a time, for outputs whose message only holds
+/// a repeated message field, like `message Transfers { repeated Transfer transfers = 1; }`.
+///
+/// The bytes emitted by [finish](ChunkedOutput::finish) are identical to the encoding of the
+/// whole message, but the items don't need to be collected in a `Vec` first, only the encoded
+/// output is kept in memory:
+///
+/// ```rust
+/// use substreams::chunked::ChunkedOutput;
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Transfer { #[prost(string, tag = "1")] pub id: String }
+/// # }
+///
+/// fn map_transfers(ids: Vec<String>) {
+///     let mut output = ChunkedOutput::new().limit(64 * 1024 * 1024);
+///     for id in ids {
+///         output.push(&pb::Transfer { id });
+///     }
+///
+///     output.finish();
+/// }
+/// ```
+///
+/// The handler must not return its output as well, it is emitted by
+/// [finish](ChunkedOutput::finish).
+pub struct ChunkedOutput {
+    tag: u32,
+    max_bytes: Option<usize>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkedOutput {
+    /// Encodes the items as field `1` of the output message.
+    pub fn new() -> Self {
+        Self::with_tag(1)
+    }
+
+    /// Encodes the items as field `tag` of the output message.
+    pub fn with_tag(tag: u32) -> Self {
+        ChunkedOutput {
+            tag,
+            max_bytes: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Panics like [output_with_limit](crate::output_with_limit) as soon as the encoded
+    /// output exceeds `max_bytes`.
+    pub fn limit(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Encodes `item` at the end of the output.
+    pub fn push<M: Codec>(&mut self, item: &M) {
+        let data = crate::proto::encode(item).unwrap_or_else(|_| {
+            panic!(
+                "Unable to encode '{}' message's struct to Protobuf data",
+                std::any::type_name::<M>()
+            )
+        });
+
+        encode_key(self.tag, WireType::LengthDelimited, &mut self.buffer);
+        encode_varint(data.len() as u64, &mut self.buffer);
+        self.buffer.extend_from_slice(&data);
+
+        if let Some(max_bytes) = self.max_bytes {
+            crate::check_output_len::<M>(self.buffer.len(), max_bytes);
+        }
+    }
+
+    /// Encodes every item of `items` at the end of the output.
+    pub fn extend<M: Codec, I: IntoIterator<Item = M>>(&mut self, items: I) {
+        for item in items {
+            self.push(&item);
+        }
+    }
+
+    /// The size of the output encoded so far.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Emits the output.
+    pub fn finish(self) {
+        crate::output_raw(self.buffer)
+    }
+}
+
+impl Default for ChunkedOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "prost-codec"))]
+mod tests {
+    use super::ChunkedOutput;
+    use crate::testing;
+    use prost_types::{value::Kind, ListValue, Value};
+
+    #[test]
+    fn it_encodes_like_the_whole_message() {
+        testing::reset();
+
+        let values: Vec<Value> = (0..100)
+            .map(|i| Value {
+                kind: Some(Kind::StringValue(format!("value {}", i))),
+            })
+            .collect();
+
+        crate::output_chunked(values.clone());
+
+        assert_eq!(
+            testing::take_output::<ListValue>(),
+            Some(ListValue { values })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the limit of 10 bytes")]
+    fn it_panics_over_the_limit() {
+        let mut output = ChunkedOutput::new().limit(10);
+        for i in 0..10 {
+            output.push(&Value {
+                kind: Some(Kind::NumberValue(i as f64)),
+            });
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Access to the [Clock] of the block being processed.
+//!
+//! Handlers taking a `clock: Clock` input record it, it's then available anywhere in the
+//! module through [crate::clock] without passing it around:
+//!
+//! ```rust
+//! use substreams::clock::Clock;
+//! use substreams::store::{StoreAdd, StoreAddInt64, StoreNew};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transfers { #[prost(string, repeated, tag = "1")] pub tokens: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::store]
+//! fn store_daily_volumes(clock: Clock, transfers: pb::Transfers, volumes: StoreAddInt64) {
+//!     for token in transfers.tokens.iter() {
+//!         volumes.add(clock.number, volume_key(token), 1);
+//!     }
+//! }
+//!
+//! fn volume_key(token: &str) -> String {
+//!     let clock = substreams::clock().expect("handler takes a clock input");
+//!     format!("day:{}:{}", clock.day(), token)
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::fmt;
+
+pub use crate::pb::substreams::Clock;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 86400;
+
+#[derive(Default)]
+struct Clocks {
+    current: Option<Clock>,
+    previous: Option<Clock>,
+}
+
+thread_local! {
+    static CLOCKS: RefCell<Clocks> = RefCell::new(Clocks::default());
+}
+
+/// A `sf.substreams.v1.Clock` message, the bundled [Clock] or one generated by the user with
+/// another `prost` version (see [crate::pb]), recorded by [set].
+pub trait ToClock {
+    fn to_clock(&self) -> Clock;
+}
+
+impl ToClock for Clock {
+    fn to_clock(&self) -> Clock {
+        self.clone()
+    }
+}
+
+/// Records `clock` as the clock of the block being processed, called by the handler macros
+/// for `Clock` inputs.
+pub fn set<C: ToClock>(clock: &C) {
+    let clock = clock.to_clock();
+    CLOCKS.with(|clocks| {
+        let mut clocks = clocks.borrow_mut();
+        let is_new_block = clocks
+            .current
+            .as_ref()
+            .map_or(true, |current| current.number != clock.number);
+        if is_new_block {
+            clocks.previous = clocks.current.take();
+        }
+        clocks.current = Some(clock);
+    })
+}
+
+/// Returns the clock of the block being processed, `None` if the handler doesn't take a
+/// `Clock` input.
+pub fn current() -> Option<Clock> {
+    CLOCKS.with(|clocks| clocks.borrow().current.clone())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reset() {
+    CLOCKS.with(|clocks| *clocks.borrow_mut() = Clocks::default())
+}
+
+/// Returns the timestamp of the last recorded block before `clock`.
+fn previous_timestamp(clock: &Clock) -> Option<i64> {
+    CLOCKS.with(|clocks| {
+        let clocks = clocks.borrow();
+        [clocks.current.as_ref(), clocks.previous.as_ref()]
+            .iter()
+            .flatten()
+            .find(|previous| previous.number < clock.number)
+            .map(|previous| previous.timestamp_seconds())
+    })
+}
+
+impl Clock {
+    /// Returns the block timestamp in seconds since the Unix epoch, `0` if it's not set.
+    pub fn timestamp_seconds(&self) -> i64 {
+        self.timestamp
+            .as_ref()
+            .map_or(0, |timestamp| timestamp.seconds)
+    }
+
+    /// Returns the block timestamp in milliseconds since the Unix epoch, `0` if it's not set.
+    pub fn to_unix_millis(&self) -> i64 {
+        self.timestamp.as_ref().map_or(0, |timestamp| {
+            timestamp.seconds * 1000 + i64::from(timestamp.nanos) / 1_000_000
+        })
+    }
+
+    /// Returns the UTC date of the block as `YYYY-MM-DD`.
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days(self.day());
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    /// Returns the number and id of the block.
+    pub fn block_ref(&self) -> BlockRef {
+        BlockRef {
+            number: self.number,
+            id: self.id.clone(),
+        }
+    }
+
+    /// Returns the number of days between the Unix epoch and the block timestamp (UTC), to
+    /// key daily aggregations.
+    pub fn day(&self) -> i64 {
+        self.timestamp_seconds().div_euclid(SECONDS_PER_DAY)
+    }
+
+    /// Returns the number of hours between the Unix epoch and the block timestamp, to key
+    /// hourly aggregations.
+    pub fn hour(&self) -> i64 {
+        self.timestamp_seconds().div_euclid(SECONDS_PER_HOUR)
+    }
+
+    /// Returns whether the block is the first of its [day](Clock::day).
+    ///
+    /// The block is compared to the previous block processed by the module instance, the
+    /// first block an instance processes (at the start of a segment when running in
+    /// parallel) is always reported as the first of its day: writes depending on it must
+    /// be idempotent, like setting the opening value of a daily bucket.
+    pub fn is_first_block_of_day(&self) -> bool {
+        previous_timestamp(self).map_or(true, |previous| {
+            previous.div_euclid(SECONDS_PER_DAY) != self.day()
+        })
+    }
+
+    /// Same as [is_first_block_of_day](Clock::is_first_block_of_day) for the block's
+    /// [hour](Clock::hour).
+    pub fn is_first_block_of_hour(&self) -> bool {
+        previous_timestamp(self).map_or(true, |previous| {
+            previous.div_euclid(SECONDS_PER_HOUR) != self.hour()
+        })
+    }
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian `(year, month, day)`, see
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months starting from March, so the leap day is the last day of the year
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+/// A reference to a block, its number and id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BlockRef {
+    pub number: u64,
+    pub id: String,
+}
+
+impl BlockRef {
+    pub fn new<S: Into<String>>(number: u64, id: S) -> Self {
+        BlockRef {
+            number,
+            id: id.into(),
+        }
+    }
+}
+
+impl From<Clock> for BlockRef {
+    fn from(clock: Clock) -> Self {
+        BlockRef {
+            number: clock.number,
+            id: clock.id,
+        }
+    }
+}
+
+impl From<&Clock> for BlockRef {
+    fn from(clock: &Clock) -> Self {
+        clock.block_ref()
+    }
+}
+
+impl fmt::Display for BlockRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} ({})", self.number, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{current, reset, set, BlockRef, Clock};
+    use prost_types::Timestamp;
+
+    fn clock(number: u64, seconds: i64) -> Clock {
+        Clock {
+            id: format!("block{}", number),
+            number,
+            timestamp: Some(Timestamp { seconds, nanos: 0 }),
+        }
+    }
+
+    #[test]
+    fn it_tracks_days_across_blocks() {
+        reset();
+        assert_eq!(current(), None);
+
+        let first = clock(10, 86400 * 3 + 7200);
+        set(&first);
+        assert_eq!(current(), Some(first.clone()));
+        assert_eq!(first.day(), 3);
+        assert_eq!(first.hour(), 74);
+        assert!(first.is_first_block_of_day());
+
+        let second = clock(11, 86400 * 3 + 7212);
+        set(&second);
+        assert!(!second.is_first_block_of_day());
+        assert!(!second.is_first_block_of_hour());
+
+        set(&second);
+        assert!(!second.is_first_block_of_day());
+
+        let third = clock(12, 86400 * 4 + 1);
+        set(&third);
+        assert!(third.is_first_block_of_day());
+        assert!(third.is_first_block_of_hour());
+    }
+
+    #[test]
+    fn it_formats_timestamps() {
+        let block = Clock {
+            id: "abc".to_string(),
+            number: 7,
+            timestamp: Some(Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 250_000_000,
+            }),
+        };
+        assert_eq!(block.to_unix_millis(), 1_700_000_000_250);
+        assert_eq!(block.date_string(), "2023-11-14");
+        assert_eq!(BlockRef::from(&block), BlockRef::new(7, "abc"));
+        assert_eq!(BlockRef::from(block).to_string(), "#7 (abc)");
+
+        assert_eq!(clock(1, 951_782_400).date_string(), "2000-02-29");
+        assert_eq!(clock(1, -1).date_string(), "1969-12-31");
+        assert_eq!(Clock::default().date_string(), "1970-01-01");
+    }
+}
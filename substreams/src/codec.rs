@@ -0,0 +1,99 @@
+//! Encoding of handler inputs, outputs and store values.
+//!
+//! Handler inputs and outputs, `StoreSetProto`, `StoreGetProto` and `DeltaProto` values all go
+//! through the [Codec] trait. With the default `prost-codec` feature every `prost::Message`
+//! implements it, disabling the feature allows messages generated by another Protobuf library
+//! (or handcrafted ones) to provide their own implementation, typically to reduce the size of
+//! the WASM binary:
+//!
+//! ```rust
+//! use substreams::codec::Codec;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Counter {
+//!     value: u64,
+//! }
+//!
+//! impl Codec for Counter {
+//!     type EncodeError = std::convert::Infallible;
+//!     type DecodeError = std::array::TryFromSliceError;
+//!
+//!     fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError> {
+//!         Ok(self.value.to_le_bytes().to_vec())
+//!     }
+//!
+//!     fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+//!         use std::convert::TryInto;
+//!
+//!         Ok(Counter { value: u64::from_le_bytes(bytes.try_into()?) })
+//!     }
+//! }
+//!
+//! let bytes = Counter { value: 3 }.to_bytes().unwrap();
+//! assert_eq!(Counter::from_bytes(&bytes).unwrap(), Counter { value: 3 });
+//! ```
+//!
+//! Without the `prost-codec` feature, the messages of this crate used as handler inputs or
+//! outputs still implement [Codec], other `prost` messages can opt in with [crate::prost_codec].
+
+pub use substreams_core::codec::{prost_decode, prost_encode, Codec};
+
+/// Implements [Codec] with `prost` for the listed messages, for use when the `prost-codec`
+/// feature is disabled.
+///
+/// ```rust,ignore
+/// substreams::prost_codec!(pb::Pool, pb::Pools);
+/// ```
+#[macro_export]
+macro_rules! prost_codec {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl $crate::codec::Codec for $t {
+                type EncodeError = ::prost::EncodeError;
+                type DecodeError = ::prost::DecodeError;
+
+                fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError> {
+                    $crate::codec::prost_encode(self)
+                }
+
+                fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+                    $crate::codec::prost_decode(bytes)
+                }
+
+                fn encoded_len(&self) -> Option<usize> {
+                    Some(::prost::Message::encoded_len(self))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(not(feature = "prost-codec"))]
+prost_codec!(crate::block::AnyBlock, crate::pb::substreams::StoreDeltas);
+
+#[cfg(all(not(feature = "prost-codec"), feature = "pb-bundled"))]
+prost_codec!(
+    crate::entity::EntityChanges,
+    crate::index::Keys,
+    crate::sink::kv::KvOperations,
+    crate::telemetry::Telemetry,
+);
+
+#[cfg(all(test, feature = "prost-codec"))]
+mod tests {
+    use super::Codec;
+    use prost_types::Timestamp;
+
+    #[test]
+    fn it_encodes_prost_messages() {
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 2,
+        };
+
+        let bytes = timestamp.to_bytes().unwrap();
+        assert_eq!(bytes, prost::Message::encode_to_vec(&timestamp));
+        assert_eq!(Timestamp::from_bytes(&bytes).unwrap(), timestamp);
+        assert!(Timestamp::from_bytes(&[0xff]).is_err());
+    }
+}
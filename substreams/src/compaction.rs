@@ -0,0 +1,212 @@
+//! Squashes store deltas into minimal change sets.
+//!
+//! Sink-facing modules usually receive many deltas per key within a block and need to emit a
+//! single create, update or delete per entity. [Compactor] squashes one or more delta streams
+//! per key, dropping keys that were created then deleted within the block as well as updates
+//! that ended up restoring the original value.
+//!
+//! ```rust
+//! use substreams::compaction::{Compactor, ChangeOperation};
+//! use substreams::store::{DeltaProto, Deltas};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pool { #[prost(string, tag = "1")] pub name: String }
+//! # }
+//!
+//! fn db_out(pools: Deltas<DeltaProto<pb::Pool>>) {
+//!     for change in Compactor::from_deltas(pools).finish() {
+//!         match change.operation {
+//!             ChangeOperation::Create => { /* insert change.new_value */ }
+//!             ChangeOperation::Update => { /* update from change.old_value to change.new_value */ }
+//!             ChangeOperation::Delete => { /* delete change.key */ }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::codec::Codec;
+use crate::store::{DeltaOperation, DeltaProto, Deltas};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// The net change of a key over all the deltas seen for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<T> {
+    pub operation: ChangeOperation,
+    /// Ordinal of the last delta seen for the key.
+    pub ordinal: u64,
+    pub key: String,
+    /// Value before the first delta, `None` for [ChangeOperation::Create].
+    pub old_value: Option<T>,
+    /// Value after the last delta, `None` for [ChangeOperation::Delete].
+    pub new_value: Option<T>,
+}
+
+struct Entry<T> {
+    ordinal: u64,
+    key: String,
+    old_value: Option<T>,
+    new_value: Option<T>,
+}
+
+/// Accumulates deltas and squashes them per key, keys are emitted in the order they were
+/// first seen.
+pub struct Compactor<T> {
+    entries: Vec<Entry<T>>,
+    positions: HashMap<String, usize>,
+}
+
+impl<T: Codec + PartialEq> Compactor<T> {
+    pub fn new() -> Self {
+        Compactor {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn from_deltas(deltas: Deltas<DeltaProto<T>>) -> Self {
+        let mut compactor = Self::new();
+        compactor.extend(deltas);
+        compactor
+    }
+
+    /// Adds a delta, deltas of a key must be pushed in ordinal order.
+    pub fn push(&mut self, delta: DeltaProto<T>) {
+        let new_value = match delta.operation {
+            DeltaOperation::Delete => None,
+            _ => Some(delta.new_value),
+        };
+
+        if let Some(position) = self.positions.get(&delta.key) {
+            let entry = &mut self.entries[*position];
+            entry.ordinal = delta.ordinal;
+            entry.new_value = new_value;
+            return;
+        }
+
+        let old_value = match delta.operation {
+            DeltaOperation::Create => None,
+            _ => Some(delta.old_value),
+        };
+
+        self.positions.insert(delta.key.clone(), self.entries.len());
+        self.entries.push(Entry {
+            ordinal: delta.ordinal,
+            key: delta.key,
+            old_value,
+            new_value,
+        });
+    }
+
+    /// Adds all deltas of a stream, can be called once per stream to merge multiple stores.
+    pub fn extend<I: IntoIterator<Item = DeltaProto<T>>>(&mut self, deltas: I) {
+        for delta in deltas {
+            self.push(delta);
+        }
+    }
+
+    /// Returns the minimal set of changes, no-op keys are omitted.
+    pub fn finish(self) -> Vec<Change<T>> {
+        self.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let operation = match (&entry.old_value, &entry.new_value) {
+                    (None, None) => return None,
+                    (None, Some(_)) => ChangeOperation::Create,
+                    (Some(_), None) => ChangeOperation::Delete,
+                    (Some(old), Some(new)) if old == new => return None,
+                    (Some(_), Some(_)) => ChangeOperation::Update,
+                };
+
+                Some(Change {
+                    operation,
+                    ordinal: entry.ordinal,
+                    key: entry.key,
+                    old_value: entry.old_value,
+                    new_value: entry.new_value,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<T: Codec + PartialEq> Default for Compactor<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeOperation, Compactor};
+    use crate::pb::substreams::store_delta::Operation;
+    use crate::store::DeltaProto;
+    use prost_types::Timestamp;
+
+    fn delta(
+        operation: Operation,
+        ordinal: u64,
+        key: &str,
+        old: i64,
+        new: i64,
+    ) -> DeltaProto<Timestamp> {
+        DeltaProto {
+            operation: operation.into(),
+            ordinal,
+            key: key.to_string(),
+            old_value: Timestamp {
+                seconds: old,
+                nanos: 0,
+            },
+            new_value: Timestamp {
+                seconds: new,
+                nanos: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn it_squashes_deltas_per_key() {
+        let mut compactor = Compactor::new();
+        compactor.extend(vec![
+            delta(Operation::Create, 1, "a", 0, 1),
+            delta(Operation::Update, 2, "b", 5, 6),
+            delta(Operation::Update, 3, "a", 1, 2),
+            delta(Operation::Create, 4, "c", 0, 1),
+            delta(Operation::Delete, 5, "c", 1, 0),
+            delta(Operation::Update, 6, "b", 6, 5),
+            delta(Operation::Delete, 7, "d", 3, 0),
+            delta(Operation::Update, 8, "e", 1, 2),
+        ]);
+
+        let changes = compactor.finish();
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.operation,
+                    c.ordinal,
+                    c.key.as_str(),
+                    c.old_value.as_ref().map(|v| v.seconds),
+                    c.new_value.as_ref().map(|v| v.seconds),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (ChangeOperation::Create, 3, "a", None, Some(2)),
+                (ChangeOperation::Delete, 7, "d", Some(3), None),
+                (ChangeOperation::Update, 8, "e", Some(1), Some(2)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Component model exports of the handlers, available with the `component` feature.
+//!
+//! Each handler keeps its classic export and gets a second one named after it in kebab-case
+//! (`map_transfers` is exported as `map-transfers`), callable as the WIT function
+//! `map-transfers: func(blk: list<u8>) -> list<u8>`. Inputs are the same encoded messages
+//! as in the classic ABI, each store input and output is a `u32` store index, and the
+//! output is returned as a `list<u8>` instead of going through the `env.output` import
+//! (store handlers return an empty list). Both exports run the same generated decoding and
+//! handler body, one module can be deployed to Substreams and wrapped as a WASI preview2
+//! component for local simulation without `wasm-bindgen`:
+//!
+//! ```text
+//! cargo build --target wasm32-wasip2 --features substreams/component,substreams/wasi
+//! ```
+//!
+//! The module also exports `cabi_realloc`, used by the component host to allocate inputs,
+//! and `cabi_post_<export>` releasing the returned output. Store and log host calls keep
+//! their classic imports, the component host provides them. Handlers with more than 16 raw
+//! parameters (two per input, one per store) can't be exported and fail to compile.
+
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static OUTPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static RETURNED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static RETURN_AREA: Cell<[u32; 2]> = const { Cell::new([0, 0]) };
+}
+
+/// Captures the handler output, replacing the `env.output` import.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes.
+pub unsafe fn output(ptr: *const u8, len: u32) {
+    let data = std::slice::from_raw_parts(ptr, len as usize);
+    OUTPUT.with(|output| *output.borrow_mut() = data.to_vec());
+}
+
+/// Moves the captured output to the return area read by the component host, as the
+/// `(pointer, length)` pair of the returned `list<u8>`, and returns the area's address.
+#[doc(hidden)]
+pub fn return_output() -> *const u8 {
+    let output = OUTPUT.with(|output| output.take());
+    let area = [output.as_ptr() as u32, output.len() as u32];
+    RETURNED.with(|returned| *returned.borrow_mut() = output);
+    RETURN_AREA.with(|cell| {
+        cell.set(area);
+        cell.as_ptr() as *const u8
+    })
+}
+
+/// Releases the output returned by the last call, once the host copied it.
+#[doc(hidden)]
+pub fn post_return() {
+    RETURNED.with(|returned| *returned.borrow_mut() = Vec::new());
+}
+
+/// Allocator used by the component host to pass inputs, following the canonical ABI.
+///
+/// # Safety
+///
+/// `old_ptr` must have been allocated by this function with `old_len` and `align`, or be
+/// null with `old_len` equal to `0`.
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub unsafe extern "C" fn cabi_realloc(
+    old_ptr: *mut u8,
+    old_len: usize,
+    align: usize,
+    new_len: usize,
+) -> *mut u8 {
+    if new_len == 0 {
+        return align as *mut u8;
+    }
+
+    crate::metrics::on_alloc(new_len);
+    let layout =
+        std::alloc::Layout::from_size_align(new_len, align).expect("invalid allocation layout");
+    let ptr = match old_len {
+        0 => std::alloc::alloc(layout),
+        _ => std::alloc::realloc(
+            old_ptr,
+            std::alloc::Layout::from_size_align_unchecked(old_len, align),
+            new_len,
+        ),
+    };
+    if ptr.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    ptr
+}
+
+/// Exports a handler as a component model function, called by the handler macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __component_export {
+    (unsupported $msg:literal) => {
+        compile_error!($msg);
+    };
+    ($handler:ident, $wrapper:ident, $export:literal, $post_return:literal, ($($arg:ident: $ty:ty),*)) => {
+        #[cfg(target_arch = "wasm32")]
+        #[doc(hidden)]
+        pub mod $wrapper {
+            #[export_name = $export]
+            pub extern "C" fn call($($arg: $ty),*) -> *const u8 {
+                super::$handler($($arg),*);
+                $crate::component::return_output()
+            }
+
+            #[export_name = $post_return]
+            pub extern "C" fn post_return(_area: *const u8) {
+                $crate::component::post_return()
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output, post_return, return_output};
+
+    #[test]
+    fn it_returns_captured_output() {
+        let data = b"output".to_vec();
+        unsafe { output(data.as_ptr(), data.len() as u32) };
+
+        let area = unsafe { *(return_output() as *const [u32; 2]) };
+        assert_eq!(area[1], 6);
+        post_return();
+
+        let area = unsafe { *(return_output() as *const [u32; 2]) };
+        assert_eq!(area[1], 0);
+    }
+}
@@ -0,0 +1,166 @@
+//! Logical context attached to handler panics.
+//!
+//! The handler macros record the name of the running handler, and code can open named
+//! spans forming a short logical backtrace. When the handler panics, the panic hook
+//! registered by [crate::register_panic_hook] appends both to the message surfaced by
+//! the server:
+//!
+//! ```rust
+//! use substreams::context;
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pools { #[prost(string, repeated, tag = "1")] pub addresses: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_prices(pools: pb::Pools) -> pb::Pools {
+//!     for address in pools.addresses.iter() {
+//!         let _span = context::span(format!("pool {}", address));
+//!         compute_price(address);
+//!     }
+//!
+//!     pools
+//! }
+//!
+//! fn compute_price(address: &str) {
+//!     context::in_span("compute_price", || {
+//!         // A panic here is reported as:
+//!         //   <message>
+//!         //   handler: map_prices
+//!         //   spans: pool 0x... > compute_price
+//!     })
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+/// Number of innermost spans included in panic messages.
+pub const MAX_REPORTED_SPANS: usize = 16;
+
+#[derive(Default)]
+struct Context {
+    handler: Option<&'static str>,
+    spans: Vec<Cow<'static, str>>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+/// Records `name` as the running handler and clears the spans, called by the handler
+/// macros before running the handler.
+pub fn set_handler(name: &'static str) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.handler = Some(name);
+        context.spans.clear();
+    })
+}
+
+/// Returns the name of the running handler.
+pub fn handler() -> Option<&'static str> {
+    CONTEXT.with(|context| context.borrow().handler)
+}
+
+/// Opens the span `name`, it's closed when the returned guard is dropped.
+pub fn span<S: Into<Cow<'static, str>>>(name: S) -> SpanGuard {
+    CONTEXT.with(|context| context.borrow_mut().spans.push(name.into()));
+
+    SpanGuard { _private: () }
+}
+
+/// Runs `f` within the span `name`.
+pub fn in_span<S: Into<Cow<'static, str>>, R, F: FnOnce() -> R>(name: S, f: F) -> R {
+    let _span = span(name);
+    f()
+}
+
+/// Returns the open spans, outermost first.
+pub fn spans() -> Vec<String> {
+    CONTEXT.with(|context| {
+        context
+            .borrow()
+            .spans
+            .iter()
+            .map(|span| span.to_string())
+            .collect()
+    })
+}
+
+#[must_use = "the span is closed when the guard is dropped"]
+pub struct SpanGuard {
+    _private: (),
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| {
+            context.borrow_mut().spans.pop();
+        })
+    }
+}
+
+/// Appends the running handler and the innermost spans to a panic message.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub(crate) fn panic_message(msg: &str) -> Cow<'_, str> {
+    CONTEXT.with(|context| {
+        let context = context.borrow();
+        if context.handler.is_none() && context.spans.is_empty() {
+            return Cow::Borrowed(msg);
+        }
+
+        let mut out = msg.to_string();
+        if let Some(handler) = context.handler {
+            out.push_str("\nhandler: ");
+            out.push_str(handler);
+        }
+
+        if !context.spans.is_empty() {
+            let skipped = context.spans.len().saturating_sub(MAX_REPORTED_SPANS);
+            let spans: Vec<&str> = context.spans[skipped..]
+                .iter()
+                .map(|span| span.as_ref())
+                .collect();
+
+            out.push_str("\nspans: ");
+            if skipped > 0 {
+                out.push_str("... > ");
+            }
+            out.push_str(&spans.join(" > "));
+        }
+
+        Cow::Owned(out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{in_span, panic_message, set_handler, span, spans};
+
+    #[test]
+    fn it_enriches_panic_message() {
+        set_handler("map_prices");
+        assert_eq!(panic_message("boom"), "boom\nhandler: map_prices");
+
+        let _outer = span("pool 0xabc");
+        in_span("compute_price", || {
+            assert_eq!(spans(), vec!["pool 0xabc", "compute_price"]);
+            assert_eq!(
+                panic_message("boom"),
+                "boom\nhandler: map_prices\nspans: pool 0xabc > compute_price"
+            );
+        });
+
+        assert_eq!(spans(), vec!["pool 0xabc"]);
+    }
+
+    #[test]
+    fn it_reports_innermost_spans() {
+        set_handler("map_deep");
+        let _spans: Vec<_> = (0..20).map(|i| span(format!("s{}", i))).collect();
+
+        let message = panic_message("boom");
+        assert!(message.ends_with("spans: ... > s4 > s5 > s6 > s7 > s8 > s9 > s10 > s11 > s12 > s13 > s14 > s15 > s16 > s17 > s18 > s19"));
+    }
+}
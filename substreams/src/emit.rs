@@ -0,0 +1,148 @@
+//! Auxiliary output of store handlers, see [emit()].
+//!
+//! A store handler declared with `emit = "<type>"` can send one message of that type per
+//! block with [emit()], alongside its store writes, for example a summary consumed by
+//! downstream modules without a second pass over the block:
+//!
+//! ```rust
+//! use substreams::store::{StoreAdd, StoreAddInt64, StoreNew};
+//! # mod eth { pub type Block = prost_types::Timestamp; }
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Summary { #[prost(uint64, tag = "1")] pub transfers: u64 }
+//! # }
+//!
+//! #[substreams::handlers::store(emit = "pb::Summary")]
+//! fn store_transfers(blk: eth::Block, store: StoreAddInt64) {
+//!     store.add(0, "transfers", 2);
+//!
+//!     substreams::emit(pb::Summary { transfers: 2 });
+//! }
+//! ```
+//!
+//! The message is sent through the `env.emit` host call once the handler returns, which
+//! requires a server supporting auxiliary outputs of store modules. Handlers not calling
+//! [emit()] emit nothing.
+//!
+//! The attribute takes the type as a string since attribute arguments must be literals.
+
+use std::cell::RefCell;
+
+use crate::codec::Codec;
+
+#[derive(Default)]
+struct State {
+    /// Type declared by the running handler, `None` outside of `emit` handlers.
+    declared: Option<&'static str>,
+    message: Option<Vec<u8>>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Emits `msg` as the auxiliary output of the running store handler.
+///
+/// # Panics
+///
+/// Panics if the handler isn't declared with `emit`, if `M` isn't the declared type or if a
+/// message was already emitted for this block.
+pub fn emit<M: Codec>(msg: M) {
+    let type_name = std::any::type_name::<M>();
+    let data = crate::proto::encode(&msg).unwrap_or_else(|_| {
+        panic!(
+            "Unable to encode '{}' message's struct to Protobuf data",
+            type_name
+        )
+    });
+
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        match state.declared {
+            None => panic!(
+                "substreams::emit called outside of a store handler declared with `emit = \"<type>\"`"
+            ),
+            Some(declared) if declared != type_name => panic!(
+                "substreams::emit called with '{}' but the handler declares `emit = \"{}\"`",
+                type_name, declared
+            ),
+            Some(_) if state.message.is_some() => {
+                panic!("substreams::emit can only be called once per block")
+            }
+            Some(_) => state.message = Some(data),
+        }
+    })
+}
+
+/// Called by the handler macros before running an `emit` handler.
+#[doc(hidden)]
+pub fn begin<M: Codec>() {
+    STATE.with(|state| {
+        *state.borrow_mut() = State {
+            declared: Some(std::any::type_name::<M>()),
+            message: None,
+        }
+    })
+}
+
+/// Called by the handler macros once an `emit` handler returned, sends the emitted message.
+#[doc(hidden)]
+pub fn finish() {
+    let message = STATE.with(|state| std::mem::take(&mut *state.borrow_mut()).message);
+    let data = match message {
+        Some(data) => data,
+        None => return,
+    };
+
+    #[cfg(feature = "replay")]
+    crate::replay::write("emit", 0, "", &data);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    crate::testing::emit(data);
+
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        crate::externs::emit(data.as_ptr(), data.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{begin, emit, finish};
+    use crate::testing;
+    use prost_types::Timestamp;
+
+    #[test]
+    fn it_emits_the_declared_message() {
+        testing::reset();
+
+        begin::<Timestamp>();
+        finish();
+        assert_eq!(testing::take_emitted::<Timestamp>(), None);
+
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 0,
+        };
+        begin::<Timestamp>();
+        emit(timestamp);
+        finish();
+        assert_eq!(testing::take_emitted(), Some(timestamp));
+        assert!(testing::take_outputs().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "can only be called once per block")]
+    fn it_rejects_a_second_message() {
+        begin::<Timestamp>();
+        emit(Timestamp::default());
+        emit(Timestamp::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of a store handler declared with `emit")]
+    fn it_rejects_undeclared_messages() {
+        finish();
+        emit(Timestamp::default());
+    }
+}
@@ -0,0 +1,306 @@
+//! Typed builder for `sf.substreams.sink.entity.v1.EntityChanges`, the output of
+//! `graph_out` modules consumed by entity sinks like `graph-node`.
+//!
+//! Changes are keyed by entity type and id: creating, updating or deleting the same
+//! entity more than once in a block yields a single [EntityChange] whose fields are
+//! merged, an entity created then updated in the same block stays a creation. Changes
+//! are emitted in the order their entity was first touched, which is also recorded as
+//! their ordinal:
+//!
+//! ```rust
+//! use substreams::entity::{EntityChanges, EntityChangesBuilder};
+//! use substreams::scalar::{BigDecimal, BigInt};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pool { #[prost(string, tag = "1")] pub address: String }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pools { #[prost(message, repeated, tag = "1")] pub pools: Vec<Pool> }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn graph_out(pools: pb::Pools) -> EntityChanges {
+//!     let mut changes = EntityChangesBuilder::new();
+//!     for pool in pools.pools {
+//!         changes
+//!             .create("Pool", &pool.address)
+//!             .set("liquidity", BigInt::zero())
+//!             .set("price", BigDecimal::zero())
+//!             .set("tokens", vec!["0xa0b8", "0xc02a"]);
+//!     }
+//!
+//!     changes.build()
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+pub use crate::pb::sf::substreams::sink::entity::v1::{
+    entity_change::Operation, value::Typed, Array, EntityChange, EntityChanges, Field, Value,
+};
+use crate::scalar::{BigDecimal, BigInt};
+
+#[derive(Debug, Default)]
+pub struct EntityChangesBuilder {
+    changes: Vec<EntityChange>,
+    positions: HashMap<(String, String), usize>,
+}
+
+impl EntityChangesBuilder {
+    pub fn new() -> Self {
+        EntityChangesBuilder::default()
+    }
+
+    /// Creates the entity, fields set by earlier changes of the same entity are kept.
+    pub fn create<E: AsRef<str>, I: AsRef<str>>(&mut self, entity: E, id: I) -> &mut EntityChange {
+        self.change(entity.as_ref(), id.as_ref(), Operation::Create)
+    }
+
+    /// Updates the entity, it remains a creation if it was created by this builder.
+    pub fn update<E: AsRef<str>, I: AsRef<str>>(&mut self, entity: E, id: I) -> &mut EntityChange {
+        self.change(entity.as_ref(), id.as_ref(), Operation::Update)
+    }
+
+    /// Deletes the entity, dropping the fields set by earlier changes of the same entity.
+    pub fn delete<E: AsRef<str>, I: AsRef<str>>(&mut self, entity: E, id: I) {
+        self.change(entity.as_ref(), id.as_ref(), Operation::Delete)
+            .fields
+            .clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn build(self) -> EntityChanges {
+        EntityChanges {
+            entity_changes: self.changes,
+        }
+    }
+
+    #[allow(deprecated)]
+    fn change(&mut self, entity: &str, id: &str, operation: Operation) -> &mut EntityChange {
+        let next = self.changes.len();
+        let position = *self
+            .positions
+            .entry((entity.to_string(), id.to_string()))
+            .or_insert(next);
+
+        if position == next {
+            self.changes.push(EntityChange {
+                entity: entity.to_string(),
+                id: id.to_string(),
+                ordinal: next as u64,
+                operation: operation as i32,
+                fields: vec![],
+            });
+        }
+
+        let change = &mut self.changes[position];
+        if !(change.operation() == Operation::Create && operation == Operation::Update) {
+            change.set_operation(operation);
+        }
+        change
+    }
+}
+
+impl From<EntityChangesBuilder> for EntityChanges {
+    fn from(builder: EntityChangesBuilder) -> Self {
+        builder.build()
+    }
+}
+
+impl EntityChange {
+    /// Sets field `name` to `value`, replacing the value previously set for that field.
+    pub fn set<N: AsRef<str>, V: ToValue>(&mut self, name: N, value: V) -> &mut Self {
+        let value = Some(value.to_value());
+        let name = name.as_ref();
+
+        match self.fields.iter_mut().find(|field| field.name == name) {
+            Some(field) => field.new_value = value,
+            None => self.fields.push(Field {
+                name: name.to_string(),
+                new_value: value,
+                old_value: None,
+            }),
+        }
+        self
+    }
+}
+
+/// Conversion of a field value to its entity [Value]. Byte values are base64 encoded and
+/// `Vec<T>` values become arrays.
+pub trait ToValue {
+    fn to_value(self) -> Value;
+}
+
+fn typed(typed: Typed) -> Value {
+    Value { typed: Some(typed) }
+}
+
+impl ToValue for Value {
+    fn to_value(self) -> Value {
+        self
+    }
+}
+
+impl ToValue for i32 {
+    fn to_value(self) -> Value {
+        typed(Typed::Int32(self))
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(self) -> Value {
+        typed(Typed::Bool(self))
+    }
+}
+
+impl ToValue for &str {
+    fn to_value(self) -> Value {
+        typed(Typed::String(self.to_string()))
+    }
+}
+
+impl ToValue for String {
+    fn to_value(self) -> Value {
+        typed(Typed::String(self))
+    }
+}
+
+impl ToValue for &String {
+    fn to_value(self) -> Value {
+        typed(Typed::String(self.clone()))
+    }
+}
+
+impl ToValue for BigInt {
+    fn to_value(self) -> Value {
+        typed(Typed::Bigint(self.to_string()))
+    }
+}
+
+impl ToValue for &BigInt {
+    fn to_value(self) -> Value {
+        typed(Typed::Bigint(self.to_string()))
+    }
+}
+
+impl ToValue for BigDecimal {
+    fn to_value(self) -> Value {
+        typed(Typed::Bigdecimal(self.to_string()))
+    }
+}
+
+impl ToValue for &BigDecimal {
+    fn to_value(self) -> Value {
+        typed(Typed::Bigdecimal(self.to_string()))
+    }
+}
+
+impl ToValue for &[u8] {
+    fn to_value(self) -> Value {
+        typed(Typed::Bytes(encode_base64(self)))
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(self) -> Value {
+        typed(Typed::Bytes(encode_base64(&self)))
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(self) -> Value {
+        typed(Typed::Array(Array {
+            value: self.into_iter().map(ToValue::to_value).collect(),
+        }))
+    }
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding, the encoding entity sinks expect for bytes.
+fn encode_base64(input: &[u8]) -> String {
+    let mut out = String::with_capacity((input.len() + 2) / 3 * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_CHARS[(n >> (18 - 6 * i)) & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_base64, EntityChangesBuilder, Operation, ToValue, Typed};
+    use crate::scalar::BigInt;
+
+    #[test]
+    #[allow(deprecated)]
+    fn it_merges_changes_per_entity() {
+        let mut builder = EntityChangesBuilder::new();
+        builder.create("Pool", "a").set("count", 1).set("name", "A");
+        builder.update("Token", "t").set("count", 2);
+        builder.update("Pool", "a").set("count", 3);
+        builder.create("Pool", "b").set("count", 4);
+        builder.delete("Pool", "b");
+
+        let changes = builder.build().entity_changes;
+        assert_eq!(changes.len(), 3);
+
+        assert_eq!(changes[0].operation(), Operation::Create);
+        assert_eq!(changes[0].ordinal, 0);
+        assert_eq!(changes[0].fields.len(), 2);
+        assert_eq!(
+            changes[0].fields[0].new_value.clone().unwrap().typed,
+            Some(Typed::Int32(3))
+        );
+
+        assert_eq!(changes[1].operation(), Operation::Update);
+        assert_eq!(changes[1].ordinal, 1);
+
+        assert_eq!(changes[2].operation(), Operation::Delete);
+        assert!(changes[2].fields.is_empty());
+    }
+
+    #[test]
+    fn it_converts_typed_values() {
+        assert_eq!(
+            BigInt::from(12).to_value().typed,
+            Some(Typed::Bigint("12".to_string()))
+        );
+        assert_eq!(
+            b"hi".to_vec().to_value().typed,
+            Some(Typed::Bytes("aGk=".to_string()))
+        );
+
+        match vec!["a", "b"].to_value().typed {
+            Some(Typed::Array(array)) => assert_eq!(array.value.len(), 2),
+            other => panic!("expected array, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_encodes_base64() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -38,15 +38,174 @@
 //!     unimplemented!("do something");
 //! }
 //!```
+//!
+//! ## Classified errors
+//!
+//! An [Error] returned by a handler aborts the request without telling the backend whether
+//! retrying could help. Returning a [SubstreamsError] (directly or wrapped in an [Error])
+//! classifies the failure, its kind is encoded as a prefix of the panic message, see
+//! [SubstreamsError::to_payload]:
+//!
+//! ```rust
+//! # pub type Block = ();
+//! # mod pb { pub type Custom = (); };
+//! use substreams::errors::SubstreamsError;
+//!
+//! fn map_handler(params: String, block: Block) -> Result<pb::Custom, SubstreamsError> {
+//!     if params.is_empty() {
+//!         return Err(SubstreamsError::Fatal("missing params".to_string()));
+//!     }
+//!
+//!     let address = substreams::Hex::decode(&params)?;
+//!
+//!     unimplemented!("do something");
+//! }
+//!```
+
+use std::any::Any;
+use std::fmt::{self, Debug, Display};
 
 /// Error proxy for Substreams, simply a type alias to [anyhow::Error].
 ///
 /// See module [crate::errors] level documentation for more information.
 pub type Error = anyhow::Error;
 
+const PAYLOAD_PREFIX: &str = "[substreams:";
+
+/// Error classified by how the backend should handle it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstreamsError {
+    /// The same input always fails the same way (invalid data, bug in the module), the
+    /// request fails without retrying. Unclassified errors converted from [Error] are
+    /// deterministic.
+    Deterministic(String),
+    /// The failure is transient, the backend may retry the block.
+    Retryable(String),
+    /// The module can't run at all (invalid params, missing configuration), the request
+    /// fails without retrying and without processing further blocks.
+    Fatal(String),
+}
+
+impl SubstreamsError {
+    /// Returns the kind of the error, as written in the payload prefix.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SubstreamsError::Deterministic(_) => "deterministic",
+            SubstreamsError::Retryable(_) => "retryable",
+            SubstreamsError::Fatal(_) => "fatal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            SubstreamsError::Deterministic(msg)
+            | SubstreamsError::Retryable(msg)
+            | SubstreamsError::Fatal(msg) => msg,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SubstreamsError::Retryable(_))
+    }
+
+    /// Encodes the error as a panic payload, `[substreams:<kind>] <message>`.
+    pub fn to_payload(&self) -> String {
+        format!("{}{}] {}", PAYLOAD_PREFIX, self.kind(), self.message())
+    }
+
+    /// Decodes a panic payload produced by [to_payload](SubstreamsError::to_payload), returns
+    /// `None` for unclassified payloads. Context appended to the message by the panic hook is
+    /// kept in the message.
+    pub fn from_payload(payload: &str) -> Option<SubstreamsError> {
+        let rest = payload.strip_prefix(PAYLOAD_PREFIX)?;
+        let (kind, msg) = rest.split_once("] ")?;
+        let msg = msg.to_string();
+
+        match kind {
+            "deterministic" => Some(SubstreamsError::Deterministic(msg)),
+            "retryable" => Some(SubstreamsError::Retryable(msg)),
+            "fatal" => Some(SubstreamsError::Fatal(msg)),
+            _ => None,
+        }
+    }
+
+    fn with_message(&self, msg: String) -> SubstreamsError {
+        match self {
+            SubstreamsError::Deterministic(_) => SubstreamsError::Deterministic(msg),
+            SubstreamsError::Retryable(_) => SubstreamsError::Retryable(msg),
+            SubstreamsError::Fatal(_) => SubstreamsError::Fatal(msg),
+        }
+    }
+}
+
+impl Display for SubstreamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for SubstreamsError {}
+
+/// Keeps the kind of a [SubstreamsError] wrapped in the error, with the context attached to
+/// it, other errors are deterministic.
+impl From<Error> for SubstreamsError {
+    fn from(err: Error) -> Self {
+        let msg = format!("{:?}", err);
+        match err.downcast_ref::<SubstreamsError>() {
+            Some(classified) if classified.message() == msg => classified.clone(),
+            Some(classified) => classified.with_message(msg),
+            None => SubstreamsError::Deterministic(msg),
+        }
+    }
+}
+
+macro_rules! impl_from_error {
+    ($($err:ty),+ $(,)?) => {
+        $(
+            impl From<$err> for SubstreamsError {
+                fn from(err: $err) -> Self {
+                    SubstreamsError::from(Error::from(err))
+                }
+            }
+        )+
+    };
+}
+
+impl_from_error!(
+    std::io::Error,
+    std::fmt::Error,
+    std::num::ParseIntError,
+    std::num::ParseFloatError,
+    std::string::FromUtf8Error,
+    std::str::Utf8Error,
+    hex::FromHexError,
+    prost::DecodeError,
+    prost::EncodeError,
+);
+
+/// Returns the panic message of an error returned by a handler, used by the handler macros.
+///
+/// [SubstreamsError], directly or wrapped in an [Error], are encoded with their
+/// [payload](SubstreamsError::to_payload), other errors are debug formatted.
+pub fn panic_payload<E: Debug + Any>(err: &E) -> String {
+    let any = err as &dyn Any;
+    if let Some(err) = any.downcast_ref::<SubstreamsError>() {
+        return err.to_payload();
+    }
+
+    if let Some(err) = any.downcast_ref::<Error>() {
+        if let Some(classified) = err.downcast_ref::<SubstreamsError>() {
+            return classified.with_message(format!("{:?}", err)).to_payload();
+        }
+    }
+
+    format!("{:?}", err)
+}
+
 #[cfg(test)]
 mod test {
-    use super::Error;
+    use super::{panic_payload, Error, SubstreamsError};
+    use anyhow::Context;
 
     #[test]
     fn test_from_std_error() {
@@ -63,4 +222,35 @@ mod test {
 
         assert_eq!(format!("{:?}", converted), "source");
     }
+
+    #[test]
+    fn test_panic_payload_classifies_errors() {
+        let fatal = SubstreamsError::Fatal("missing params".to_string());
+        assert_eq!(panic_payload(&fatal), "[substreams:fatal] missing params");
+        assert_eq!(
+            SubstreamsError::from_payload("[substreams:fatal] missing params\nhandler: map_pools"),
+            Some(SubstreamsError::Fatal(
+                "missing params\nhandler: map_pools".to_string()
+            ))
+        );
+
+        let wrapped: Result<(), Error> =
+            Err(SubstreamsError::Retryable("rpc timeout".to_string())).context("fetching pool");
+        let payload = panic_payload(&wrapped.unwrap_err());
+        assert_eq!(
+            SubstreamsError::from_payload(&payload),
+            Some(SubstreamsError::Retryable(
+                "fetching pool\n\nCaused by:\n    rpc timeout".to_string()
+            ))
+        );
+
+        let converted = SubstreamsError::from("a".parse::<u32>().unwrap_err());
+        assert_eq!(
+            converted,
+            SubstreamsError::Deterministic("invalid digit found in string".to_string())
+        );
+
+        assert_eq!(panic_payload(&anyhow::anyhow!("plain")), "plain");
+        assert_eq!(SubstreamsError::from_payload("plain"), None);
+    }
 }
@@ -1,6 +1,30 @@
+//! Matching of keys against boolean expressions like `amount > 1000 && !(type:swap || burn)`.
+//!
+//! Two parsers implement the expression grammar, selected with cargo features:
+//!
+//! - `expr` (enabled by default) parses expressions with a [pest](https://pest.rs) grammar.
+//! - `expr-lite` uses a small hand-rolled recursive-descent parser instead. Modules
+//!   disabling default features and enabling `expr-lite` don't pull pest and its generated
+//!   tables in their `.wasm` binary. It is ignored when `expr` is enabled.
+//!
+//! Both accept the same expressions and match keys the same way. Expressions are compiled
+//! once to a [CompiledExpr] tree evaluated without allocating, an [ExprMatcher] can be kept
+//! for the whole block and matched against hundreds of thousands of keys.
+
 use anyhow::{Context, Error};
-use pest::{iterators::Pair, Parser};
-use pest_derive::Parser;
+
+mod compiled;
+#[cfg(not(feature = "expr"))]
+mod lite_parser;
+#[cfg(feature = "expr")]
+mod pest_parser;
+
+#[cfg(not(feature = "expr"))]
+use lite_parser::Expression;
+#[cfg(feature = "expr")]
+use pest_parser::Expression;
+
+pub use compiled::CompiledExpr;
 
 /// An expression matcher that can be used to match keys from a given expression.
 /// You create a new [ExprMatcher] by calling [expr_matcher] with the input expression
@@ -8,20 +32,39 @@ use pest_derive::Parser;
 ///
 /// You can then re-use the matcher to match multiple keys against the same expression.
 /// re-using the expression "parsed" state.
+///
+/// Besides key terms combined with `&&`, `||` and parentheses, expressions support negation
+/// with `!` or `not` and numeric comparisons (`>`, `>=`, `<`, `<=`, `==`, `!=`) like
+/// `amount > 1000 && !(type:swap || type:burn)`. A comparison matches when a value of its
+/// key satisfies it, see [ExprMatcher::matches_keys] and [ExprMatcher::matches_pairs].
 pub struct ExprMatcher<'a> {
-    pair: Pair<'a, Rule>,
+    expression: CompiledExpr<'a>,
 }
 
 impl<'a> ExprMatcher<'a> {
     pub fn new(input: &'a str) -> Result<Self, Error> {
         Ok(ExprMatcher {
-            pair: parsing(input)?,
+            expression: CompiledExpr::compile(input)?,
         })
     }
 
+    /// The compiled expression, matched by the matcher.
+    pub fn compiled(&self) -> &CompiledExpr<'a> {
+        &self.expression
+    }
+
     /// Matches the given keys against the expression. Returns true if the keys match the expression.
+    ///
+    /// Comparisons are evaluated against segmented keys, `amount > 1000` matching the key
+    /// `amount:1500`, the value being the part after the last `:`.
     pub fn matches_keys<K: AsRef<str>>(&self, keys: &[K]) -> bool {
-        apply_rule(self.pair.clone(), keys)
+        self.expression.matches(&Keys(keys))
+    }
+
+    /// Matches the given key/value pairs against the expression. Key terms match the pairs
+    /// keys and comparisons are evaluated against the values of the pairs with their key.
+    pub fn matches_pairs<K: AsRef<str>, V: AsRef<str>>(&self, pairs: &[(K, V)]) -> bool {
+        self.expression.matches(&Pairs(pairs))
     }
 }
 
@@ -31,125 +74,48 @@ pub fn expr_matcher(input: &'_ str) -> ExprMatcher<'_> {
     ExprMatcher::new(input).expect("creating expression matcher failed")
 }
 
-#[derive(Parser)]
-#[grammar = "expr_parser_rule.pest"]
-struct EParser;
-
-fn parsing(input: &str) -> Result<Pair<Rule>, Error> {
-    let pairs = EParser::parse(Rule::expression, input)
-        .context("parsing input based on the expression rule")?;
-
-    match pairs.into_iter().next() {
-        Some(pair) => Ok(pair),
-        None => Err(anyhow::Error::msg("no pairs found in input")),
-    }
-}
-
 pub fn matches_keys_in_parsed_expr<K: AsRef<str>, I: AsRef<str>>(
     keys: &[K],
     input: I,
 ) -> Result<bool, Error> {
-    let successful_parse = parsing(input.as_ref()).context("parsing expression")?;
-    Ok(apply_rule(successful_parse, keys))
+    let expression = CompiledExpr::compile(input.as_ref()).context("parsing expression")?;
+    Ok(expression.matches(&Keys(keys)))
 }
 
-fn apply_rule<K: AsRef<str>>(pair: Pair<Rule>, keys: &[K]) -> bool {
-    match pair.as_rule() {
-        Rule::expression => {
-            let inner_pair = pair.into_inner().next().unwrap();
-            return apply_rule(inner_pair, keys);
-        }
-        Rule::or => {
-            let mut result = false;
-            for inner_pair in pair.into_inner() {
-                result = result || apply_rule(inner_pair, keys);
-            }
-            return result;
-        }
-        Rule::and => {
-            let mut result = true;
-            for inner_pair in pair.into_inner() {
-                result = result && apply_rule(inner_pair, keys);
-            }
-            return result;
-        }
-        Rule::value => {
-            let inner_pair = pair.into_inner().next().unwrap();
-            return apply_rule(inner_pair, keys);
-        }
-        Rule::keyterm => {
-            return keys.iter().any(|key| key.as_ref() == pair.as_str());
-        }
-        Rule::singleQuoteKeyTerm => {
-            return keys
-                .iter()
-                .any(|key| key.as_ref() == pair.as_str().trim_matches('\''));
-        }
-        Rule::doubleQuoteKeyTerm => {
-            return keys
-                .iter()
-                .any(|key| key.as_ref() == pair.as_str().trim_matches('"'));
-        }
-        _ => {
-            panic!("Unexpected rule encountered")
-        }
-    }
+/// What an expression is evaluated against.
+trait Terms {
+    fn has_key(&self, key: &str) -> bool;
+
+    /// Returns true if one of the values of `key` satisfies `f`.
+    fn any_value<F: Fn(&str) -> bool>(&self, key: &str, f: F) -> bool;
 }
 
-#[cfg(test)]
-fn expression_to_string(parsing: Pair<Rule>) -> String {
-    let rule = parsing.as_rule();
-    match rule {
-        Rule::expression => {
-            let inner_pair = parsing.into_inner().next().unwrap();
-            return expression_to_string(inner_pair);
-        }
-        Rule::or => {
-            let mut result = String::new();
-            result.push_str("[");
-            for inner_pair in parsing.into_inner() {
-                result.push_str(&expression_to_string(inner_pair));
-                result.push_str("||");
-            }
+struct Keys<'k, K>(&'k [K]);
 
-            if result.ends_with("||") {
-                result.truncate(result.len() - 2);
-            }
+impl<K: AsRef<str>> Terms for Keys<'_, K> {
+    fn has_key(&self, key: &str) -> bool {
+        self.0.iter().any(|k| k.as_ref() == key)
+    }
 
-            result.push_str("]");
-            return result;
-        }
-        Rule::and => {
-            let mut result = String::new();
-            result.push_str("<");
-            for inner_pair in parsing.into_inner() {
-                result.push_str(&expression_to_string(inner_pair));
-                result.push_str("&&");
-            }
+    fn any_value<F: Fn(&str) -> bool>(&self, key: &str, f: F) -> bool {
+        self.0.iter().any(|k| match k.as_ref().rsplit_once(':') {
+            Some((name, value)) => name == key && f(value),
+            None => false,
+        })
+    }
+}
 
-            if result.ends_with("&&") {
-                result.truncate(result.len() - 2);
-            }
+struct Pairs<'p, K, V>(&'p [(K, V)]);
 
-            result.push_str(">");
-            return result;
-        }
-        Rule::value => {
-            let inner_pair = parsing.into_inner().next().unwrap();
-            return expression_to_string(inner_pair);
-        }
-        Rule::keyterm => {
-            return parsing.as_str().to_string();
-        }
-        Rule::singleQuoteKeyTerm => {
-            return parsing.as_str().trim_matches('\'').to_string();
-        }
-        Rule::doubleQuoteKeyTerm => {
-            return parsing.as_str().trim_matches('\"').to_string();
-        }
-        _ => {
-            panic!("Unexpected rule encountered")
-        }
+impl<K: AsRef<str>, V: AsRef<str>> Terms for Pairs<'_, K, V> {
+    fn has_key(&self, key: &str) -> bool {
+        self.0.iter().any(|(k, _)| k.as_ref() == key)
+    }
+
+    fn any_value<F: Fn(&str) -> bool>(&self, key: &str, f: F) -> bool {
+        self.0
+            .iter()
+            .any(|(k, v)| k.as_ref() == key && f(v.as_ref()))
     }
 }
 
@@ -238,19 +204,24 @@ mod tests {
         "(test1 && test6 && test7) || (test4 && test5) || test3 ",
         true
     )]
+    #[case(TEST_KEYS, "!test6", true)]
+    #[case(TEST_KEYS, "!test1", false)]
+    #[case(TEST_KEYS, "not test6 && test1", true)]
+    #[case(TEST_KEYS, "!(test1 || test6)", false)]
+    #[case(TEST_KEYS, "test1 && ! (test6 || test7)", true)]
+    #[case(TEST_KEYS, "test > 7", true)]
+    #[case(TEST_KEYS, "test >= 8.5", false)]
+    #[case(TEST_KEYS, "test != 1 || test1", true)]
 
     fn test_matches_keys_in_parsed_expr(
         #[case] keys: &[&str],
         #[case] input: &str,
         #[case] expected: bool,
     ) {
-        let pair = parsing(input).unwrap();
-        let expr_as_string = expression_to_string(pair);
-
         let result =
             matches_keys_in_parsed_expr(keys, input).expect("matching keys in parsed expression");
 
-        assert_eq!(result, expected, "This expression ast is {expr_as_string}");
+        assert_eq!(result, expected, "matching expression {}", input);
     }
 
     #[rstest]
@@ -261,9 +232,11 @@ mod tests {
     #[case("test-8", false)]
     #[case("'te't'", true)]
     #[case("\"te\"st\"", true)]
+    #[case("!", true)]
+    #[case("amount>=-10.5", false)]
 
     fn test_parsing_error(#[case] input: &str, #[case] expected: bool) {
-        let pair = parsing(input);
+        let pair = Expression::parse(input);
 
         if expected {
             assert!(pair.is_err());
@@ -276,4 +249,15 @@ mod tests {
     fn it_expr_matcher_matches_keys() {
         assert_eq!(expr_matcher("test").matches_keys(TEST_KEYS), true);
     }
+
+    #[test]
+    fn it_expr_matcher_matches_pairs() {
+        let pairs = [("type", "swap"), ("amount", "1500"), ("fee", "0.3")];
+
+        let matcher = expr_matcher("amount > 1000 && fee < 1 && !(type == 1 || burn)");
+        assert!(matcher.matches_pairs(&pairs));
+        assert!(!expr_matcher("amount > 1500").matches_pairs(&pairs));
+        assert!(!expr_matcher("price > 0").matches_pairs(&pairs));
+        assert!(expr_matcher("type").matches_pairs(&pairs));
+    }
 }
@@ -0,0 +1,246 @@
+//! Expressions compiled to a tree evaluated without going back to the parser, see
+//! [CompiledExpr].
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::Error;
+
+use super::{Expression, Terms};
+use crate::scalar::BigDecimal;
+
+/// An expression compiled once and evaluated in tight loops.
+///
+/// Single-term `&&` and `||` groups are collapsed and comparison numbers are parsed at
+/// compile time. Evaluating it doesn't allocate, except for comparisons against values that
+/// aren't plain decimals (like `1e3`), which fall back to [BigDecimal] parsing.
+pub struct CompiledExpr<'a> {
+    root: Node<'a>,
+}
+
+impl<'a> CompiledExpr<'a> {
+    pub fn compile(input: &'a str) -> Result<Self, Error> {
+        Ok(CompiledExpr {
+            root: Expression::parse(input)?.compile(),
+        })
+    }
+
+    /// Same as [ExprMatcher::matches_keys](super::ExprMatcher::matches_keys).
+    pub fn matches_keys<K: AsRef<str>>(&self, keys: &[K]) -> bool {
+        self.root.matches(&super::Keys(keys))
+    }
+
+    /// Same as [ExprMatcher::matches_pairs](super::ExprMatcher::matches_pairs).
+    pub fn matches_pairs<K: AsRef<str>, V: AsRef<str>>(&self, pairs: &[(K, V)]) -> bool {
+        self.root.matches(&super::Pairs(pairs))
+    }
+
+    pub(super) fn matches<T: Terms>(&self, terms: &T) -> bool {
+        self.root.matches(terms)
+    }
+}
+
+pub(super) enum Node<'a> {
+    Or(Box<[Node<'a>]>),
+    And(Box<[Node<'a>]>),
+    Not(Box<Node<'a>>),
+    Key(&'a str),
+    Comparison {
+        key: &'a str,
+        operator: Operator,
+        number: Number<'a>,
+    },
+}
+
+impl<'a> Node<'a> {
+    /// Builds an `||` (or `&&` with `or` false) node, collapsed to its term when alone.
+    pub(super) fn group(or: bool, mut nodes: Vec<Node<'a>>) -> Self {
+        if nodes.len() == 1 {
+            return nodes.pop().expect("one node");
+        }
+
+        match or {
+            true => Node::Or(nodes.into_boxed_slice()),
+            false => Node::And(nodes.into_boxed_slice()),
+        }
+    }
+
+    pub(super) fn comparison(key: &'a str, operator: &str, number: &'a str) -> Self {
+        Node::Comparison {
+            key,
+            operator: Operator::parse(operator),
+            number: Number::parse(number),
+        }
+    }
+
+    fn matches<T: Terms>(&self, terms: &T) -> bool {
+        match self {
+            Node::Or(nodes) => nodes.iter().any(|node| node.matches(terms)),
+            Node::And(nodes) => nodes.iter().all(|node| node.matches(terms)),
+            Node::Not(node) => !node.matches(terms),
+            Node::Key(key) => terms.has_key(key),
+            Node::Comparison {
+                key,
+                operator,
+                number,
+            } => terms.any_value(key, |value| match number.compare(value.trim()) {
+                Some(ordering) => operator.accepts(ordering),
+                None => false,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) enum Operator {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Operator {
+    fn parse(operator: &str) -> Self {
+        match operator {
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterOrEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            "==" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            _ => panic!("Unexpected comparison operator {}", operator),
+        }
+    }
+
+    /// Returns true if a value ordered `ordering` relative to the number satisfies it.
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterOrEqual => ordering != Ordering::Less,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessOrEqual => ordering != Ordering::Greater,
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+        }
+    }
+}
+
+/// The number of a comparison, parsed as a [PlainDecimal] for allocation-free comparisons
+/// and as a [BigDecimal] for the other values.
+pub(super) struct Number<'a> {
+    plain: PlainDecimal<'a>,
+    decimal: BigDecimal,
+}
+
+impl<'a> Number<'a> {
+    fn parse(number: &'a str) -> Self {
+        Number {
+            plain: PlainDecimal::parse(number).expect("number rule is a plain decimal"),
+            decimal: BigDecimal::from_str(number).expect("number rule is a valid decimal"),
+        }
+    }
+
+    /// Orders `value` relative to the number, `None` if `value` isn't a number.
+    fn compare(&self, value: &str) -> Option<Ordering> {
+        match PlainDecimal::parse(value) {
+            Some(plain) => Some(plain.cmp(&self.plain)),
+            None => BigDecimal::from_str(value)
+                .ok()
+                .map(|value| value.cmp(&self.decimal)),
+        }
+    }
+}
+
+/// A decimal written `-?[0-9]+(\.[0-9]+)?`, compared digit by digit without allocating.
+#[derive(PartialEq, Eq)]
+struct PlainDecimal<'a> {
+    negative: bool,
+    /// Without leading zeros
+    integer: &'a str,
+    /// Without trailing zeros
+    fraction: &'a str,
+}
+
+impl<'a> PlainDecimal<'a> {
+    fn parse(value: &'a str) -> Option<Self> {
+        let (negative, unsigned) = match value.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, value),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) if !fraction.is_empty() => (integer, fraction),
+            Some(_) => return None,
+            None => (unsigned, ""),
+        };
+        let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+            return None;
+        }
+
+        let integer = integer.trim_start_matches('0');
+        let fraction = fraction.trim_end_matches('0');
+        Some(PlainDecimal {
+            // `-0` is zero
+            negative: negative && !(integer.is_empty() && fraction.is_empty()),
+            integer,
+            fraction,
+        })
+    }
+
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        self.integer
+            .len()
+            .cmp(&other.integer.len())
+            .then_with(|| self.integer.cmp(other.integer))
+            .then_with(|| self.fraction.cmp(other.fraction))
+    }
+}
+
+impl Ord for PlainDecimal<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for PlainDecimal<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompiledExpr, PlainDecimal};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn it_compares_plain_decimals_like_big_decimals() {
+        let cmp = |a, b| {
+            PlainDecimal::parse(a)
+                .unwrap()
+                .cmp(&PlainDecimal::parse(b).unwrap())
+        };
+
+        assert_eq!(cmp("10", "9.99"), Ordering::Greater);
+        assert_eq!(cmp("0.45", "0.5"), Ordering::Less);
+        assert_eq!(cmp("007.50", "7.5"), Ordering::Equal);
+        assert_eq!(cmp("-0", "0.0"), Ordering::Equal);
+        assert_eq!(cmp("-10.5", "-9"), Ordering::Less);
+        assert_eq!(cmp("-1", "0.1"), Ordering::Less);
+        assert!(PlainDecimal::parse("1e3").is_none());
+        assert!(PlainDecimal::parse("1.").is_none());
+        assert!(PlainDecimal::parse(".5").is_none());
+
+        let compiled = CompiledExpr::compile("amount > 999.5 && (fee < 1 || free)").unwrap();
+        assert!(compiled.matches_keys(&["amount:1000", "fee:0.3"]));
+        assert!(compiled.matches_keys(&["amount:1e4", "free"]));
+        assert!(!compiled.matches_keys(&["amount:999.50", "free"]));
+    }
+}
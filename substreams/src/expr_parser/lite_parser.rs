@@ -0,0 +1,286 @@
+//! A recursive-descent parser for the grammar defined in `expr_parser_rule.pest`, each rule
+//! being a method of [Parser]. Rules return the position after what they matched, or `None`
+//! without consuming anything, giving the same ordered choice semantics as pest.
+
+use anyhow::Error;
+
+use super::compiled::Node;
+
+const OPERATORS: [&str; 6] = [">=", "<=", "!=", "==", ">", "<"];
+
+/// An expression parsed by the hand-rolled parser.
+pub(super) enum Expression<'a> {
+    Or(Vec<Expression<'a>>),
+    And(Vec<Expression<'a>>),
+    Not(Box<Expression<'a>>),
+    Comparison {
+        key: &'a str,
+        operator: &'a str,
+        number: &'a str,
+    },
+    Key(&'a str),
+}
+
+impl<'a> Expression<'a> {
+    pub(super) fn parse(input: &'a str) -> Result<Self, Error> {
+        Parser { input }
+            .expression()
+            .ok_or_else(|| anyhow::anyhow!("invalid expression {:?}", input))
+    }
+
+    pub(super) fn compile(&self) -> Node<'a> {
+        let compile_all =
+            |expressions: &[Expression<'a>]| expressions.iter().map(Expression::compile).collect();
+
+        match self {
+            Expression::Or(expressions) => Node::group(true, compile_all(expressions)),
+            Expression::And(expressions) => Node::group(false, compile_all(expressions)),
+            Expression::Not(expression) => Node::Not(Box::new(expression.compile())),
+            Expression::Comparison {
+                key,
+                operator,
+                number,
+            } => Node::comparison(key, operator, number),
+            Expression::Key(key) => Node::Key(key),
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn expression(&self) -> Option<Expression<'a>> {
+        let (expression, pos) = self.or(0)?;
+        if self.optional_space(pos) == self.input.len() {
+            Some(expression)
+        } else {
+            None
+        }
+    }
+
+    fn or(&self, pos: usize) -> Option<(Expression<'a>, usize)> {
+        let (first, mut pos) = self.and(pos)?;
+        let mut expressions = vec![first];
+
+        loop {
+            let next = self
+                .literal(self.optional_space(pos), "||")
+                .and_then(|p| self.and(self.optional_space(p)));
+            match next {
+                Some((expression, p)) => {
+                    expressions.push(expression);
+                    pos = p;
+                }
+                None => break,
+            }
+        }
+
+        Some((Expression::Or(expressions), pos))
+    }
+
+    fn and(&self, pos: usize) -> Option<(Expression<'a>, usize)> {
+        let (first, mut pos) = self.value(pos)?;
+        let mut expressions = vec![first];
+
+        loop {
+            let next = self.space(pos).and_then(|p| self.value(p)).or_else(|| {
+                self.literal(self.optional_space(pos), "&&")
+                    .and_then(|p| self.value(self.optional_space(p)))
+                    .map(|(expression, p)| (expression, self.optional_space(p)))
+            });
+            match next {
+                Some((expression, p)) => {
+                    expressions.push(expression);
+                    pos = p;
+                }
+                None => break,
+            }
+        }
+
+        Some((Expression::And(expressions), pos))
+    }
+
+    fn value(&self, pos: usize) -> Option<(Expression<'a>, usize)> {
+        let spaced = self.optional_space(pos);
+        let parsed = self
+            .not(spaced)
+            .or_else(|| self.comparison(spaced))
+            .or_else(|| self.quoted_key(spaced, "'"))
+            .or_else(|| self.quoted_key(spaced, "\""));
+        if let Some((expression, p)) = parsed {
+            return Some((expression, self.optional_space(p)));
+        }
+
+        if let Some((key, p)) = self.keyterm(pos) {
+            return Some((Expression::Key(key), p));
+        }
+
+        let p = self.literal(spaced, "(")?;
+        let (expression, p) = self.or(self.optional_space(p))?;
+        let p = self.literal(self.optional_space(p), ")")?;
+        Some((expression, self.optional_space(p)))
+    }
+
+    fn not(&self, pos: usize) -> Option<(Expression<'a>, usize)> {
+        let p = self
+            .literal(pos, "!")
+            .or_else(|| self.literal(pos, "not").and_then(|p| self.space(p)))?;
+        let (expression, p) = self.value(self.optional_space(p))?;
+        Some((Expression::Not(Box::new(expression)), p))
+    }
+
+    fn comparison(&self, pos: usize) -> Option<(Expression<'a>, usize)> {
+        if self.literal(pos, "-").is_some() {
+            return None;
+        }
+        let key_end = self.repeat(pos, |p| match self.operator(p) {
+            Some(_) => None,
+            None => self.accepted(p),
+        })?;
+
+        let (operator, p) = self.operator(self.optional_space(key_end))?;
+        let p = self.optional_space(p);
+        let number_end = self.number(p)?;
+        if self.accepted(number_end).is_some() {
+            return None;
+        }
+
+        let expression = Expression::Comparison {
+            key: &self.input[pos..key_end],
+            operator,
+            number: &self.input[p..number_end],
+        };
+        Some((expression, number_end))
+    }
+
+    fn operator(&self, pos: usize) -> Option<(&'a str, usize)> {
+        OPERATORS
+            .iter()
+            .find_map(|operator| self.literal(pos, operator))
+            .map(|p| (&self.input[pos..p], p))
+    }
+
+    fn number(&self, pos: usize) -> Option<usize> {
+        let digits = |p| self.repeat(p, |p| self.char_matching(p, |c| c.is_ascii_digit()));
+
+        let p = self.literal(pos, "-").unwrap_or(pos);
+        let p = digits(p)?;
+        Some(self.literal(p, ".").and_then(digits).unwrap_or(p))
+    }
+
+    fn keyterm(&self, pos: usize) -> Option<(&'a str, usize)> {
+        if self
+            .literal(pos, "-")
+            .or_else(|| self.literal(pos, "!"))
+            .is_some()
+        {
+            return None;
+        }
+        let end = self.repeat(pos, |p| self.accepted(p))?;
+        Some((&self.input[pos..end], end))
+    }
+
+    fn quoted_key(&self, pos: usize, quote: &str) -> Option<(Expression<'a>, usize)> {
+        let start = self.literal(pos, quote)?;
+        if self.literal(start, "-").is_some() {
+            return None;
+        }
+        let end = self.repeat(start, |p| match self.literal(p, quote) {
+            Some(_) => None,
+            None => self.char_matching(p, |_| true),
+        })?;
+        let p = self.literal(end, quote)?;
+        Some((Expression::Key(&self.input[start..end]), p))
+    }
+
+    /// The `ctr_accepted` rule, any character but spaces, quotes, parentheses, `||` and `&&`.
+    fn accepted(&self, pos: usize) -> Option<usize> {
+        let rest = &self.input[pos..];
+        if ["'", "\"", "(", ")", "||", "&&"]
+            .iter()
+            .any(|excluded| rest.starts_with(excluded))
+        {
+            return None;
+        }
+        self.char_matching(pos, |c| !is_space(c))
+    }
+
+    fn space(&self, pos: usize) -> Option<usize> {
+        self.repeat(pos, |p| self.char_matching(p, is_space))
+    }
+
+    fn optional_space(&self, pos: usize) -> usize {
+        self.space(pos).unwrap_or(pos)
+    }
+
+    fn literal(&self, pos: usize, literal: &str) -> Option<usize> {
+        if self.input[pos..].starts_with(literal) {
+            Some(pos + literal.len())
+        } else {
+            None
+        }
+    }
+
+    fn char_matching<F: Fn(char) -> bool>(&self, pos: usize, f: F) -> Option<usize> {
+        match self.input[pos..].chars().next() {
+            Some(c) if f(c) => Some(pos + c.len_utf8()),
+            _ => None,
+        }
+    }
+
+    /// Applies `rule` one or more times, returning the position after the last match.
+    fn repeat<F: Fn(usize) -> Option<usize>>(&self, pos: usize, rule: F) -> Option<usize> {
+        let mut end = rule(pos)?;
+        while let Some(p) = rule(end) {
+            end = p;
+        }
+        Some(end)
+    }
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expression;
+
+    fn render(expression: &Expression) -> String {
+        let join = |expressions: &[Expression], separator| {
+            let rendered: Vec<String> = expressions.iter().map(render).collect();
+            rendered.join(separator)
+        };
+
+        match expression {
+            Expression::Or(expressions) => format!("[{}]", join(expressions, "||")),
+            Expression::And(expressions) => format!("<{}>", join(expressions, "&&")),
+            Expression::Not(expression) => format!("!{}", render(expression)),
+            Expression::Comparison {
+                key,
+                operator,
+                number,
+            } => format!("{}{}{}", key, operator, number),
+            Expression::Key(key) => key.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_parses_like_the_pest_grammar() {
+        let parsed = |input| render(&Expression::parse(input).unwrap());
+
+        assert_eq!(parsed("test1 test2"), "[<test1&&test2>]");
+        assert_eq!(parsed("(a || 'b c') && not d"), "[<[<a>||<b c>]&&!d>]");
+        assert_eq!(
+            parsed("amount>=-10.5 && fee < 1"),
+            "[<amount>=-10.5&&fee<1>]"
+        );
+        assert_eq!(parsed("notice nothing"), "[<notice&&nothing>]");
+        assert_eq!(parsed("a|b && c"), "[<a|b&&c>]");
+        assert_eq!(parsed("price > 7x"), "[<price&&>&&7x>]");
+        assert!(Expression::parse("a ||").is_err());
+        assert!(Expression::parse("(a").is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::{Context, Error};
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+
+use super::compiled::Node;
+
+#[derive(Parser)]
+#[grammar = "expr_parser_rule.pest"]
+struct EParser;
+
+/// An expression parsed by the pest grammar.
+pub(super) struct Expression<'a> {
+    pair: Pair<'a, Rule>,
+}
+
+impl<'a> Expression<'a> {
+    pub(super) fn parse(input: &'a str) -> Result<Self, Error> {
+        let pairs = EParser::parse(Rule::expression, input)
+            .context("parsing input based on the expression rule")?;
+
+        match pairs.into_iter().next() {
+            Some(pair) => Ok(Expression { pair }),
+            None => Err(anyhow::Error::msg("no pairs found in input")),
+        }
+    }
+
+    pub(super) fn compile(&self) -> Node<'a> {
+        compile(self.pair.clone())
+    }
+}
+
+fn compile(pair: Pair<Rule>) -> Node {
+    match pair.as_rule() {
+        Rule::expression | Rule::value => compile(pair.into_inner().next().unwrap()),
+        Rule::or => Node::group(true, pair.into_inner().map(compile).collect()),
+        Rule::and => Node::group(false, pair.into_inner().map(compile).collect()),
+        Rule::not => Node::Not(Box::new(compile(pair.into_inner().next().unwrap()))),
+        Rule::comparison => {
+            let mut inner = pair.into_inner();
+            let key = inner.next().unwrap().as_str();
+            let operator = inner.next().unwrap().as_str();
+            let number = inner.next().unwrap().as_str();
+
+            Node::comparison(key, operator, number)
+        }
+        Rule::keyterm => Node::Key(pair.as_str()),
+        Rule::singleQuoteKeyTerm => Node::Key(pair.as_str().trim_matches('\'')),
+        Rule::doubleQuoteKeyTerm => Node::Key(pair.as_str().trim_matches('"')),
+        _ => {
+            panic!("Unexpected rule encountered")
+        }
+    }
+}
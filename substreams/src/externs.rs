@@ -1,137 +1,308 @@
-#[cfg(target_arch = "wasm32")]
-#[link(wasm_import_module = "env")]
-extern "C" {
-    pub fn skip_empty_output();
-    pub fn output(ptr: *const u8, len: u32);
-    pub fn register_panic(
-        msg_ptr: *const u8,
-        msg_len: u32,
-        file_ptr: *const u8,
-        file_len: u32,
-        line: u32,
-        column: u32,
-    );
-}
+//! Host functions imported by the module.
+//!
+//! Imports are declared through [host_imports], which gives each of them a WASM import module
+//! (so no bare symbol is left for the native linker to resolve or decorate) and a native stub
+//! doing nothing and returning the default value of its result. Native builds link without a
+//! host, the crate itself only calls the imports on `wasm32` and goes through
+//! [crate::testing] otherwise.
+
+/// Declares the host imports of an `extern "C"` block: the block is kept for `wasm32` targets,
+/// with its attributes, and each function gets a native stub.
+macro_rules! host_imports {
+    (
+        $(#[$attr:meta])*
+        extern "C" {
+            $(pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*
+        }
+    ) => {
+        #[cfg(target_arch = "wasm32")]
+        $(#[$attr])*
+        extern "C" {
+            $(pub fn $name($($arg: $ty),*) $(-> $ret)?;)*
+        }
 
-#[cfg(target_arch = "wasm32")]
-#[link(wasm_import_module = "logger")]
-extern "C" {
-    pub fn println(ptr: *const u8, len: usize);
+        $(
+            /// Native stub of the host import, does nothing and returns the default value.
+            ///
+            /// # Safety
+            ///
+            /// Always safe to call, `unsafe` like the import it replaces.
+            #[cfg(not(target_arch = "wasm32"))]
+            #[allow(unused_variables)]
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                $(<$ret as Default>::default())?
+            }
+        )*
+    };
 }
 
-pub mod state {
-    #[cfg(target_arch = "wasm32")]
-    #[link(wasm_import_module = "state")]
+host_imports! {
+    #[link(wasm_import_module = "env")]
     extern "C" {
-        pub fn get_first(store_idx: u32, key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
-        pub fn get_last(store_idx: u32, key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
-        pub fn get_at(
-            store_idx: u32,
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            output_ptr: u32,
-        ) -> u32;
-        pub fn has_first(store_idx: u32, key_ptr: *const u8, key_len: u32) -> u32;
-        pub fn has_last(store_idx: u32, key_ptr: *const u8, key_len: u32) -> u32;
-        pub fn has_at(store_idx: u32, ord: i64, key_ptr: *const u8, key_len: u32) -> u32;
-        pub fn set(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_if_not_exists(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn append(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn delete_prefix(ord: i64, prefix_ptr: *const u8, prefix_len: u32);
-        pub fn add_bigint(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn add_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
-        pub fn add_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
-        pub fn add_bigdecimal(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_min_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
-        pub fn set_min_bigint(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_min_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
-        pub fn set_min_bigdecimal(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_max_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
-        pub fn set_max_bigint(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_max_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
-        pub fn set_max_bigdecimal(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_sum_bigint(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_sum_bigdecimal(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_sum_int64(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
-        );
-        pub fn set_sum_float64(
-            ord: i64,
-            key_ptr: *const u8,
-            key_len: u32,
-            value_ptr: *const u8,
-            value_len: u32,
+        pub fn skip_empty_output();
+        pub fn emit(ptr: *const u8, len: u32);
+        pub fn register_panic(
+            msg_ptr: *const u8,
+            msg_len: u32,
+            file_ptr: *const u8,
+            file_len: u32,
+            line: u32,
+            column: u32,
         );
     }
 }
+
+// With the `wasi` feature these go through WASI stdio, see below, and with the `component`
+// feature the output is returned by the component export of the handler
+host_imports! {
+    #[cfg(not(any(feature = "wasi", feature = "component")))]
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        pub fn output(ptr: *const u8, len: u32);
+    }
+}
+
+host_imports! {
+    #[cfg(not(feature = "wasi"))]
+    #[link(wasm_import_module = "logger")]
+    extern "C" {
+        pub fn println(ptr: *const u8, len: usize);
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasi", not(feature = "component")))]
+pub use self::wasi::output;
+#[cfg(all(target_arch = "wasm32", feature = "wasi"))]
+pub use self::wasi::println;
+#[cfg(all(target_arch = "wasm32", feature = "component"))]
+pub use crate::component::output;
+
+/// With the `wasi` feature the output and logs go through WASI stdio instead of the
+/// `env.output` and `logger.println` imports: the output is written to stdout and each
+/// log line to stderr. Signatures are kept identical to the imports they replace.
+#[cfg(all(target_arch = "wasm32", feature = "wasi"))]
+mod wasi {
+    use std::io::Write;
+
+    /// Writes the `len` bytes at `ptr` to stdout.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` readable bytes.
+    #[cfg(not(feature = "component"))]
+    pub unsafe fn output(ptr: *const u8, len: u32) {
+        let data = std::slice::from_raw_parts(ptr, len as usize);
+
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout
+            .write_all(data)
+            .and_then(|_| stdout.flush())
+            .unwrap_or_else(|e| panic!("Unable to write output to stdout: {}", e));
+    }
+
+    /// Writes the `len` bytes at `ptr` to stderr as a single line.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` readable bytes.
+    pub unsafe fn println(ptr: *const u8, len: usize) {
+        let msg = std::slice::from_raw_parts(ptr, len);
+
+        let stderr = std::io::stderr();
+        let mut stderr = stderr.lock();
+        // Logging must never fail the module, a closed stderr drops the line
+        let _ = stderr.write_all(msg).and_then(|_| stderr.write_all(b"\n"));
+    }
+}
+
+pub mod config {
+    host_imports! {
+        #[cfg(feature = "runtime-config")]
+        #[link(wasm_import_module = "config")]
+        extern "C" {
+            pub fn get(key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
+        }
+    }
+}
+
+pub mod state {
+    host_imports! {
+        #[link(wasm_import_module = "state")]
+        extern "C" {
+            pub fn get_first(
+                store_idx: u32,
+                key_ptr: *const u8,
+                key_len: u32,
+                output_ptr: u32,
+            ) -> u32;
+            pub fn get_last(
+                store_idx: u32,
+                key_ptr: *const u8,
+                key_len: u32,
+                output_ptr: u32,
+            ) -> u32;
+            pub fn get_at(
+                store_idx: u32,
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                output_ptr: u32,
+            ) -> u32;
+            pub fn get_last_into(
+                store_idx: u32,
+                key_ptr: *const u8,
+                key_len: u32,
+                buf_ptr: u32,
+                buf_cap: u32,
+            ) -> i64;
+            pub fn get_many_last(
+                store_idx: u32,
+                keys_ptr: *const u8,
+                keys_len: u32,
+                output_ptr: u32,
+            );
+            pub fn get_many_at(
+                store_idx: u32,
+                ord: i64,
+                keys_ptr: *const u8,
+                keys_len: u32,
+                output_ptr: u32,
+            );
+            pub fn scan_prefix(
+                store_idx: u32,
+                prefix_ptr: *const u8,
+                prefix_len: u32,
+                after_ptr: *const u8,
+                after_len: u32,
+                limit: u32,
+                output_ptr: u32,
+            ) -> u32;
+            pub fn has_first(store_idx: u32, key_ptr: *const u8, key_len: u32) -> u32;
+            pub fn has_last(store_idx: u32, key_ptr: *const u8, key_len: u32) -> u32;
+            pub fn has_at(store_idx: u32, ord: i64, key_ptr: *const u8, key_len: u32) -> u32;
+            pub fn set(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_if_not_exists(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_once(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn append(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn delete_prefix(ord: i64, prefix_ptr: *const u8, prefix_len: u32);
+            pub fn delete_key(ord: i64, key_ptr: *const u8, key_len: u32);
+            pub fn write_batch(frame_ptr: *const u8, frame_len: u32);
+            pub fn select_output(output_idx: u32);
+            pub fn add_bigint(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn add_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
+            pub fn add_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
+            pub fn add_bigdecimal(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_min_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
+            pub fn set_min_bigint(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_min_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
+            pub fn set_min_bigdecimal(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_max_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64);
+            pub fn set_max_bigint(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_max_float64(ord: i64, key_ptr: *const u8, key_len: u32, value: f64);
+            pub fn set_max_bigdecimal(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_max_bytes(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_min_bytes(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_sum_bigint(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_sum_bigdecimal(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_sum_int64(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+            pub fn set_sum_float64(
+                ord: i64,
+                key_ptr: *const u8,
+                key_len: u32,
+                value_ptr: *const u8,
+                value_len: u32,
+            );
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Low-level access to the host functions, for intrinsics this crate doesn't wrap yet.
+//!
+//! [raw] re-exports the imports declared by the crate (stubs doing nothing on native
+//! targets, so code calling them still builds and links for tests), new host functions are
+//! declared in the module itself with the same conventions: strings and bytes are passed as a
+//! pointer and a `u32` length ([str_parts], [bytes_parts]), and data returned by the host is
+//! written in memory allocated through the `alloc` export, its pointer and length stored in an
+//! 8 bytes [OutputSlot]:
+//!
+//! ```rust
+//! use substreams::ffi;
+//!
+//! #[cfg(target_arch = "wasm32")]
+//! #[link(wasm_import_module = "state")]
+//! extern "C" {
+//!     fn get_metadata(key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
+//! }
+//!
+//! #[cfg(target_arch = "wasm32")]
+//! fn metadata(key: &str) -> Option<Vec<u8>> {
+//!     let (key_ptr, key_len) = ffi::str_parts(key);
+//!     let slot = ffi::OutputSlot::new();
+//!
+//!     unsafe {
+//!         match get_metadata(key_ptr, key_len, slot.as_u32()) {
+//!             1 => Some(slot.take()),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Calling host functions is `unsafe`: the signature must match the one of the server, which
+//! is not checked until the module is instantiated.
+
+use crate::memory;
+
+/// The host functions imported by the crate.
+pub mod raw {
+    pub use crate::externs::{config, state};
+    pub use crate::externs::{emit, output, println, register_panic, skip_empty_output};
+}
+
+pub use crate::memory::{alloc, dealloc};
+
+/// The pointer and length of `value` as passed to host functions.
+pub fn str_parts(value: &str) -> (*const u8, u32) {
+    bytes_parts(value.as_bytes())
+}
+
+/// The pointer and length of `value` as passed to host functions.
+pub fn bytes_parts(value: &[u8]) -> (*const u8, u32) {
+    (value.as_ptr(), value.len() as u32)
+}
+
+/// The 8 bytes where host functions returning data write its pointer and length, both as
+/// little-endian `u32`. The slot is freed when dropped, the data is read with
+/// [take](OutputSlot::take).
+pub struct OutputSlot {
+    ptr: *mut u8,
+}
+
+impl OutputSlot {
+    pub fn new() -> Self {
+        OutputSlot {
+            ptr: memory::alloc(8),
+        }
+    }
+
+    /// The address of the slot, the `output_ptr` argument of host functions.
+    pub fn as_u32(&self) -> u32 {
+        self.ptr as u32
+    }
+
+    /// Takes ownership of the data written by the host.
+    ///
+    /// # Safety
+    ///
+    /// The host must have written the slot with data allocated through the `alloc` export,
+    /// which is only the case when the host function reported a value.
+    pub unsafe fn take(self) -> Vec<u8> {
+        memory::get_output_data(self.ptr)
+    }
+}
+
+impl Default for OutputSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for OutputSlot {
+    fn drop(&mut self) {
+        unsafe { memory::dealloc(self.ptr, 8) }
+    }
+}
@@ -0,0 +1,238 @@
+//! Write guardrails protecting against runaway modules.
+//!
+//! Limits are configured per handler invocation, either through the handler macro
+//! or by calling [set_limits] directly at the start of the handler:
+//!
+//! ```rust
+//! # mod pb { pub type Custom = (); }
+//! use substreams::prelude::*;
+//!
+//! #[substreams::handlers::store(max_keys_written = 10000, max_value_bytes = 1048576)]
+//! fn store_transfers(transfers: pb::Custom, output: StoreAddInt64) {
+//!     // writing more than 10000 distinct keys aborts the module
+//! }
+//! ```
+//!
+//! Handlers declared without limits reset them to unlimited, so they never inherit the
+//! limits and counters of the handler that ran before them.
+//!
+//! When a limit is exceeded the handler panics with a [GuardrailError] naming the limit
+//! and the key prefix (the first `:` separated segment of the key) that triggered it.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Limits enforced on the current handler invocation, `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of distinct keys written to the output store.
+    pub max_keys_written: Option<usize>,
+    /// Maximum number of value bytes written to the output store.
+    pub max_value_bytes: Option<usize>,
+    /// Maximum number of store deltas received as inputs.
+    pub max_deltas_consumed: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    KeysWritten,
+    ValueBytes,
+    DeltasConsumed,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::KeysWritten => write!(f, "max_keys_written"),
+            Limit::ValueBytes => write!(f, "max_value_bytes"),
+            Limit::DeltasConsumed => write!(f, "max_deltas_consumed"),
+        }
+    }
+}
+
+/// Error raised when a [Limits] is exceeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardrailError {
+    pub limit: Limit,
+    pub max: usize,
+    pub actual: usize,
+    /// Key prefix of the write or delta that exceeded the limit.
+    pub prefix: String,
+}
+
+impl fmt::Display for GuardrailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "guardrail {} exceeded on prefix {:?}: {} > {}",
+            self.limit, self.prefix, self.actual, self.max
+        )
+    }
+}
+
+impl std::error::Error for GuardrailError {}
+
+#[derive(Default)]
+struct State {
+    limits: Limits,
+    keys_written: HashSet<String>,
+    value_bytes: usize,
+    deltas_consumed: usize,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Configures the limits for the current handler invocation and resets the counters.
+pub fn set_limits(limits: Limits) {
+    STATE.with(|state| {
+        *state.borrow_mut() = State {
+            limits,
+            ..Default::default()
+        }
+    })
+}
+
+/// Returns the limits currently enforced.
+pub fn limits() -> Limits {
+    STATE.with(|state| state.borrow().limits)
+}
+
+/// Accounts for a write of `key`, `value_len` is only evaluated when value bytes are limited.
+///
+/// # Panics
+///
+/// Panics with a [GuardrailError] if a limit is exceeded.
+pub(crate) fn on_write<F: FnOnce() -> usize>(key: &str, value_len: F) {
+    let result = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+
+        if let Some(max) = state.limits.max_keys_written {
+            if !state.keys_written.contains(key) {
+                state.keys_written.insert(key.to_string());
+            }
+
+            let actual = state.keys_written.len();
+            if actual > max {
+                return Err(error(Limit::KeysWritten, max, actual, key));
+            }
+        }
+
+        if let Some(max) = state.limits.max_value_bytes {
+            state.value_bytes += value_len();
+
+            let actual = state.value_bytes;
+            if actual > max {
+                return Err(error(Limit::ValueBytes, max, actual, key));
+            }
+        }
+
+        Ok(())
+    });
+
+    if let Err(err) = result {
+        panic!("{}", err)
+    }
+}
+
+/// Accounts for store deltas received as handler inputs.
+///
+/// # Panics
+///
+/// Panics with a [GuardrailError] if a limit is exceeded.
+pub(crate) fn on_deltas<'a, I: Iterator<Item = &'a str>>(keys: I) {
+    let result = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+
+        if let Some(max) = state.limits.max_deltas_consumed {
+            for key in keys {
+                state.deltas_consumed += 1;
+
+                let actual = state.deltas_consumed;
+                if actual > max {
+                    return Err(error(Limit::DeltasConsumed, max, actual, key));
+                }
+            }
+        }
+
+        Ok(())
+    });
+
+    if let Err(err) = result {
+        panic!("{}", err)
+    }
+}
+
+fn error(limit: Limit, max: usize, actual: usize, key: &str) -> GuardrailError {
+    GuardrailError {
+        limit,
+        max,
+        actual,
+        prefix: key.split(':').next().unwrap_or_default().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_distinct_keys() {
+        set_limits(Limits {
+            max_keys_written: Some(2),
+            ..Default::default()
+        });
+
+        on_write("pool:a", || 1);
+        on_write("pool:a", || 1);
+        on_write("pool:b", || 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "guardrail max_keys_written exceeded on prefix \"token\": 3 > 2")]
+    fn it_panics_on_too_many_keys() {
+        set_limits(Limits {
+            max_keys_written: Some(2),
+            ..Default::default()
+        });
+
+        on_write("pool:a", || 1);
+        on_write("pool:b", || 1);
+        on_write("token:c", || 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "guardrail max_value_bytes exceeded on prefix \"pool\": 12 > 10")]
+    fn it_panics_on_too_many_value_bytes() {
+        set_limits(Limits {
+            max_value_bytes: Some(10),
+            ..Default::default()
+        });
+
+        on_write("pool:a", || 6);
+        on_write("pool:a", || 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "guardrail max_deltas_consumed exceeded on prefix \"pair\": 2 > 1")]
+    fn it_panics_on_too_many_deltas() {
+        set_limits(Limits {
+            max_deltas_consumed: Some(1),
+            ..Default::default()
+        });
+
+        on_deltas(vec!["pool:a", "pair:b"].into_iter());
+    }
+
+    #[test]
+    fn it_is_unlimited_by_default() {
+        set_limits(Limits::default());
+
+        on_write("pool:a", || {
+            panic!("value length evaluated while unlimited")
+        });
+        on_deltas(vec!["pool:a"; 100].into_iter());
+    }
+}
@@ -38,6 +38,7 @@
 /// #[no_mangle]
 /// pub extern "C" fn map_handler(blk_ptr: *mut u8, blk_len: usize) {
 ///     substreams::register_panic_hook();
+///     substreams::context::set_handler("map_handler");
 ///     let func = || -> Result<proto::Custom, substreams::errors::Error> {
 ///         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len).unwrap();
 ///         {
@@ -85,6 +86,7 @@
 /// #[no_mangle]
 /// pub extern "C" fn map_handler(blk_ptr: *mut u8, blk_len: usize) {
 ///     substreams::register_panic_hook();
+///     substreams::context::set_handler("map_handler");
 ///     let func = || -> Option<proto::Custom> {
 ///         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len).unwrap();
 ///         {
@@ -131,6 +133,7 @@
 /// #[no_mangle]
 /// pub extern "C" fn map_handler(blk_ptr: *mut u8, blk_len: usize) {
 ///     substreams::register_panic_hook();
+///     substreams::context::set_handler("map_handler");
 ///     let func = || -> proto::Custom {
 ///         let blk: eth::Block = substreams::proto::decode_ptr(blk_ptr, blk_len).unwrap();
 ///         {
@@ -141,6 +144,184 @@
 ///     substreams::output(result);
 /// }
 /// ```
+///
+/// ## Borrowed inputs
+///
+/// Inputs can be declared as `&T` (`&str` for strings) or `Cow<T>`, the input is still
+/// decoded once but the handler only gets a reference to it, which avoids cloning
+/// when passing it to helpers taking references.
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// # mod eth {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Block { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Custom { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(blk: &eth::Block, previous: Cow<proto::Custom>) -> proto::Custom {
+///     proto::Custom { number: block_number(blk).max(previous.number) }
+/// }
+///
+/// fn block_number(blk: &eth::Block) -> u64 {
+///     blk.number
+/// }
+/// ```
+///
+/// Message inputs declared as `Rc<T>` are shared: when the manifest feeds several `Rc<T>`
+/// inputs of the same type with the same bytes, like a module wired twice to compare
+/// its output with a filtered version of itself, only the first one is decoded and the
+/// others point to it.
+///
+/// ```rust
+/// use std::rc::Rc;
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Custom { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(all: Rc<proto::Custom>, filtered: Rc<proto::Custom>) -> proto::Custom {
+///     proto::Custom { number: all.number - filtered.number }
+/// }
+/// ```
+///
+/// ## Undecoded inputs
+///
+/// A [crate::input::RawBytes] input receives the encoded input as-is and a
+/// [crate::input::LazyInput] decodes it on first access, see [crate::input].
+///
+/// ## Optional stores
+///
+/// A store in get mode taken as `Option<StoreGet*>` is `None` when the host passes
+/// [crate::store::MISSING_STORE] as its index, so one binary serves manifests wiring the
+/// store or not (feature-flagged pipelines):
+///
+/// ```rust
+/// use substreams::store::{StoreGet, StoreGetProto};
+/// # mod eth { pub type Block = prost_types::Timestamp; }
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Pool { #[prost(string, tag = "1")] pub name: String }
+/// # }
+///
+/// #[substreams::handlers::map]
+/// fn map_pool(blk: eth::Block, pools: Option<StoreGetProto<proto::Pool>>) -> Option<proto::Pool> {
+///     pools?.get_last(format!("pool:{}", blk.seconds))
+/// }
+/// ```
+///
+/// ## Generic handlers
+///
+/// The exported function can't be generic, a handler generic over types is instantiated
+/// with the `instantiate` option listing one type per generic parameter. The parameters
+/// are replaced in the inputs and output, and aliased to their type in the handler's body.
+/// `impl Into<T>` inputs are decoded as `T`.
+///
+/// ```rust
+/// # mod eth {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Block { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Custom { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// trait Numbered {
+///     fn number(&self) -> u64;
+/// }
+///
+/// impl Numbered for eth::Block {
+///     fn number(&self) -> u64 {
+///         self.number
+///     }
+/// }
+///
+/// #[substreams::handlers::map(instantiate = "eth::Block")]
+/// fn map_handler<B: Numbered>(blk: B, params: impl Into<String>) -> proto::Custom {
+///     let params: String = params.into();
+///     let offset: u64 = params.parse().unwrap_or(0);
+///     proto::Custom { number: B::number(&blk) + offset }
+/// }
+/// ```
+///
+/// Without `instantiate` a generic handler is rejected at compile time, the generic code can
+/// also be kept in a plain function called by thin non-generic handlers.
+///
+/// ## Pipelines
+///
+/// With `pipeline`, the handler's body returns a [crate::pipeline::Pipeline] of stages, run
+/// on the handler's first input. The handler must return a `Result`, the error of a failing
+/// stage being converted into its error type:
+///
+/// ```rust
+/// # mod eth {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Block { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Custom { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// use substreams::errors::Error;
+/// use substreams::pipeline::Pipeline;
+///
+/// fn extract(blk: eth::Block) -> u64 {
+///     blk.number
+/// }
+///
+/// fn aggregate(number: u64) -> proto::Custom {
+///     proto::Custom { number: number * 2 }
+/// }
+///
+/// #[substreams::handlers::map(pipeline, test_entrypoint)]
+/// fn map_handler(blk: eth::Block) -> Result<proto::Custom, Error> {
+///     Pipeline::new(extract).then(aggregate)
+/// }
+///
+/// # fn main() {
+/// let custom = map_handler::entrypoint(eth::Block { number: 21 }).unwrap();
+/// assert_eq!(custom.number, 42);
+/// # }
+/// ```
+///
+/// ## Module name
+///
+/// Handlers also generate a module named after them holding a `MODULE_NAME` constant
+/// (`map_handler::MODULE_NAME`) and are listed by [crate::registry()], a handler can't share
+/// its name with another module in scope.
+///
+/// ## Calling handlers from tests
+///
+/// With `test_entrypoint`, the generated module also holds an `entrypoint` function on
+/// non-wasm targets, a copy of the handler taking and returning its Rust types so tests can
+/// call it without encoding its inputs:
+///
+/// ```rust
+/// # mod eth {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Block { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// # mod proto {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Custom { #[prost(uint64, tag = "1")] pub number: u64 }
+/// # }
+/// #[substreams::handlers::map(test_entrypoint)]
+/// fn map_handler(blk: eth::Block) -> proto::Custom {
+///     proto::Custom { number: blk.number }
+/// }
+///
+/// # fn main() {
+/// let custom = map_handler::entrypoint(eth::Block { number: 42 });
+/// assert_eq!(custom.number, 42);
+/// # }
+/// ```
+///
+/// Handlers can't be `async`, they are called synchronously once per block.
 pub use substreams_macro::map;
 
 /// Marks function to setup substreams store handler WASM boilerplate
@@ -182,6 +363,7 @@ pub use substreams_macro::map;
 /// #[no_mangle]
 /// pub extern "C" fn build_nft_state(data_ptr: *mut u8, data_len: usize, pairs_idx: u32, tokens_idx: u32) {
 ///    substreams::register_panic_hook();
+///    substreams::context::set_handler("build_nft_state");
 ///    let data: proto::Custom = substreams::proto::decode_ptr(data_ptr, data_len).unwrap();
 ///    let pairs: StoreGetProto<proto::Pairs> = store::StoreGet::new(pairs_idx);
 ///    let tokens: StoreGetProto<proto::Tokens> = store::StoreGet::new(tokens_idx);
@@ -191,4 +373,63 @@ pub use substreams_macro::map;
 ///    }
 /// }
 /// ```
+///
+/// ## Errors
+///
+/// A store handler can return `Result<(), Error>` to use `?`, an error panics the handler with
+/// the error and its context chain, like map handlers returning a `Result`:
+///
+/// ```rust
+/// use anyhow::Context;
+/// use substreams::errors::Error;
+/// use substreams::store::{StoreAdd, StoreAddInt64, StoreNew};
+///
+/// #[substreams::handlers::store]
+/// fn store_amounts(amount: String, s: StoreAddInt64) -> Result<(), Error> {
+///     let amount: i64 = amount.parse().context("invalid amount")?;
+///     s.add(0, "total", amount);
+///     Ok(())
+/// }
+/// ```
+///
+/// ## Multiple writable stores
+///
+/// With the `multi_writable` option a store handler can take several writable stores, to
+/// write to stores with different policies without decoding the same inputs twice. Each
+/// writable store adds a `<name>_idx: u32` argument, at its position, holding the index of
+/// the output store it writes to. The module must be built with the `multi-writable-stores`
+/// feature, writes then select their output store with the `state.select_output` host call.
+///
+/// ```rust
+/// use substreams::store::{StoreAdd, StoreAddInt64, StoreNew, StoreSet, StoreSetString};
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Transfer { #[prost(string, tag = "1")] pub token: String }
+/// # }
+///
+/// #[substreams::handlers::store(multi_writable)]
+/// fn store_tokens(transfer: pb::Transfer, counts: StoreAddInt64, last: StoreSetString) {
+///     counts.add(0, &transfer.token, 1);
+///     last.set(0, "token", &transfer.token);
+/// }
+/// ```
 pub use substreams_macro::store;
+
+/// Marks function to setup substreams index handler WASM boilerplate
+/// ## Usage
+///
+/// Index handlers take the same inputs as `map` handlers, except stores, and return
+/// the [crate::index::Keys] of the block, see the [crate::index] module.
+///
+/// ```rust
+/// use substreams::index::Keys;
+/// # mod eth { pub type Block = (); }
+///
+/// #[substreams::handlers::index]
+/// fn index_events(blk: eth::Block) -> Keys {
+///     let mut keys = Keys::new();
+///     keys.add("evt:transfer");
+///     keys
+/// }
+/// ```
+pub use substreams_macro::index;
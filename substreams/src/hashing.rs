@@ -0,0 +1,97 @@
+//! Deterministic hashing shared by modules, so bucket assignment and sampling give the same
+//! results in every module and on every machine:
+//!
+//! ```rust
+//! use substreams::hashing;
+//!
+//! // The same pool always lands in the same of the 16 shards
+//! let shard = hashing::stable_bucket("pool:0x1f98431c8ad98523631ae4a59f267346ea31f984", 16);
+//! assert!(shard < 16);
+//!
+//! // Keeps about 1% of the transactions, always the same ones
+//! let sampled = hashing::is_sampled("0x6e8b...", 1, 100);
+//! ```
+//!
+//! [xxhash64] is always available, cryptographic hashes are enabled by features:
+//!
+//! - `keccak`: [keccak256], the Ethereum hash, dependency-free;
+//! - `sha256`: [sha256], through the `sha2` crate.
+//!
+//! None of the hashes depend on the platform or on a random state, unlike
+//! `std::collections::hash_map::DefaultHasher` whose algorithm may change between Rust
+//! releases.
+
+#[cfg(feature = "keccak")]
+mod keccak;
+mod xxhash;
+
+#[cfg(feature = "keccak")]
+pub use keccak::keccak256;
+pub use xxhash::xxhash64;
+
+/// The SHA-256 hash of `data`. Requires the `sha256` feature.
+#[cfg(feature = "sha256")]
+pub fn sha256<T: AsRef<[u8]>>(data: T) -> [u8; 32] {
+    use sha2::Digest;
+
+    sha2::Sha256::digest(data.as_ref()).into()
+}
+
+/// Assigns `key` to one of `buckets` buckets, in `0..buckets`, from its [xxhash64] with seed
+/// `0`. Keys are spread evenly across buckets.
+///
+/// # Panics
+///
+/// Panics if `buckets` is zero.
+pub fn stable_bucket<T: AsRef<[u8]>>(key: T, buckets: u64) -> u64 {
+    if buckets == 0 {
+        panic!("stable_bucket requires at least one bucket");
+    }
+
+    // Multiply-shift maps the hash to the range without the bias of a modulo
+    ((xxhash64(key, 0) as u128 * buckets as u128) >> 64) as u64
+}
+
+/// Returns true for `numerator` out of `denominator` keys, always the same ones, to sample
+/// entities deterministically.
+///
+/// # Panics
+///
+/// Panics if `denominator` is zero.
+pub fn is_sampled<T: AsRef<[u8]>>(key: T, numerator: u64, denominator: u64) -> bool {
+    stable_bucket(key, denominator) < numerator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_sampled, stable_bucket};
+
+    #[test]
+    fn it_assigns_stable_buckets() {
+        assert_eq!(stable_bucket("", 16), 14);
+        assert_eq!(stable_bucket("abc", 16), 4);
+        assert_eq!(stable_bucket("abc", 1), 0);
+
+        let mut counts = [0u32; 8];
+        for i in 0..8000 {
+            counts[stable_bucket(format!("pool:{}", i), 8) as usize] += 1;
+        }
+        assert!(counts.iter().all(|count| (900..1100).contains(count)));
+
+        let sampled = (0..10000)
+            .filter(|i| is_sampled(i.to_string(), 1, 10))
+            .count();
+        assert!((900..1100).contains(&sampled));
+        assert!(!is_sampled("abc", 0, 10));
+        assert!(is_sampled("abc", 10, 10));
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn it_hashes_sha256() {
+        assert_eq!(
+            crate::Hex(super::sha256("abc")).to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
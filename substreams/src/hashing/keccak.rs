@@ -0,0 +1,134 @@
+//! Keccak-256 as used by Ethereum, the original Keccak padding rather than the SHA-3 one.
+
+use std::convert::TryInto;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808A,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808B,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008A,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000A,
+    0x0000_0000_8000_808B,
+    0x8000_0000_0000_008B,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800A,
+    0x8000_0000_8000_000A,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// Rotation of the lanes visited by the combined rho and pi steps.
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// Lanes visited by the combined rho and pi steps.
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Bytes absorbed per permutation for a 256 bits output.
+const RATE: usize = 136;
+
+/// The Keccak-256 hash of `data`, as computed by Ethereum (`keccak256` in Solidity). Requires
+/// the `keccak` feature.
+pub fn keccak256<T: AsRef<[u8]>>(data: T) -> [u8; 32] {
+    let mut state = [0u64; 25];
+
+    let mut blocks = data.as_ref().chunks_exact(RATE);
+    for block in &mut blocks {
+        absorb(&mut state, block);
+    }
+
+    let remainder = blocks.remainder();
+    let mut last = [0u8; RATE];
+    last[..remainder.len()].copy_from_slice(remainder);
+    last[remainder.len()] ^= 0x01;
+    last[RATE - 1] ^= 0x80;
+    absorb(&mut state, &last);
+
+    let mut out = [0u8; 32];
+    for (chunk, lane) in out.chunks_exact_mut(8).zip(state.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    out
+}
+
+fn absorb(state: &mut [u64; 25], block: &[u8]) {
+    for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+        *lane ^= u64::from_le_bytes(bytes.try_into().expect("8 bytes"));
+    }
+
+    keccak_f(state);
+}
+
+fn keccak_f(a: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS.iter() {
+        // Theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[y * 5 + x] ^= d;
+            }
+        }
+
+        // Rho and pi
+        let mut last = a[1];
+        for (rotation, lane) in RHO.iter().zip(PI.iter()) {
+            let current = a[*lane];
+            a[*lane] = last.rotate_left(*rotation);
+            last = current;
+        }
+
+        // Chi
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&a[y * 5..y * 5 + 5]);
+            for x in 0..5 {
+                a[y * 5 + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // Iota
+        a[0] ^= round_constant;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keccak256;
+    use crate::Hex;
+
+    #[test]
+    fn it_matches_ethereum_hashes() {
+        assert_eq!(
+            Hex(keccak256("")).to_string(),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            Hex(keccak256("abc")).to_string(),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        // ERC-20 `Transfer` event signature
+        assert_eq!(
+            Hex(keccak256("Transfer(address,address,uint256)")).to_string(),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+    }
+}
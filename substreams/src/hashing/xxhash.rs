@@ -0,0 +1,102 @@
+//! XXH64, see <https://github.com/Cyan4973/xxHash/blob/dev/doc/xxhash_spec.md>.
+
+use std::convert::TryInto;
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// The 64 bits xxHash (XXH64) of `data` with `seed`, a fast non-cryptographic hash.
+pub fn xxhash64<T: AsRef<[u8]>>(data: T, seed: u64) -> u64 {
+    let data = data.as_ref();
+    let mut stripes = data.chunks_exact(32);
+
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [
+            seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+            seed.wrapping_add(PRIME_2),
+            seed,
+            seed.wrapping_sub(PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, acc) in acc.iter_mut().enumerate() {
+                *acc = round(*acc, read_u64(&stripe[lane * 8..]));
+            }
+        }
+
+        let mut hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        for acc in acc {
+            hash = (hash ^ round(0, acc))
+                .wrapping_mul(PRIME_1)
+                .wrapping_add(PRIME_4);
+        }
+        hash
+    } else {
+        seed.wrapping_add(PRIME_5)
+    };
+
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut remainder = stripes.remainder();
+    while remainder.len() >= 8 {
+        hash ^= round(0, read_u64(remainder));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(PRIME_1)
+            .wrapping_add(PRIME_4);
+        remainder = &remainder[8..];
+    }
+
+    if remainder.len() >= 4 {
+        let value = u32::from_le_bytes(remainder[..4].try_into().expect("4 bytes")) as u64;
+        hash ^= value.wrapping_mul(PRIME_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(PRIME_2)
+            .wrapping_add(PRIME_3);
+        remainder = &remainder[4..];
+    }
+
+    for byte in remainder {
+        hash ^= (*byte as u64).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+fn round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME_1)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xxhash64;
+
+    #[test]
+    fn it_matches_reference_hashes() {
+        assert_eq!(xxhash64("", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxhash64("a", 0), 0xD24E_C4F1_A98C_6E5B);
+        assert_eq!(xxhash64("abc", 0), 0x44BC_2CF5_AD77_0999);
+        assert_eq!(
+            xxhash64("Nobody inspects the spammish repetition", 0),
+            0xFBCE_A83C_8A37_8BF1
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str::FromStr;
 
 /// Hex is a simple wrapper type that you can use to wrap your type so that it
 /// prints in lower hexadecimal format when use as a formatting argument.
@@ -21,6 +22,34 @@ use std::fmt;
 ///
 /// let encode = Hex::encode(&[0xabu8, 0xcdu8, 0xefu8]);
 /// ```
+///
+/// The alternate flag adds the `0x` prefix and `{:X}` prints in upper hexadecimal
+/// format, fixed-size values like addresses and hashes can be decoded directly into
+/// an array:
+///
+/// ```
+/// use substreams::Hex;
+///
+/// let address: [u8; 20] = Hex::decode_to_array("0x1f98431c8ad98523631ae4a59f267346ea31f984").unwrap();
+///
+/// assert_eq!(format!("{:#}", Hex(address)), "0x1f98431c8ad98523631ae4a59f267346ea31f984");
+/// assert_eq!(format!("{:X}", Hex(&address[..2])), "1F98");
+/// assert_eq!(format!("{:#X}", Hex(&address[..2])), "0x1F98");
+/// ```
+///
+/// The `_checked` decoding variants fail with a [HexError] quoting the offending input,
+/// making parsing failures of params and other runtime values actionable:
+///
+/// ```
+/// use substreams::Hex;
+///
+/// let err = Hex::try_decode_to::<20>("0x1f98431c").unwrap_err();
+/// assert_eq!(err.to_string(), "invalid hex \"0x1f98431c\": expected 20 bytes, got 4");
+///
+/// let err = "0x6e8z".parse::<Hex<Vec<u8>>>().unwrap_err();
+/// assert_eq!(err.to_string(), "invalid hex \"0x6e8z\": invalid character 'z' at position 5");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Hex<T>(pub T);
 
 impl<T: AsRef<[u8]>> Hex<T> {
@@ -30,13 +59,180 @@ impl<T: AsRef<[u8]>> Hex<T> {
         ::hex::decode(remove_hex_prefix(data_ref))
     }
 
+    /// Decodes `data` into a fixed-size array, failing with `InvalidStringLength` if
+    /// it doesn't hold exactly `N` bytes.
+    pub fn decode_to_array<const N: usize>(data: T) -> Result<[u8; N], hex::FromHexError> {
+        let mut out = [0u8; N];
+        ::hex::decode_to_slice(remove_hex_prefix(data.as_ref()), &mut out)?;
+
+        Ok(out)
+    }
+
+    /// Same as [Hex::decode] but failing with a [HexError] carrying the input.
+    pub fn decode_checked(data: T) -> Result<Vec<u8>, HexError> {
+        let data = data.as_ref();
+
+        ::hex::decode(remove_hex_prefix(data)).map_err(|err| HexError::new(data, err, None))
+    }
+
+    /// Same as [Hex::decode_to_array] but failing with a [HexError] carrying the input.
+    pub fn try_decode_to<const N: usize>(data: T) -> Result<[u8; N], HexError> {
+        let data = data.as_ref();
+        let mut out = [0u8; N];
+        ::hex::decode_to_slice(remove_hex_prefix(data), &mut out)
+            .map_err(|err| HexError::new(data, err, Some(N)))?;
+
+        Ok(out)
+    }
+
     pub fn encode(input: T) -> String {
         encode_lower_hex(input.as_ref())
     }
 
+    /// Encodes `input` as upper hexadecimal `String`.
+    pub fn encode_upper(input: T) -> String {
+        Hex(input).to_upper_string()
+    }
+
+    /// Encodes `input` as lower hexadecimal `String` prefixed with `0x`.
+    pub fn encode_prefixed(input: T) -> String {
+        Hex(input).to_prefixed_string()
+    }
+
     pub fn to_string(&self) -> String {
         encode_lower_hex(self.0.as_ref())
     }
+
+    pub fn to_upper_string(&self) -> String {
+        format!("{:X}", self)
+    }
+
+    pub fn to_prefixed_string(&self) -> String {
+        format!("{:#x}", self)
+    }
+}
+
+impl<const N: usize> FromStr for Hex<[u8; N]> {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hex::decode_to_array(s).map(Hex)
+    }
+}
+
+impl FromStr for Hex<Vec<u8>> {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hex::decode_checked(s).map(Hex)
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Hex<[u8; N]> {
+    fn from(bytes: [u8; N]) -> Self {
+        Hex(bytes)
+    }
+}
+
+/// Serializes to the lower hexadecimal string, without the `0x` prefix, like [fmt::Display].
+#[cfg(feature = "serde")]
+impl<T: AsRef<[u8]>> serde::Serialize for Hex<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Deserializes from an hexadecimal string, with or without the `0x` prefix.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Hex<Vec<u8>> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Hex::decode(value)
+            .map(Hex)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserializes from an hexadecimal string, with or without the `0x` prefix, holding
+/// exactly `N` bytes.
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Hex<[u8; N]> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Hex::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The error of the `_checked` decoding variants, carrying the input that failed to decode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HexError {
+    input: String,
+    kind: hex::FromHexError,
+    expected_len: Option<usize>,
+}
+
+impl HexError {
+    fn new(input: &[u8], kind: hex::FromHexError, expected_len: Option<usize>) -> Self {
+        let kind = match kind {
+            // Positions are reported relative to the input, `0x` prefix included
+            hex::FromHexError::InvalidHexCharacter { c, index } => {
+                hex::FromHexError::InvalidHexCharacter {
+                    c,
+                    index: index + (input.len() - remove_hex_prefix(input).len()),
+                }
+            }
+            kind => kind,
+        };
+
+        HexError {
+            input: String::from_utf8_lossy(input).into_owned(),
+            kind,
+            expected_len,
+        }
+    }
+
+    /// The input that failed to decode.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The position of the invalid character in the input, if any.
+    pub fn position(&self) -> Option<usize> {
+        match self.kind {
+            hex::FromHexError::InvalidHexCharacter { index, .. } => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &hex::FromHexError {
+        &self.kind
+    }
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid hex {:?}: ", self.input)?;
+
+        match (self.kind, self.expected_len) {
+            (hex::FromHexError::InvalidHexCharacter { c, index }, _) => {
+                write!(f, "invalid character {:?} at position {}", c, index)
+            }
+            (hex::FromHexError::OddLength, _) => f.write_str("odd number of digits"),
+            (hex::FromHexError::InvalidStringLength, Some(expected)) => write!(
+                f,
+                "expected {} bytes, got {}",
+                expected,
+                remove_hex_prefix(self.input.as_bytes()).len() / 2
+            ),
+            (hex::FromHexError::InvalidStringLength, None) => f.write_str("invalid length"),
+        }
+    }
+}
+
+impl std::error::Error for HexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
 }
 
 impl<T: AsRef<[u8]>> fmt::Debug for Hex<T> {
@@ -47,16 +243,30 @@ impl<T: AsRef<[u8]>> fmt::Debug for Hex<T> {
 
 impl<T: AsRef<[u8]>> fmt::Display for Hex<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write_lower_hex(self.0.as_ref(), f)
+        fmt::LowerHex::fmt(self, f)
     }
 }
 
 impl<T: AsRef<[u8]>> fmt::LowerHex for Hex<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+
         write_lower_hex(self.0.as_ref(), f)
     }
 }
 
+impl<T: AsRef<[u8]>> fmt::UpperHex for Hex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+
+        write_upper_hex(self.0.as_ref(), f)
+    }
+}
+
 const LOWER_HEX_BYTES: [&str; 256] = [
     "00", "01", "02", "03", "04", "05", "06", "07", "08", "09", "0a", "0b", "0c", "0d", "0e", "0f",
     "10", "11", "12", "13", "14", "15", "16", "17", "18", "19", "1a", "1b", "1c", "1d", "1e", "1f",
@@ -76,13 +286,10 @@ const LOWER_HEX_BYTES: [&str; 256] = [
     "f0", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "fa", "fb", "fc", "fd", "fe", "ff",
 ];
 
-fn remove_hex_prefix(data: &[u8]) -> &[u8] {
-    // Removing 0x prefix. "0" = 48, "x" = 120 (UTF-8)
-    if data[0] == (48 as u8) && data[1] == (120 as u8) {
-        return &data[2..data.len()];
-    }
+const UPPER_HEX_CHARS: &[u8; 16] = b"0123456789ABCDEF";
 
-    return data;
+fn remove_hex_prefix(data: &[u8]) -> &[u8] {
+    data.strip_prefix(b"0x").unwrap_or(data)
 }
 
 fn write_lower_hex(input: &[u8], mut w: impl std::fmt::Write) -> fmt::Result {
@@ -93,6 +300,15 @@ fn write_lower_hex(input: &[u8], mut w: impl std::fmt::Write) -> fmt::Result {
     return Ok(());
 }
 
+fn write_upper_hex(input: &[u8], mut w: impl std::fmt::Write) -> fmt::Result {
+    for byte in input {
+        w.write_char(UPPER_HEX_CHARS[(*byte >> 4) as usize] as char)?;
+        w.write_char(UPPER_HEX_CHARS[(*byte & 0x0f) as usize] as char)?;
+    }
+
+    Ok(())
+}
+
 fn encode_lower_hex<T: AsRef<[u8]>>(input: T) -> String {
     let bytes: &[u8] = input.as_ref();
 
@@ -100,7 +316,7 @@ fn encode_lower_hex<T: AsRef<[u8]>>(input: T) -> String {
         return String::new();
     }
 
-    let mut buffer = String::with_capacity(bytes.len());
+    let mut buffer = String::with_capacity(bytes.len() * 2);
     write_lower_hex(bytes, &mut buffer).expect("non-fallible pre-allocated buffer");
     buffer
 }
@@ -152,4 +368,89 @@ mod tests {
         let result = Hex::decode(input).unwrap();
         assert_eq!(Hex::encode(result), expected_result)
     }
+
+    #[test]
+    fn it_decode_to_array_correctly() {
+        let result: [u8; 2] = Hex::decode_to_array("0x6e8b").unwrap();
+        assert_eq!(result, [110, 139]);
+
+        assert_eq!(
+            Hex::decode_to_array::<3>("0x6e8b"),
+            Err(hex::FromHexError::InvalidStringLength)
+        );
+        assert_eq!(
+            Hex::decode_to_array::<1>("6e8"),
+            Err(hex::FromHexError::OddLength)
+        );
+        assert_eq!(Hex::decode_to_array::<0>("").unwrap(), [0u8; 0]);
+    }
+
+    #[test]
+    fn it_parses_fixed_size_hex() {
+        let address: Hex<[u8; 20]> = "0x1f98431c8ad98523631ae4a59f267346ea31f984"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            address.to_string(),
+            "1f98431c8ad98523631ae4a59f267346ea31f984"
+        );
+        assert!("0x1f98".parse::<Hex<[u8; 20]>>().is_err());
+    }
+
+    #[test]
+    fn it_formats_with_options() {
+        let value = Hex([0xa1u8, 0x0c]);
+
+        assert_eq!(format!("{}", value), "a10c");
+        assert_eq!(format!("{:#}", value), "0xa10c");
+        assert_eq!(format!("{:x}", value), "a10c");
+        assert_eq!(format!("{:#x}", value), "0xa10c");
+        assert_eq!(format!("{:X}", value), "A10C");
+        assert_eq!(format!("{:#X}", value), "0xA10C");
+        assert_eq!(format!("{:#?}", value), "a10c");
+        assert_eq!(Hex::encode_upper([0xa1u8]), "A1");
+        assert_eq!(Hex::encode_prefixed([0xa1u8]), "0xa1");
+    }
+
+    #[test]
+    fn it_reports_decoding_errors_with_input() {
+        let err = Hex::decode_checked("0x6e8z").unwrap_err();
+        assert_eq!(err.input(), "0x6e8z");
+        assert_eq!(err.position(), Some(5));
+        assert_eq!(
+            err.to_string(),
+            "invalid hex \"0x6e8z\": invalid character 'z' at position 5"
+        );
+
+        let err = Hex::decode_checked("6e8").unwrap_err();
+        assert_eq!(err.kind(), &hex::FromHexError::OddLength);
+        assert_eq!(err.to_string(), "invalid hex \"6e8\": odd number of digits");
+
+        assert_eq!(
+            Hex::try_decode_to::<3>("0x6e8b").unwrap_err().to_string(),
+            "invalid hex \"0x6e8b\": expected 3 bytes, got 2"
+        );
+        assert_eq!(Hex::try_decode_to::<2>("0x6e8b").unwrap(), [110, 139]);
+
+        let value: Hex<Vec<u8>> = "0x6e8b".parse().unwrap();
+        assert_eq!(value, Hex(vec![110, 139]));
+        assert_eq!(
+            "6e8x".parse::<Hex<Vec<u8>>>().unwrap_err().position(),
+            Some(3)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_as_string() {
+        let json = serde_json::to_string(&Hex([0xa1u8, 0x0c])).unwrap();
+        assert_eq!(json, r#""a10c""#);
+
+        let value: Hex<[u8; 2]> = serde_json::from_str(r#""0xa10c""#).unwrap();
+        assert_eq!(value, Hex([0xa1u8, 0x0c]));
+        let value: Hex<Vec<u8>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(value, Hex(vec![0xa1u8, 0x0c]));
+        assert!(serde_json::from_str::<Hex<[u8; 20]>>(&json).is_err());
+    }
 }
@@ -0,0 +1,80 @@
+//! Support for block index modules.
+//!
+//! Index modules output the [Keys] present in each block, the Substreams engine uses them
+//! to skip blocks that don't match a module's `blockFilter`. Use them with the
+//! [crate::handlers::index] macro:
+//!
+//! ```rust
+//! use substreams::index::Keys;
+//! # mod eth {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Block { #[prost(string, repeated, tag = "1")] pub addresses: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::index]
+//! fn index_addresses(blk: eth::Block) -> Keys {
+//!     blk.addresses.iter().map(|address| format!("address:{}", address)).collect()
+//! }
+//! ```
+
+use std::iter::FromIterator;
+
+pub use crate::pb::sf::substreams::index::v1::Keys;
+
+impl Keys {
+    pub fn new() -> Self {
+        Keys::default()
+    }
+
+    /// Adds `key` unless it's already present.
+    pub fn add<K: Into<String>>(&mut self, key: K) -> &mut Self {
+        let key = key.into();
+        if !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+        self
+    }
+
+    pub fn contains<K: AsRef<str>>(&self, key: K) -> bool {
+        self.keys.iter().any(|k| k == key.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<K: Into<String>> Extend<K> for Keys {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.add(key);
+        }
+    }
+}
+
+impl<K: Into<String>> FromIterator<K> for Keys {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys = Keys::new();
+        keys.extend(iter);
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Keys;
+
+    #[test]
+    fn it_deduplicates_keys() {
+        let mut keys: Keys = vec!["a", "b", "a"].into_iter().collect();
+        keys.add("c").add("b");
+
+        assert_eq!(keys.keys, vec!["a", "b", "c"]);
+        assert!(keys.contains("c"));
+        assert_eq!(keys.len(), 3);
+    }
+}
@@ -0,0 +1,247 @@
+//! Handler inputs received without decoding.
+//!
+//! A [RawBytes] input receives the encoded input message as-is, to hash or forward it
+//! without paying for its decoding. A [LazyInput] only decodes the message on the first
+//! call to [LazyInput::get], so a handler that samples its input or skips most blocks
+//! only decodes the ones it uses:
+//!
+//! ```rust
+//! use substreams::input::{LazyInput, RawBytes};
+//! use substreams::Hex;
+//! # mod eth { pub type Block = prost_types::Timestamp; }
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Sample { #[prost(string, tag = "1")] pub digest: String }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_forward(blk: RawBytes) -> pb::Sample {
+//!     pb::Sample { digest: Hex::encode(&blk[..8.min(blk.len())]) }
+//! }
+//!
+//! #[substreams::handlers::map]
+//! fn map_sample(blk: LazyInput<eth::Block>) -> Option<pb::Sample> {
+//!     if blk.bytes().len() % 100 != 0 {
+//!         return None;
+//!     }
+//!
+//!     Some(pb::Sample { digest: blk.get().seconds.to_string() })
+//! }
+//! ```
+
+use std::ops::Deref;
+use std::rc::Rc;
+
+use once_cell::unsync::OnceCell;
+
+use crate::codec::Codec;
+
+/// The encoded bytes of a handler input, received without being decoded.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawBytes(pub Vec<u8>);
+
+impl RawBytes {
+    /// Takes ownership of the input buffer allocated by the host, called by the handler
+    /// macros for `RawBytes` inputs.
+    pub fn from_ptr(ptr: *mut u8, size: usize) -> Self {
+        RawBytes(take_input(ptr, size))
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for RawBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for RawBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for RawBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        RawBytes(bytes)
+    }
+}
+
+impl From<RawBytes> for Vec<u8> {
+    fn from(bytes: RawBytes) -> Self {
+        bytes.0
+    }
+}
+
+/// A handler input decoded as `T` on first access.
+pub struct LazyInput<T> {
+    bytes: Vec<u8>,
+    value: OnceCell<T>,
+}
+
+impl<T: Codec> LazyInput<T> {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        LazyInput {
+            bytes,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Takes ownership of the input buffer allocated by the host, called by the handler
+    /// macros for `LazyInput<T>` inputs.
+    pub fn from_ptr(ptr: *mut u8, size: usize) -> Self {
+        Self::new(take_input(ptr, size))
+    }
+
+    /// Decodes the input on first call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input cannot be decoded as `T`.
+    pub fn get(&self) -> &T {
+        self.try_get().unwrap_or_else(|e| {
+            panic!(
+                "Unable to decode Protobuf data ({} bytes) to '{}' message's struct: {}",
+                self.bytes.len(),
+                std::any::type_name::<T>(),
+                e
+            )
+        })
+    }
+
+    /// Decodes the input on first call, returning the decoding error instead of panicking.
+    pub fn try_get(&self) -> Result<&T, T::DecodeError> {
+        self.value.get_or_try_init(|| {
+            crate::metrics::on_decode(self.bytes.len());
+            T::from_bytes(&self.bytes)
+        })
+    }
+
+    /// Returns `true` once the input has been decoded.
+    pub fn is_decoded(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// The encoded input, available whether it has been decoded or not.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Shares the `first` input decoded from `first_ptr` with another input of the same type
+/// received as the same bytes, returning `None` when they differ. Called by the handler
+/// macros for `Rc<T>` inputs, the input buffers being left allocated once decoded.
+///
+/// # Safety
+///
+/// `first_ptr` and `ptr` must point to `first_size` and `size` readable bytes.
+#[doc(hidden)]
+pub unsafe fn share_input<T>(
+    first: &Rc<T>,
+    first_ptr: *mut u8,
+    first_size: usize,
+    ptr: *mut u8,
+    size: usize,
+) -> Option<Rc<T>> {
+    let first_bytes = std::slice::from_raw_parts(first_ptr, first_size);
+    let bytes = std::slice::from_raw_parts(ptr, size);
+    if first_bytes != bytes {
+        return None;
+    }
+
+    #[cfg(feature = "replay")]
+    crate::replay::input(bytes);
+    Some(Rc::clone(first))
+}
+
+fn take_input(ptr: *mut u8, size: usize) -> Vec<u8> {
+    // The buffer was allocated by `memory::alloc` with a capacity of `size`
+    let bytes = unsafe { Vec::from_raw_parts(ptr, size, size) };
+    #[cfg(feature = "replay")]
+    crate::replay::input(&bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{share_input, LazyInput, RawBytes};
+    use crate::memory;
+    use prost_types::Timestamp;
+    use std::rc::Rc;
+
+    fn host_input(bytes: &[u8]) -> *mut u8 {
+        let ptr = memory::alloc(bytes.len());
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+        ptr
+    }
+
+    #[test]
+    fn it_receives_inputs_undecoded() {
+        let encoded = crate::proto::encode(&Timestamp {
+            seconds: 42,
+            nanos: 0,
+        })
+        .unwrap();
+
+        let raw = RawBytes::from_ptr(host_input(&encoded), encoded.len());
+        assert_eq!(&raw[..], &encoded[..]);
+
+        let lazy = LazyInput::<Timestamp>::from_ptr(host_input(&encoded), encoded.len());
+        assert!(!lazy.is_decoded());
+        assert_eq!(lazy.get().seconds, 42);
+        assert!(lazy.is_decoded());
+        assert_eq!(lazy.bytes(), &encoded[..]);
+
+        let invalid = LazyInput::<Timestamp>::new(vec![0xff]);
+        assert!(invalid.try_get().is_err());
+    }
+
+    #[test]
+    fn it_shares_identical_inputs() {
+        let encoded = crate::proto::encode(&Timestamp {
+            seconds: 42,
+            nanos: 0,
+        })
+        .unwrap();
+        let other = crate::proto::encode(&Timestamp {
+            seconds: 43,
+            nanos: 0,
+        })
+        .unwrap();
+
+        let first_ptr = host_input(&encoded);
+        let first =
+            Rc::new(crate::proto::decode_ptr::<Timestamp>(first_ptr, encoded.len()).unwrap());
+
+        let shared = unsafe {
+            share_input(
+                &first,
+                first_ptr,
+                encoded.len(),
+                host_input(&encoded),
+                encoded.len(),
+            )
+        };
+        assert!(Rc::ptr_eq(&first, &shared.unwrap()));
+
+        let distinct = unsafe {
+            share_input(
+                &first,
+                first_ptr,
+                encoded.len(),
+                host_input(&other),
+                other.len(),
+            )
+        };
+        assert!(distinct.is_none());
+    }
+}
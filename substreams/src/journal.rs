@@ -0,0 +1,183 @@
+//! Journal of the store operations performed by handlers, enabled by the `record` feature.
+//!
+//! Every store read and write going through this crate is appended to an in-memory journal
+//! as a [JournalEntry] holding the operation, the store, the ordinal, the key (along with the
+//! `after` key and the `limit` of scans) and the [crate::hashing::xxhash64] of the value, taken
+//! with [crate::testing::take_journal]. Running a handler on a fixed input and diffing the
+//! journal against a golden file validates that a refactor performs the same store accesses:
+//!
+//! ```rust,ignore
+//! testing::reset();
+//! let (ptr, len) = testing::input(&block);
+//! store_pools(ptr, len);
+//!
+//! let journal: Vec<String> = testing::take_journal().iter().map(ToString::to_string).collect();
+//! assert_eq!(journal.join("\n"), include_str!("golden/store_pools.txt").trim_end());
+//! ```
+//!
+//! In `wasm32` builds, where the rest of [crate::testing] isn't available, the journal is
+//! taken the same way and can be logged line by line with [crate::log::println]. It grows
+//! with every store access, enable the feature in debug builds only.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::hashing::xxhash64;
+
+/// A store access, `store_idx` is the readable store of a read and the selected output store
+/// of a write.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub op: String,
+    pub store_idx: u32,
+    pub ordinal: i64,
+    pub key: String,
+    /// The key the entries of a `scan_prefix` read sort after, `key` holding the scanned prefix.
+    pub after: Option<String>,
+    /// The maximum number of entries of a `scan_prefix` read, `None` for other accesses.
+    pub limit: Option<u32>,
+    /// The `xxhash64` of the value read or written, `None` for reads of missing keys. `has_*`
+    /// reads of existing keys and deletions hash an empty value.
+    pub value_hash: Option<u64>,
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} store={} ord={} key={:?}",
+            self.op, self.store_idx, self.ordinal, self.key
+        )?;
+
+        if let Some(limit) = self.limit {
+            match &self.after {
+                Some(after) => write!(f, " after={:?}", after)?,
+                None => write!(f, " after=-")?,
+            }
+            write!(f, " limit={}", limit)?;
+        }
+
+        match self.value_hash {
+            Some(hash) => write!(f, " value={:016x}", hash),
+            None => write!(f, " value=-"),
+        }
+    }
+}
+
+thread_local! {
+    static JOURNAL: RefCell<Vec<JournalEntry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the store accesses performed since the last call, in order, and clears the journal.
+pub fn take_journal() -> Vec<JournalEntry> {
+    JOURNAL.with(|journal| journal.take())
+}
+
+/// Journals the store read `op` of `key`, `value` being `None` when it's not found.
+pub(crate) fn read(op: &str, store_idx: u32, ordinal: i64, key: &str, value: Option<&[u8]>) {
+    push(op, store_idx, ordinal, key, value)
+}
+
+/// Journals the `scan_prefix` read of up to `limit` entries of `prefix` after `after`, `page`
+/// being the encoded entries, `None` when none matched.
+pub(crate) fn scan(
+    store_idx: u32,
+    prefix: &str,
+    after: Option<&str>,
+    limit: u32,
+    page: Option<&[u8]>,
+) {
+    JOURNAL.with(|journal| {
+        journal.borrow_mut().push(JournalEntry {
+            op: "scan_prefix".to_string(),
+            store_idx,
+            ordinal: 0,
+            key: prefix.to_string(),
+            after: after.map(str::to_string),
+            limit: Some(limit),
+            value_hash: page.map(|page| xxhash64(page, 0)),
+        })
+    })
+}
+
+/// Journals the store write `op` of `value` to `key` in the selected output store.
+pub(crate) fn write(op: &str, ordinal: i64, key: &str, value: &[u8]) {
+    push(
+        op,
+        crate::state::selected_output(),
+        ordinal,
+        key,
+        Some(value),
+    )
+}
+
+fn push(op: &str, store_idx: u32, ordinal: i64, key: &str, value: Option<&[u8]>) {
+    let entry = JournalEntry {
+        op: op.to_string(),
+        store_idx,
+        ordinal,
+        key: key.to_string(),
+        after: None,
+        limit: None,
+        value_hash: value.map(|value| xxhash64(value, 0)),
+    };
+
+    JOURNAL.with(|journal| journal.borrow_mut().push(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state;
+    use crate::store::{
+        StoreAdd, StoreAddInt64, StoreDelete, StoreGet, StoreGetString, StoreNew, StoreSet,
+        StoreSetString,
+    };
+    use crate::testing::{self, take_journal};
+
+    #[test]
+    fn it_journals_store_accesses() {
+        testing::reset();
+        testing::set_store_value(1, "token:dai", "DAI");
+
+        let names = StoreGetString::new(1);
+        let name = names.get_last("token:dai").unwrap();
+        assert!(!names.has_last("token:usdc"));
+
+        StoreSetString::new().set(3, "name:dai", &name);
+        StoreAddInt64::new().add(4, "count", 2);
+        StoreSetString::new().delete_prefix(5, &"name:".to_string());
+
+        let journal: Vec<String> = take_journal().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            journal,
+            vec![
+                r#"get_last store=1 ord=0 key="token:dai" value=5a5d80b6145f4534"#,
+                r#"has_last store=1 ord=0 key="token:usdc" value=-"#,
+                r#"set store=0 ord=3 key="name:dai" value=5a5d80b6145f4534"#,
+                r#"add_int64 store=0 ord=4 key="count" value=6021b5621680598b"#,
+                r#"delete_prefix store=0 ord=5 key="name:" value=ef46db3751d8e999"#,
+            ]
+        );
+
+        assert!(take_journal().is_empty());
+    }
+
+    #[test]
+    fn it_journals_scans() {
+        testing::reset();
+        testing::set_store_value(0, "a:1", "x");
+        testing::set_store_value(0, "a:2", "y");
+
+        state::scan_prefix(0, "a:", None, 1);
+        state::scan_prefix(0, "a:", Some("a:2"), 10);
+
+        let journal: Vec<String> = take_journal().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            journal,
+            vec![
+                r#"scan_prefix store=0 ord=0 key="a:" after=- limit=1 value=770e040abd35db17"#,
+                r#"scan_prefix store=0 ord=0 key="a:" after="a:2" limit=10 value=-"#,
+            ]
+        );
+    }
+}
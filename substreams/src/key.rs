@@ -36,17 +36,53 @@
 //! }
 //! ```
 //!
+//! Keys can be composed with [build] or [Builder], which reject empty segments and segments
+//! containing the `:` separator. Both produce values that can be passed directly to store
+//! methods:
+//!
+//! ```rust
+//! use substreams::key;
+//! use substreams::prelude::*;
+//!
+//! const USER: &str = key::static_segment("user");
+//!
+//! fn store_balances(address: String, store: StoreSetInt64) {
+//!     store.set(0, key::build([USER, &address, "balance"]), &10);
+//!     store.set(0, key::Builder::new().segment(USER).segment(&address).segment("count"), &1);
+//! }
+//! ```
+//!
+//! Segments holding arbitrary strings, like names or tenant identifiers, must be escaped so a
+//! `:` they contain doesn't shift the segments of the key. [escape_segment] replaces `%` with
+//! `%25` and `:` with `%3A`, [join_escaped] escapes every segment and [unescape_segment]
+//! reverts it. Literal segments without `%` and `:` are left untouched by the escaping, so
+//! they can be mixed with escaped ones:
+//!
+//! ```rust
+//! use substreams::key;
+//!
+//! let key = key::join_escaped(["tenant", "acme:eu", "balance"]);
+//! assert_eq!(key.as_str(), "tenant:acme%3Aeu:balance");
+//! assert_eq!(key::unescape_segment(key::segment_at(&key.to_string(), 1)), "acme:eu");
+//! ```
+//!
+//! The [DeltaExt](crate::store::DeltaExt) segment filters compare raw segments, they panic
+//! when given a value containing the separator, which could never match: escape it first.
+//!
+use crate::memo::Cache;
 use crate::prelude::Delta;
+use anyhow::{anyhow, Error};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hasher;
 use std::io::BufRead;
+use std::rc::Rc;
 
-pub fn segment_at(key: &String, index: usize) -> &str {
-    try_segment_at(key, index).unwrap_or_else(|| {
-        panic!(
-            "Unable to extract segment index {} out of key {}",
-            index, key
-        )
-    })
-}
+pub use substreams_core::key::{
+    escape_segment, first_segment, last_segment, segment_at, strip_namespace, try_first_segment,
+    try_last_segment, try_segment_at, try_unescape_segment, unescape_segment, SEPARATOR,
+};
 
 pub fn segment_at_owned(key: String, index: usize) -> String {
     let mut parts = std::io::Cursor::new(key.into_bytes()).split(b':');
@@ -71,34 +107,6 @@ pub fn segment_at_owned(key: String, index: usize) -> String {
         .expect("Must be valid UTF-8 here as we split an initially valid String")
 }
 
-pub fn first_segment(key: &String) -> &str {
-    segment_at(key, 0)
-}
-
-pub fn last_segment(key: &String) -> &str {
-    try_last_segment(key)
-        .unwrap_or_else(|| panic!("Unable to extract last segment out of key {}", key))
-}
-
-pub fn try_segment_at(key: &String, index: usize) -> Option<&str> {
-    let val = key.split(":").nth(index);
-    match val {
-        Some(val) => Some(val),
-        None => None,
-    }
-}
-
-pub fn try_first_segment(key: &String) -> Option<&str> {
-    try_segment_at(key, 0)
-}
-
-pub fn try_last_segment(key: &String) -> Option<&str> {
-    match key.split(":").last() {
-        Some(val) => Some(val),
-        None => None,
-    }
-}
-
 pub struct SegmentAtEq<I, S>
 where
     I: Iterator,
@@ -117,6 +125,8 @@ where
     S: AsRef<str>,
 {
     pub(crate) fn new(segment: S, at: Option<usize>, underlying: I) -> Self {
+        assert_filter_segment(segment.as_ref());
+
         Self {
             segment,
             at,
@@ -169,6 +179,10 @@ where
     V: AsRef<[S]>,
 {
     pub(crate) fn new(segments: V, at: Option<usize>, underlying: I) -> Self {
+        for segment in segments.as_ref() {
+            assert_filter_segment(segment.as_ref());
+        }
+
         Self {
             segments,
             at,
@@ -205,3 +219,505 @@ where
         None
     }
 }
+
+pub struct KeyStartsWith<I, S>
+where
+    I: Iterator,
+    S: AsRef<str>,
+{
+    prefix: S,
+    underlying: I,
+}
+
+impl<I, S> KeyStartsWith<I, S>
+where
+    I: Iterator,
+    I::Item: Delta,
+    S: AsRef<str>,
+{
+    pub(crate) fn new(prefix: S, underlying: I) -> Self {
+        Self { prefix, underlying }
+    }
+}
+
+impl<I, S> Iterator for KeyStartsWith<I, S>
+where
+    I: Iterator,
+    I::Item: Delta,
+    S: AsRef<str>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix = self.prefix.as_ref();
+        self.underlying
+            .by_ref()
+            .find(|x| x.get_key().starts_with(prefix))
+    }
+}
+
+pub struct KeyEndsWith<I, S>
+where
+    I: Iterator,
+    S: AsRef<str>,
+{
+    suffix: S,
+    underlying: I,
+}
+
+impl<I, S> KeyEndsWith<I, S>
+where
+    I: Iterator,
+    I::Item: Delta,
+    S: AsRef<str>,
+{
+    pub(crate) fn new(suffix: S, underlying: I) -> Self {
+        Self { suffix, underlying }
+    }
+}
+
+impl<I, S> Iterator for KeyEndsWith<I, S>
+where
+    I: Iterator,
+    I::Item: Delta,
+    S: AsRef<str>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let suffix = self.suffix.as_ref();
+        self.underlying
+            .by_ref()
+            .find(|x| x.get_key().ends_with(suffix))
+    }
+}
+
+#[cfg(feature = "regex")]
+pub struct KeyMatches<I, R>
+where
+    I: Iterator,
+    R: std::borrow::Borrow<regex::Regex>,
+{
+    regex: R,
+    underlying: I,
+}
+
+#[cfg(feature = "regex")]
+impl<I, R> KeyMatches<I, R>
+where
+    I: Iterator,
+    I::Item: Delta,
+    R: std::borrow::Borrow<regex::Regex>,
+{
+    pub(crate) fn new(regex: R, underlying: I) -> Self {
+        Self { regex, underlying }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl<I, R> Iterator for KeyMatches<I, R>
+where
+    I: Iterator,
+    I::Item: Delta,
+    R: std::borrow::Borrow<regex::Regex>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let regex = self.regex.borrow();
+        self.underlying
+            .by_ref()
+            .find(|x| regex.is_match(x.get_key()))
+    }
+}
+
+/// A key composed of validated segments, see [build] and [Builder].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key(String);
+
+impl Key {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split(SEPARATOR)
+    }
+}
+
+impl AsRef<str> for Key {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.0
+    }
+}
+
+/// Incrementally composes a [Key], each segment is validated as it's added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Builder {
+    key: String,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder { key: String::new() }
+    }
+
+    /// Appends a segment, panics if it's empty or contains the `:` separator.
+    pub fn segment<S: AsRef<str>>(self, segment: S) -> Self {
+        self.try_segment(segment)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_segment<S: AsRef<str>>(mut self, segment: S) -> Result<Self, Error> {
+        let segment = segment.as_ref();
+        validate_segment(segment)?;
+
+        if !self.key.is_empty() {
+            self.key.push(SEPARATOR);
+        }
+        self.key.push_str(segment);
+
+        Ok(self)
+    }
+
+    /// Appends `segment` escaped with [escape_segment], panics if it's empty.
+    pub fn escaped_segment<S: AsRef<str>>(self, segment: S) -> Self {
+        self.segment(escape_segment(segment.as_ref()))
+    }
+
+    /// Returns the key, panics if no segment were added.
+    pub fn build(self) -> Key {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_build(self) -> Result<Key, Error> {
+        if self.key.is_empty() {
+            return Err(anyhow!("key must have at least one segment"));
+        }
+
+        Ok(Key(self.key))
+    }
+}
+
+impl AsRef<str> for Builder {
+    fn as_ref(&self) -> &str {
+        &self.key
+    }
+}
+
+/// Joins `segments` into a [Key], panics if there is no segment or if one of them is empty
+/// or contains the `:` separator.
+pub fn build<S: AsRef<str>, I: IntoIterator<Item = S>>(segments: I) -> Key {
+    try_build(segments).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_build<S: AsRef<str>, I: IntoIterator<Item = S>>(segments: I) -> Result<Key, Error> {
+    segments
+        .into_iter()
+        .try_fold(Builder::new(), |builder, segment| {
+            builder.try_segment(segment)
+        })?
+        .try_build()
+}
+
+/// Same as [build] but escapes the segments with [escape_segment] instead of rejecting the
+/// ones containing the `:` separator, panics if there is no segment or if one is empty.
+pub fn join_escaped<S: AsRef<str>, I: IntoIterator<Item = S>>(segments: I) -> Key {
+    try_join_escaped(segments).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_join_escaped<S: AsRef<str>, I: IntoIterator<Item = S>>(
+    segments: I,
+) -> Result<Key, Error> {
+    try_build(
+        segments
+            .into_iter()
+            .map(|segment| escape_segment(segment.as_ref()).into_owned()),
+    )
+}
+
+/// Validates a literal segment at compile time when used in a `const` context.
+///
+/// ```compile_fail
+/// const INVALID: &str = substreams::key::static_segment("user:balance");
+/// ```
+pub const fn static_segment(segment: &'static str) -> &'static str {
+    let bytes = segment.as_bytes();
+    if bytes.is_empty() {
+        panic!("key segment cannot be empty");
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b':' {
+            panic!("key segment cannot contain the ':' separator");
+        }
+        i += 1;
+    }
+
+    segment
+}
+
+/// Default maximum number of keys kept by an [Interner].
+pub const DEFAULT_INTERNER_CAPACITY: usize = 4096;
+
+/// A bounded cache of formatted keys, to avoid formatting and allocating the same keys
+/// again and again when a module writes the same keys for many blocks.
+///
+/// Keys are looked up by formatting the arguments into a hasher, which doesn't allocate,
+/// and are only formatted into a new `String` when missing. The returned `Rc<str>` can be
+/// passed directly to store methods. When full, the oldest inserted key is evicted first.
+///
+/// [format_key!](crate::format_key) uses an interner shared by the whole module:
+///
+/// ```rust
+/// use substreams::format_key;
+/// use substreams::store::{StoreAdd, StoreAddInt64};
+///
+/// fn count_swaps(pools: Vec<String>, counts: StoreAddInt64) {
+///     for pool in pools.iter() {
+///         counts.add(0, format_key!("pool:{}:swaps", pool), 1);
+///     }
+/// }
+/// ```
+pub struct Interner {
+    keys: Cache<u64, Rc<str>>,
+}
+
+impl Interner {
+    pub fn new(capacity: usize) -> Self {
+        Interner {
+            keys: Cache::new(capacity),
+        }
+    }
+
+    /// Returns the interned copy of `key`.
+    pub fn intern(&mut self, key: &str) -> Rc<str> {
+        self.intern_fmt(format_args!("{}", key))
+    }
+
+    /// Returns the interned key formatted from `args`, usually built with `format_args!`.
+    pub fn intern_fmt(&mut self, args: fmt::Arguments<'_>) -> Rc<str> {
+        let mut hasher = HashWriter(DefaultHasher::new());
+        fmt::write(&mut hasher, args).expect("hashing a key cannot fail");
+        let hash = hasher.0.finish();
+
+        if let Some(key) = self.keys.get(&hash) {
+            let mut eq = EqWriter { rest: &key };
+            if fmt::write(&mut eq, args).is_ok() && eq.rest.is_empty() {
+                return key;
+            }
+
+            // Different keys with the same hash, the newest one isn't cached
+            return Rc::from(fmt::format(args));
+        }
+
+        let key: Rc<str> = Rc::from(fmt::format(args));
+        self.keys.insert(hash, key.clone());
+        key
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear()
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Interner::new(DEFAULT_INTERNER_CAPACITY)
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+/// Returns the key formatted from `args` from the module's shared [Interner], prefer the
+/// [format_key!](crate::format_key) macro.
+pub fn intern(args: fmt::Arguments<'_>) -> Rc<str> {
+    INTERNER.with(|interner| interner.borrow_mut().intern_fmt(args))
+}
+
+/// Formats a key like `format!` and interns it in the module's shared [Interner], returning
+/// an `Rc<str>` that can be passed directly to store methods. The key is only formatted into
+/// a new `String` the first time it's seen.
+///
+/// ```rust
+/// use substreams::format_key;
+///
+/// let key = format_key!("pool:{}:token0", "0x1234");
+/// assert_eq!(&*key, "pool:0x1234:token0");
+/// ```
+#[macro_export]
+macro_rules! format_key {
+    ($($arg:tt)*) => {
+        $crate::key::intern(format_args!($($arg)*))
+    };
+}
+
+/// Feeds the formatted key to the hasher, chunk by chunk.
+struct HashWriter(DefaultHasher);
+
+impl fmt::Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Compares the formatted key to an interned one, chunk by chunk, failing on the first
+/// difference.
+struct EqWriter<'a> {
+    rest: &'a str,
+}
+
+impl fmt::Write for EqWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.rest.strip_prefix(s) {
+            Some(rest) => {
+                self.rest = rest;
+                Ok(())
+            }
+            None => Err(fmt::Error),
+        }
+    }
+}
+
+/// Segments containing the separator never match a key segment, it's a missing escaping.
+fn assert_filter_segment(segment: &str) {
+    if segment.contains(SEPARATOR) {
+        panic!(
+            "key segment filter {:?} cannot contain the ':' separator, escape it with key::escape_segment",
+            segment
+        );
+    }
+}
+
+fn validate_segment(segment: &str) -> Result<(), Error> {
+    if segment.is_empty() {
+        return Err(anyhow!("key segment cannot be empty"));
+    }
+
+    if segment.contains(SEPARATOR) {
+        return Err(anyhow!(
+            "key segment {:?} cannot contain the ':' separator",
+            segment
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build, escape_segment, join_escaped, try_build, try_unescape_segment, unescape_segment,
+        Builder, Interner,
+    };
+
+    #[test]
+    fn it_builds_keys() {
+        let address = "0x1234".to_string();
+        let key = build(["user", &address, "balance"]);
+
+        assert_eq!(key.as_str(), "user:0x1234:balance");
+        assert_eq!(
+            key.segments().collect::<Vec<_>>(),
+            vec!["user", "0x1234", "balance"]
+        );
+        assert_eq!(
+            Builder::new().segment("user").segment(&address).build(),
+            build(["user", "0x1234"])
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_segments() {
+        assert!(try_build(["user", ""]).is_err());
+        assert!(try_build(["user", "a:b"]).is_err());
+        assert!(try_build(Vec::<String>::new()).is_err());
+        assert!(Builder::new().try_segment("a:b").is_err());
+    }
+
+    #[test]
+    fn it_escapes_segments() {
+        assert_eq!(escape_segment("0x1234"), "0x1234");
+        assert_eq!(escape_segment("a:b%c"), "a%3Ab%25c");
+        assert_eq!(unescape_segment("a%3Ab%25c"), "a:b%c");
+        assert_eq!(unescape_segment("%253A"), "%3A");
+        assert_eq!(try_unescape_segment("%3a").unwrap(), ":");
+        assert_eq!(try_unescape_segment("100%"), None);
+        assert_eq!(try_unescape_segment("%zz"), None);
+
+        for segment in ["", ":", "%", "%3A", "a::b%%", "é:%ü"] {
+            assert_eq!(unescape_segment(&escape_segment(segment)), segment);
+        }
+
+        let key = join_escaped(["tenant", "acme:eu", "100%"]);
+        assert_eq!(key.as_str(), "tenant:acme%3Aeu:100%25");
+        assert_eq!(
+            Builder::new()
+                .segment("tenant")
+                .escaped_segment("acme:eu")
+                .segment("100%25")
+                .build(),
+            key
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "escape it with key::escape_segment")]
+    fn it_rejects_filters_with_separators() {
+        use crate::pb::substreams::StoreDelta;
+        use crate::store::{DeltaExt, DeltaString, Deltas};
+
+        let deltas = Deltas::<DeltaString>::new(vec![StoreDelta {
+            key: "tenant:acme%3Aeu".to_string(),
+            ..Default::default()
+        }]);
+        let _ = deltas.iter().key_last_segment_eq("acme:eu");
+    }
+
+    #[test]
+    fn it_interns_formatted_keys() {
+        let mut interner = Interner::new(2);
+        let address = "0x1234";
+
+        let key = interner.intern_fmt(format_args!("pool:{}:token{}", address, 0));
+        assert_eq!(&*key, "pool:0x1234:token0");
+
+        let again = interner.intern_fmt(format_args!("pool:{}:token{}", address, 0));
+        assert!(std::rc::Rc::ptr_eq(&key, &again));
+        assert!(std::rc::Rc::ptr_eq(
+            &key,
+            &interner.intern("pool:0x1234:token0")
+        ));
+
+        interner.intern("pool:0x1234:token1");
+        interner.intern("pool:0x1234:token2");
+        assert_eq!(interner.len(), 2);
+        let evicted = interner.intern_fmt(format_args!("pool:{}:token{}", address, 0));
+        assert!(!std::rc::Rc::ptr_eq(&key, &evicted));
+        assert_eq!(key, evicted);
+    }
+}
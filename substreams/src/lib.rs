@@ -106,29 +106,119 @@
 //!     // to something
 //! }
 //!```
+//!
+//! ## Targets
+//!
+//! Modules are compiled to `wasm32-unknown-unknown` by default. To run on engines using
+//! the WASI import model, compile to `wasm32-wasip1` (or `wasm32-wasip2`) with the `wasi`
+//! feature enabled, the module output is then written to stdout and logs to stderr, the
+//! `store`, `log` and `output` APIs are unchanged.
+//!
+//! With the `component` feature each handler is also exported as a component model function
+//! returning its output, so the same module can be wrapped as a WASI preview2 component, see
+//! the `component` module.
 extern crate core;
 
+pub mod abi;
+mod batch;
+pub mod block;
+pub mod cache;
+pub mod chunked;
+pub mod clock;
+pub mod codec;
+pub mod compaction;
+#[cfg(feature = "component")]
+pub mod component;
+pub mod context;
+pub mod emit;
+#[cfg(feature = "pb-bundled")]
+pub mod entity;
 pub mod errors;
 
 mod externs;
+pub mod ffi;
+pub mod guardrails;
 pub mod handlers;
+pub mod hashing;
 mod hex;
+#[cfg(feature = "pb-bundled")]
+pub mod index;
+pub mod input;
+#[cfg(feature = "record")]
+mod journal;
 pub mod log;
+pub mod memo;
 pub mod memory;
+pub mod metadata;
+pub mod metrics;
+pub mod migrate;
+pub mod params;
+pub mod pipeline;
 
 /// Protobuf generated Substreams models
+///
+/// `pb::substreams` holds the `sf.substreams.v1` messages the engine exchanges with the
+/// module (store deltas, clock, progress), always compiled with this crate's `prost`. The
+/// index, sink and telemetry messages of `pb::sf` come with the default `pb-bundled` feature.
+///
+/// Workspaces generating those messages themselves, possibly with another `prost` version,
+/// build without default features and with `pb-external` instead: `pb::sf` and the `index`,
+/// `entity`, `sink` and `telemetry` modules are left out and the user's types are used in
+/// their place. Handler inputs and outputs only need a [codec::Codec] implementation, and a
+/// user-supplied `Clock` input implements [clock::ToClock] so [clock()] keeps returning it:
+///
+/// ```rust
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Clock { #[prost(string, tag = "1")] pub id: String, #[prost(uint64, tag = "2")] pub number: u64 }
+/// # }
+/// impl substreams::clock::ToClock for pb::Clock {
+///     fn to_clock(&self) -> substreams::clock::Clock {
+///         substreams::clock::Clock {
+///             id: self.id.clone(),
+///             number: self.number,
+///             timestamp: None,
+///         }
+///     }
+/// }
+/// ```
 pub mod pb;
+pub mod progress;
 pub mod proto;
+pub mod registry;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod retention;
+pub mod runtime_config;
 pub mod scalar;
+#[cfg(feature = "pb-bundled")]
+pub mod sink;
 
 mod state;
 
 pub mod key;
+#[cfg(feature = "keccak")]
+pub mod storage_layout;
 pub mod store;
+#[cfg(feature = "pb-bundled")]
+pub mod telemetry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+/// The store journal of `wasm32` builds with the `record` feature, see [journal].
+#[cfg(all(target_arch = "wasm32", feature = "record"))]
+pub mod testing {
+    pub use crate::journal::{take_journal, JournalEntry};
+}
+pub mod windows;
+#[cfg(feature = "debug-assertions")]
+pub mod write_checks;
 
+#[cfg(any(feature = "expr", feature = "expr-lite"))]
 pub mod expr_parser;
+#[cfg(any(feature = "expr", feature = "expr-lite"))]
 pub use expr_parser::{expr_matcher, matches_keys_in_parsed_expr, ExprMatcher};
 
+mod change;
 mod operation;
 
 /// A prelude that makes all store traits available.
@@ -142,59 +232,340 @@ pub mod prelude {
     pub use crate::scalar::{BigDecimal, BigInt};
     pub use crate::store::{
         Appender, Delta, DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaBool, DeltaBytes,
-        DeltaFloat64, DeltaInt32, DeltaInt64, DeltaProto, DeltaString, Deltas, StoreAdd,
+        DeltaBytesArray, DeltaEnum, DeltaFloat64, DeltaInt32, DeltaInt64, DeltaProto,
+        DeltaProtoArray, DeltaString, DeltaUint64, DeltaValues, Deltas, LazyDeltaProto, StoreAdd,
         StoreAddBigDecimal, StoreAddBigInt, StoreAddFloat64, StoreAddInt64, StoreAppend,
-        StoreDelete, StoreGet, StoreGetBigDecimal, StoreGetBigInt, StoreGetFloat64, StoreGetInt64,
-        StoreGetProto, StoreGetRaw, StoreGetString, StoreMax, StoreMaxBigDecimal, StoreMaxBigInt,
+        StoreAppendBytes, StoreAppendProto, StoreDelete, StoreGet, StoreGetBigDecimal,
+        StoreGetBigInt, StoreGetBool, StoreGetBytesArray, StoreGetEnum, StoreGetFloat64,
+        StoreGetInt32, StoreGetInt64, StoreGetProto, StoreGetProtoArray, StoreGetRaw,
+        StoreGetString, StoreGetUint64, StoreMax, StoreMaxBigDecimal, StoreMaxBigInt,
         StoreMaxFloat64, StoreMaxInt64, StoreMin, StoreMinBigDecimal, StoreMinBigInt,
         StoreMinFloat64, StoreMinInt64, StoreNew, StoreSet, StoreSetBigDecimal, StoreSetBigInt,
-        StoreSetFloat64, StoreSetIfNotExists, StoreSetIfNotExistsBigDecimal,
-        StoreSetIfNotExistsBigInt, StoreSetIfNotExistsFloat64, StoreSetIfNotExistsInt64,
-        StoreSetIfNotExistsProto, StoreSetIfNotExistsRaw, StoreSetIfNotExistsString, StoreSetInt64,
-        StoreSetProto, StoreSetRaw, StoreSetString,
+        StoreSetBool, StoreSetEnum, StoreSetFloat64, StoreSetIfNotExists,
+        StoreSetIfNotExistsBigDecimal, StoreSetIfNotExistsBigInt, StoreSetIfNotExistsBool,
+        StoreSetIfNotExistsFloat64, StoreSetIfNotExistsInt32, StoreSetIfNotExistsInt64,
+        StoreSetIfNotExistsProto, StoreSetIfNotExistsRaw, StoreSetIfNotExistsString,
+        StoreSetIfNotExistsUint64, StoreSetInt32, StoreSetInt64, StoreSetOnce, StoreSetOnceBigInt,
+        StoreSetOnceProto, StoreSetOnceRaw, StoreSetOnceString, StoreSetProto, StoreSetRaw,
+        StoreSetString, StoreSetUint64,
     };
+    #[cfg(feature = "lexicographic-min-max")]
+    pub use crate::store::{StoreMaxBytes, StoreMaxString, StoreMinBytes, StoreMinString};
+
+    /// Leaner prelude bringing only the store traits in scope, concrete types are imported
+    /// through the [store::get](crate::store::get), [store::set](crate::store::set) and
+    /// [store::delta](crate::store::delta) namespaces so they don't collide with the crate's
+    /// own types:
+    ///
+    /// ```
+    /// use substreams::prelude::v2::*;
+    /// use substreams::store::{delta, get, set};
+    ///
+    /// // The crate's own type isn't shadowed by the prelude
+    /// struct DeltaInt64;
+    ///
+    /// #[substreams::handlers::store]
+    /// fn store_totals(
+    ///     deltas: delta::Deltas<delta::DeltaInt64>,
+    ///     prices: get::StoreGetInt64,
+    ///     totals: set::StoreAddInt64,
+    /// ) {
+    ///     for d in deltas.deltas.iter() {
+    ///         let price = prices.get_last(d.get_key()).unwrap_or(1);
+    ///         totals.add(d.ordinal, d.get_key(), (d.new_value - d.old_value) * price);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The [prelude](crate::prelude) itself is unchanged.
+    pub mod v2 {
+        pub use crate::store::{
+            AppendCodec, Appender, Delta, DeltaExt, DeltaValues, NumericDelta, StoreAdd,
+            StoreDelete, StoreGet, StoreMax, StoreMin, StoreNew, StoreNewOutput, StoreSet,
+            StoreSetIfNotExists, StoreSetOnce, StoreSetSum,
+        };
+    }
 }
 
-pub use crate::hex::Hex;
+pub use crate::emit::emit;
+pub use crate::hex::{Hex, HexError};
+pub use crate::params::Params;
+pub use crate::registry::registry;
 pub use hex_literal::hex;
 
+/// Caches the results of a pure function in a bounded per-instance map keyed by its
+/// arguments, see the [memo] module for details.
+pub use substreams_macro::memoize;
+
+/// Checks at compile time the `#[substreams::handlers::map]` and `#[substreams::handlers::store]`
+/// functions of the crate against the modules declared in a Substreams manifest, the path
+/// being relative to the crate's `Cargo.toml`:
+///
+/// ```rust,ignore
+/// substreams::manifest!("substreams.yaml");
+/// ```
+///
+/// The crate's `src` files are scanned for handlers and a compile error is reported when a
+/// handler isn't declared in the manifest, when its kind differs, when its inputs don't match
+/// the module inputs (sources, maps, params, stores in `get` or `deltas` mode and their value
+/// types), when a map handler's output isn't the declared output type or when a store
+/// handler's writable store doesn't match the `updatePolicy` and `valueType`. Modules without
+/// a handler in the crate are ignored.
+pub use substreams_macro::manifest;
+
+use std::cell::{Cell, RefCell};
+
+/// Returns the value of `key` provided by the server running the module, `None` if it
+/// doesn't provide one or doesn't support runtime settings, see the [runtime_config] module.
+pub fn runtime_config(key: &str) -> Option<String> {
+    runtime_config::get(key)
+}
+
+/// Returns the clock of the block being processed, recorded by handlers taking a
+/// `clock: Clock` input, see the [clock] module.
+pub fn clock() -> Option<clock::Clock> {
+    clock::current()
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn output<M: prost::Message>(msg: M) {
+pub fn output<M: codec::Codec>(msg: M) {
+    // Need to return the buffer and forget about it issue occurred when trying to write large data
+    // wasm was "dropping" the data before we could write to it, which causes us to have garbage
+    // value. By forgetting the data we can properly call external output function to write the
+    // msg to heap.
+    let (ptr, len, buffer) = proto::encode_to_ptr(&msg).unwrap_or_else(|_| {
+        panic!(
+            "Unable to encode '{}' message's struct to Protobuf data",
+            stringify!(M)
+        )
+    });
+
+    #[cfg(feature = "replay")]
+    replay::write("output", 0, "", &buffer);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::output(buffer);
+
     #[cfg(target_arch = "wasm32")]
     {
-        // Need to return the buffer and forget about it issue occurred when trying to write large data
-        // wasm was "dropping" the data before we could write to it, which causes us to have garbage
-        // value. By forgetting the data we can properly call external output function to write the
-        // msg to heap.
-        let (ptr, len, buffer) = proto::encode_to_ptr(&msg).unwrap_or_else(|_| {
-            panic!(
-                "Unable to encode '{}' message's struct to Protobuf data",
-                stringify!(M)
-            )
-        });
         std::mem::forget(buffer);
         unsafe { externs::output(ptr, len as u32) }
     }
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+/// Same as [output] but panics when the encoded message is larger than `max_bytes`, before
+/// encoding it when the [codec::Codec] knows the encoded size (`prost` messages do), instead
+/// of failing on the server limits once the whole output has been sent:
+///
+/// ```rust
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Transfers { #[prost(string, repeated, tag = "1")] pub ids: Vec<String> }
+/// # }
+/// fn emit(transfers: pb::Transfers) {
+///     substreams::output_with_limit(transfers, 64 * 1024 * 1024);
+/// }
+/// ```
+pub fn output_with_limit<M: codec::Codec>(msg: M, max_bytes: usize) {
+    if let Some(len) = msg.encoded_len() {
+        check_output_len::<M>(len, max_bytes);
+    }
+
+    let data = proto::encode(&msg).unwrap_or_else(|_| {
+        panic!(
+            "Unable to encode '{}' message's struct to Protobuf data",
+            std::any::type_name::<M>()
+        )
+    });
+    check_output_len::<M>(data.len(), max_bytes);
+
+    output_raw(data)
+}
+
+/// Outputs the items as the repeated message field `1` of the output, encoded one at a time,
+/// see [chunked::ChunkedOutput] (**experimental**).
+pub fn output_chunked<M: codec::Codec, I: IntoIterator<Item = M>>(items: I) {
+    let mut output = chunked::ChunkedOutput::new();
+    output.extend(items);
+    output.finish()
+}
+
+pub(crate) fn check_output_len<M>(len: usize, max_bytes: usize) {
+    if len > max_bytes {
+        panic!(
+            "output '{}' of {} bytes exceeds the limit of {} bytes",
+            std::any::type_name::<M>(),
+            len,
+            max_bytes
+        );
+    }
+}
+
+/// Tells the engine to skip the module's output when it's empty, see [output_policy] to
+/// decide from within a handler.
 pub fn skip_empty_output() {
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::skip_empty_output();
+
     #[cfg(target_arch = "wasm32")]
     unsafe {
         externs::skip_empty_output()
     }
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+/// Whether the engine skips the module's output when it's empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputPolicy {
+    /// Empty outputs are skipped, the default of handlers generated by the handler macros.
+    SkipEmpty,
+    /// Empty outputs are kept, the default of handlers with the `keep_empty_output` option.
+    KeepEmpty,
+}
+
+thread_local! {
+    static OUTPUT_POLICY: Cell<OutputPolicy> = const { Cell::new(OutputPolicy::KeepEmpty) };
+}
+
+/// Sets the output policy of the running handler, overriding the one selected by the
+/// handler macro options. The last policy set wins, it's applied once the handler returns:
+///
+/// ```rust
+/// use substreams::OutputPolicy;
+/// # mod eth { pub type Block = (); }
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Transfers { #[prost(string, repeated, tag = "1")] pub ids: Vec<String> }
+/// # }
+///
+/// #[substreams::handlers::map]
+/// fn map_transfers(blk: eth::Block) -> pb::Transfers {
+///     let transfers = pb::Transfers { ids: vec![] };
+///     if transfers.ids.is_empty() {
+///         // Downstream modules rely on an output for every block
+///         substreams::output_policy(OutputPolicy::KeepEmpty);
+///     }
+///
+///     transfers
+/// }
+/// ```
+pub fn output_policy(policy: OutputPolicy) {
+    OUTPUT_POLICY.with(|current| current.set(policy))
+}
+
+/// Returns the output policy of the running handler.
+pub fn current_output_policy() -> OutputPolicy {
+    OUTPUT_POLICY.with(|current| current.get())
+}
+
+/// Applies the output policy of the running handler and resets it to
+/// [OutputPolicy::KeepEmpty], called by the handler macros once the handler returned.
+pub fn apply_output_policy() {
+    let policy = OUTPUT_POLICY.with(|current| current.replace(OutputPolicy::KeepEmpty));
+    if policy == OutputPolicy::SkipEmpty {
+        skip_empty_output();
+    }
+}
+
 pub fn output_raw(data: Vec<u8>) {
+    #[cfg(feature = "replay")]
+    replay::write("output", 0, "", &data);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::output(data);
+
     #[cfg(target_arch = "wasm32")]
     unsafe {
         externs::output(data.as_ptr(), data.len() as u32)
     }
 }
 
-/// Registers a Substreams custom panic hook. The panic hook is invoked when then handler panics
+/// Suppresses a handler's output when it's identical to the output emitted on the previous
+/// block by the same instance, the first output seen is always emitted.
+///
+/// Used by `#[substreams::handlers::map(skip_identical_output)]` from a `thread_local!`, it
+/// keeps the previous encoded output around between blocks to compare the next one against
+/// it byte for byte.
+pub struct IdenticalOutputFilter {
+    last: RefCell<Option<Vec<u8>>>,
+}
+
+impl IdenticalOutputFilter {
+    pub fn new() -> Self {
+        IdenticalOutputFilter {
+            last: RefCell::new(None),
+        }
+    }
+
+    /// Records `data` as the last output and returns `true` if it's identical to the
+    /// previously recorded one.
+    pub fn is_identical(&self, data: &[u8]) -> bool {
+        let mut last = self.last.borrow_mut();
+        match last.as_mut() {
+            Some(last) if last.as_slice() == data => true,
+            Some(last) => {
+                last.clear();
+                last.extend_from_slice(data);
+                false
+            }
+            None => {
+                *last = Some(data.to_vec());
+                false
+            }
+        }
+    }
+
+    /// Encodes `msg` and outputs it unless it's identical to the previous output.
+    pub fn output<M: codec::Codec>(&self, msg: M) {
+        let data = proto::encode(&msg).unwrap_or_else(|_| {
+            panic!(
+                "Unable to encode '{}' message's struct to Protobuf data",
+                std::any::type_name::<M>()
+            )
+        });
+
+        if self.is_identical(&data) {
+            return;
+        }
+
+        output_raw(data)
+    }
+}
+
+impl Default for IdenticalOutputFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Starts recording the host interactions of the handler with the `replay` feature, called
+/// by the handler macros before decoding the inputs. Does nothing without it.
+#[doc(hidden)]
+pub fn begin_handler_trace() {
+    #[cfg(feature = "replay")]
+    replay::begin_handler();
+}
 
+/// Finishes the trace started by [begin_handler_trace], called by the handler macros once
+/// the output is emitted or before panicking on an error, and by the panic hook. See
+/// `replay::last_trace`, does nothing without the `replay` feature.
+#[doc(hidden)]
+pub fn finish_handler_trace() {
+    #[cfg(feature = "replay")]
+    replay::finish_handler();
+}
+
+/// Returns `value`, recorded as a handler input with the `replay` feature. Called by the
+/// handler macros for inputs copied out of their buffer instead of decoded.
+#[doc(hidden)]
+pub fn trace_input<T: AsRef<[u8]>>(value: T) -> T {
+    #[cfg(feature = "replay")]
+    replay::input(value.as_ref());
+    value
+}
+
+/// Registers a Substreams custom panic hook. The panic hook is invoked when then handler panics,
+/// the reported message includes the running handler and the spans opened through
+/// [context::span], unless replaced with [set_error_reporter].
 pub fn register_panic_hook() {
     #[cfg(target_arch = "wasm32")]
     {
@@ -206,43 +577,136 @@ pub fn register_panic_hook() {
     }
 }
 
+thread_local! {
+    static ERROR_REPORTER: Cell<Option<ErrorReporter>> = const { Cell::new(None) };
+}
+
+/// A function reporting a handler panic to the host, see [set_error_reporter].
+// `PanicHookInfo` is only available from Rust 1.81
+#[allow(deprecated)]
+pub type ErrorReporter = fn(&std::panic::PanicInfo<'_>);
+
+/// Replaces the default formatting of the panic hook: `reporter` is called with the panic
+/// instead and reports it to the host through [report_error], with its own serialization
+/// (for example including module-specific context).
+///
+/// The reporter is only called once the panic hook is registered, handlers declared with
+/// `no_panic_hook` must call [register_panic_hook] themselves.
+///
+/// ```rust
+/// # mod eth { pub type Block = prost_types::Timestamp; }
+/// fn report(info: &std::panic::PanicInfo<'_>) {
+///     let handler = substreams::context::handler().unwrap_or("unknown");
+///     let message = format!("[pools:{}] {}", handler, info);
+///     substreams::report_error(&message, info.location());
+/// }
+///
+/// #[substreams::handlers::map]
+/// fn map_pools(blk: eth::Block) -> eth::Block {
+///     substreams::set_error_reporter(report);
+///     blk
+/// }
+/// ```
+pub fn set_error_reporter(reporter: ErrorReporter) {
+    ERROR_REPORTER.with(|current| current.set(Some(reporter)))
+}
+
+/// Reports a handler error to the host the way the panic hook does, to be called by an
+/// [ErrorReporter]. Does nothing outside of WASM.
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused))]
-fn hook(info: &std::panic::PanicInfo<'_>) {
+pub fn report_error(message: &str, location: Option<&std::panic::Location<'_>>) {
     #[cfg(target_arch = "wasm32")]
-    {
-        let error_msg = info
-            .payload()
-            .downcast_ref::<String>()
-            .map(String::as_str)
-            .or_else(|| info.payload().downcast_ref::<&'static str>().copied())
-            .unwrap_or("");
-        let location = info.location();
-
-        unsafe {
-            let _ = match location {
-                Some(loc) => {
-                    let file = loc.file();
-                    let line = loc.line();
-                    let column = loc.column();
-
-                    externs::register_panic(
-                        error_msg.as_ptr(),
-                        error_msg.len() as u32,
-                        file.as_ptr(),
-                        file.len() as u32,
-                        line,
-                        column,
-                    )
-                }
-                None => externs::register_panic(
-                    error_msg.as_ptr(),
-                    error_msg.len() as u32,
-                    std::ptr::null(),
-                    0,
-                    0,
-                    0,
-                ),
-            };
+    unsafe {
+        match location {
+            Some(loc) => {
+                let file = loc.file();
+                let line = loc.line();
+                let column = loc.column();
+
+                externs::register_panic(
+                    message.as_ptr(),
+                    message.len() as u32,
+                    file.as_ptr(),
+                    file.len() as u32,
+                    line,
+                    column,
+                )
+            }
+            None => externs::register_panic(
+                message.as_ptr(),
+                message.len() as u32,
+                std::ptr::null(),
+                0,
+                0,
+                0,
+            ),
         }
     }
 }
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused))]
+fn hook(info: &std::panic::PanicInfo<'_>) {
+    // The handler aborts, log its trace now so the failing run can be replayed
+    finish_handler_trace();
+
+    if let Some(reporter) = ERROR_REPORTER.with(|current| current.get()) {
+        return reporter(info);
+    }
+
+    let error_msg = info
+        .payload()
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| info.payload().downcast_ref::<&'static str>().copied())
+        .unwrap_or("");
+    let error_msg = context::panic_message(error_msg);
+    report_error(&error_msg, info.location());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_output_policy, current_output_policy, output_policy, testing};
+    use super::{IdenticalOutputFilter, OutputPolicy};
+
+    #[test]
+    fn it_identical_output_filter_always_emits_first() {
+        let filter = IdenticalOutputFilter::new();
+
+        assert!(!filter.is_identical(b""));
+        assert!(filter.is_identical(b""));
+        assert!(!filter.is_identical(b"a"));
+        assert!(filter.is_identical(b"a"));
+        assert!(!filter.is_identical(b"b"));
+        assert!(!filter.is_identical(b"bb"));
+        assert!(!filter.is_identical(b"b"));
+    }
+
+    #[test]
+    fn it_applies_last_output_policy() {
+        testing::reset();
+
+        output_policy(OutputPolicy::SkipEmpty);
+        output_policy(OutputPolicy::KeepEmpty);
+        apply_output_policy();
+        assert!(!testing::skips_empty_output());
+
+        output_policy(OutputPolicy::SkipEmpty);
+        apply_output_policy();
+        assert!(testing::skips_empty_output());
+        assert_eq!(current_output_policy(), OutputPolicy::KeepEmpty);
+    }
+
+    #[test]
+    #[should_panic(expected = "of 5 bytes exceeds the limit of 4 bytes")]
+    fn it_limits_output_size() {
+        let timestamp = prost_types::Timestamp {
+            seconds: 1000,
+            nanos: 1,
+        };
+
+        super::output_with_limit(timestamp, 5);
+        assert_eq!(testing::take_output(), Some(timestamp));
+
+        super::output_with_limit(timestamp, 4);
+    }
+}
@@ -0,0 +1,106 @@
+//! Memoization support for Substreams handlers.
+//!
+//! This module holds the runtime pieces used by the [crate::memoize] attribute. A WASM
+//! instance processes many blocks in a row, so results cached here survive across
+//! blocks for as long as the instance lives.
+//!
+//! The arguments of a memoized function are its cache key: they must implement `Clone`,
+//! `Hash` and `Eq`, borrowed arguments being kept as their owned form (a `&str` as a
+//! `String`), so a cached value is only returned for arguments equal to the ones that
+//! produced it.
+//!
+//! ```rust
+//! #[substreams::memoize(capacity = 512)]
+//! fn price_path(token: String, depth: u32) -> Vec<String> {
+//!     // Expensive computation that always returns the same output for the same inputs
+//!     vec![token; depth as usize]
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Default maximum number of entries kept by a memoized function.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A bounded cache of values keyed by the arguments that produced them.
+///
+/// When full, the oldest inserted entry is evicted first.
+pub struct Cache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    insertion_order: VecDeque<K>,
+}
+
+impl<K: Clone + Hash + Eq, V: Clone> Cache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Cache {
+            capacity,
+            entries: HashMap::new(),
+            insertion_order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries.get(key).cloned()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.insert(key.clone(), value).is_some() {
+            return;
+        }
+
+        self.insertion_order.push_back(key);
+        if self.insertion_order.len() > self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.insertion_order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+
+    #[test]
+    fn it_evicts_oldest_entry_when_full() {
+        let mut cache = Cache::new(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(1, "a");
+        cache.insert(3, "c");
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some("b"));
+        assert_eq!(cache.get(&3), Some("c"));
+    }
+
+    #[test]
+    fn it_only_returns_values_of_equal_keys() {
+        let mut cache = Cache::new(4);
+        cache.insert(("a".to_string(), 1u32), 10);
+
+        assert_eq!(cache.get(&("a".to_string(), 1)), Some(10));
+        assert_eq!(cache.get(&("a".to_string(), 2)), None);
+        assert_eq!(cache.get(&("b".to_string(), 1)), None);
+    }
+}
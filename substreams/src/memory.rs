@@ -2,6 +2,7 @@ use std::{convert::TryInto, slice};
 
 #[no_mangle]
 pub fn alloc(size: usize) -> *mut u8 {
+    crate::metrics::on_alloc(size);
     let mut buf = Vec::with_capacity(size);
     let ptr = buf.as_mut_ptr();
 
@@ -0,0 +1,198 @@
+//! Handlers metadata embedded in the compiled WASM module.
+//!
+//! Each function annotated with `#[substreams::handlers::map]` or `#[substreams::handlers::store]`
+//! contributes a record to the [SECTION_NAME] custom section of the WASM binary. Tooling (CLI
+//! validators, registries) can read that section and use [parse] to introspect a module's
+//! handlers without having access to its manifest.
+//!
+//! Each record is a single `\n` terminated line made of `\t` separated fields:
+//!
+//! ```text
+//! <format_version>\t<kind>\t<name>\t<inputs>\t<output>
+//! ```
+//!
+//! Where `<inputs>` is a `;` separated list of `<role>:<name>:<type>` entries and `<output>`
+//! is the handler's return type as written in the source (empty for `store` handlers).
+
+use anyhow::{anyhow, Context, Error};
+
+/// Name of the WASM custom section holding handlers metadata.
+pub const SECTION_NAME: &str = "substreams_metadata";
+
+/// Version of the record format written by this crate's macros.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The kind of a handler, matches the `kind` of the module in the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerKind {
+    Map,
+    Store,
+    Index,
+}
+
+/// How a handler's input is received by the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputRole {
+    /// A Protobuf message, either a source or the output of a `map` module.
+    Proto,
+    /// A raw `String`, usually the module's `params`.
+    String,
+    /// The module's `params` decoded as [crate::Params].
+    Params,
+    /// Raw `Vec<u8>` params.
+    Bytes,
+    /// A store in `get` mode.
+    Get,
+    /// A store in `deltas` mode.
+    Deltas,
+    /// The store the handler writes to.
+    Store,
+    /// A raw store index passed as-is to the handler.
+    Idx,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMetadata {
+    pub role: InputRole,
+    pub name: String,
+    pub type_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerMetadata {
+    pub kind: HandlerKind,
+    pub name: String,
+    pub inputs: Vec<InputMetadata>,
+    /// The handler's return type, `None` for `store` handlers.
+    pub output: Option<String>,
+}
+
+/// Parses the content of the [SECTION_NAME] custom section into the list of handlers it describes.
+pub fn parse(section: &[u8]) -> Result<Vec<HandlerMetadata>, Error> {
+    let content = std::str::from_utf8(section).context("metadata section is not valid UTF-8")?;
+
+    content
+        .lines()
+        // The linker may pad the section between records
+        .map(|line| line.trim_matches('\0'))
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_record(line).with_context(|| format!("invalid record {:?}", line)))
+        .collect()
+}
+
+fn parse_record(line: &str) -> Result<HandlerMetadata, Error> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 5 {
+        return Err(anyhow!("expected 5 fields, got {}", fields.len()));
+    }
+
+    let version: u32 = fields[0].parse().context("invalid format version")?;
+    if version != FORMAT_VERSION {
+        return Err(anyhow!("unsupported format version {}", version));
+    }
+
+    let kind = match fields[1] {
+        "map" => HandlerKind::Map,
+        "store" => HandlerKind::Store,
+        "index" => HandlerKind::Index,
+        other => return Err(anyhow!("unknown handler kind {:?}", other)),
+    };
+
+    let inputs = fields[3]
+        .split(';')
+        .filter(|input| !input.is_empty())
+        .map(parse_input)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(HandlerMetadata {
+        kind,
+        name: fields[2].to_string(),
+        inputs,
+        output: match fields[4] {
+            "" => None,
+            output => Some(output.to_string()),
+        },
+    })
+}
+
+fn parse_input(input: &str) -> Result<InputMetadata, Error> {
+    let mut parts = input.splitn(3, ':');
+    let (role, name, type_name) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(role), Some(name), Some(type_name)) => (role, name, type_name),
+        _ => return Err(anyhow!("invalid input {:?}", input)),
+    };
+
+    let role = match role {
+        "proto" => InputRole::Proto,
+        "string" => InputRole::String,
+        "params" => InputRole::Params,
+        "bytes" => InputRole::Bytes,
+        "get" => InputRole::Get,
+        "deltas" => InputRole::Deltas,
+        "store" => InputRole::Store,
+        "idx" => InputRole::Idx,
+        other => return Err(anyhow!("unknown input role {:?}", other)),
+    };
+
+    Ok(InputMetadata {
+        role,
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_section() {
+        let section = b"1\tmap\tmap_transfers\tproto:blk:eth::Block;get:pairs:StoreGetProto<pb::Pairs>\tResult<pb::Custom,Error>\n\
+            1\tstore\tstore_values\tproto:blk:eth::Block;store:store:StoreAddInt64\t\n";
+
+        assert_eq!(
+            parse(section).unwrap(),
+            vec![
+                HandlerMetadata {
+                    kind: HandlerKind::Map,
+                    name: "map_transfers".to_string(),
+                    inputs: vec![
+                        InputMetadata {
+                            role: InputRole::Proto,
+                            name: "blk".to_string(),
+                            type_name: "eth::Block".to_string(),
+                        },
+                        InputMetadata {
+                            role: InputRole::Get,
+                            name: "pairs".to_string(),
+                            type_name: "StoreGetProto<pb::Pairs>".to_string(),
+                        },
+                    ],
+                    output: Some("Result<pb::Custom,Error>".to_string()),
+                },
+                HandlerMetadata {
+                    kind: HandlerKind::Store,
+                    name: "store_values".to_string(),
+                    inputs: vec![
+                        InputMetadata {
+                            role: InputRole::Proto,
+                            name: "blk".to_string(),
+                            type_name: "eth::Block".to_string(),
+                        },
+                        InputMetadata {
+                            role: InputRole::Store,
+                            name: "store".to_string(),
+                            type_name: "StoreAddInt64".to_string(),
+                        },
+                    ],
+                    output: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_version() {
+        assert!(parse(b"2\tmap\tmap_transfers\t\tpb::Custom\n").is_err());
+    }
+}
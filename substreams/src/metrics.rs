@@ -0,0 +1,163 @@
+//! Resource usage counters maintained by the SDK, to profile a module without external
+//! tooling.
+//!
+//! The counters accumulate store writes and reads, bytes encoded and decoded through
+//! [crate::proto] (including the handler output) and memory allocated through the `alloc`
+//! export the host uses to pass inputs. [log] reports them and starts a new measurement, call
+//! it at the end of the handler to profile each block:
+//!
+//! ```rust
+//! use substreams::metrics;
+//! use substreams::store::{StoreAdd, StoreAddInt64, StoreNew};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Tokens { #[prost(string, repeated, tag = "1")] pub tokens: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::store]
+//! fn store_counts(tokens: pb::Tokens, counts: StoreAddInt64) {
+//!     for token in tokens.tokens.iter() {
+//!         counts.add(0, token, 1);
+//!     }
+//!
+//!     // Logs e.g. `metrics: store_writes=12 store_reads=0 bytes_encoded=0 bytes_decoded=0 ...`
+//!     metrics::log();
+//! }
+//! ```
+//!
+//! [snapshot] and [take] give access to the counters, to output them along the module's data.
+
+use std::cell::Cell;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Writes made to writable stores, including the ones buffered by [crate::store::batch].
+    pub store_writes: u64,
+    /// Reads from readable stores: keys looked up with `get_*` and `has_*`, and prefix scans.
+    pub store_reads: u64,
+    /// Bytes produced by [crate::proto::encode], the handler output included.
+    pub bytes_encoded: u64,
+    /// Bytes decoded by [crate::proto::decode] and [crate::proto::decode_ptr], the handler
+    /// inputs included.
+    pub bytes_decoded: u64,
+    /// Calls to the `alloc` export.
+    pub allocations: u64,
+    /// Bytes requested through the `alloc` export.
+    pub allocated_bytes: u64,
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "store_writes={} store_reads={} bytes_encoded={} bytes_decoded={} allocations={} allocated_bytes={}",
+            self.store_writes,
+            self.store_reads,
+            self.bytes_encoded,
+            self.bytes_decoded,
+            self.allocations,
+            self.allocated_bytes
+        )
+    }
+}
+
+thread_local! {
+    static METRICS: Cell<Metrics> = const { Cell::new(Metrics {
+        store_writes: 0,
+        store_reads: 0,
+        bytes_encoded: 0,
+        bytes_decoded: 0,
+        allocations: 0,
+        allocated_bytes: 0,
+    }) };
+}
+
+/// Returns the counters accumulated since the last [take], [log] or [reset].
+pub fn snapshot() -> Metrics {
+    METRICS.with(|metrics| metrics.get())
+}
+
+/// Returns the counters accumulated since the last [take], [log] or [reset] and resets them.
+pub fn take() -> Metrics {
+    METRICS.with(|metrics| metrics.replace(Metrics::default()))
+}
+
+/// Resets the counters.
+pub fn reset() {
+    take();
+}
+
+/// Logs the counters accumulated since the last [take], [log] or [reset] and resets them.
+pub fn log() {
+    crate::log::println(format!("metrics: {}", take()));
+}
+
+fn update<F: FnOnce(&mut Metrics)>(f: F) {
+    METRICS.with(|metrics| {
+        let mut current = metrics.get();
+        f(&mut current);
+        metrics.set(current);
+    })
+}
+
+pub(crate) fn on_write() {
+    update(|m| m.store_writes += 1)
+}
+
+pub(crate) fn on_read(count: usize) {
+    update(|m| m.store_reads += count as u64)
+}
+
+pub(crate) fn on_encode(len: usize) {
+    update(|m| m.bytes_encoded += len as u64)
+}
+
+pub(crate) fn on_decode(len: usize) {
+    update(|m| m.bytes_decoded += len as u64)
+}
+
+pub(crate) fn on_alloc(size: usize) {
+    update(|m| {
+        m.allocations += 1;
+        m.allocated_bytes += size as u64;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{snapshot, take, Metrics};
+    use crate::pb::substreams::{StoreDelta, StoreDeltas};
+    use crate::store::{StoreGet, StoreGetInt64, StoreNew, StoreSet, StoreSetInt64};
+
+    #[test]
+    fn it_counts_store_and_codec_usage() {
+        crate::testing::reset();
+        take();
+
+        StoreSetInt64::new().set(1, "a", &1);
+        StoreSetInt64::new().set_many(1, &vec!["b", "c"], &2);
+        StoreGetInt64::new(0).get_many_last(["a", "b"]);
+        let encoded = crate::proto::encode(&StoreDeltas {
+            deltas: vec![StoreDelta {
+                key: "key".to_string(),
+                ..Default::default()
+            }],
+        })
+        .unwrap();
+        crate::proto::decode::<StoreDeltas>(&encoded).unwrap();
+        crate::memory::alloc(16);
+
+        let expected = Metrics {
+            store_writes: 3,
+            store_reads: 2,
+            bytes_encoded: encoded.len() as u64,
+            bytes_decoded: encoded.len() as u64,
+            allocations: 1,
+            allocated_bytes: 16,
+        };
+        assert_eq!(snapshot(), expected);
+        assert_eq!(take(), expected);
+        assert_eq!(snapshot(), Metrics::default());
+    }
+}
@@ -0,0 +1,287 @@
+//! Versioned store values, upgraded on read when their encoding changes.
+//!
+//! Long-lived stores keep the values written by previous versions of a module, changing the
+//! encoding of a value (a Protobuf field changing type, a message replaced by another) breaks
+//! the decoding of these values. Wrapping the value in [Versioned] prefixes it with the
+//! [Schema::VERSION] of its type on write, values of older versions are upgraded on read by the
+//! [Migrations] of the type:
+//!
+//! ```rust
+//! use substreams::migrate::{Migrations, Schema, Versioned};
+//! use substreams::store::{StoreGet, StoreGetProto};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct PoolV1 { #[prost(string, tag = "1")] pub fee: String }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pool { #[prost(uint32, tag = "2")] pub fee_bps: u32 }
+//! # }
+//!
+//! impl Schema for pb::Pool {
+//!     const VERSION: u8 = 2;
+//!
+//!     fn migrations() -> Migrations<Self> {
+//!         // Version 1 stored the fee as a decimal string
+//!         Migrations::new().upgrade_from(1, |old: pb::PoolV1| pb::Pool {
+//!             fee_bps: old.fee.parse().unwrap_or_default(),
+//!         })
+//!     }
+//! }
+//!
+//! fn read_pool(pools: StoreGetProto<Versioned<pb::Pool>>) -> Option<pb::Pool> {
+//!     pools.get_last("pool:0x1f98").map(Versioned::into_inner)
+//! }
+//! ```
+//!
+//! Every value of the store must be written through [Versioned], values written before
+//! adopting it have no version byte and can't be told apart from versioned ones.
+
+use std::fmt;
+use std::ops::Deref;
+
+use anyhow::{anyhow, Context, Error};
+
+use crate::codec::Codec;
+
+/// A type stored with a version, see the [module documentation](self).
+pub trait Schema: Codec {
+    /// The version written in front of new values, starting at 1 for the first encoding.
+    const VERSION: u8;
+
+    /// Upgrades of the values written by older versions, only built when such a value is read.
+    fn migrations() -> Migrations<Self> {
+        Migrations::new()
+    }
+}
+
+type Upgrade<T> = Box<dyn Fn(&[u8]) -> Result<T, Error>>;
+
+/// Upgrade functions of a [Schema] keyed by the version they read.
+pub struct Migrations<T> {
+    upgrades: Vec<(u8, Upgrade<T>)>,
+}
+
+impl<T> Migrations<T> {
+    pub fn new() -> Self {
+        Migrations {
+            upgrades: Vec::new(),
+        }
+    }
+
+    /// Registers `upgrade` decoding the bytes of values written with `version`, replacing a
+    /// previous upgrade of the same version.
+    pub fn upgrade<F>(mut self, version: u8, upgrade: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<T, Error> + 'static,
+    {
+        self.upgrades.retain(|(v, _)| *v != version);
+        self.upgrades.push((version, Box::new(upgrade)));
+        self
+    }
+
+    /// Registers an upgrade decoding values written with `version` as `O` before converting
+    /// them with `convert`.
+    pub fn upgrade_from<O, F>(self, version: u8, convert: F) -> Self
+    where
+        O: Codec,
+        F: Fn(O) -> T + 'static,
+    {
+        self.upgrade(version, move |bytes| {
+            O::from_bytes(bytes)
+                .map(&convert)
+                .map_err(|e| anyhow!("{}", e))
+        })
+    }
+
+    /// The versions having an upgrade, in registration order.
+    pub fn versions(&self) -> impl Iterator<Item = u8> + '_ {
+        self.upgrades.iter().map(|(version, _)| *version)
+    }
+
+    fn apply(&self, version: u8, bytes: &[u8]) -> Option<Result<T, Error>> {
+        self.upgrades
+            .iter()
+            .find(|(v, _)| *v == version)
+            .map(|(_, upgrade)| upgrade(bytes))
+    }
+}
+
+impl<T> Default for Migrations<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Migrations<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.versions()).finish()
+    }
+}
+
+/// A value encoded with its [Schema::VERSION] as first byte, decoding values of older versions
+/// through [Schema::migrations].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Versioned<T>(pub T);
+
+impl<T> Versioned<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Versioned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Versioned<T> {
+    fn from(value: T) -> Self {
+        Versioned(value)
+    }
+}
+
+impl<T: Schema> Codec for Versioned<T> {
+    type EncodeError = T::EncodeError;
+    type DecodeError = Error;
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError> {
+        let mut bytes = Vec::with_capacity(1 + self.0.encoded_len().unwrap_or_default());
+        bytes.push(T::VERSION);
+        bytes.extend(self.0.to_bytes()?);
+        Ok(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        let (version, data) = match bytes.split_first() {
+            Some((version, data)) => (*version, data),
+            None => {
+                return Err(anyhow!(
+                    "versioned value is empty, missing its version byte"
+                ))
+            }
+        };
+
+        if version == T::VERSION {
+            return T::from_bytes(data)
+                .map(Versioned)
+                .map_err(|e| anyhow!("decoding version {}: {}", version, e));
+        }
+
+        if version > T::VERSION {
+            return Err(anyhow!(
+                "value written by version {}, newer than the current version {}",
+                version,
+                T::VERSION
+            ));
+        }
+
+        match T::migrations().apply(version, data) {
+            Some(result) => result
+                .map(Versioned)
+                .with_context(|| format!("upgrading version {} to {}", version, T::VERSION)),
+            None => Err(anyhow!(
+                "no migration registered for version {} (current version is {})",
+                version,
+                T::VERSION
+            )),
+        }
+    }
+
+    fn encoded_len(&self) -> Option<usize> {
+        self.0.encoded_len().map(|len| len + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Migrations, Schema, Versioned};
+    use crate::codec::Codec;
+    use anyhow::anyhow;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Price(u64);
+
+    impl Codec for Price {
+        type EncodeError = std::convert::Infallible;
+        type DecodeError = anyhow::Error;
+
+        fn to_bytes(&self) -> Result<Vec<u8>, Self::EncodeError> {
+            Ok(self.0.to_le_bytes().to_vec())
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+            use std::convert::TryInto;
+
+            Ok(Price(u64::from_le_bytes(bytes.try_into()?)))
+        }
+    }
+
+    impl Schema for Price {
+        const VERSION: u8 = 3;
+
+        fn migrations() -> Migrations<Self> {
+            Migrations::new()
+                // Version 1 stored the price as a decimal string
+                .upgrade(1, |bytes| {
+                    let price = std::str::from_utf8(bytes)?.parse::<u64>()?;
+                    Ok(Price(price))
+                })
+                // Version 2 stored the price in cents as a `u32`
+                .upgrade(2, |bytes| match bytes {
+                    [a, b, c, d] => Ok(Price(u32::from_le_bytes([*a, *b, *c, *d]) as u64 * 10)),
+                    _ => Err(anyhow!("expected 4 bytes")),
+                })
+        }
+    }
+
+    #[test]
+    fn it_prefixes_the_current_version() {
+        let bytes = Versioned(Price(42)).to_bytes().unwrap();
+        assert_eq!(bytes, [3, 42, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Versioned(Price(42)).encoded_len(), None);
+        assert_eq!(
+            Versioned::<Price>::from_bytes(&bytes).unwrap(),
+            Versioned(Price(42))
+        );
+    }
+
+    #[test]
+    fn it_upgrades_older_versions() {
+        assert_eq!(
+            Versioned::<Price>::from_bytes(b"\x01120")
+                .unwrap()
+                .into_inner(),
+            Price(120)
+        );
+        assert_eq!(
+            *Versioned::<Price>::from_bytes(&[2, 12, 0, 0, 0]).unwrap(),
+            Price(120)
+        );
+
+        let err = Versioned::<Price>::from_bytes(&[2, 12]).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "upgrading version 2 to 3: expected 4 bytes"
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_versions() {
+        let err = Versioned::<Price>::from_bytes(&[0, 1]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no migration registered for version 0 (current version is 3)"
+        );
+
+        let err = Versioned::<Price>::from_bytes(&[4, 1]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value written by version 4, newer than the current version 3"
+        );
+
+        assert!(Versioned::<Price>::from_bytes(&[]).is_err());
+        assert_eq!(format!("{:?}", Price::migrations()), "[1, 2]");
+    }
+}
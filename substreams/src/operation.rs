@@ -1,7 +1,74 @@
+use std::convert::TryFrom;
+
 use crate::{pb::substreams::store_delta::Operation, store::Delta};
 
+/// The operation of a store delta.
+///
+/// Owned by this crate rather than being the Protobuf `Operation` enum so that operations
+/// introduced server-side after a module was compiled are received as [DeltaOperation::Unknown]
+/// instead of aborting the module. It compares equal to the matching Protobuf `Operation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeltaOperation {
+    Unset,
+    Create,
+    Update,
+    Delete,
+    /// An operation unknown to this version of the crate, holding its Protobuf value.
+    Unknown(i32),
+}
+
+impl DeltaOperation {
+    /// Returns the Protobuf value of the operation.
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            DeltaOperation::Unset => Operation::Unset as i32,
+            DeltaOperation::Create => Operation::Create as i32,
+            DeltaOperation::Update => Operation::Update as i32,
+            DeltaOperation::Delete => Operation::Delete as i32,
+            DeltaOperation::Unknown(value) => *value,
+        }
+    }
+
+    /// Returns `true` for operations unknown to this version of the crate.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, DeltaOperation::Unknown(_))
+    }
+}
+
+impl From<i32> for DeltaOperation {
+    fn from(value: i32) -> Self {
+        match Operation::try_from(value) {
+            Ok(operation) => operation.into(),
+            Err(_) => DeltaOperation::Unknown(value),
+        }
+    }
+}
+
+impl From<Operation> for DeltaOperation {
+    fn from(operation: Operation) -> Self {
+        match operation {
+            Operation::Unset => DeltaOperation::Unset,
+            Operation::Create => DeltaOperation::Create,
+            Operation::Update => DeltaOperation::Update,
+            Operation::Delete => DeltaOperation::Delete,
+        }
+    }
+}
+
+impl PartialEq<Operation> for DeltaOperation {
+    fn eq(&self, other: &Operation) -> bool {
+        *self == DeltaOperation::from(*other)
+    }
+}
+
+impl PartialEq<DeltaOperation> for Operation {
+    fn eq(&self, other: &DeltaOperation) -> bool {
+        DeltaOperation::from(*self) == *other
+    }
+}
+
 pub struct OperationIs<I: Iterator> {
-    operation: Operation,
+    operation: DeltaOperation,
     negate: bool,
     underlying: I,
 }
@@ -11,7 +78,7 @@ where
     I: Iterator,
     I::Item: Delta,
 {
-    pub(crate) fn new(operation: Operation, negate: bool, underlying: I) -> Self {
+    pub(crate) fn new(operation: DeltaOperation, negate: bool, underlying: I) -> Self {
         Self {
             operation,
             negate,
@@ -0,0 +1,224 @@
+//! Typed access to a module's `params` input.
+//!
+//! Params are received as a raw string, [Params] decodes them as URL query string
+//! (`key=value` pairs separated by `&`, percent-encoded) and offers typed accessors:
+//!
+//! ```rust
+//! use substreams::Params;
+//! use substreams::errors::Error;
+//! # mod eth { pub type Block = (); }
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pools {}
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_pools(params: Params, blk: eth::Block) -> Result<pb::Pools, Error> {
+//!     let factory_block: u64 = params.get("factory_block")?;
+//!     let factory = params.get_str("factory").unwrap_or("0x1f98431c8ad98523631ae4a59f267346ea31f984");
+//!
+//!     Ok(pb::Pools {})
+//! }
+//! ```
+//!
+//! Params that are not `key=value` pairs, like a single address, remain accessible
+//! through [Params::as_str]. Parsing never fails, malformed percent-encoding is kept as-is
+//! and only reported when the value is accessed through [Params::get] or [Params::try_get].
+
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Params {
+    raw: String,
+    pairs: Vec<(String, String)>,
+    /// Indexes of the pairs holding malformed percent-encoding, kept as-is in `pairs`.
+    malformed: Vec<usize>,
+}
+
+impl Params {
+    /// Decodes `raw` as URL query string, malformed percent-encoding is kept as-is.
+    pub fn parse<S: Into<String>>(raw: S) -> Params {
+        let raw = raw.into();
+        let mut pairs = Vec::new();
+        let mut malformed = Vec::new();
+
+        for pair in raw.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair.find('=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, ""),
+            };
+
+            let (key, valid_key) = decode_component(key);
+            let (value, valid_value) = decode_component(value);
+            if !valid_key || !valid_value {
+                malformed.push(pairs.len());
+            }
+
+            pairs.push((key, value));
+        }
+
+        Params {
+            raw,
+            pairs,
+            malformed,
+        }
+    }
+
+    /// The params as received, before any decoding.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Decoded `key=value` pairs in the order they appear.
+    pub fn as_pairs(&self) -> &[(String, String)] {
+        &self.pairs
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get_str(key).is_some()
+    }
+
+    /// Returns the decoded value of the first pair named `key`.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.position(key).map(|i| self.pairs[i].1.as_str())
+    }
+
+    /// Returns the decoded values of all pairs named `key`.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Parses the value of `key`, failing if it's missing or invalid.
+    pub fn get<T>(&self, key: &str) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.try_get(key)?
+            .ok_or_else(|| anyhow!("missing param {:?}", key))
+    }
+
+    /// Parses the value of `key` if present, failing if it's invalid or holds malformed
+    /// percent-encoding.
+    pub fn try_get<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let i = match self.position(key) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+
+        let value = &self.pairs[i].1;
+        if self.malformed.contains(&i) {
+            return Err(anyhow!(
+                "invalid percent-encoding in param {:?} value {:?}",
+                key,
+                value
+            ));
+        }
+
+        T::from_str(value)
+            .map(Some)
+            .map_err(|e| anyhow!("invalid param {:?} value {:?}: {}", key, value, e))
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.pairs.iter().position(|(k, _)| k == key)
+    }
+}
+
+impl FromStr for Params {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Params::parse(s))
+    }
+}
+
+/// Percent-decodes `input`, returning `false` alongside when it's malformed: invalid escapes
+/// are then kept as-is and invalid UTF-8 replaced.
+fn decode_component(input: &str) -> (String, bool) {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut valid = true;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                match hex {
+                    Some(hex) => {
+                        out.push(hex);
+                        i += 2;
+                    }
+                    None => {
+                        out.push(b'%');
+                        valid = false;
+                    }
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    match String::from_utf8(out) {
+        Ok(out) => (out, valid),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Params;
+
+    #[test]
+    fn it_parses_query_string() {
+        let params = Params::parse("factory_block=12369621&name=Uniswap+V3&tag=a%26b&tag=c");
+
+        assert_eq!(params.get::<u64>("factory_block").unwrap(), 12369621);
+        assert_eq!(params.get_str("name"), Some("Uniswap V3"));
+        assert_eq!(params.get_all("tag"), vec!["a&b", "c"]);
+        assert_eq!(params.try_get::<u64>("missing").unwrap(), None);
+        assert!(params.get::<u64>("missing").is_err());
+        assert!(params.get::<u64>("name").is_err());
+    }
+
+    #[test]
+    fn it_keeps_raw_params() {
+        let params = Params::parse("0xabcd");
+
+        assert_eq!(params.as_str(), "0xabcd");
+        assert_eq!(params.as_pairs(), &[("0xabcd".to_string(), "".to_string())]);
+    }
+
+    #[test]
+    fn it_keeps_malformed_percent_encoding() {
+        let params = Params::parse("a=%zz&b=1&c=100%&d=%ff&e=50%25");
+
+        assert_eq!(params.as_str(), "a=%zz&b=1&c=100%&d=%ff&e=50%25");
+        assert_eq!(params.get_str("a"), Some("%zz"));
+        assert_eq!(params.get_str("c"), Some("100%"));
+        assert_eq!(params.get::<u64>("b").unwrap(), 1);
+        assert_eq!(params.get::<String>("e").unwrap(), "50%");
+        assert!(params.get::<String>("a").is_err());
+        assert!(params.get::<String>("c").is_err());
+        assert!(params.try_get::<String>("d").is_err());
+    }
+}
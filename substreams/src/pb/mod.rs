@@ -1,3 +1,4 @@
+#[cfg(feature = "pb-bundled")]
 pub mod sf {
     pub mod substreams {
         pub mod index {
@@ -7,6 +8,29 @@ pub mod sf {
                 // @@protoc_insertion_point(sf.substreams.index.v1)
             }
         }
+        pub mod sink {
+            pub mod entity {
+                // @@protoc_insertion_point(attribute:sf.substreams.sink.entity.v1)
+                pub mod v1 {
+                    include!("sf.substreams.sink.entity.v1.rs");
+                    // @@protoc_insertion_point(sf.substreams.sink.entity.v1)
+                }
+            }
+            pub mod kv {
+                // @@protoc_insertion_point(attribute:sf.substreams.sink.kv.v1)
+                pub mod v1 {
+                    include!("sf.substreams.sink.kv.v1.rs");
+                    // @@protoc_insertion_point(sf.substreams.sink.kv.v1)
+                }
+            }
+        }
+        pub mod telemetry {
+            // @@protoc_insertion_point(attribute:sf.substreams.telemetry.v1)
+            pub mod v1 {
+                include!("sf.substreams.telemetry.v1.rs");
+                // @@protoc_insertion_point(sf.substreams.telemetry.v1)
+            }
+        }
     }
 }
 
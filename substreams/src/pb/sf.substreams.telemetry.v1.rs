@@ -0,0 +1,58 @@
+// @generated
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Telemetry {
+    #[prost(message, repeated, tag="1")]
+    pub metrics: ::prost::alloc::vec::Vec<Metric>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Metric {
+    #[prost(string, tag="1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag="2")]
+    pub labels: ::prost::alloc::vec::Vec<Label>,
+    #[prost(oneof="metric::Value", tags="3, 4, 5")]
+    pub value: ::core::option::Option<metric::Value>,
+}
+/// Nested message and enum types in `Metric`.
+pub mod metric {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(double, tag="3")]
+        Counter(f64),
+        #[prost(double, tag="4")]
+        Gauge(f64),
+        #[prost(message, tag="5")]
+        Histogram(super::Histogram),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Label {
+    #[prost(string, tag="1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Histogram {
+    /// Cumulative counts, the observations lower or equal to each bucket's upper bound
+    #[prost(message, repeated, tag="1")]
+    pub buckets: ::prost::alloc::vec::Vec<Bucket>,
+    #[prost(uint64, tag="2")]
+    pub count: u64,
+    #[prost(double, tag="3")]
+    pub sum: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Bucket {
+    #[prost(double, tag="1")]
+    pub upper_bound: f64,
+    #[prost(uint64, tag="2")]
+    pub count: u64,
+}
+// @@protoc_insertion_point(module)
@@ -0,0 +1,217 @@
+//! Composition of a handler out of stages.
+//!
+//! A big `map` handler is easier to test split in stages, like extract → enrich → aggregate,
+//! each stage being a plain function taking the previous stage's output. A [Pipeline] chains
+//! them statically, running it is the same as calling the functions one after the other: no
+//! boxing, no dynamic dispatch and no extra trip through the wasm boundary.
+//!
+//! ```rust
+//! use substreams::errors::Error;
+//! use substreams::pipeline::Pipeline;
+//!
+//! fn extract(amounts: Vec<i64>) -> Vec<i64> {
+//!     amounts.into_iter().filter(|amount| *amount > 0).collect()
+//! }
+//!
+//! fn enrich(amounts: Vec<i64>, fee: i64) -> Result<Vec<i64>, Error> {
+//!     amounts
+//!         .into_iter()
+//!         .map(|amount| amount.checked_sub(fee).ok_or_else(|| anyhow::anyhow!("overflow")))
+//!         .collect()
+//! }
+//!
+//! fn aggregate(amounts: Vec<i64>) -> i64 {
+//!     amounts.iter().sum()
+//! }
+//!
+//! let fee = 1;
+//! let pipeline = Pipeline::new(extract)
+//!     .try_then(|amounts| enrich(amounts, fee))
+//!     .then(aggregate);
+//!
+//! assert_eq!(pipeline.run(vec![10, -3, 5]).unwrap(), 13);
+//! ```
+//!
+//! With `#[substreams::handlers::map(pipeline)]`, the handler body returns the pipeline and the
+//! generated glue runs it with the handler's first input, the other inputs (stores, params) are
+//! captured by the stages needing them. The handler returns the pipeline's output as a
+//! `Result`, the error of a failing stage being converted into the handler's error type:
+//!
+//! ```no_run
+//! use substreams::errors::Error;
+//! use substreams::pipeline::Pipeline;
+//! use substreams::store::{StoreGet, StoreGetInt64};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Block {}
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Swaps {}
+//! # }
+//! # fn extract_swaps(blk: pb::Block) -> Vec<u64> { vec![] }
+//! # fn enrich(swaps: Vec<u64>, fees: &StoreGetInt64) -> Vec<u64> { swaps }
+//! # fn aggregate(swaps: Vec<u64>) -> Result<pb::Swaps, Error> { Ok(pb::Swaps {}) }
+//!
+//! #[substreams::handlers::map(pipeline)]
+//! fn map_swaps(blk: pb::Block, fees: StoreGetInt64) -> Result<pb::Swaps, Error> {
+//!     Pipeline::new(extract_swaps)
+//!         .then(|swaps| enrich(swaps, &fees))
+//!         .try_then(aggregate)
+//! }
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::errors::Error;
+
+/// A step of a [Pipeline] turning an `I` into its output.
+pub trait Stage<I> {
+    type Output;
+
+    fn run(&self, input: I) -> Result<Self::Output, Error>;
+}
+
+/// Stages run one after the other, taking an `I` and returning the output of the last stage.
+pub struct Pipeline<I, S> {
+    stage: S,
+    input: PhantomData<fn(I)>,
+}
+
+impl<I, O, F> Pipeline<I, Map<F>>
+where
+    F: Fn(I) -> O,
+{
+    /// A pipeline starting with the infallible `stage`.
+    pub fn new(stage: F) -> Self {
+        Pipeline {
+            stage: Map(stage),
+            input: PhantomData,
+        }
+    }
+}
+
+impl<I, O, E, F> Pipeline<I, TryMap<F>>
+where
+    F: Fn(I) -> Result<O, E>,
+    Error: From<E>,
+{
+    /// A pipeline starting with the fallible `stage`.
+    pub fn try_new(stage: F) -> Self {
+        Pipeline {
+            stage: TryMap(stage),
+            input: PhantomData,
+        }
+    }
+}
+
+impl<I, S: Stage<I>> Pipeline<I, S> {
+    /// Appends the infallible `stage`, taking the output of the current last stage.
+    pub fn then<O, F>(self, stage: F) -> Pipeline<I, Then<S, Map<F>>>
+    where
+        F: Fn(S::Output) -> O,
+    {
+        Pipeline {
+            stage: Then(self.stage, Map(stage)),
+            input: PhantomData,
+        }
+    }
+
+    /// Appends the fallible `stage`, its error stops the pipeline.
+    pub fn try_then<O, E, F>(self, stage: F) -> Pipeline<I, Then<S, TryMap<F>>>
+    where
+        F: Fn(S::Output) -> Result<O, E>,
+        Error: From<E>,
+    {
+        Pipeline {
+            stage: Then(self.stage, TryMap(stage)),
+            input: PhantomData,
+        }
+    }
+
+    /// Runs the stages in order on `input`, stopping at the first failing stage.
+    pub fn run(&self, input: I) -> Result<S::Output, Error> {
+        self.stage.run(input)
+    }
+}
+
+/// An infallible stage, see [Pipeline::then].
+pub struct Map<F>(F);
+
+impl<I, O, F> Stage<I> for Map<F>
+where
+    F: Fn(I) -> O,
+{
+    type Output = O;
+
+    fn run(&self, input: I) -> Result<O, Error> {
+        Ok((self.0)(input))
+    }
+}
+
+/// A fallible stage, see [Pipeline::try_then].
+pub struct TryMap<F>(F);
+
+impl<I, O, E, F> Stage<I> for TryMap<F>
+where
+    F: Fn(I) -> Result<O, E>,
+    Error: From<E>,
+{
+    type Output = O;
+
+    fn run(&self, input: I) -> Result<O, Error> {
+        (self.0)(input).map_err(Error::from)
+    }
+}
+
+/// Stage `B` fed with the output of stage `A`.
+pub struct Then<A, B>(A, B);
+
+impl<I, A, B> Stage<I> for Then<A, B>
+where
+    A: Stage<I>,
+    B: Stage<A::Output>,
+{
+    type Output = B::Output;
+
+    fn run(&self, input: I) -> Result<B::Output, Error> {
+        self.1.run(self.0.run(input)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pipeline;
+    use crate::errors::SubstreamsError;
+    use std::cell::Cell;
+
+    fn parse(raw: &str) -> Result<Vec<u64>, std::num::ParseIntError> {
+        raw.split(',').map(|value| value.parse()).collect()
+    }
+
+    #[test]
+    fn it_runs_stages_in_order() {
+        let pipeline = Pipeline::try_new(parse)
+            .then(|values| values.into_iter().map(|v| v * 2).collect::<Vec<_>>())
+            .then(|values| values.iter().sum::<u64>());
+
+        assert_eq!(pipeline.run("1,2,3").unwrap(), 12);
+        assert_eq!(pipeline.run("4").unwrap(), 8);
+    }
+
+    #[test]
+    fn it_stops_at_the_first_failing_stage() {
+        let aggregated = Cell::new(0);
+        let pipeline = Pipeline::try_new(parse)
+            .try_then(|values| match values.is_empty() {
+                true => Err(SubstreamsError::Fatal("no values".to_string())),
+                false => Ok(values),
+            })
+            .then(|values| aggregated.set(aggregated.get() + values.len()));
+
+        let err = pipeline.run("1,x").unwrap_err();
+        assert!(err.downcast_ref::<std::num::ParseIntError>().is_some());
+        assert_eq!(aggregated.get(), 0);
+
+        pipeline.run("1,2").unwrap();
+        assert_eq!(aggregated.get(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+//! Typed view of the `sf.substreams.v1.ModuleProgress` messages sent by the server.
+//!
+//! The generated [pb::ModuleProgress] holds its payload in an optional `oneof`, a
+//! [ModuleProgress] converts it to a [Progress] enum matched directly:
+//!
+//! ```rust
+//! use substreams::pb::substreams::ModulesProgress;
+//! use substreams::progress::{ModuleProgress, Progress};
+//!
+//! fn failures(message: ModulesProgress) -> Vec<String> {
+//!     message
+//!         .modules
+//!         .into_iter()
+//!         .map(ModuleProgress::from)
+//!         .filter_map(|module| match module.progress {
+//!             Progress::Failed { reason, .. } => Some(format!("{}: {}", module.name, reason)),
+//!             _ => None,
+//!         })
+//!         .collect()
+//! }
+//! ```
+
+use std::ops::Range;
+
+use crate::pb::substreams as pb;
+use crate::pb::substreams::module_progress::Type;
+
+/// The progress of a module, see the [module](crate::progress) documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleProgress {
+    pub name: String,
+    pub progress: Progress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Progress {
+    /// Block ranges processed by the module, `start_block..end_block`.
+    ProcessedRanges(Vec<Range<u64>>),
+    InitialState {
+        available_up_to_block: u64,
+    },
+    ProcessedBytes {
+        total_bytes_read: u64,
+        total_bytes_written: u64,
+    },
+    Failed {
+        reason: String,
+        logs: Vec<String>,
+        /// Whether the logs were truncated by the server.
+        logs_truncated: bool,
+    },
+    /// The message didn't carry a payload, or one unknown to this version of the crate.
+    Unknown,
+}
+
+impl From<pb::ModuleProgress> for ModuleProgress {
+    fn from(module: pb::ModuleProgress) -> Self {
+        ModuleProgress {
+            name: module.name,
+            progress: module.r#type.map_or(Progress::Unknown, Progress::from),
+        }
+    }
+}
+
+impl From<Type> for Progress {
+    fn from(progress: Type) -> Self {
+        match progress {
+            Type::ProcessedRanges(ranges) => Progress::ProcessedRanges(
+                ranges
+                    .processed_ranges
+                    .into_iter()
+                    .map(|range| range.start_block..range.end_block)
+                    .collect(),
+            ),
+            Type::InitialState(state) => Progress::InitialState {
+                available_up_to_block: state.available_up_to_block,
+            },
+            Type::ProcessedBytes(bytes) => Progress::ProcessedBytes {
+                total_bytes_read: bytes.total_bytes_read,
+                total_bytes_written: bytes.total_bytes_written,
+            },
+            Type::Failed(failed) => Progress::Failed {
+                reason: failed.reason,
+                logs: failed.logs,
+                logs_truncated: failed.logs_truncated,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModuleProgress, Progress};
+    use crate::pb::substreams::module_progress::{ProcessedRange, Type};
+    use crate::pb::substreams::{self as pb, BlockRange};
+    use std::ops::Range;
+
+    #[test]
+    fn it_converts_generated_progress() {
+        let module = ModuleProgress::from(pb::ModuleProgress {
+            name: "map_pools".to_string(),
+            r#type: Some(Type::ProcessedRanges(ProcessedRange {
+                processed_ranges: vec![BlockRange {
+                    start_block: 10,
+                    end_block: 20,
+                }],
+            })),
+        });
+        assert_eq!(module.name, "map_pools");
+        assert_eq!(
+            module.progress,
+            Progress::ProcessedRanges(vec![Range { start: 10, end: 20 }])
+        );
+
+        let empty = ModuleProgress::from(pb::ModuleProgress {
+            name: "map_pools".to_string(),
+            r#type: None,
+        });
+        assert_eq!(empty.progress, Progress::Unknown);
+    }
+}
@@ -1,46 +1,40 @@
 //! Protobuf helpers for Substreams.
 //!
 //! This crate offers a few protobuf helper functions which
-//! are used across Substreams
+//! are used across Substreams, messages are encoded and decoded
+//! through their [Codec] implementation.
 //!
 
-use prost::{DecodeError, EncodeError};
+use crate::codec::Codec;
 
 /// Given an array of bytes, it will decode data in a Protobuf Message
-pub fn decode<T: Default + prost::Message>(buf: &Vec<u8>) -> Result<T, DecodeError> {
-    ::prost::Message::decode(&buf[..])
+pub fn decode<T: Codec>(buf: &[u8]) -> Result<T, T::DecodeError> {
+    crate::metrics::on_decode(buf.len());
+    T::from_bytes(buf)
 }
 
 /// Given a pointer to a byte array, it will read and decode the data in a Protobuf message.
-pub fn decode_ptr<T: Default + prost::Message>(
-    ptr: *mut u8,
-    size: usize,
-) -> Result<T, DecodeError> {
+pub fn decode_ptr<T: Codec>(ptr: *mut u8, size: usize) -> Result<T, T::DecodeError> {
     unsafe {
         let input_data = Vec::from_raw_parts(ptr, size, size);
-        let obj = ::prost::Message::decode(&input_data[..]);
+        #[cfg(feature = "replay")]
+        crate::replay::input(&input_data);
+        crate::metrics::on_decode(size);
+        let obj = T::from_bytes(&input_data[..]);
         std::mem::forget(input_data); // otherwise tries to free that memory at the end and crashes
         obj
     }
 }
 
 /// Given a Protobuf message it will encode it and return the byte array.
-pub fn encode<M: prost::Message>(msg: &M) -> Result<Vec<u8>, EncodeError> {
-    let mut buf = Vec::new();
-
-    let encoded_len = msg.encoded_len();
-    buf.reserve(encoded_len);
-
-    match msg.encode(&mut buf) {
-        Ok(_) => Ok(buf),
-        Err(e) => Err(e),
-    }
+pub fn encode<M: Codec>(msg: &M) -> Result<Vec<u8>, M::EncodeError> {
+    let bytes = msg.to_bytes()?;
+    crate::metrics::on_encode(bytes.len());
+    Ok(bytes)
 }
 
 /// Given a Protobuf message it will encode it and return a pointer to the byte array
-pub fn encode_to_ptr<M: prost::Message>(
-    msg: &M,
-) -> Result<(*const u8, usize, Vec<u8>), EncodeError> {
+pub fn encode_to_ptr<M: Codec>(msg: &M) -> Result<(*const u8, usize, Vec<u8>), M::EncodeError> {
     match encode(msg) {
         Ok(buffer) => Ok((buffer.as_ptr(), buffer.len(), buffer)),
         Err(e) => Err(e),
@@ -0,0 +1,115 @@
+//! Runtime enumeration of the handlers compiled in a binary.
+//!
+//! Besides the exported function, the handler macros generate a module named after the
+//! handler holding its `MODULE_NAME`, and register the handler in the [registry] of the
+//! binary. Sinks, tests and tooling compiled alongside the module (like self-describing
+//! native binaries) use them instead of hardcoding handler names:
+//!
+//! ```rust
+//! use substreams::metadata::HandlerKind;
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transfers {}
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_transfers(transfers: pb::Transfers) -> pb::Transfers {
+//!     transfers
+//! }
+//!
+//! assert_eq!(map_transfers::MODULE_NAME, "map_transfers");
+//!
+//! # #[cfg(target_os = "linux")]
+//! # {
+//! let handler = substreams::registry()
+//!     .iter()
+//!     .find(|handler| handler.name == map_transfers::MODULE_NAME)
+//!     .unwrap();
+//! assert_eq!(handler.kind, HandlerKind::Map);
+//! assert_eq!(handler.inputs, 1);
+//! # }
+//! ```
+//!
+//! Handlers are collected by the linker in the [SECTION_NAME] section, which is only
+//! supported on ELF targets (Linux, Android, FreeBSD). On other targets, WASM included, the
+//! registry is empty: WASM tooling reads the [crate::metadata] custom section instead.
+
+use crate::metadata::HandlerKind;
+
+/// Name of the linker section holding the registered handlers.
+pub const SECTION_NAME: &str = "substreams_registry";
+
+/// A handler compiled in the binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisteredHandler {
+    /// The name of the exported function, the name of the module in the manifest.
+    pub name: &'static str,
+    pub kind: HandlerKind,
+    /// The number of inputs of the handler, stores included.
+    pub inputs: usize,
+}
+
+/// Returns the handlers compiled in the binary, in no particular order.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+pub fn registry() -> &'static [RegisteredHandler] {
+    extern "Rust" {
+        #[link_name = "__start_substreams_registry"]
+        static START: RegisteredHandler;
+        #[link_name = "__stop_substreams_registry"]
+        static STOP: RegisteredHandler;
+    }
+
+    // Guarantees the section exists, the linker only defines the bounds of existing sections
+    #[link_section = "substreams_registry"]
+    #[used]
+    static EMPTY: [RegisteredHandler; 0] = [];
+
+    unsafe {
+        let start = &START as *const RegisteredHandler;
+        let stop = &STOP as *const RegisteredHandler;
+        let len = (stop as usize - start as usize) / std::mem::size_of::<RegisteredHandler>();
+        std::slice::from_raw_parts(start, len)
+    }
+}
+
+/// Returns the handlers compiled in the binary, always empty on this target.
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+pub fn registry() -> &'static [RegisteredHandler] {
+    &[]
+}
+
+/// Registers a handler, called by the handler macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_handler {
+    ($static_name:ident, $name:expr, $kind:ident, $inputs:expr) => {
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+        #[link_section = "substreams_registry"]
+        #[used]
+        static $static_name: $crate::registry::RegisteredHandler =
+            $crate::registry::RegisteredHandler {
+                name: $name,
+                kind: $crate::metadata::HandlerKind::$kind,
+                inputs: $inputs,
+            };
+    };
+}
+
+/// Exports a handler as a component model function, called by the handler macros. Without
+/// the `component` feature handlers only have their classic export, see the `component` module.
+#[cfg(not(feature = "component"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __component_export {
+    ($($tokens:tt)*) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::registry;
+
+    #[test]
+    fn it_links_without_handlers() {
+        assert!(registry().is_empty());
+    }
+}
@@ -0,0 +1,497 @@
+//! Record and replay of host interactions, available with the `replay` feature.
+//!
+//! When recording, every host call performed through this crate (store reads and writes,
+//! module outputs) as well as the raw handler inputs are appended to a [Trace]. The trace
+//! is a Protobuf message so it can be extracted from a production run, for example through
+//! [log_recording], and saved to a file.
+//!
+//! Handlers declared with `#[substreams::handlers::map]` and `#[substreams::handlers::store]`
+//! record each run on their own: recording starts before the inputs are decoded and the trace
+//! is finished once the output is emitted, or when the handler returns an error or panics.
+//! It's then logged prefixed by [LOG_PREFIX] and kept as the [last_trace], so the failing
+//! runs can be replayed too.
+//!
+//! The [Replayer] then re-executes a handler natively against that trace: store reads are
+//! answered from the recorded values and writes/outputs are captured so they can be compared
+//! with the recorded ones, enabling offline step-through debugging of production incidents.
+//!
+//! ```rust,ignore
+//! // In the module, compiled to wasm32 with the `replay` feature, each run logs its trace
+//! #[substreams::handlers::map]
+//! fn map_pools(blk: eth::Block, pools: StoreGetProto<Pool>) -> Result<Pools, Error> {
+//!     compute(blk, pools)
+//! }
+//!
+//! // In a native test, with the `replay` feature
+//! let trace = Trace::decode_hex(&std::fs::read_to_string("trace.hex")?)?;
+//! let replayer = Replayer::new(trace);
+//! let replayed = replayer.run(|inputs| {
+//!     let blk: eth::Block = prost::Message::decode(inputs[0].as_slice()).unwrap();
+//!     compute(blk, StoreGetProto::new(0))
+//! });
+//! ```
+
+use std::cell::RefCell;
+
+use anyhow::{anyhow, Context, Error};
+use prost::Message;
+
+/// A sequence of host interactions recorded while running a handler.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Trace {
+    #[prost(message, repeated, tag = "1")]
+    pub calls: ::prost::alloc::vec::Vec<HostCall>,
+}
+
+/// A single host interaction, `value` holds the data sent to the host and `output`
+/// the data received back from it.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HostCall {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub store_idx: u32,
+    #[prost(int64, tag = "3")]
+    pub ordinal: i64,
+    #[prost(string, tag = "4")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bool, tag = "6")]
+    pub found: bool,
+    #[prost(bytes = "vec", tag = "7")]
+    pub output: ::prost::alloc::vec::Vec<u8>,
+    /// The key entries of a `scan_prefix` read sort after, `key` holding the scanned prefix.
+    #[prost(string, optional, tag = "8")]
+    pub after: ::core::option::Option<::prost::alloc::string::String>,
+    /// The maximum number of entries of a `scan_prefix` read.
+    #[prost(uint32, tag = "9")]
+    pub limit: u32,
+}
+
+impl Trace {
+    /// Calls that are handler inputs, in the order they were decoded.
+    pub fn inputs(&self) -> Vec<Vec<u8>> {
+        self.calls
+            .iter()
+            .filter(|call| call.name == INPUT)
+            .map(|call| call.value.clone())
+            .collect()
+    }
+
+    /// Calls that changed the state of the store or the module output, in the order they happened.
+    pub fn writes(&self) -> Vec<HostCall> {
+        self.calls
+            .iter()
+            .filter(|call| call.name != INPUT && !is_read(&call.name))
+            .cloned()
+            .collect()
+    }
+
+    pub fn encode_hex(&self) -> String {
+        crate::Hex::encode(self.encode_to_vec())
+    }
+
+    pub fn decode_hex(input: &str) -> Result<Trace, Error> {
+        let bytes = crate::Hex::decode(input.trim()).context("invalid hex trace")?;
+        Trace::decode(bytes.as_slice()).context("invalid trace")
+    }
+}
+
+const INPUT: &str = "input";
+const SCAN_PREFIX: &str = "scan_prefix";
+
+/// Prefix of the log line emitted by [log_recording].
+pub const LOG_PREFIX: &str = "substreams-replay-trace:";
+
+fn is_read(name: &str) -> bool {
+    name.starts_with("get_") || name.starts_with("has_") || name == SCAN_PREFIX
+}
+
+enum Mode {
+    Recording(Trace),
+    Replaying {
+        pending: Trace,
+        position: usize,
+        observed: Trace,
+    },
+}
+
+thread_local! {
+    static MODE: RefCell<Option<Mode>> = const { RefCell::new(None) };
+    static LAST_TRACE: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+/// Starts recording host interactions, discarding any previous recording.
+pub fn start_recording() {
+    MODE.with(|mode| *mode.borrow_mut() = Some(Mode::Recording(Trace::default())));
+}
+
+/// Stops recording and returns the recorded trace, empty if no recording was in progress.
+pub fn stop_recording() -> Trace {
+    MODE.with(|mode| {
+        let current = mode.borrow_mut().take();
+        match current {
+            Some(Mode::Recording(trace)) => trace,
+            other => {
+                *mode.borrow_mut() = other;
+                Trace::default()
+            }
+        }
+    })
+}
+
+/// Stops recording and logs the hex encoded trace prefixed by [LOG_PREFIX].
+pub fn log_recording() {
+    let trace = stop_recording();
+
+    crate::log::println(format!("{}{}", LOG_PREFIX, trace.encode_hex()));
+}
+
+/// Returns the trace recorded by the last run of a handler declared with the handler macros,
+/// `None` if none ran or the last one was replayed.
+pub fn last_trace() -> Option<Trace> {
+    LAST_TRACE.with(|last| last.borrow().clone())
+}
+
+/// Starts recording a handler run, unless it's replayed by a [Replayer].
+pub(crate) fn begin_handler() {
+    MODE.with(|mode| {
+        let mut mode = mode.borrow_mut();
+        if !matches!(*mode, Some(Mode::Replaying { .. })) {
+            *mode = Some(Mode::Recording(Trace::default()));
+        }
+    });
+    LAST_TRACE.with(|last| last.borrow_mut().take());
+}
+
+/// Finishes the recording started by [begin_handler], logging it like [log_recording]. Does
+/// nothing if it was already finished, which happens when the panic hook runs after the
+/// handler returned an error.
+pub(crate) fn finish_handler() {
+    let trace = MODE.with(|mode| {
+        // From the panic hook, `MODE` is still borrowed if the panic was raised while recording
+        let mut mode = mode.try_borrow_mut().ok()?;
+        match mode.take() {
+            Some(Mode::Recording(trace)) => Some(trace),
+            other => {
+                *mode = other;
+                None
+            }
+        }
+    });
+
+    if let Some(trace) = trace {
+        crate::log::println(format!("{}{}", LOG_PREFIX, trace.encode_hex()));
+        LAST_TRACE.with(|last| *last.borrow_mut() = Some(trace));
+    }
+}
+
+/// Re-executes handler code against a recorded [Trace].
+pub struct Replayer {
+    trace: Trace,
+}
+
+impl Replayer {
+    pub fn new(trace: Trace) -> Self {
+        Replayer { trace }
+    }
+
+    /// Runs `f` while answering store reads from the recorded trace. `f` receives the
+    /// recorded handler inputs. Returns the value produced by `f` along with the trace
+    /// of writes and outputs observed during the run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` performs a read that diverges from the recorded one.
+    pub fn run<T, F: FnOnce(Vec<Vec<u8>>) -> T>(&self, f: F) -> (T, Trace) {
+        MODE.with(|mode| {
+            *mode.borrow_mut() = Some(Mode::Replaying {
+                pending: self.trace.clone(),
+                position: 0,
+                observed: Trace::default(),
+            })
+        });
+
+        let value = f(self.trace.inputs());
+
+        let observed = MODE.with(|mode| match mode.borrow_mut().take() {
+            Some(Mode::Replaying { observed, .. }) => observed,
+            _ => Trace::default(),
+        });
+
+        (value, observed)
+    }
+
+    /// Same as [Replayer::run] but also checks that writes and outputs observed during
+    /// the run are the same as the recorded ones.
+    pub fn run_and_compare<T, F: FnOnce(Vec<Vec<u8>>) -> T>(&self, f: F) -> Result<T, Error> {
+        let (value, observed) = self.run(f);
+
+        let expected = self.trace.writes();
+        let actual = observed.writes();
+        for (i, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+            if expected != actual {
+                return Err(anyhow!(
+                    "write #{} differs, expected {:?} got {:?}",
+                    i,
+                    expected,
+                    actual
+                ));
+            }
+        }
+
+        if expected.len() != actual.len() {
+            return Err(anyhow!(
+                "expected {} writes, got {}",
+                expected.len(),
+                actual.len()
+            ));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Performs the store read `name` through `host`, unless replaying in which case the
+/// recorded value is returned instead.
+pub(crate) fn read<F>(
+    name: &str,
+    store_idx: u32,
+    ordinal: i64,
+    key: &str,
+    host: F,
+) -> Option<Vec<u8>>
+where
+    F: FnOnce() -> Option<Vec<u8>>,
+{
+    read_call(
+        HostCall {
+            name: name.to_string(),
+            store_idx,
+            ordinal,
+            key: key.to_string(),
+            ..Default::default()
+        },
+        host,
+    )
+}
+
+/// Same as [read] for a `scan_prefix` read of the entries of `prefix` after `after`.
+pub(crate) fn scan<F>(
+    store_idx: u32,
+    prefix: &str,
+    after: Option<&str>,
+    limit: u32,
+    host: F,
+) -> Option<Vec<u8>>
+where
+    F: FnOnce() -> Option<Vec<u8>>,
+{
+    read_call(
+        HostCall {
+            name: SCAN_PREFIX.to_string(),
+            store_idx,
+            key: prefix.to_string(),
+            after: after.map(str::to_string),
+            limit,
+            ..Default::default()
+        },
+        host,
+    )
+}
+
+/// Performs the read described by `request` through `host` or from the replayed trace.
+fn read_call<F>(request: HostCall, host: F) -> Option<Vec<u8>>
+where
+    F: FnOnce() -> Option<Vec<u8>>,
+{
+    let replayed = MODE.with(|mode| match mode.borrow_mut().as_mut() {
+        Some(Mode::Replaying {
+            pending, position, ..
+        }) => Some(next_read(pending, position, &request)),
+        _ => None,
+    });
+
+    if let Some(value) = replayed {
+        return value;
+    }
+
+    let value = host();
+    record(HostCall {
+        found: value.is_some(),
+        output: value.clone().unwrap_or_default(),
+        ..request
+    });
+    value
+}
+
+/// Records the store write or output `name`, while replaying it's captured in the observed trace.
+pub(crate) fn write(name: &str, ordinal: i64, key: &str, value: &[u8]) {
+    record(HostCall {
+        name: name.to_string(),
+        ordinal,
+        key: key.to_string(),
+        value: value.to_vec(),
+        ..Default::default()
+    })
+}
+
+/// Records raw handler input data.
+pub(crate) fn input(value: &[u8]) {
+    MODE.with(|mode| {
+        if let Some(Mode::Recording(trace)) = mode.borrow_mut().as_mut() {
+            trace.calls.push(HostCall {
+                name: INPUT.to_string(),
+                value: value.to_vec(),
+                ..Default::default()
+            })
+        }
+    })
+}
+
+fn record(call: HostCall) {
+    MODE.with(|mode| match mode.borrow_mut().as_mut() {
+        Some(Mode::Recording(trace)) => trace.calls.push(call),
+        Some(Mode::Replaying { observed, .. }) => observed.calls.push(call),
+        None => {}
+    })
+}
+
+fn next_read(pending: &Trace, position: &mut usize, request: &HostCall) -> Option<Vec<u8>> {
+    while let Some(call) = pending.calls.get(*position) {
+        *position += 1;
+        if !is_read(&call.name) {
+            continue;
+        }
+
+        if call.name != request.name
+            || call.store_idx != request.store_idx
+            || call.ordinal != request.ordinal
+            || call.key != request.key
+            || call.after != request.after
+            || call.limit != request.limit
+        {
+            panic!(
+                "replay diverged: expected {}, got {}",
+                describe_read(call),
+                describe_read(request)
+            );
+        }
+
+        return match call.found {
+            true => Some(call.output.clone()),
+            false => None,
+        };
+    }
+
+    panic!(
+        "replay diverged: no more recorded reads, got {}",
+        describe_read(request)
+    );
+}
+
+fn describe_read(call: &HostCall) -> String {
+    let mut out = format!(
+        "{} (store {}, ordinal {}, key {:?}",
+        call.name, call.store_idx, call.ordinal, call.key
+    );
+    if call.name == SCAN_PREFIX {
+        out.push_str(&format!(", after {:?}, limit {}", call.after, call.limit));
+    }
+    out.push(')');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_records_and_replays_reads() {
+        start_recording();
+        input(b"block");
+        assert_eq!(
+            read("get_last", 0, 0, "a", || Some(b"1".to_vec())),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(read("get_last", 0, 0, "b", || None), None);
+        write("set", 1, "c", b"2");
+        let trace = stop_recording();
+
+        let trace = Trace::decode_hex(&trace.encode_hex()).unwrap();
+        let replayer = Replayer::new(trace);
+
+        let value = replayer
+            .run_and_compare(|inputs| {
+                assert_eq!(inputs, vec![b"block".to_vec()]);
+                let a = read("get_last", 0, 0, "a", || {
+                    panic!("host called while replaying")
+                });
+                let b = read("get_last", 0, 0, "b", || {
+                    panic!("host called while replaying")
+                });
+                write("set", 1, "c", b"2");
+                (a, b)
+            })
+            .unwrap();
+
+        assert_eq!(value, (Some(b"1".to_vec()), None));
+    }
+
+    #[test]
+    fn it_keeps_the_last_handler_trace() {
+        begin_handler();
+        input(b"block");
+        write("output", 0, "", b"out");
+        finish_handler();
+        // Finishing twice, from the error path then the panic hook, keeps the trace
+        finish_handler();
+
+        let trace = last_trace().expect("handler run recorded");
+        assert_eq!(trace.inputs(), vec![b"block".to_vec()]);
+        assert_eq!(trace.writes().len(), 1);
+
+        let replayer = Replayer::new(trace);
+        replayer.run(|_| {
+            begin_handler();
+            write("output", 0, "", b"out");
+            finish_handler();
+        });
+        assert_eq!(last_trace(), None);
+    }
+
+    #[test]
+    fn it_detects_diverging_writes() {
+        start_recording();
+        write("set", 1, "c", b"2");
+        let replayer = Replayer::new(stop_recording());
+
+        assert!(replayer
+            .run_and_compare(|_| write("set", 1, "c", b"3"))
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "replay diverged")]
+    fn it_panics_on_diverging_reads() {
+        start_recording();
+        read("get_last", 0, 0, "a", || None);
+        let replayer = Replayer::new(stop_recording());
+
+        replayer.run(|_| read("get_last", 0, 0, "b", || None));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "limit 10), got scan_prefix (store 0, ordinal 0, key \"a:\", after Some(\"a:1\")"
+    )]
+    fn it_panics_on_diverging_scans() {
+        start_recording();
+        scan(0, "a:", None, 10, || None);
+        let replayer = Replayer::new(stop_recording());
+        assert!(replayer.run_and_compare(|_| ()).is_ok());
+
+        replayer.run(|_| scan(0, "a:", Some("a:1"), 10, || None));
+    }
+}
@@ -0,0 +1,310 @@
+//! Windowed retention of store values.
+//!
+//! Stores only needing the last N days of data keep growing unless old keys are deleted. A
+//! [RetainedStore] writes each value under the time bucket its timestamp falls in, keys being
+//! prefixed with the bucket, `<bucket>:<key>` where `<bucket>` is the number of buckets between
+//! the Unix epoch and the timestamp (see [Window::bucket]). [RetainedStore::purge_older_than]
+//! then drops the buckets that just expired with one `delete_prefix` each:
+//!
+//! ```rust
+//! use substreams::clock::Clock;
+//! use substreams::retention::RetainedStore;
+//! use substreams::store::{StoreNew, StoreSetInt64};
+//! use substreams::windows::Window;
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Prices { #[prost(string, repeated, tag = "1")] pub tokens: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::store]
+//! fn store_recent_prices(clock: Clock, prices: pb::Prices, store: StoreSetInt64) {
+//!     let store = RetainedStore::new(store, Window::DAY);
+//!     store.purge_older_than(&clock, Window::seconds(30 * 86400));
+//!
+//!     for token in prices.tokens.iter() {
+//!         store.set(0, token, clock.timestamp_seconds(), &1);
+//!     }
+//! }
+//! ```
+//!
+//! A [RetainedGet] reads the buckets back from a store in `get` mode. The store must only
+//! hold keys written through a [RetainedStore] with the same bucket size, other keys starting
+//! with a number and `:` would be purged with the bucket of that number.
+
+use std::ops::Range;
+
+use crate::clock::Clock;
+use crate::store::{
+    Ordinal, StoreAdd, StoreDelete, StoreGet, StoreMax, StoreMin, StoreSet, StoreSetIfNotExists,
+};
+use crate::windows::Window;
+
+/// Returns the prefix of the keys of `bucket`.
+fn bucket_prefix(bucket: i64) -> String {
+    format!("{}:", bucket)
+}
+
+/// Returns the bucket and the key of `key` written by a [RetainedStore], `None` if it's not
+/// prefixed by a bucket.
+pub fn parse_key(key: &str) -> Option<(i64, &str)> {
+    let (bucket, key) = key.split_once(':')?;
+    Some((bucket.parse().ok()?, key))
+}
+
+/// A writable store whose keys are bucketed by timestamp to purge old buckets, see the
+/// [module](crate::retention) documentation.
+pub struct RetainedStore<S> {
+    store: S,
+    bucket: Window,
+    lookback: u32,
+}
+
+impl<S> RetainedStore<S> {
+    /// Buckets the keys written to `store` by periods of `bucket`, the granularity at which
+    /// keys are purged.
+    pub fn new(store: S, bucket: Window) -> Self {
+        RetainedStore {
+            store,
+            bucket,
+            lookback: 1,
+        }
+    }
+
+    /// Purges the `lookback` buckets below the cutoff instead of the last one, see
+    /// [RetainedStore::purge_older_than]. Raise it when blocks can be more than one bucket
+    /// apart, a chain producing a block every few hours with hourly buckets.
+    pub fn with_lookback(self, lookback: u32) -> Self {
+        RetainedStore { lookback, ..self }
+    }
+
+    pub fn bucket(&self) -> Window {
+        self.bucket
+    }
+
+    /// The wrapped store, to write keys that are not retained.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the key holding the value of `key` for the bucket `timestamp` falls in.
+    pub fn key<K: AsRef<str>>(&self, key: K, timestamp: i64) -> String {
+        retained_key(self.bucket.bucket(timestamp), key.as_ref())
+    }
+
+    pub fn set<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: &V)
+    where
+        S: StoreSet<V>,
+    {
+        self.store.set(ord, self.key(key, timestamp), value)
+    }
+
+    pub fn set_if_not_exists<K: AsRef<str>, V>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        timestamp: i64,
+        value: &V,
+    ) where
+        S: StoreSetIfNotExists<V>,
+    {
+        self.store
+            .set_if_not_exists(ord, self.key(key, timestamp), value)
+    }
+
+    pub fn add<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreAdd<V>,
+    {
+        self.store.add(ord, self.key(key, timestamp), value)
+    }
+
+    pub fn max<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreMax<V>,
+    {
+        self.store.max(ord, self.key(key, timestamp), value)
+    }
+
+    pub fn min<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreMin<V>,
+    {
+        self.store.min(ord, self.key(key, timestamp), value)
+    }
+
+    /// Deletes the buckets ending more than `window` before the block of `clock`, returning
+    /// the purged buckets: the last bucket before the cutoff, or the `lookback` last ones
+    /// (see [RetainedStore::with_lookback]).
+    ///
+    /// The purged buckets only depend on `clock`, every block deletes them again so the store
+    /// is the same whichever blocks a module instance processed before, like at the start
+    /// of a segment when running in parallel. Buckets expired more than `lookback` buckets
+    /// before a block are not purged, blocks must be closer than that.
+    ///
+    /// Deletions use ordinal `0`, call it before writing the block's values.
+    pub fn purge_older_than(&self, clock: &Clock, window: Window) -> Range<i64>
+    where
+        S: StoreDelete,
+    {
+        let oldest = self
+            .bucket
+            .bucket(clock.timestamp_seconds() - window.as_seconds());
+        let purged = oldest - i64::from(self.lookback)..oldest;
+
+        for bucket in purged.clone() {
+            self.store.delete_prefix(0, &bucket_prefix(bucket));
+        }
+        purged
+    }
+}
+
+fn retained_key(bucket: i64, key: &str) -> String {
+    format!("{}{}", bucket_prefix(bucket), key)
+}
+
+/// Reads the buckets written by a [RetainedStore] from a store in `get` mode.
+pub struct RetainedGet<S> {
+    store: S,
+    bucket: Window,
+}
+
+impl<S> RetainedGet<S> {
+    pub fn new(store: S, bucket: Window) -> Self {
+        RetainedGet { store, bucket }
+    }
+
+    pub fn bucket(&self) -> Window {
+        self.bucket
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the value of `key` in the bucket `timestamp` falls in.
+    pub fn get<K: AsRef<str>, V>(&self, key: K, timestamp: i64) -> Option<V>
+    where
+        S: StoreGet<V>,
+    {
+        let bucket = self.bucket.bucket(timestamp);
+        self.store.get_last(retained_key(bucket, key.as_ref()))
+    }
+
+    /// Returns the value of `key` in the most recent bucket holding it, from the one
+    /// `timestamp` falls in back to the oldest one overlapping `window`, with the timestamp
+    /// of the start of that bucket.
+    pub fn get_latest<K: AsRef<str>, V>(
+        &self,
+        key: K,
+        timestamp: i64,
+        window: Window,
+    ) -> Option<(i64, V)>
+    where
+        S: StoreGet<V>,
+    {
+        let current = self.bucket.bucket(timestamp);
+        let oldest = self.bucket.bucket(timestamp - window.as_seconds());
+        (oldest..=current).rev().find_map(|bucket| {
+            self.store
+                .get_last(retained_key(bucket, key.as_ref()))
+                .map(|value| (self.bucket.bucket_start(bucket), value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_key, RetainedGet, RetainedStore};
+    use crate::clock::Clock;
+    use crate::store::{StoreGet, StoreGetInt64, StoreNew, StoreSetInt64};
+    use crate::testing;
+    use crate::windows::Window;
+    use prost_types::Timestamp;
+
+    const DAY: i64 = 86400;
+
+    fn block(number: u64, seconds: i64) -> Clock {
+        Clock {
+            id: format!("block-{}", number),
+            number,
+            timestamp: Some(Timestamp { seconds, nanos: 0 }),
+        }
+    }
+
+    fn keys() -> Vec<String> {
+        testing::output_store().into_keys().collect()
+    }
+
+    #[test]
+    fn it_purges_buckets_below_the_cutoff() {
+        testing::reset();
+        let store = RetainedStore::new(StoreSetInt64::new(), Window::DAY);
+        let retention = Window::seconds(2 * DAY);
+
+        for day in 0..3 {
+            store.set(0, "price", day * DAY + 10, &day);
+        }
+        assert_eq!(store.key("price", 2 * DAY), "2:price");
+        assert_eq!(keys(), vec!["0:price", "1:price", "2:price"]);
+
+        // Day 3 only keeps buckets overlapping the last 2 days, day 0 just expired
+        assert_eq!(
+            store.purge_older_than(&block(10, 3 * DAY + 5), retention),
+            0..1
+        );
+        assert_eq!(keys(), vec!["1:price", "2:price"]);
+
+        // Later blocks of the same bucket purge the same bucket again
+        assert_eq!(
+            store.purge_older_than(&block(11, 3 * DAY + 50), retention),
+            0..1
+        );
+        assert_eq!(keys(), vec!["1:price", "2:price"]);
+    }
+
+    #[test]
+    fn it_purges_skipped_buckets_from_a_fresh_clock() {
+        testing::reset();
+        let store = RetainedStore::new(StoreSetInt64::new(), Window::DAY).with_lookback(4);
+        let retention = Window::seconds(2 * DAY);
+
+        for day in 0..4 {
+            store.set(0, "price", day * DAY, &day);
+        }
+
+        // The first block of the instance is 3 buckets after the last write
+        assert_eq!(store.purge_older_than(&block(12, 6 * DAY), retention), 0..4);
+        assert!(keys().is_empty());
+
+        // Without lookback, only the last bucket before the cutoff is purged
+        for day in 0..4 {
+            store.set(0, "price", day * DAY, &day);
+        }
+        let store = RetainedStore::new(StoreSetInt64::new(), Window::DAY);
+        assert_eq!(store.purge_older_than(&block(12, 6 * DAY), retention), 3..4);
+        assert_eq!(keys(), vec!["0:price", "1:price", "2:price"]);
+    }
+
+    #[test]
+    fn it_reads_latest_retained_value() {
+        testing::reset();
+        testing::set_store_value(0, "3:price", "30");
+        testing::set_store_value(0, "5:price", "50");
+
+        let prices = RetainedGet::new(StoreGetInt64::new(0), Window::DAY);
+        assert_eq!(prices.get("price", 5 * DAY), Some(50));
+        assert_eq!(prices.get("price", 4 * DAY), None::<i64>);
+        assert_eq!(
+            prices.get_latest("price", 4 * DAY + 10, Window::seconds(DAY)),
+            Some((3 * DAY, 30))
+        );
+        assert_eq!(
+            prices.get_latest("price", 7 * DAY, Window::seconds(DAY)),
+            None::<(i64, i64)>
+        );
+
+        assert_eq!(parse_key("3:price:usd"), Some((3, "price:usd")));
+        assert_eq!(parse_key("-1:price"), Some((-1, "price")));
+        assert_eq!(parse_key("price"), None);
+    }
+}
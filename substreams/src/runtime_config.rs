@@ -0,0 +1,88 @@
+//! Settings provided by the server running the module, read with [crate::runtime_config].
+//!
+//! Params are part of the manifest and of the module hash, values that must stay out of it
+//! (API keys of `eth_call`-like intrinsics, tuning knobs of an operator) are instead provided
+//! by the server at runtime and read by key:
+//!
+//! ```rust
+//! fn batch_size() -> usize {
+//!     substreams::runtime_config("batch_size")
+//!         .and_then(|size| size.parse().ok())
+//!         .unwrap_or(100)
+//! }
+//! ```
+//!
+//! Values are read through the `config.get` host call, only imported with the
+//! `runtime-config` feature: a server without it rejects modules importing it, so without
+//! the feature every key reads as `None` and the module keeps its defaults. Values are
+//! expected not to change while the module runs, each key is only read once from the host.
+//! Natively, values come from [crate::testing::set_runtime_config].
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Option<String>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the value of `key` provided by the server, `None` if it doesn't provide one.
+pub fn get(key: &str) -> Option<String> {
+    if let Some(value) = CACHE.with(|cache| cache.borrow().get(key).cloned()) {
+        return value;
+    }
+
+    let value = host_get(key);
+    CACHE.with(|cache| cache.borrow_mut().insert(key.to_string(), value.clone()));
+    value
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reset() {
+    CACHE.with(|cache| cache.borrow_mut().clear())
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "runtime-config"))]
+fn host_get(key: &str) -> Option<String> {
+    let (key_ptr, key_len) = crate::ffi::str_parts(key);
+    let slot = crate::ffi::OutputSlot::new();
+
+    let value = unsafe {
+        if crate::externs::config::get(key_ptr, key_len, slot.as_u32()) != 1 {
+            return None;
+        }
+        slot.take()
+    };
+
+    Some(
+        String::from_utf8(value)
+            .unwrap_or_else(|_| panic!("runtime config value of key {:?} is not valid UTF-8", key)),
+    )
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "runtime-config")))]
+fn host_get(_key: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn host_get(key: &str) -> Option<String> {
+    crate::testing::runtime_config(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+
+    #[test]
+    fn it_reads_runtime_config() {
+        testing::reset();
+        assert_eq!(crate::runtime_config("api_key"), None);
+
+        testing::set_runtime_config("api_key", "secret");
+        assert_eq!(crate::runtime_config("api_key"), Some("secret".to_string()));
+        assert_eq!(crate::runtime_config("batch_size"), None);
+
+        testing::reset();
+        assert_eq!(crate::runtime_config("api_key"), None);
+    }
+}
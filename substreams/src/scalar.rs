@@ -19,6 +19,11 @@ use {
     thiserror::Error,
 };
 
+mod convert;
+pub mod math;
+
+pub use convert::BigDecimalConversionError;
+
 // ---------- BigDecimal ---------- //
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BigDecimal(bigdecimal::BigDecimal);
@@ -94,6 +99,32 @@ impl BigDecimal {
         BigDecimal::from(self.0.abs())
     }
 
+    /// Never `None`, kept for symmetry with [BigDecimal::checked_div].
+    pub fn checked_add(&self, other: &BigDecimal) -> Option<BigDecimal> {
+        Some(BigDecimal(&self.0 + &other.0))
+    }
+
+    /// Never `None`, kept for symmetry with [BigDecimal::checked_div].
+    pub fn checked_sub(&self, other: &BigDecimal) -> Option<BigDecimal> {
+        Some(BigDecimal(&self.0 - &other.0))
+    }
+
+    /// Never `None`, kept for symmetry with [BigDecimal::checked_div].
+    pub fn checked_mul(&self, other: &BigDecimal) -> Option<BigDecimal> {
+        Some(BigDecimal(&self.0 * &other.0))
+    }
+
+    /// `None` when dividing by zero instead of panicking.
+    pub fn checked_div(&self, other: &BigDecimal) -> Option<BigDecimal> {
+        if other.is_zero() {
+            return None;
+        }
+
+        Some(BigDecimal(&self.0 / &other.0))
+    }
+
+    /// The integer part of the value, truncated toward zero. `BigInt::try_from(&value)`
+    /// instead fails for values with a fractional part.
     pub fn to_bigint(&self) -> BigInt {
         BigInt(
             self.0
@@ -144,6 +175,22 @@ impl FromStr for BigDecimal {
     }
 }
 
+/// Serializes to the decimal string representation, like [Display], which keeps the full
+/// precision (JSON numbers are usually read back as `f64`).
+#[cfg(feature = "serde")]
+impl serde::Serialize for BigDecimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BigDecimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
 impl TryFrom<String> for BigDecimal {
     type Error = ParseBigDecimalError;
 
@@ -336,12 +383,15 @@ impl Div<&BigDecimal> for BigDecimal {
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BigInt(num_bigint::BigInt);
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BigIntOutOfRangeError {
     #[error("Cannot convert negative BigInt into type")]
     Negative,
     #[error("BigInt value is too large for type")]
     Overflow,
+    #[error("BigInt value is too small for type")]
+    Underflow,
 }
 
 impl fmt::Debug for BigInt {
@@ -417,12 +467,14 @@ impl BigInt {
         self.0.to_signed_bytes_be()
     }
 
+    #[deprecated(note = "panics when out of range, use `u64::try_from(&value)` instead")]
     pub fn to_u64(&self) -> u64 {
         self.0
             .to_u64()
             .unwrap_or_else(|| panic!("BigInt '{}' is too large to fit into u64", self))
     }
 
+    #[deprecated(note = "panics when out of range, use `i32::try_from(&value)` instead")]
     pub fn to_i32(&self) -> i32 {
         self.0
             .to_i32()
@@ -477,6 +529,30 @@ impl BigInt {
         let (quotient, remainder) = num_bigint::BigInt::div_rem(&self.0, &other.0);
         return (BigInt(quotient), BigInt(remainder));
     }
+
+    /// Never `None`, integers have no upper bound, kept for symmetry with [BigInt::checked_div].
+    pub fn checked_add(&self, other: &BigInt) -> Option<BigInt> {
+        Some(BigInt(&self.0 + &other.0))
+    }
+
+    /// Never `None`, integers have no lower bound, kept for symmetry with [BigInt::checked_div].
+    pub fn checked_sub(&self, other: &BigInt) -> Option<BigInt> {
+        Some(BigInt(&self.0 - &other.0))
+    }
+
+    /// Never `None`, integers have no upper bound, kept for symmetry with [BigInt::checked_div].
+    pub fn checked_mul(&self, other: &BigInt) -> Option<BigInt> {
+        Some(BigInt(&self.0 * &other.0))
+    }
+
+    /// The quotient truncated toward zero, `None` when dividing by zero instead of panicking.
+    pub fn checked_div(&self, other: &BigInt) -> Option<BigInt> {
+        if other.is_zero() {
+            return None;
+        }
+
+        Some(BigInt(&self.0 / &other.0))
+    }
 }
 
 impl Default for BigInt {
@@ -493,6 +569,31 @@ impl FromStr for BigInt {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for BigInt {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BigInt {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    T::from_str(&value).map_err(serde::de::Error::custom)
+}
+
 impl From<u32> for BigInt {
     fn from(i: u32) -> BigInt {
         BigInt(i.into())
@@ -557,53 +658,6 @@ impl Into<num_bigint::BigInt> for BigInt {
     }
 }
 
-impl TryFrom<BigInt> for u64 {
-    type Error = BigIntOutOfRangeError;
-    fn try_from(value: BigInt) -> Result<u64, BigIntOutOfRangeError> {
-        (&value).try_into()
-    }
-}
-
-impl<'a> TryFrom<&'a BigInt> for u64 {
-    type Error = BigIntOutOfRangeError;
-    fn try_from(value: &'a BigInt) -> Result<u64, BigIntOutOfRangeError> {
-        let (sign, bytes) = value.to_bytes_le();
-
-        if sign == num_bigint::Sign::Minus {
-            return Err(BigIntOutOfRangeError::Negative);
-        }
-
-        if bytes.len() > 8 {
-            return Err(BigIntOutOfRangeError::Overflow);
-        }
-
-        // Replace this with u64::from_le_bytes when stabilized
-        let mut n = 0u64;
-        let mut shift_dist = 0;
-        for b in bytes {
-            n |= (b as u64) << shift_dist;
-            shift_dist += 8;
-        }
-        Ok(n)
-    }
-}
-
-impl Into<u32> for BigInt {
-    fn into(self) -> u32 {
-        self.0
-            .to_u32()
-            .unwrap_or_else(|| panic!("BigInt '{}' is too large to fit into u32", self))
-    }
-}
-
-impl Into<i32> for BigInt {
-    fn into(self) -> i32 {
-        self.0
-            .to_i32()
-            .unwrap_or_else(|| panic!("BigInt '{}' is too large to fit into i32", self))
-    }
-}
-
 impl Into<String> for BigInt {
     fn into(self) -> String {
         self.to_string()
@@ -1059,6 +1113,8 @@ mod tests {
     use super::BigDecimal;
     use super::BigInt;
     use std::convert::TryFrom;
+    #[cfg(feature = "serde")]
+    use std::str::FromStr;
 
     fn big_decimal(input: f64) -> BigDecimal {
         BigDecimal::try_from(input).unwrap()
@@ -1375,4 +1431,21 @@ mod tests {
             big_decimal(0.1120545018)
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_as_string() {
+        let value = BigInt::from_str("-123456789012345678901234567890").unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#""-123456789012345678901234567890""#);
+        assert_eq!(serde_json::from_str::<BigInt>(&json).unwrap(), value);
+
+        let value = BigDecimal::from_str("1234567890.000000000000000001").unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#""1234567890.000000000000000001""#);
+        assert_eq!(serde_json::from_str::<BigDecimal>(&json).unwrap(), value);
+
+        assert!(serde_json::from_str::<BigInt>(r#""1.5""#).is_err());
+        assert!(serde_json::from_str::<BigInt>("15").is_err());
+    }
 }
@@ -0,0 +1,346 @@
+//! Fallible conversions between [BigInt], [BigDecimal] and the primitive numeric types.
+//!
+//! Every primitive integer and float converts from a `&BigInt` or a `&BigDecimal` (and from
+//! owned values, except `u32` and `i32` from an owned `BigInt` which go through their
+//! panicking `Into` implementations) with `TryFrom`, never panicking:
+//!
+//! ```rust
+//! use std::convert::TryFrom;
+//! use substreams::scalar::{BigDecimal, BigDecimalConversionError, BigInt, BigIntOutOfRangeError};
+//!
+//! let amount = BigInt::from(300);
+//! assert_eq!(u16::try_from(&amount), Ok(300));
+//! assert_eq!(u8::try_from(&amount), Err(BigIntOutOfRangeError::Overflow));
+//!
+//! let price: BigDecimal = "12.5".parse().unwrap();
+//! assert_eq!(f64::try_from(&price), Ok(12.5));
+//! assert_eq!(u64::try_from(&price), Err(BigDecimalConversionError::Fractional));
+//! assert_eq!(BigInt::try_from(price * 2), Ok(BigInt::from(25)));
+//! ```
+
+use std::convert::TryFrom;
+
+use num_traits::{FromPrimitive, Signed, ToPrimitive};
+use thiserror::Error;
+
+use super::{BigDecimal, BigInt, BigIntOutOfRangeError};
+
+/// Error converting a [BigDecimal], or a float into a [BigInt].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BigDecimalConversionError {
+    #[error("value has a fractional part")]
+    Fractional,
+    #[error("NaN and infinite floats have no decimal value")]
+    NotFinite,
+    #[error(transparent)]
+    OutOfRange(#[from] BigIntOutOfRangeError),
+}
+
+impl BigInt {
+    /// The error of a value out of the range of a primitive type.
+    fn range_error(&self, unsigned: bool) -> BigIntOutOfRangeError {
+        match (self.0.is_negative(), unsigned) {
+            (true, true) => BigIntOutOfRangeError::Negative,
+            (true, false) => BigIntOutOfRangeError::Underflow,
+            (false, _) => BigIntOutOfRangeError::Overflow,
+        }
+    }
+}
+
+impl BigDecimal {
+    /// The integer value, `None` if it has a fractional part.
+    fn to_exact_bigint(&self) -> Option<BigInt> {
+        if !self.0.is_integer() {
+            return None;
+        }
+
+        let (digits, scale) = self.0.with_scale(0).as_bigint_and_exponent();
+        debug_assert_eq!(scale, 0);
+        Some(BigInt(digits))
+    }
+
+    /// The closest float, parsed from the decimal representation so it's correctly rounded.
+    fn to_float<F: std::str::FromStr>(&self) -> Option<F> {
+        self.0.to_string().parse().ok()
+    }
+}
+
+macro_rules! impl_try_from_bigint {
+    ($unsigned:literal; $($t:ty => $to:ident),* $(,)?) => {
+        $(
+            impl<'a> TryFrom<&'a BigInt> for $t {
+                type Error = BigIntOutOfRangeError;
+
+                fn try_from(value: &'a BigInt) -> Result<$t, BigIntOutOfRangeError> {
+                    value
+                        .0
+                        .$to()
+                        .ok_or_else(|| value.range_error($unsigned))
+                }
+            }
+
+            impl<'a> TryFrom<&'a BigDecimal> for $t {
+                type Error = BigDecimalConversionError;
+
+                fn try_from(value: &'a BigDecimal) -> Result<$t, BigDecimalConversionError> {
+                    let int = value
+                        .to_exact_bigint()
+                        .ok_or(BigDecimalConversionError::Fractional)?;
+                    Ok(<$t>::try_from(&int)?)
+                }
+            }
+
+            impl TryFrom<BigDecimal> for $t {
+                type Error = BigDecimalConversionError;
+
+                fn try_from(value: BigDecimal) -> Result<$t, BigDecimalConversionError> {
+                    <$t>::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_bigint!(
+    false;
+    i8 => to_i8,
+    i16 => to_i16,
+    i32 => to_i32,
+    i64 => to_i64,
+    i128 => to_i128,
+    isize => to_isize,
+);
+impl_try_from_bigint!(
+    true;
+    u8 => to_u8,
+    u16 => to_u16,
+    u32 => to_u32,
+    u64 => to_u64,
+    u128 => to_u128,
+    usize => to_usize,
+);
+
+macro_rules! impl_try_from_owned_bigint {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<BigInt> for $t {
+                type Error = BigIntOutOfRangeError;
+
+                fn try_from(value: BigInt) -> Result<$t, BigIntOutOfRangeError> {
+                    <$t>::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_owned_bigint!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_try_from_bigint_float {
+    ($($t:ty => $to:ident),*) => {
+        $(
+            /// The closest float, failing when the value is beyond the range of the float.
+            impl<'a> TryFrom<&'a BigInt> for $t {
+                type Error = BigIntOutOfRangeError;
+
+                fn try_from(value: &'a BigInt) -> Result<$t, BigIntOutOfRangeError> {
+                    match value.0.$to() {
+                        Some(float) if float.is_finite() => Ok(float),
+                        _ => Err(value.range_error(false)),
+                    }
+                }
+            }
+
+            impl TryFrom<BigInt> for $t {
+                type Error = BigIntOutOfRangeError;
+
+                fn try_from(value: BigInt) -> Result<$t, BigIntOutOfRangeError> {
+                    <$t>::try_from(&value)
+                }
+            }
+
+            /// The closest float, failing when the value is beyond the range of the float.
+            /// Values too close to zero round to zero.
+            impl<'a> TryFrom<&'a BigDecimal> for $t {
+                type Error = BigDecimalConversionError;
+
+                fn try_from(value: &'a BigDecimal) -> Result<$t, BigDecimalConversionError> {
+                    match value.to_float::<$t>() {
+                        Some(float) if float.is_finite() => Ok(float),
+                        _ => Err(match value.0.is_negative() {
+                            true => BigIntOutOfRangeError::Underflow.into(),
+                            false => BigIntOutOfRangeError::Overflow.into(),
+                        }),
+                    }
+                }
+            }
+
+            impl TryFrom<BigDecimal> for $t {
+                type Error = BigDecimalConversionError;
+
+                fn try_from(value: BigDecimal) -> Result<$t, BigDecimalConversionError> {
+                    <$t>::try_from(&value)
+                }
+            }
+
+            /// The integer value of the float, failing for NaN, infinite and non-integer floats.
+            impl TryFrom<$t> for BigInt {
+                type Error = BigDecimalConversionError;
+
+                fn try_from(value: $t) -> Result<BigInt, BigDecimalConversionError> {
+                    if !value.is_finite() {
+                        return Err(BigDecimalConversionError::NotFinite);
+                    }
+                    if value.fract() != 0.0 {
+                        return Err(BigDecimalConversionError::Fractional);
+                    }
+
+                    num_bigint::BigInt::from_f64(f64::from(value))
+                        .map(BigInt)
+                        .ok_or(BigDecimalConversionError::NotFinite)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_bigint_float!(f32 => to_f32, f64 => to_f64);
+
+/// The integer value of the decimal, failing when it has a fractional part. Use
+/// [BigDecimal::to_bigint] to truncate it instead.
+impl<'a> TryFrom<&'a BigDecimal> for BigInt {
+    type Error = BigDecimalConversionError;
+
+    fn try_from(value: &'a BigDecimal) -> Result<BigInt, BigDecimalConversionError> {
+        value
+            .to_exact_bigint()
+            .ok_or(BigDecimalConversionError::Fractional)
+    }
+}
+
+impl TryFrom<BigDecimal> for BigInt {
+    type Error = BigDecimalConversionError;
+
+    fn try_from(value: BigDecimal) -> Result<BigInt, BigDecimalConversionError> {
+        BigInt::try_from(&value)
+    }
+}
+
+macro_rules! impl_from_small_ints {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for BigInt {
+                fn from(i: $t) -> BigInt {
+                    BigInt(i.into())
+                }
+            }
+
+            impl From<$t> for BigDecimal {
+                fn from(n: $t) -> Self {
+                    BigDecimal::from(BigInt::from(n))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_small_ints!(i8, i16, i128, u8, u16, u128);
+
+impl From<isize> for BigDecimal {
+    fn from(n: isize) -> Self {
+        BigDecimal::from(BigInt::from(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigDecimalConversionError::{Fractional, NotFinite, OutOfRange};
+    use crate::scalar::BigIntOutOfRangeError::{Negative, Overflow, Underflow};
+    use crate::scalar::{BigDecimal, BigInt};
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn bigint_to_primitives() {
+        assert_eq!(u8::try_from(&BigInt::from(255)), Ok(255));
+        assert_eq!(u8::try_from(&BigInt::from(256)), Err(Overflow));
+        assert_eq!(u64::try_from(BigInt::from(-1)), Err(Negative));
+        assert_eq!(i8::try_from(BigInt::from(-129)), Err(Underflow));
+        assert_eq!(u32::try_from(&BigInt::from(u64::MAX)), Err(Overflow));
+        assert_eq!(u32::try_from(BigInt::from(-1)), Err(Negative));
+        assert_eq!(i32::try_from(BigInt::from(i32::MIN)), Ok(i32::MIN));
+        assert_eq!(i128::try_from(&BigInt::from(i128::MIN)), Ok(i128::MIN));
+        assert_eq!(u128::try_from(BigInt::from(u128::MAX)), Ok(u128::MAX));
+
+        assert_eq!(
+            f64::try_from(&BigInt::from(1u64 << 60)),
+            Ok((1u64 << 60) as f64)
+        );
+        let huge = BigInt::from(10).pow(400);
+        assert_eq!(f64::try_from(&huge), Err(Overflow));
+        assert_eq!(f32::try_from(huge.neg()), Err(Underflow));
+    }
+
+    #[test]
+    fn bigdecimal_to_primitives() {
+        assert_eq!(i64::try_from(&dec("-42.000")), Ok(-42));
+        assert_eq!(u16::try_from(dec("1e3")), Ok(1000));
+        assert_eq!(u16::try_from(dec("0.5")), Err(Fractional));
+        assert_eq!(u16::try_from(dec("-1")), Err(OutOfRange(Negative)));
+        assert_eq!(u8::try_from(dec("2.56e2")), Err(OutOfRange(Overflow)));
+
+        assert_eq!(f64::try_from(&dec("0.1")), Ok(0.1));
+        assert_eq!(f32::try_from(dec("-12.5")), Ok(-12.5));
+        assert_eq!(f64::try_from(&dec("1e400")), Err(OutOfRange(Overflow)));
+        assert_eq!(f64::try_from(&dec("-1e400")), Err(OutOfRange(Underflow)));
+        assert_eq!(f64::try_from(&dec("1e-400")), Ok(0.0));
+    }
+
+    #[test]
+    fn bigdecimal_and_floats_to_bigint() {
+        assert_eq!(BigInt::try_from(&dec("120.00")), Ok(BigInt::from(120)));
+        assert_eq!(BigInt::try_from(dec("1.2e3")), Ok(BigInt::from(1200)));
+        assert_eq!(BigInt::try_from(dec("-0.5")), Err(Fractional));
+
+        assert_eq!(BigInt::try_from(-3.0f64), Ok(BigInt::from(-3)));
+        assert_eq!(
+            BigInt::try_from(1e20f64),
+            Ok(BigInt::from(100_000_000_000_000_000_000u128))
+        );
+        assert_eq!(BigInt::try_from(2.5f32), Err(Fractional));
+        assert_eq!(BigInt::try_from(f64::NAN), Err(NotFinite));
+        assert_eq!(BigInt::try_from(f32::INFINITY), Err(NotFinite));
+    }
+
+    #[test]
+    fn checked_operations() {
+        let (a, b) = (BigInt::from(7), BigInt::from(-2));
+        assert_eq!(a.checked_add(&b), Some(BigInt::from(5)));
+        assert_eq!(a.checked_sub(&b), Some(BigInt::from(9)));
+        assert_eq!(a.checked_mul(&b), Some(BigInt::from(-14)));
+        assert_eq!(a.checked_div(&b), Some(BigInt::from(-3)));
+        assert_eq!(a.checked_div(&BigInt::zero()), None);
+
+        let (a, b) = (dec("1.5"), dec("0.5"));
+        assert_eq!(a.checked_add(&b), Some(dec("2")));
+        assert_eq!(a.checked_sub(&b), Some(dec("1")));
+        assert_eq!(a.checked_mul(&b), Some(dec("0.75")));
+        assert_eq!(a.checked_div(&b), Some(dec("3")));
+        assert_eq!(a.checked_div(&BigDecimal::zero()), None);
+    }
+
+    #[test]
+    fn small_ints_into_scalars() {
+        assert_eq!(BigInt::from(-5i8), BigInt::from(-5));
+        assert_eq!(BigDecimal::from(7u16), BigDecimal::from(7));
+        assert_eq!(
+            BigDecimal::from(u128::MAX).to_string(),
+            u128::MAX.to_string()
+        );
+        assert_eq!(BigDecimal::from(-1isize), BigDecimal::from(-1));
+    }
+}
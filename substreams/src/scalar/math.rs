@@ -0,0 +1,494 @@
+//! Deterministic fixed-precision math functions on [BigDecimal].
+//!
+//! The functions only use integer arithmetic, so every module computes the exact same digits
+//! whatever the platform, unlike `f64` round-trips. Results are rounded half to even to the
+//! requested number of significant digits, [DEFAULT_PRECISION] for the variants without
+//! `_with_prec`, and have no trailing zeros.
+//!
+//! Intermediate values are fixed-point integers carrying [GUARD_DIGITS] more digits than the
+//! requested precision:
+//!
+//! - `sqrt` is the integer square root of the digits scaled to twice the working precision.
+//! - `ln(x)` splits `x = y * 2^b * 10^-s` with `y` in `[1, 2)` and sums
+//!   `ln(y) + b * ln(2) - s * ln(10)`. The logarithms are computed with the
+//!   `ln((1 + z) / (1 - z)) = 2 * atanh(z)` series, `|z| <= 1/3`.
+//! - `exp(x)` splits `x = n * ln(2) + r` with `|r| <= ln(2) / 2` and multiplies `2^n` by the
+//!   Taylor series of `exp(r)`.
+//! - `powi` is computed by squaring, `powf(x, y)` as `exp(y * ln(x))` for non-integer `y`.
+
+use std::cmp::Ordering;
+
+use num_bigint::BigInt as Int;
+use num_integer::Integer;
+use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+
+use super::BigDecimal;
+
+/// Significant digits of the results of the functions called without precision, the
+/// precision of IEEE-754 decimal128.
+pub const DEFAULT_PRECISION: u64 = BigDecimal::MAX_SIGNIFICANT_DIGITS as u64;
+
+/// Digits computed beyond the requested precision so the rounded result is exact in all but
+/// pathological cases.
+pub const GUARD_DIGITS: u64 = 10;
+
+impl BigDecimal {
+    /// The square root, `None` for negative values.
+    pub fn sqrt(&self) -> Option<BigDecimal> {
+        self.sqrt_with_prec(DEFAULT_PRECISION)
+    }
+
+    pub fn sqrt_with_prec(&self, prec: u64) -> Option<BigDecimal> {
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        match digits.sign() {
+            num_bigint::Sign::Minus => return None,
+            num_bigint::Sign::NoSign => return Some(BigDecimal::zero()),
+            num_bigint::Sign::Plus => {}
+        }
+
+        // Scales the digits by `10^k` so they have an even scale and at least twice the working
+        // precision, their integer square root then has the working precision
+        let mut k = (2 * working(prec) - decimal_digits(&digits)).max(0);
+        if (scale + k) % 2 != 0 {
+            k += 1;
+        }
+        let scaled = digits * pow10(k);
+        let root = scaled.sqrt();
+        let (root, root_scale) = match &root * &root == scaled {
+            true => (root, (scale + k) / 2),
+            // Marks the root as inexact for the rounding, with a trailing digit
+            false => (root * 10u8 + 1u8, (scale + k) / 2 + 1),
+        };
+
+        Some(round(root, root_scale, prec))
+    }
+
+    /// The natural logarithm, `None` for zero and negative values.
+    pub fn ln(&self) -> Option<BigDecimal> {
+        self.ln_with_prec(DEFAULT_PRECISION)
+    }
+
+    pub fn ln_with_prec(&self, prec: u64) -> Option<BigDecimal> {
+        if !self.0.is_positive() {
+            return None;
+        }
+
+        let distance = &self.0 - bigdecimal::BigDecimal::one();
+        if distance.is_zero() {
+            return Some(BigDecimal::zero());
+        }
+
+        // `ln(1 + d)` is close to `d`, the digits of `d` after the point are needed on top of
+        // the precision when it's small
+        let (distance, distance_scale) = distance.as_bigint_and_exponent();
+        let w = working(prec) + (-magnitude(&distance, distance_scale)).max(0);
+
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        Some(round(ln_fixed(&digits, scale, w), w, prec))
+    }
+
+    /// The exponential, `e` raised to this value.
+    pub fn exp(&self) -> BigDecimal {
+        self.exp_with_prec(DEFAULT_PRECISION)
+    }
+
+    pub fn exp_with_prec(&self, prec: u64) -> BigDecimal {
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        let (result, result_scale) = exp_fixed(&digits, scale, working(prec));
+        round(result, result_scale, prec)
+    }
+
+    /// This value raised to the integer power `exponent`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is zero and `exponent` negative.
+    pub fn powi(&self, exponent: i64) -> BigDecimal {
+        self.powi_with_prec(exponent, DEFAULT_PRECISION)
+    }
+
+    pub fn powi_with_prec(&self, exponent: i64, prec: u64) -> BigDecimal {
+        if exponent == 0 {
+            return BigDecimal::one();
+        }
+        if self.is_zero() {
+            if exponent < 0 {
+                panic!("attempt to divide by zero");
+            }
+            return BigDecimal::zero();
+        }
+
+        // Each multiplication loses a digit at most, twice per bit of the exponent
+        let w = working(prec) + decimal_digits(&Int::from(exponent.unsigned_abs()));
+        let (mut base, mut base_scale) = self.0.as_bigint_and_exponent();
+        let (mut result, mut result_scale) = (Int::one(), 0);
+        let mut remaining = exponent.unsigned_abs();
+        loop {
+            if remaining & 1 == 1 {
+                let (digits, scale) = truncate(&result * &base, result_scale + base_scale, w);
+                result = digits;
+                result_scale = scale;
+            }
+            remaining >>= 1;
+            if remaining == 0 {
+                break;
+            }
+            let (digits, scale) = truncate(&base * &base, 2 * base_scale, w);
+            base = digits;
+            base_scale = scale;
+        }
+
+        if exponent < 0 {
+            // `1 / (m * 10^-s)` is `(10^(w + d) / m) * 10^-(w + d - s)`, `d` the digits of `m`
+            let shift = w + decimal_digits(&result);
+            let (quotient, remainder) = pow10(shift).div_rem(&result);
+            let (quotient, quotient_scale) = match remainder.is_zero() {
+                true => (quotient, shift - result_scale),
+                false => (quotient * 10u8 + result.signum(), shift - result_scale + 1),
+            };
+            return round(quotient, quotient_scale, prec);
+        }
+
+        round(result, result_scale, prec)
+    }
+
+    /// This value raised to the power `exponent`, `None` if the result isn't a real number:
+    /// for negative values raised to non-integer powers and zero raised to negative powers.
+    pub fn powf(&self, exponent: &BigDecimal) -> Option<BigDecimal> {
+        self.powf_with_prec(exponent, DEFAULT_PRECISION)
+    }
+
+    pub fn powf_with_prec(&self, exponent: &BigDecimal, prec: u64) -> Option<BigDecimal> {
+        if exponent.0.is_integer() {
+            if let Some(exponent) = exponent.0.to_i64() {
+                if self.is_zero() && exponent < 0 {
+                    return None;
+                }
+                return Some(self.powi_with_prec(exponent, prec));
+            }
+        }
+
+        match self.0.sign() {
+            num_bigint::Sign::Minus => return None,
+            num_bigint::Sign::NoSign if exponent.0.is_positive() => return Some(BigDecimal::zero()),
+            num_bigint::Sign::NoSign => return None,
+            num_bigint::Sign::Plus => {}
+        }
+
+        // The error of `ln(x)` is multiplied by `y`, its integer digits are needed on top of
+        // the precision
+        let (y, y_scale) = exponent.0.as_bigint_and_exponent();
+        let w = working(prec) + (magnitude(&y, y_scale) + 1).max(0);
+
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        let product = ln_fixed(&digits, scale, w) * y;
+        let (result, result_scale) = exp_fixed(&product, w + y_scale, working(prec));
+        Some(round(result, result_scale, prec))
+    }
+}
+
+/// Digits of the intermediate values for a result of `prec` significant digits.
+fn working(prec: u64) -> i64 {
+    (prec + GUARD_DIGITS) as i64
+}
+
+fn pow10(exponent: i64) -> Int {
+    Int::from(10u8).pow(exponent as u64)
+}
+
+/// The number of decimal digits of `value`, 0 for zero.
+fn decimal_digits(value: &Int) -> i64 {
+    match value.is_zero() {
+        true => 0,
+        false => value.abs().to_str_radix(10).len() as i64,
+    }
+}
+
+/// The power of ten of the most significant digit of `digits * 10^-scale`.
+fn magnitude(digits: &Int, scale: i64) -> i64 {
+    decimal_digits(digits) - 1 - scale
+}
+
+/// Converts fixed-point `digits * 10^-from` to the scale `to`, truncated toward zero.
+fn rescale(digits: &Int, from: i64, to: i64) -> Int {
+    match to.cmp(&from) {
+        Ordering::Greater => digits * pow10(to - from),
+        Ordering::Equal => digits.clone(),
+        Ordering::Less => digits / pow10(from - to),
+    }
+}
+
+/// Truncates `digits * 10^-scale` to `prec` significant digits, followed by a non-zero digit
+/// when inexact so the final rounding never sees a false tie.
+fn truncate(digits: Int, scale: i64, prec: i64) -> (Int, i64) {
+    let excess = decimal_digits(&digits) - prec;
+    if excess <= 0 {
+        return (digits, scale);
+    }
+
+    let (quotient, remainder) = digits.div_rem(&pow10(excess));
+    match remainder.is_zero() {
+        true => (quotient, scale - excess),
+        false => (quotient * 10u8 + remainder.signum(), scale - excess + 1),
+    }
+}
+
+/// Rounds `digits * 10^-scale` half to even to `prec` significant digits and strips the
+/// trailing zeros.
+fn round(digits: Int, scale: i64, prec: u64) -> BigDecimal {
+    let excess = decimal_digits(&digits) - prec as i64;
+    let (mut digits, mut scale) = match excess > 0 {
+        true => {
+            let divisor = pow10(excess);
+            let (quotient, remainder) = digits.div_rem(&divisor);
+            let round_up = match (remainder.abs() * 2u8).cmp(&divisor) {
+                Ordering::Greater => true,
+                Ordering::Equal => quotient.is_odd(),
+                Ordering::Less => false,
+            };
+            match round_up {
+                true => (quotient + digits.signum(), scale - excess),
+                false => (quotient, scale - excess),
+            }
+        }
+        false => (digits, scale),
+    };
+
+    if digits.is_zero() {
+        return BigDecimal::zero();
+    }
+    let ten = Int::from(10u8);
+    while digits.is_multiple_of(&ten) {
+        digits /= 10u8;
+        scale -= 1;
+    }
+
+    BigDecimal(bigdecimal::BigDecimal::new(digits, scale))
+}
+
+/// `atanh(x)` with `x` at the fixed-point scale of `one`, `|x| <= 1/3`.
+fn atanh(x: &Int, one: &Int) -> Int {
+    let square = x * x / one;
+    let mut power = x.clone();
+    let mut sum = Int::zero();
+    let mut k = 1u32;
+    loop {
+        let term = &power / k;
+        if term.is_zero() {
+            return sum;
+        }
+        sum += term;
+        power = power * &square / one;
+        k += 2;
+    }
+}
+
+/// `ln(2) = 2 * atanh(1/3)` at the fixed-point scale of `one`.
+fn ln2(one: &Int) -> Int {
+    atanh(&(one / 3u8), one) * 2u8
+}
+
+/// `ln(10) = 3 * ln(2) + ln(1.25)` with `ln(1.25) = 2 * atanh(1/9)`, at the fixed-point scale
+/// of `one`.
+fn ln10(one: &Int) -> Int {
+    ln2(one) * 3u8 + atanh(&(one / 9u8), one) * 2u8
+}
+
+/// `ln(digits * 10^-scale)` at the fixed-point scale `w`, `digits` positive.
+fn ln_fixed(digits: &Int, scale: i64, w: i64) -> Int {
+    // `digits / 2^b` is in `[1, 2)`
+    let b = digits.bits() as i64 - 1;
+
+    // The errors of the constants are multiplied by `b` and `scale`
+    let wc = w + decimal_digits(&Int::from(b.max(scale.abs()))) + 2;
+    let one = pow10(wc);
+
+    let y = (digits * &one) >> b as usize;
+    let z = (&y - &one) * &one / (&y + &one);
+    let mut ln = atanh(&z, &one) * 2u8;
+    if b != 0 {
+        ln += ln2(&one) * b;
+    }
+    if scale != 0 {
+        ln -= ln10(&one) * scale;
+    }
+
+    rescale(&ln, wc, w)
+}
+
+/// `exp(x * 10^-scale)` as digits and scale, with at least `w` significant digits.
+fn exp_fixed(x: &Int, scale: i64, w: i64) -> (Int, i64) {
+    // `n = round(x / ln(2))`, a coarse `ln(2)` is enough to keep `|r|` close to `ln(2) / 2`
+    let coarse = pow10(20);
+    let coarse_ln2 = ln2(&coarse);
+    let n = (rescale(x, scale, 20) * 2u8 + &coarse_ln2).div_floor(&(coarse_ln2 * 2u8));
+    let n = n
+        .to_i64()
+        .unwrap_or_else(|| panic!("exponential of {}e-{} overflows", x, scale));
+
+    // The error of `ln(2)` is multiplied by `n`
+    let wc = w + decimal_digits(&Int::from(n)) + 2;
+    let one = pow10(wc);
+    let r = rescale(x, scale, wc) - ln2(&one) * n;
+
+    let mut sum = one.clone();
+    let mut term = one.clone();
+    let mut k = 1u32;
+    loop {
+        term = term * &r / &one / k;
+        if term.is_zero() {
+            break;
+        }
+        sum += &term;
+        k += 1;
+    }
+
+    match n >= 0 {
+        true => (sum << n as usize, wc),
+        // `2^-n` is `5^n * 10^-n`
+        false => (sum * Int::from(5u8).pow(n.unsigned_abs()), wc - n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scalar::BigDecimal;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    // Expected values are computed with Python's `decimal` module, which rounds these functions
+    // correctly half to even, at 34 digits of precision.
+
+    #[test]
+    fn it_computes_square_roots() {
+        for (value, expected) in [
+            ("2", "1.414213562373095048801688724209698"),
+            ("0.0001", "0.01"),
+            (
+                "12345678901234567890.123",
+                "3513641828.820144253111157379483579",
+            ),
+            ("0.0000001", "0.0003162277660168379331998893544432719"),
+            ("1.0001", "1.000049998750062496094023416993799"),
+            ("3", "1.732050807568877293527446341505872"),
+            ("0", "0"),
+        ] {
+            assert_eq!(dec(value).sqrt(), Some(dec(expected)), "sqrt({})", value);
+        }
+
+        assert_eq!(dec("0.0001").sqrt().unwrap().to_string(), "0.01");
+        assert_eq!(dec("-4").sqrt(), None);
+    }
+
+    #[test]
+    fn it_computes_logarithms() {
+        for (value, expected) in [
+            ("2", "0.6931471805599453094172321214581766"),
+            ("10", "2.302585092994045684017991454684364"),
+            ("0.5", "-0.6931471805599453094172321214581766"),
+            (
+                "1.0000000001",
+                "0.00000000009999999999500000000033333333330833",
+            ),
+            (
+                "0.000000000000000000000000000001",
+                "-69.07755278982137052053974364053093",
+            ),
+            ("123456.789", "11.72364648718588098113995898391011"),
+            ("0.999", "-0.001000500333583533500142982254068345"),
+            ("1", "0"),
+        ] {
+            assert_eq!(dec(value).ln(), Some(dec(expected)), "ln({})", value);
+        }
+
+        assert_eq!(
+            dec("2").ln_with_prec(60),
+            Some(dec(
+                "0.69314718055994530941723212145817656807550013436025525412068"
+            ))
+        );
+        assert_eq!(dec("0").ln(), None);
+        assert_eq!(dec("-1").ln(), None);
+    }
+
+    #[test]
+    fn it_computes_exponentials() {
+        for (value, expected) in [
+            ("1", "2.718281828459045235360287471352662"),
+            ("-1", "0.3678794411714423215955237701614609"),
+            ("0.5", "1.648721270700128146848650787814164"),
+            ("100", "26881171418161354484126255515800140000000000"),
+            (
+                "-50.25",
+                "0.0000000000000000000001502111891943152253933286421309578",
+            ),
+            ("0.00000000000000000001", "1.00000000000000000001"),
+            ("-0.693", "0.5000735956957676578171691224591216"),
+            ("0", "1"),
+        ] {
+            assert_eq!(dec(value).exp(), dec(expected), "exp({})", value);
+        }
+
+        assert_eq!(
+            dec("1").exp_with_prec(60),
+            dec("2.71828182845904523536028747135266249775724709369995957496697")
+        );
+    }
+
+    #[test]
+    fn it_computes_powers() {
+        for (value, exponent, expected) in [
+            ("1.0001", 887272, "340256786836388094050805785052946500000"),
+            (
+                "1.0001",
+                -887272,
+                "0.000000000000000000000000000000000000002938956807585584838874754864968834",
+            ),
+            ("2.5", -3, "0.064"),
+            ("-1.5", 3, "-3.375"),
+            ("1.1", 100, "13780.61233982227018411833717208964"),
+            ("7", -1, "0.1428571428571428571428571428571429"),
+            ("0", 5, "0"),
+            ("0", 0, "1"),
+        ] {
+            assert_eq!(
+                dec(value).powi(exponent),
+                dec(expected),
+                "powi({}, {})",
+                value,
+                exponent
+            );
+        }
+
+        for (value, exponent, expected) in [
+            ("2", "0.5", "1.414213562373095048801688724209698"),
+            ("1.0001", "-1234.5", "0.8838712887459447519535485904701664"),
+            ("10", "2.5", "316.2277660168379331998893544432719"),
+            ("0.3", "1.7", "0.1291534860749802672257598893637269"),
+            ("123.456", "-0.25", "0.3000004800019200092160479234607037"),
+            ("-1.5", "3", "-3.375"),
+            ("0", "0.5", "0"),
+        ] {
+            assert_eq!(
+                dec(value).powf(&dec(exponent)),
+                Some(dec(expected)),
+                "powf({}, {})",
+                value,
+                exponent
+            );
+        }
+
+        assert_eq!(dec("-2").powf(&dec("0.5")), None);
+        assert_eq!(dec("0").powf(&dec("-0.5")), None);
+        assert_eq!(dec("0").powf(&dec("-1")), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to divide by zero")]
+    fn it_panics_on_zero_raised_to_negative_powers() {
+        dec("0").powi(-1);
+    }
+}
@@ -0,0 +1,166 @@
+//! Output of `kv_out` modules consumed by the key-value sink, the standard
+//! `sf.substreams.sink.kv.v1.KVOperations` message.
+//!
+//! [KvOperations] records `set` and `delete` operations in order, it can be returned
+//! directly from a map handler:
+//!
+//! ```rust
+//! use substreams::scalar::BigInt;
+//! use substreams::sink::kv::KvOperations;
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pool { #[prost(string, tag = "1")] pub address: String }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Pools { #[prost(message, repeated, tag = "1")] pub pools: Vec<Pool> }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn kv_out(pools: pb::Pools) -> KvOperations {
+//!     let mut operations = KvOperations::new();
+//!     for (i, pool) in pools.pools.iter().enumerate() {
+//!         operations.set_proto(i as u64, format!("pool:{}", pool.address), pool);
+//!         operations.set(i as u64, format!("pool:{}:liquidity", pool.address), BigInt::zero());
+//!         operations.delete(i as u64, format!("pending:{}", pool.address));
+//!     }
+//!
+//!     operations
+//! }
+//! ```
+
+pub use crate::pb::sf::substreams::sink::kv::v1::{kv_operation::Type, KvOperation, KvOperations};
+use crate::scalar::{BigDecimal, BigInt};
+
+impl KvOperations {
+    pub fn new() -> Self {
+        KvOperations::default()
+    }
+
+    /// Sets `key` to `value` at ordinal `ord`.
+    pub fn set<K: Into<String>, V: ToValue>(&mut self, ord: u64, key: K, value: V) {
+        self.push(ord, key.into(), value.to_value(), Type::Set)
+    }
+
+    /// Sets `key` to the Protobuf encoding of `msg` at ordinal `ord`.
+    pub fn set_proto<K: Into<String>, M: prost::Message>(&mut self, ord: u64, key: K, msg: &M) {
+        self.push(ord, key.into(), msg.encode_to_vec(), Type::Set)
+    }
+
+    /// Deletes `key` at ordinal `ord`.
+    pub fn delete<K: Into<String>>(&mut self, ord: u64, key: K) {
+        self.push(ord, key.into(), vec![], Type::Delete)
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    fn push(&mut self, ord: u64, key: String, value: Vec<u8>, kind: Type) {
+        self.operations.push(KvOperation {
+            key,
+            value,
+            ordinal: ord,
+            r#type: kind as i32,
+        })
+    }
+}
+
+/// Conversion of a value to the bytes stored by the key-value sink. Strings are stored as
+/// UTF-8 and numbers, like in stores, as their decimal representation.
+pub trait ToValue {
+    fn to_value(self) -> Vec<u8>;
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl ToValue for &[u8] {
+    fn to_value(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl ToValue for String {
+    fn to_value(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl ToValue for &str {
+    fn to_value(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl ToValue for i64 {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl ToValue for u64 {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl ToValue for BigInt {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl ToValue for &BigInt {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl ToValue for BigDecimal {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl ToValue for &BigDecimal {
+    fn to_value(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KvOperations, Type};
+    use crate::scalar::BigInt;
+
+    #[test]
+    fn it_records_operations_in_order() {
+        let mut operations = KvOperations::new();
+        operations.set(1, "a", "value");
+        operations.set(2, "b", BigInt::from(-12));
+        operations.set_proto(3, "c", &KvOperations::new());
+        operations.delete(4, "a");
+
+        let kinds: Vec<(u64, Type, Vec<u8>)> = operations
+            .operations
+            .iter()
+            .map(|op| (op.ordinal, op.r#type(), op.value.clone()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (1, Type::Set, b"value".to_vec()),
+                (2, Type::Set, b"-12".to_vec()),
+                (3, Type::Set, vec![]),
+                (4, Type::Delete, vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+//! Helpers building the outputs consumed by Substreams sinks.
+
+pub mod kv;
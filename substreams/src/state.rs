@@ -1,9 +1,42 @@
+use crate::batch;
+use crate::guardrails;
+#[cfg(feature = "record")]
+use crate::journal;
+use crate::metrics;
+#[cfg(feature = "replay")]
+use crate::replay;
 use crate::scalar::{BigDecimal, BigInt};
+use crate::store::value::format_f64;
+use crate::store::Ordinal;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::testing;
+#[cfg(feature = "debug-assertions")]
+use crate::write_checks;
 #[cfg(target_arch = "wasm32")]
 use crate::{externs, memory};
+use std::cell::Cell;
+use std::convert::TryInto;
+
+pub fn get_at<K: AsRef<str>>(store_idx: u32, ord: impl Into<Ordinal>, key: K) -> Option<Vec<u8>> {
+    let ord = ord.into().to_host();
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let value = replay::read("get_at", store_idx, ord, key.as_ref(), || {
+        host_get_at(store_idx, ord, key.as_ref())
+    });
+
+    #[cfg(not(feature = "replay"))]
+    let value = host_get_at(store_idx, ord, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read("get_at", store_idx, ord, key.as_ref(), value.as_deref());
+
+    value
+}
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn get_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> Option<Vec<u8>> {
+fn host_get_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> Option<Vec<u8>> {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -18,20 +51,45 @@ pub fn get_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> Option<Vec<u8>
                 key_bytes.len() as u32,
                 output_ptr as u32,
             );
-            return if found == 1 {
+            if found == 1 {
                 Some(memory::get_output_data(output_ptr))
             } else {
                 None
-            };
+            }
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    None
+    testing::get(store_idx, key.as_ref())
+}
+
+pub fn has_at<K: AsRef<str>>(store_idx: u32, ord: impl Into<Ordinal>, key: K) -> bool {
+    let ord = ord.into().to_host();
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let found = replay::read("has_at", store_idx, ord, key.as_ref(), || {
+        host_has_at(store_idx, ord, key.as_ref()).then(Vec::new)
+    })
+    .is_some();
+
+    #[cfg(not(feature = "replay"))]
+    let found = host_has_at(store_idx, ord, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read(
+        "has_at",
+        store_idx,
+        ord,
+        key.as_ref(),
+        found.then(|| &[][..]),
+    );
+
+    found
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn has_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> bool {
+fn host_has_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> bool {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -45,11 +103,27 @@ pub fn has_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> bool {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    false
+    testing::get(store_idx, key.as_ref()).is_some()
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
 pub fn get_last<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let value = replay::read("get_last", store_idx, 0, key.as_ref(), || {
+        host_get_last(store_idx, key.as_ref())
+    });
+
+    #[cfg(not(feature = "replay"))]
+    let value = host_get_last(store_idx, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read("get_last", store_idx, 0, key.as_ref(), value.as_deref());
+
+    value
+}
+
+fn host_get_last<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -64,20 +138,101 @@ pub fn get_last<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
                 output_ptr as u32,
             );
 
-            return if found == 1 {
+            if found == 1 {
                 Some(memory::get_output_data(output_ptr))
             } else {
                 None
-            };
+            }
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    None
+    testing::get(store_idx, key.as_ref())
+}
+
+/// Reads the last value of `key` into `buf`, replacing its content and reusing its allocation.
+/// Returns the length of the value, `None` (with `buf` left empty) if the key is not found.
+///
+/// The host writes the value directly into `buf` when it fits its capacity, otherwise it
+/// only returns the value length and the read is retried once `buf` has grown.
+pub fn get_last_into<K: AsRef<str>>(store_idx: u32, key: K, buf: &mut Vec<u8>) -> Option<usize> {
+    buf.clear();
+
+    #[cfg(any(feature = "replay", not(target_arch = "wasm32")))]
+    return get_last(store_idx, key).map(|value| {
+        buf.extend_from_slice(&value);
+        value.len()
+    });
+
+    #[cfg(all(target_arch = "wasm32", not(feature = "replay")))]
+    {
+        metrics::on_read(1);
+        let key_bytes = key.as_ref().as_bytes();
+
+        unsafe {
+            let mut len = externs::state::get_last_into(
+                store_idx,
+                key_bytes.as_ptr(),
+                key_bytes.len() as u32,
+                buf.as_mut_ptr() as u32,
+                buf.capacity() as u32,
+            );
+            if len < 0 {
+                #[cfg(feature = "record")]
+                journal::read("get_last", store_idx, 0, key.as_ref(), None);
+                return None;
+            }
+
+            if len as usize > buf.capacity() {
+                buf.reserve(len as usize);
+                len = externs::state::get_last_into(
+                    store_idx,
+                    key_bytes.as_ptr(),
+                    key_bytes.len() as u32,
+                    buf.as_mut_ptr() as u32,
+                    buf.capacity() as u32,
+                );
+                if len < 0 || len as usize > buf.capacity() {
+                    panic!(
+                        "invalid store read: value of key {:?} changed size",
+                        key.as_ref()
+                    );
+                }
+            }
+
+            buf.set_len(len as usize);
+            #[cfg(feature = "record")]
+            journal::read("get_last", store_idx, 0, key.as_ref(), Some(buf));
+            Some(len as usize)
+        }
+    }
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
 pub fn has_last<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let found = replay::read("has_last", store_idx, 0, key.as_ref(), || {
+        host_has_last(store_idx, key.as_ref()).then(Vec::new)
+    })
+    .is_some();
+
+    #[cfg(not(feature = "replay"))]
+    let found = host_has_last(store_idx, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read(
+        "has_last",
+        store_idx,
+        0,
+        key.as_ref(),
+        found.then(|| &[][..]),
+    );
+
+    found
+}
+
+fn host_has_last<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -91,11 +246,27 @@ pub fn has_last<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    false
+    testing::get(store_idx, key.as_ref()).is_some()
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
 pub fn get_first<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let value = replay::read("get_first", store_idx, 0, key.as_ref(), || {
+        host_get_first(store_idx, key.as_ref())
+    });
+
+    #[cfg(not(feature = "replay"))]
+    let value = host_get_first(store_idx, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read("get_first", store_idx, 0, key.as_ref(), value.as_deref());
+
+    value
+}
+
+fn host_get_first<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -111,20 +282,43 @@ pub fn get_first<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
                 output_ptr as u32,
             );
 
-            return if found == 1 {
+            if found == 1 {
                 Some(memory::get_output_data(output_ptr))
             } else {
                 None
-            };
+            }
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    None
+    testing::get(store_idx, key.as_ref())
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
 pub fn has_first<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
+    metrics::on_read(1);
+
+    #[cfg(feature = "replay")]
+    let found = replay::read("has_first", store_idx, 0, key.as_ref(), || {
+        host_has_first(store_idx, key.as_ref()).then(Vec::new)
+    })
+    .is_some();
+
+    #[cfg(not(feature = "replay"))]
+    let found = host_has_first(store_idx, key.as_ref());
+
+    #[cfg(feature = "record")]
+    journal::read(
+        "has_first",
+        store_idx,
+        0,
+        key.as_ref(),
+        found.then(|| &[][..]),
+    );
+
+    found
+}
+
+fn host_has_first<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -138,15 +332,334 @@ pub fn has_first<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    false
+    testing::get(store_idx, key.as_ref()).is_some()
+}
+
+/// Reads many keys at once, performing a single host call. Keys are sent to the host as a
+/// sequence of `<u32 little-endian length><key bytes>` and the host answers with, for each key
+/// in order, a `0` byte when not found or a `1` byte followed by `<u32 little-endian length><value>`.
+pub fn get_many_last<K: AsRef<str>>(store_idx: u32, keys: &[K]) -> Vec<Option<Vec<u8>>> {
+    // Replayed reads are counted by the single key reads
+    #[cfg(not(feature = "replay"))]
+    metrics::on_read(keys.len());
+
+    #[cfg(feature = "replay")]
+    return keys.iter().map(|key| get_last(store_idx, key)).collect();
+
+    #[cfg(all(target_arch = "wasm32", not(feature = "replay")))]
+    let values = {
+        let keys_bytes = encode_keys(keys);
+
+        unsafe {
+            let output_ptr = memory::alloc(8);
+            externs::state::get_many_last(
+                store_idx,
+                keys_bytes.as_ptr(),
+                keys_bytes.len() as u32,
+                output_ptr as u32,
+            );
+
+            decode_many(&memory::get_output_data(output_ptr), keys.len())
+        }
+    };
+
+    #[cfg(all(not(target_arch = "wasm32"), not(feature = "replay")))]
+    let values: Vec<_> = keys
+        .iter()
+        .map(|key| testing::get(store_idx, key.as_ref()))
+        .collect();
+
+    // Journaled as single key reads, like replayed reads
+    #[cfg(all(feature = "record", not(feature = "replay")))]
+    for (key, value) in keys.iter().zip(values.iter()) {
+        journal::read("get_last", store_idx, 0, key.as_ref(), value.as_deref());
+    }
+
+    #[cfg(not(feature = "replay"))]
+    values
+}
+
+/// Same as [get_many_last] but reading the keys at ordinal `ord`.
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub fn get_many_at<K: AsRef<str>>(
+    store_idx: u32,
+    ord: impl Into<Ordinal>,
+    keys: &[K],
+) -> Vec<Option<Vec<u8>>> {
+    let ord = ord.into();
+    // Replayed reads are counted by the single key reads
+    #[cfg(not(feature = "replay"))]
+    metrics::on_read(keys.len());
+
+    #[cfg(feature = "replay")]
+    return keys.iter().map(|key| get_at(store_idx, ord, key)).collect();
+
+    #[cfg(not(feature = "replay"))]
+    let ord = ord.to_host();
+
+    #[cfg(all(target_arch = "wasm32", not(feature = "replay")))]
+    let values = {
+        let keys_bytes = encode_keys(keys);
+
+        unsafe {
+            let output_ptr = memory::alloc(8);
+            externs::state::get_many_at(
+                store_idx,
+                ord,
+                keys_bytes.as_ptr(),
+                keys_bytes.len() as u32,
+                output_ptr as u32,
+            );
+
+            decode_many(&memory::get_output_data(output_ptr), keys.len())
+        }
+    };
+
+    #[cfg(all(not(target_arch = "wasm32"), not(feature = "replay")))]
+    let values: Vec<_> = keys
+        .iter()
+        .map(|key| testing::get(store_idx, key.as_ref()))
+        .collect();
+
+    // Journaled as single key reads, like replayed reads
+    #[cfg(all(feature = "record", not(feature = "replay")))]
+    for (key, value) in keys.iter().zip(values.iter()) {
+        journal::read("get_at", store_idx, ord, key.as_ref(), value.as_deref());
+    }
+
+    #[cfg(not(feature = "replay"))]
+    values
+}
+
+/// Reads up to `limit` keys starting with `prefix`, in lexicographical order, from the last
+/// state of the store. Only keys sorting after `after` are returned, so passing the last key of
+/// a page fetches the next one.
+///
+/// The host answers with, for each entry, `<u32 little-endian length><key>` followed by
+/// `<u32 little-endian length><value>`.
+pub fn scan_prefix<P: AsRef<str>>(
+    store_idx: u32,
+    prefix: P,
+    after: Option<&str>,
+    limit: u32,
+) -> Vec<(String, Vec<u8>)> {
+    metrics::on_read(1);
+    let prefix = prefix.as_ref();
+
+    #[cfg(feature = "replay")]
+    let page = replay::scan(store_idx, prefix, after, limit, || {
+        host_scan_prefix(store_idx, prefix, after, limit)
+    });
+
+    #[cfg(not(feature = "replay"))]
+    let page = host_scan_prefix(store_idx, prefix, after, limit);
+
+    #[cfg(feature = "record")]
+    journal::scan(store_idx, prefix, after, limit, page.as_deref());
+
+    page.map(|page| decode_entries(&page)).unwrap_or_default()
+}
+
+fn host_scan_prefix(
+    store_idx: u32,
+    prefix: &str,
+    after: Option<&str>,
+    limit: u32,
+) -> Option<Vec<u8>> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let after = after.unwrap_or("");
+
+        unsafe {
+            let output_ptr = memory::alloc(8);
+            let found = externs::state::scan_prefix(
+                store_idx,
+                prefix.as_ptr(),
+                prefix.len() as u32,
+                after.as_ptr(),
+                after.len() as u32,
+                limit,
+                output_ptr as u32,
+            );
+
+            if found == 1 {
+                Some(memory::get_output_data(output_ptr))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let entries = testing::scan(store_idx, prefix, after, limit as usize);
+        if entries.is_empty() {
+            return None;
+        }
+
+        Some(encode_entries(&entries))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn encode_entries(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (key, value) in entries {
+        out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        out.extend_from_slice(key.as_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+    out
+}
+
+fn decode_entries(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let key = read_chunk(data, &mut pos);
+        let key = String::from_utf8(key).expect("invalid store scan: key is not UTF-8");
+        entries.push((key, read_chunk(data, &mut pos)));
+    }
+    entries
+}
+
+fn read_chunk(data: &[u8], pos: &mut usize) -> Vec<u8> {
+    let len_bytes: [u8; 4] = data
+        .get(*pos..*pos + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .expect("invalid store scan: truncated length");
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    *pos += 4;
+
+    let chunk = data
+        .get(*pos..*pos + len)
+        .expect("invalid store scan: truncated entry")
+        .to_vec();
+    *pos += len;
+    chunk
+}
+
+#[cfg(not(feature = "replay"))]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn encode_keys<K: AsRef<str>>(keys: &[K]) -> Vec<u8> {
+    let size = keys.iter().map(|key| 4 + key.as_ref().len()).sum();
+    let mut out = Vec::with_capacity(size);
+    for key in keys {
+        let key = key.as_ref().as_bytes();
+        out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        out.extend_from_slice(key);
+    }
+    out
+}
+
+#[cfg(not(feature = "replay"))]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn decode_many(data: &[u8], count: usize) -> Vec<Option<Vec<u8>>> {
+    let mut values = Vec::with_capacity(count);
+    let mut pos = 0;
+    while values.len() < count {
+        let found = *data.get(pos).unwrap_or_else(|| {
+            panic!("invalid batched store read: missing value {}", values.len())
+        });
+        pos += 1;
+
+        if found == 0 {
+            values.push(None);
+            continue;
+        }
+
+        let len_bytes: [u8; 4] = data
+            .get(pos..pos + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .expect("invalid batched store read: truncated length");
+        let len = u32::from_le_bytes(len_bytes) as usize;
+        pos += 4;
+
+        let value = data
+            .get(pos..pos + len)
+            .expect("invalid batched store read: truncated value");
+        values.push(Some(value.to_vec()));
+        pos += len;
+    }
+    values
+}
+
+thread_local! {
+    static OUTPUT: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Selects the output store the following writes apply to, `0` being the module's own
+/// store. Handlers declared with `multi_writable` select the output store of each writable
+/// store before writing to it, see [crate::handlers::store].
+///
+/// # Panics
+///
+/// Panics when selecting another output store than `0` in a module built without the
+/// `multi-writable-stores` feature.
+pub fn select_output(output_idx: u32) {
+    if OUTPUT.with(|output| output.replace(output_idx)) == output_idx {
+        return;
+    }
+
+    #[cfg(all(target_arch = "wasm32", not(feature = "multi-writable-stores")))]
+    if output_idx != 0 {
+        panic!(
+            "writing to output store {} requires the `multi-writable-stores` feature",
+            output_idx
+        );
+    }
+
+    let value = || output_idx.to_string().into_bytes();
+    if !batch::push("select_output", 0, "", value) {
+        #[cfg(not(target_arch = "wasm32"))]
+        testing::write("select_output", "", &value());
+
+        #[cfg(all(target_arch = "wasm32", feature = "multi-writable-stores"))]
+        unsafe {
+            externs::state::select_output(output_idx)
+        }
+    }
+}
+
+/// The output store selected by the last [select_output].
+#[cfg(feature = "record")]
+pub(crate) fn selected_output() -> u32 {
+    OUTPUT.with(Cell::get)
+}
+
+/// Resets the selected output store, the testing backend goes back to the module's own
+/// store when reset.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reset_output() {
+    OUTPUT.with(|output| output.set(0))
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set<K, V>(ord: i64, key: K, value: V)
+pub fn set<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<[u8]>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("set", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("set", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set", ord, key.as_ref(), || value.as_ref().to_vec()) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set", key.as_ref(), value.as_ref());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -165,11 +678,32 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_if_not_exists<K, V>(ord: i64, key: K, value: V)
+pub fn set_if_not_exists<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<[u8]>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("set_if_not_exists", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("set_if_not_exists", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_if_not_exists", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_if_not_exists", ord, key.as_ref(), || {
+        value.as_ref().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_if_not_exists", key.as_ref(), value.as_ref());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -187,12 +721,74 @@ where
     }
 }
 
+/// Sets `key` to `value`, the host aborts the module if `key` was already set.
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub fn set_once<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("set_once", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("set_once", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_once", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_once", ord, key.as_ref(), || value.as_ref().to_vec()) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_once", key.as_ref(), value.as_ref());
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            externs::state::set_once(
+                ord,
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            )
+        }
+    }
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn append<K, V>(ord: i64, key: K, value: V)
+pub fn append<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<[u8]>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("append", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("append", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("append", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("append", ord, key.as_ref(), || value.as_ref().to_vec()) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("append", key.as_ref(), value.as_ref());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -211,7 +807,24 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn delete_prefix<K: AsRef<str>>(ord: i64, prefix: K) {
+pub fn delete_prefix<K: AsRef<str>>(ord: impl Into<Ordinal>, prefix: K) {
+    let ord = ord.into().to_host();
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_ordinal("delete_prefix", ord, prefix.as_ref());
+
+    #[cfg(feature = "replay")]
+    replay::write("delete_prefix", ord, prefix.as_ref(), &[]);
+    #[cfg(feature = "record")]
+    journal::write("delete_prefix", ord, prefix.as_ref(), &[]);
+
+    if batch::push("delete_prefix", ord, prefix.as_ref(), Vec::new) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("delete_prefix", prefix.as_ref(), &[]);
+
     #[cfg(target_arch = "wasm32")]
     {
         let prefix = prefix.as_ref();
@@ -220,12 +833,93 @@ pub fn delete_prefix<K: AsRef<str>>(ord: i64, prefix: K) {
     }
 }
 
+/// Deletes `key` exactly through the `state.delete_key` host function.
+#[cfg(feature = "delete-key")]
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn add_bigint<K, V>(ord: i64, key: K, value: V)
+pub fn delete_key<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K) {
+    let ord = ord.into().to_host();
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("delete_key", ord, key.as_ref(), 0);
+
+    #[cfg(feature = "replay")]
+    replay::write("delete_key", ord, key.as_ref(), &[]);
+    #[cfg(feature = "record")]
+    journal::write("delete_key", ord, key.as_ref(), &[]);
+
+    if batch::push("delete_key", ord, key.as_ref(), Vec::new) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("delete_key", key.as_ref(), &[]);
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let key = key.as_ref();
+
+        unsafe { externs::state::delete_key(ord, key.as_ptr(), key.len() as u32) }
+    }
+}
+
+/// Servers without the `state.delete_key` host function only delete by prefix, a prefix
+/// deletion would silently leave `key` in place unless it was written with the sentinel.
+#[cfg(not(feature = "delete-key"))]
+pub fn delete_key<K: AsRef<str>>(_ord: impl Into<Ordinal>, key: K) {
+    panic!(
+        "deleting key {:?} requires the `delete-key` feature, on servers without \
+         `state.delete_key` write keys terminated by `store::DELETE_KEY_SENTINEL` and delete \
+         them with `delete_prefix`",
+        key.as_ref()
+    );
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub fn add_bigint<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigInt>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "add_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "add_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "add_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("add_bigint", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "add_bigint",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -245,7 +939,28 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn add_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
+pub fn add_int64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: i64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("add_int64", ord, key.as_ref(), value.to_string().as_bytes());
+    #[cfg(feature = "record")]
+    journal::write("add_int64", ord, key.as_ref(), value.to_string().as_bytes());
+
+    guardrails::on_write(key.as_ref(), || value.to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("add_int64", ord, key.as_ref(), value.to_string().len());
+
+    if batch::push("add_int64", ord, key.as_ref(), || {
+        value.to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("add_int64", key.as_ref(), value.to_string().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -255,7 +970,41 @@ pub fn add_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn add_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+pub fn add_float64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: f64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "add_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "add_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || format_f64(value).len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    {
+        write_checks::on_write("add_float64", ord, key.as_ref(), format_f64(value).len());
+        write_checks::on_float("add_float64", key.as_ref(), value);
+    }
+
+    if batch::push("add_float64", ord, key.as_ref(), || {
+        format_f64(value).into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("add_float64", key.as_ref(), format_f64(value).as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -265,11 +1014,51 @@ pub fn add_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn add_bigdecimal<K, V>(ord: i64, key: K, value: V)
+pub fn add_bigdecimal<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigDecimal>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "add_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "add_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "add_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("add_bigdecimal", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "add_bigdecimal",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -289,7 +1078,38 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_min_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
+pub fn set_min_int64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: i64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_min_int64",
+        ord,
+        key.as_ref(),
+        value.to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_min_int64",
+        ord,
+        key.as_ref(),
+        value.to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_min_int64", ord, key.as_ref(), value.to_string().len());
+
+    if batch::push("set_min_int64", ord, key.as_ref(), || {
+        value.to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_min_int64", key.as_ref(), value.to_string().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -299,11 +1119,51 @@ pub fn set_min_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_min_bigint<K, V>(ord: i64, key: K, value: V)
+pub fn set_min_bigint<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigInt>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_min_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_min_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "set_min_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("set_min_bigint", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_min_bigint",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -323,7 +1183,50 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_min_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+pub fn set_min_float64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: f64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_min_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_min_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || format_f64(value).len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    {
+        write_checks::on_write(
+            "set_min_float64",
+            ord,
+            key.as_ref(),
+            format_f64(value).len(),
+        );
+        write_checks::on_float("set_min_float64", key.as_ref(), value);
+    }
+
+    if batch::push("set_min_float64", ord, key.as_ref(), || {
+        format_f64(value).into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_min_float64",
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -333,11 +1236,51 @@ pub fn set_min_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_min_bigdecimal<K, V>(ord: i64, key: K, value: V)
+pub fn set_min_bigdecimal<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigDecimal>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_min_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_min_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "set_min_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("set_min_bigdecimal", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_min_bigdecimal",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -357,7 +1300,38 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_max_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
+pub fn set_max_int64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: i64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_max_int64",
+        ord,
+        key.as_ref(),
+        value.to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_max_int64",
+        ord,
+        key.as_ref(),
+        value.to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_max_int64", ord, key.as_ref(), value.to_string().len());
+
+    if batch::push("set_max_int64", ord, key.as_ref(), || {
+        value.to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_max_int64", key.as_ref(), value.to_string().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -367,11 +1341,51 @@ pub fn set_max_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_max_bigint<K, V>(ord: i64, key: K, value: V)
+pub fn set_max_bigint<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigInt>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_max_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_max_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "set_max_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("set_max_bigint", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_max_bigint",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -391,7 +1405,50 @@ where
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_max_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+pub fn set_max_float64<K: AsRef<str>>(ord: impl Into<Ordinal>, key: K, value: f64) {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_max_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_max_float64",
+        ord,
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || format_f64(value).len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    {
+        write_checks::on_write(
+            "set_max_float64",
+            ord,
+            key.as_ref(),
+            format_f64(value).len(),
+        );
+        write_checks::on_float("set_max_float64", key.as_ref(), value);
+    }
+
+    if batch::push("set_max_float64", ord, key.as_ref(), || {
+        format_f64(value).into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_max_float64",
+        key.as_ref(),
+        format_f64(value).as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -401,11 +1458,51 @@ pub fn set_max_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_max_bigdecimal<K, V>(ord: i64, key: K, value: V)
+pub fn set_max_bigdecimal<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
 where
     K: AsRef<str>,
     V: AsRef<BigDecimal>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_max_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_max_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().to_string().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "set_max_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().to_string().len(),
+    );
+
+    if batch::push("set_max_bigdecimal", ord, key.as_ref(), || {
+        value.as_ref().to_string().into_bytes()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_max_bigdecimal",
+        key.as_ref(),
+        value.as_ref().to_string().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -424,12 +1521,136 @@ where
     }
 }
 
+/// Sets `key` to `value` when absent or sorting before `value`, compared byte by byte.
+#[cfg(feature = "lexicographic-min-max")]
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_sum_bigint<K, V>(ord: i64, key: K, value: V)
-    where
-        K: AsRef<str>,
-        V: AsRef<str>, String: From<V>
+pub fn set_max_bytes<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("set_max_bytes", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("set_max_bytes", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_max_bytes", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_max_bytes", ord, key.as_ref(), || {
+        value.as_ref().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_max_bytes", key.as_ref(), value.as_ref());
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            externs::state::set_max_bytes(
+                ord,
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            )
+        }
+    }
+}
+
+/// Sets `key` to `value` when absent or sorting after `value`, compared byte by byte.
+#[cfg(feature = "lexicographic-min-max")]
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub fn set_min_bytes<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write("set_min_bytes", ord, key.as_ref(), value.as_ref());
+    #[cfg(feature = "record")]
+    journal::write("set_min_bytes", ord, key.as_ref(), value.as_ref());
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_min_bytes", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_min_bytes", ord, key.as_ref(), || {
+        value.as_ref().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_min_bytes", key.as_ref(), value.as_ref());
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            externs::state::set_min_bytes(
+                ord,
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            )
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub fn set_sum_bigint<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+    String: From<V>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_sum_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_sum_bigint",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_sum_bigint", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_sum_bigint", ord, key.as_ref(), || {
+        value.as_ref().as_bytes().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_sum_bigint", key.as_ref(), value.as_ref().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -448,11 +1669,52 @@ pub fn set_sum_bigint<K, V>(ord: i64, key: K, value: V)
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_sum_bigdecimal<K, V>(ord: i64, key: K, value: V)
-    where
-        K: AsRef<str>,
-        V: AsRef<str>, String: From<V>
+pub fn set_sum_bigdecimal<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+    String: From<V>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_sum_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_sum_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write(
+        "set_sum_bigdecimal",
+        ord,
+        key.as_ref(),
+        value.as_ref().len(),
+    );
+
+    if batch::push("set_sum_bigdecimal", ord, key.as_ref(), || {
+        value.as_ref().as_bytes().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write(
+        "set_sum_bigdecimal",
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -471,11 +1733,43 @@ pub fn set_sum_bigdecimal<K, V>(ord: i64, key: K, value: V)
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_sum_int64<K, V>(ord: i64, key: K, value: V)
-    where
-        K: AsRef<str>,
-        V: AsRef<str>, String: From<V>
+pub fn set_sum_int64<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+    String: From<V>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_sum_int64",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_sum_int64",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    write_checks::on_write("set_sum_int64", ord, key.as_ref(), value.as_ref().len());
+
+    if batch::push("set_sum_int64", ord, key.as_ref(), || {
+        value.as_ref().as_bytes().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_sum_int64", key.as_ref(), value.as_ref().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -494,11 +1788,48 @@ pub fn set_sum_int64<K, V>(ord: i64, key: K, value: V)
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
-pub fn set_sum_float64<K, V>(ord: i64, key: K, value: V)
-    where
-        K: AsRef<str>,
-        V: AsRef<str>, String: From<V>
+pub fn set_sum_float64<K, V>(ord: impl Into<Ordinal>, key: K, value: V)
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+    String: From<V>,
 {
+    let ord = ord.into().to_host();
+
+    #[cfg(feature = "replay")]
+    replay::write(
+        "set_sum_float64",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+    #[cfg(feature = "record")]
+    journal::write(
+        "set_sum_float64",
+        ord,
+        key.as_ref(),
+        value.as_ref().as_bytes(),
+    );
+
+    guardrails::on_write(key.as_ref(), || value.as_ref().len());
+    metrics::on_write();
+    #[cfg(feature = "debug-assertions")]
+    {
+        write_checks::on_write("set_sum_float64", ord, key.as_ref(), value.as_ref().len());
+        if let Ok(value) = value.as_ref().parse() {
+            write_checks::on_float("set_sum_float64", key.as_ref(), value);
+        }
+    }
+
+    if batch::push("set_sum_float64", ord, key.as_ref(), || {
+        value.as_ref().as_bytes().to_vec()
+    }) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    testing::write("set_sum_float64", key.as_ref(), value.as_ref().as_bytes());
+
     #[cfg(target_arch = "wasm32")]
     {
         let key = key.as_ref();
@@ -515,3 +1846,41 @@ pub fn set_sum_float64<K, V>(ord: i64, key: K, value: V)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_entries, encode_entries};
+
+    #[test]
+    #[cfg(not(feature = "replay"))]
+    fn it_encodes_batched_reads() {
+        use super::{decode_many, encode_keys};
+
+        assert_eq!(
+            encode_keys(&["a", "bc"]),
+            vec![1, 0, 0, 0, b'a', 2, 0, 0, 0, b'b', b'c']
+        );
+        assert_eq!(
+            decode_many(&[1, 2, 0, 0, 0, b'x', b'y', 0, 1, 0, 0, 0, 0], 3),
+            vec![Some(b"xy".to_vec()), None, Some(vec![])]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "replay"))]
+    #[should_panic(expected = "invalid batched store read: truncated value")]
+    fn it_rejects_truncated_batched_reads() {
+        super::decode_many(&[1, 2, 0, 0, 0, b'x'], 1);
+    }
+
+    #[test]
+    fn it_encodes_scanned_entries() {
+        let entries = vec![
+            ("a:1".to_string(), b"x".to_vec()),
+            ("a:2".to_string(), vec![]),
+        ];
+
+        assert_eq!(decode_entries(&encode_entries(&entries)), entries);
+        assert_eq!(decode_entries(&[]), vec![]);
+    }
+}
@@ -0,0 +1,564 @@
+//! Accessors computing the storage slots of Solidity state variables and decoding their
+//! values from raw 32-byte storage words, to interpret the storage changes of a contract
+//! without hand-written slot math. Requires the `keccak` feature.
+//!
+//! A state variable is located from its slot, as found in the `storageLayout` output of
+//! `solc`: a [Value] reads a value type, possibly packed with others in the same slot, a
+//! [MapAccessor] a `mapping`, a [ListAccessor] a dynamic array and a [StructAccessor] a
+//! `struct`. Accessors nest, each one locating the slots of its members:
+//!
+//! ```rust
+//! use substreams::scalar::BigInt;
+//! use substreams::storage_layout::{MapAccessor, Slot, Value};
+//!
+//! // mapping(address => mapping(address => uint256)) allowance; at slot 4
+//! let allowance: MapAccessor<[u8], MapAccessor<[u8], Value>> = MapAccessor::new(Slot::new(4));
+//!
+//! fn allowance_change(
+//!     allowance: &MapAccessor<[u8], MapAccessor<[u8], Value>>,
+//!     owner: &[u8],
+//!     spender: &[u8],
+//!     key: &[u8],
+//!     new_value: &[u8],
+//! ) -> Option<BigInt> {
+//!     let value = allowance.at(owner).at(spender);
+//!     value.matches(key).then(|| value.uint(new_value))
+//! }
+//! ```
+//!
+//! Variables declared together and smaller than a slot are packed into it, right-aligned
+//! starting at their `offset`, like the `slot0` of a Uniswap v3 pool:
+//!
+//! ```rust
+//! use std::convert::TryFrom;
+//! use substreams::storage_layout::{Slot, StructAccessor};
+//!
+//! let slot0 = StructAccessor::new(Slot::new(0));
+//! let sqrt_price_x96 = slot0.field(0, 0, 20); // uint160
+//! let tick = slot0.field(0, 20, 3); // int24
+//!
+//! let mut word = [0u8; 32];
+//! word[9..12].copy_from_slice(&[0xff, 0xff, 0xfe]);
+//! word[31] = 1;
+//!
+//! assert_eq!(u64::try_from(&sqrt_price_x96.uint(&word)), Ok(1));
+//! assert_eq!(i32::try_from(&tick.int(&word)), Ok(-2));
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::hashing::keccak256;
+use crate::scalar::BigInt;
+use crate::Hex;
+
+/// The size of a storage slot, in bytes.
+pub const SLOT_SIZE: usize = 32;
+
+/// A storage slot, the big-endian 256-bit key of a storage word.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Slot([u8; SLOT_SIZE]);
+
+impl Slot {
+    pub fn new(slot: u64) -> Self {
+        Slot::from(slot as u128)
+    }
+
+    pub fn from_bytes(bytes: [u8; SLOT_SIZE]) -> Self {
+        Slot(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; SLOT_SIZE] {
+        &self.0
+    }
+
+    /// The slot `n` slots after this one, wrapping around the 256-bit slot space like the EVM.
+    pub fn offset(&self, n: u128) -> Slot {
+        let mut bytes = self.0;
+        let mut carry = n;
+        for byte in bytes.iter_mut().rev() {
+            if carry == 0 {
+                break;
+            }
+
+            let sum = *byte as u128 + (carry & 0xff);
+            *byte = sum as u8;
+            carry = (carry >> 8) + (sum >> 8);
+        }
+
+        Slot(bytes)
+    }
+
+    /// The `keccak256` of the slot, where the elements of a dynamic array stored at this slot start.
+    pub fn hash(&self) -> Slot {
+        Slot(keccak256(self.0))
+    }
+
+    /// Returns `true` if `key`, the key of a storage change, is this slot.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        self.0[..] == *key
+    }
+}
+
+impl From<u128> for Slot {
+    fn from(slot: u128) -> Self {
+        let mut bytes = [0u8; SLOT_SIZE];
+        bytes[16..].copy_from_slice(&slot.to_be_bytes());
+        Slot(bytes)
+    }
+}
+
+impl From<[u8; SLOT_SIZE]> for Slot {
+    fn from(bytes: [u8; SLOT_SIZE]) -> Self {
+        Slot(bytes)
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", Hex(self.0))
+    }
+}
+
+/// A storage location of any type, positioned by the accessor containing it.
+pub trait Layout {
+    /// Locates the variable stored from `slot`. Value types smaller than a slot are packed
+    /// `width` bytes at `offset` bytes from the right of the slot, other types always start
+    /// a new slot and ignore both.
+    fn locate(slot: Slot, offset: usize, width: usize) -> Self;
+}
+
+/// A mapping key, encoded as hashed by Solidity to locate the slot of its value.
+pub trait StorageKey {
+    fn encode_key(&self) -> Vec<u8>;
+}
+
+/// Pads value types to a full word, `[u8]` keys being addresses or fixed-size integers
+/// encoded in big-endian.
+fn left_padded(bytes: &[u8]) -> Vec<u8> {
+    assert!(
+        bytes.len() <= SLOT_SIZE,
+        "storage key of {} bytes is larger than a slot",
+        bytes.len()
+    );
+
+    let mut key = vec![0u8; SLOT_SIZE - bytes.len()];
+    key.extend_from_slice(bytes);
+    key
+}
+
+impl StorageKey for [u8] {
+    fn encode_key(&self) -> Vec<u8> {
+        left_padded(self)
+    }
+}
+
+impl StorageKey for Vec<u8> {
+    fn encode_key(&self) -> Vec<u8> {
+        left_padded(self)
+    }
+}
+
+impl<const N: usize> StorageKey for [u8; N] {
+    fn encode_key(&self) -> Vec<u8> {
+        left_padded(self)
+    }
+}
+
+impl StorageKey for bool {
+    fn encode_key(&self) -> Vec<u8> {
+        left_padded(&[*self as u8])
+    }
+}
+
+/// `string` keys are hashed as-is, without padding.
+impl StorageKey for str {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl StorageKey for String {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+/// Signed integers are sign-extended to a full word.
+impl StorageKey for BigInt {
+    fn encode_key(&self) -> Vec<u8> {
+        let bytes = self.to_signed_bytes_be();
+        let fill = if self < &BigInt::zero() { 0xff } else { 0x00 };
+        let mut key = vec![fill; SLOT_SIZE.saturating_sub(bytes.len())];
+        key.extend_from_slice(&bytes);
+        key
+    }
+}
+
+macro_rules! impl_integer_key {
+    ($($t:ty),+) => {
+        $(
+            impl StorageKey for $t {
+                fn encode_key(&self) -> Vec<u8> {
+                    BigInt::from(*self).encode_key()
+                }
+            }
+        )+
+    };
+}
+
+impl_integer_key!(u32, i32, u64, i64);
+
+/// A `bytes` mapping key, hashed as-is like `string` keys while `[u8]` keys are padded.
+pub struct RawKey<T>(pub T);
+
+impl<T: AsRef<[u8]>> StorageKey for RawKey<T> {
+    fn encode_key(&self) -> Vec<u8> {
+        self.0.as_ref().to_vec()
+    }
+}
+
+/// A value type (integers, `address`, `bool`, `bytesN`) stored in `width` bytes at `offset`
+/// bytes from the right of its slot, decoded from the 32-byte storage word of the slot.
+///
+/// Storage words shorter than a slot are left-padded with zeros.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Value {
+    slot: Slot,
+    offset: usize,
+    width: usize,
+}
+
+impl Value {
+    /// # Panics
+    ///
+    /// Panics if the value doesn't fit in the slot.
+    pub fn new(slot: Slot, offset: usize, width: usize) -> Self {
+        assert!(
+            width > 0 && offset + width <= SLOT_SIZE,
+            "value of {} bytes at offset {} doesn't fit in a slot",
+            width,
+            offset
+        );
+
+        Value {
+            slot,
+            offset,
+            width,
+        }
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns `true` if `key`, the key of a storage change, is the slot of the value.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        self.slot.matches(key)
+    }
+
+    /// The bytes of the value in `word`.
+    pub fn bytes(&self, word: &[u8]) -> [u8; SLOT_SIZE] {
+        let word = padded_word(word);
+        let end = SLOT_SIZE - self.offset;
+
+        let mut bytes = [0u8; SLOT_SIZE];
+        bytes[SLOT_SIZE - self.width..].copy_from_slice(&word[end - self.width..end]);
+        bytes
+    }
+
+    /// Decodes an unsigned integer (`uintN`).
+    pub fn uint(&self, word: &[u8]) -> BigInt {
+        BigInt::from_unsigned_bytes_be(&self.bytes(word)[SLOT_SIZE - self.width..])
+    }
+
+    /// Decodes a signed integer (`intN`) stored in two's complement over its width.
+    pub fn int(&self, word: &[u8]) -> BigInt {
+        BigInt::from_signed_bytes_be(&self.bytes(word)[SLOT_SIZE - self.width..])
+    }
+
+    /// Decodes an `address`, the 20 rightmost bytes of the value.
+    pub fn address(&self, word: &[u8]) -> [u8; 20] {
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&self.bytes(word)[SLOT_SIZE - 20..]);
+        address
+    }
+
+    pub fn bool(&self, word: &[u8]) -> bool {
+        self.bytes(word).iter().any(|byte| *byte != 0)
+    }
+}
+
+impl Layout for Value {
+    fn locate(slot: Slot, offset: usize, width: usize) -> Self {
+        Value::new(slot, offset, width)
+    }
+}
+
+fn padded_word(word: &[u8]) -> [u8; SLOT_SIZE] {
+    assert!(
+        word.len() <= SLOT_SIZE,
+        "storage word of {} bytes is larger than a slot",
+        word.len()
+    );
+
+    let mut padded = [0u8; SLOT_SIZE];
+    padded[SLOT_SIZE - word.len()..].copy_from_slice(word);
+    padded
+}
+
+/// A `mapping(K => V)`, the value of `key` being stored at `keccak256(key . slot)`.
+///
+/// Mappings of value types are read with `V` = [Value], spanning the whole slot of the
+/// value: values narrower than a slot are read with [Value::new] at the slot of
+/// [MapAccessor::slot_of].
+pub struct MapAccessor<K: ?Sized, V> {
+    slot: Slot,
+    _types: PhantomData<fn(&K) -> V>,
+}
+
+impl<K: StorageKey + ?Sized, V: Layout> MapAccessor<K, V> {
+    pub fn new(slot: Slot) -> Self {
+        MapAccessor {
+            slot,
+            _types: PhantomData,
+        }
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// The slot of the value of `key`.
+    pub fn slot_of(&self, key: &K) -> Slot {
+        let mut preimage = key.encode_key();
+        preimage.extend_from_slice(self.slot.as_bytes());
+        Slot(keccak256(preimage))
+    }
+
+    /// The value of `key`.
+    pub fn at(&self, key: &K) -> V {
+        V::locate(self.slot_of(key), 0, SLOT_SIZE)
+    }
+}
+
+impl<K: StorageKey + ?Sized, V: Layout> Layout for MapAccessor<K, V> {
+    fn locate(slot: Slot, _offset: usize, _width: usize) -> Self {
+        MapAccessor::new(slot)
+    }
+}
+
+/// A dynamic array `T[]`, its length being stored at its slot and its elements from
+/// `keccak256(slot)`.
+///
+/// Each element takes one slot by default, [ListAccessor::with_element_slots] sets the
+/// slots taken by `struct` elements and [ListAccessor::packed] the width of value types
+/// of 16 bytes or less, which are packed in the slots.
+pub struct ListAccessor<T> {
+    slot: Slot,
+    element_slots: u64,
+    packed_width: Option<usize>,
+    _element: PhantomData<fn() -> T>,
+}
+
+impl<T: Layout> ListAccessor<T> {
+    pub fn new(slot: Slot) -> Self {
+        ListAccessor {
+            slot,
+            element_slots: 1,
+            packed_width: None,
+            _element: PhantomData,
+        }
+    }
+
+    pub fn with_element_slots(mut self, element_slots: u64) -> Self {
+        self.element_slots = element_slots;
+        self
+    }
+
+    /// The slot storing the length of the array, decoded with [ListAccessor::len].
+    pub fn length_slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Decodes the length of the array from the storage word of [ListAccessor::length_slot].
+    pub fn len(&self, word: &[u8]) -> u64 {
+        let word = padded_word(word);
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&word[SLOT_SIZE - 8..]);
+        u64::from_be_bytes(len)
+    }
+
+    /// The element at `index`.
+    pub fn at(&self, index: u64) -> T {
+        let first = self.slot.hash();
+        match self.packed_width {
+            Some(width) => {
+                let per_slot = (SLOT_SIZE / width) as u64;
+                let slot = first.offset((index / per_slot) as u128);
+                T::locate(slot, (index % per_slot) as usize * width, width)
+            }
+            None => {
+                let slot = first.offset(index as u128 * self.element_slots as u128);
+                T::locate(slot, 0, SLOT_SIZE)
+            }
+        }
+    }
+}
+
+impl ListAccessor<Value> {
+    /// An array of value types of `width` bytes, several of them being packed in each slot
+    /// when `width` is 16 bytes or less.
+    pub fn packed(slot: Slot, width: usize) -> Self {
+        assert!(
+            width > 0 && width <= SLOT_SIZE,
+            "array element of {} bytes doesn't fit in a slot",
+            width
+        );
+
+        ListAccessor {
+            packed_width: Some(width),
+            ..ListAccessor::new(slot)
+        }
+    }
+}
+
+impl<T: Layout> Layout for ListAccessor<T> {
+    fn locate(slot: Slot, _offset: usize, _width: usize) -> Self {
+        ListAccessor::new(slot)
+    }
+}
+
+/// A `struct`, its members being stored from its slot in declaration order and located with
+/// the `slot` and `offset` of the `storageLayout` of its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StructAccessor {
+    slot: Slot,
+}
+
+impl StructAccessor {
+    pub fn new(slot: Slot) -> Self {
+        StructAccessor { slot }
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// The value type member of `width` bytes at `offset` bytes from the right of the
+    /// `slot`-th slot of the struct.
+    pub fn field(&self, slot: u64, offset: usize, width: usize) -> Value {
+        Value::new(self.slot.offset(slot as u128), offset, width)
+    }
+
+    /// The `mapping` member at the `slot`-th slot of the struct.
+    pub fn map<K: StorageKey + ?Sized, V: Layout>(&self, slot: u64) -> MapAccessor<K, V> {
+        MapAccessor::new(self.slot.offset(slot as u128))
+    }
+
+    /// The dynamic array member at the `slot`-th slot of the struct.
+    pub fn list<T: Layout>(&self, slot: u64) -> ListAccessor<T> {
+        ListAccessor::new(self.slot.offset(slot as u128))
+    }
+
+    /// The `struct` member at the `slot`-th slot of the struct.
+    pub fn nested(&self, slot: u64) -> StructAccessor {
+        StructAccessor::new(self.slot.offset(slot as u128))
+    }
+}
+
+impl Layout for StructAccessor {
+    fn locate(slot: Slot, _offset: usize, _width: usize) -> Self {
+        StructAccessor::new(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ListAccessor, MapAccessor, RawKey, Slot, StructAccessor, Value};
+    use crate::hashing::keccak256;
+    use crate::scalar::BigInt;
+    use crate::Hex;
+
+    #[test]
+    fn it_locates_array_elements() {
+        let list: ListAccessor<Value> = ListAccessor::new(Slot::new(0));
+        assert_eq!(
+            list.at(0).slot().to_string(),
+            "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+        assert_eq!(
+            list.at(1).slot().to_string(),
+            "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e564"
+        );
+
+        let structs: ListAccessor<StructAccessor> =
+            ListAccessor::new(Slot::new(1)).with_element_slots(3);
+        assert_eq!(
+            structs.at(2).slot().to_string(),
+            "0xb10e2d527612073b26eecdfd717e6a320cf44b4afac2b0732d9fcbe2b7fa0cfc"
+        );
+
+        let packed = ListAccessor::packed(Slot::new(0), 8);
+        assert_eq!(packed.at(5), Value::new(list.at(1).slot(), 8, 8));
+        assert_eq!(packed.len(&[0x02, 0x01]), 0x0201);
+    }
+
+    #[test]
+    fn it_locates_mapping_values() {
+        let owner: [u8; 20] =
+            Hex::decode_to_array("0x1f98431c8ad98523631ae4a59f267346ea31f984").unwrap();
+
+        let balances: MapAccessor<[u8], Value> = MapAccessor::new(Slot::new(3));
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(&owner);
+        preimage[63] = 3;
+        assert_eq!(balances.at(&owner[..]).slot(), Slot(keccak256(preimage)));
+
+        let names: MapAccessor<str, Value> = MapAccessor::new(Slot::new(3));
+        let mut preimage = b"dai".to_vec();
+        preimage.extend_from_slice(Slot::new(3).as_bytes());
+        assert_eq!(names.slot_of("dai"), Slot(keccak256(&preimage)));
+
+        let raw: MapAccessor<RawKey<&[u8]>, Value> = MapAccessor::new(Slot::new(3));
+        assert_eq!(raw.slot_of(&RawKey(&b"dai"[..])), names.slot_of("dai"));
+
+        let ticks: MapAccessor<i32, Value> = MapAccessor::new(Slot::new(5));
+        let mut preimage = [0xffu8; 64];
+        preimage[31] = 0xfe;
+        preimage[32..].copy_from_slice(Slot::new(5).as_bytes());
+        assert_eq!(ticks.slot_of(&-2), Slot(keccak256(preimage)));
+    }
+
+    #[test]
+    fn it_decodes_packed_values() {
+        let mut word = [0u8; 32];
+        word[11] = 1;
+        word[12..].copy_from_slice(&[0xab; 20]);
+
+        let value = StructAccessor::new(Slot::new(7));
+        assert_eq!(value.field(0, 0, 20).address(&word), [0xab; 20]);
+        assert!(value.field(0, 20, 1).bool(&word));
+        assert!(!value.field(0, 21, 1).bool(&word));
+        assert_eq!(value.field(0, 0, 1).int(&word), BigInt::from(-0x55));
+        assert_eq!(value.field(0, 0, 1).uint(&word), BigInt::from(0xab));
+        assert_eq!(value.field(2, 0, 32).slot(), Slot::new(9));
+
+        assert_eq!(
+            Value::new(Slot::new(0), 0, 32).uint(&[0x01, 0x00]),
+            BigInt::from(256)
+        );
+        assert_eq!(
+            Slot::new(1).offset(u128::MAX),
+            Slot::from(u128::MAX).offset(1)
+        );
+    }
+}
@@ -42,13 +42,12 @@
 //!
 //! ```rust
 //! use substreams::key;
-//! use substreams::pb::substreams::store_delta::Operation;
-//! use substreams::store::{Delta, DeltaExt, Deltas, DeltaBigDecimal};
+//! use substreams::store::{Delta, DeltaExt, DeltaOperation, Deltas, DeltaBigDecimal};
 //!
 //! fn db_out(store: Deltas<DeltaBigDecimal>) {
 //!     for delta in store
 //!         .iter()
-//!         .operation_eq(Operation::Create)
+//!         .operation_eq(DeltaOperation::Create)
 //!         .key_first_segment_in(["user", "contract"])
 //!         .key_last_segment_eq("token0")
 //!    {
@@ -56,64 +55,284 @@
 //!     }
 //! }
 //! ```
-use std::{io::BufRead, str};
+//!
+//! Keys can also be matched on a raw prefix or suffix, or against a regular expression with the
+//! `regex` feature:
+//!
+//! ```rust
+//! use substreams::store::{Delta, DeltaExt, Deltas, DeltaBigDecimal};
+//!
+//! fn db_out(store: Deltas<DeltaBigDecimal>) {
+//!     for delta in store.iter().key_starts_with("pool:0xabc").key_ends_with(":volume") {
+//!         // Do something for delta where the key was in format `pool:0xabc...:volume`
+//!     }
+//! }
+//! ```
+use once_cell::unsync::OnceCell;
+use std::{cell::RefCell, collections::HashMap, io::BufRead, iter::FromIterator, str};
 
-use crate::{key, operation, pb::substreams::store_delta::Operation};
+use crate::{cache::BlockCache, change, codec::Codec, guardrails, key, operation};
 
 use {
     crate::{
         pb::substreams::StoreDelta,
+        proto,
         scalar::{BigDecimal, BigInt},
-        state, {pb, proto},
+        state,
     },
     prost,
     std::i64,
     std::marker::PhantomData,
-    std::str::FromStr,
 };
 
+pub mod value;
+
+mod at_ordinal;
+mod bitvec;
+mod compression;
+mod enums;
+mod hashed;
+mod join;
+mod key_mapped;
+#[cfg(feature = "lexicographic-min-max")]
+mod lexicographic;
+mod or_default;
+mod ordinal;
+mod queue;
+mod scoped;
+pub use crate::operation::DeltaOperation;
+pub use at_ordinal::OrdinalDelta;
+pub use bitvec::BitVec;
+pub use compression::{decompress, Compression, DecompressError, COMPRESSION_MARKER};
+pub use enums::{DeltaEnum, StoreEnum, StoreGetEnum, StoreSetEnum};
+pub use hashed::{HashedKeyStore, KeyHash};
+pub use join::{JoinOn, JoinParts, StoreJoin};
+pub use key_mapped::KeyMappedStore;
+#[cfg(feature = "lexicographic-min-max")]
+pub use lexicographic::{StoreMaxBytes, StoreMaxString, StoreMinBytes, StoreMinString};
+pub use or_default::StoreGetOrDefault;
+pub use ordinal::{NegativeOrdinal, Ordinal};
+pub use queue::{SequenceChange, StoreQueue, StoreStack};
+pub use scoped::{Scoped, StoreScope};
+
+/// Buffers the writes made to any writable store while running `f` and sends them to the
+/// host at once when it returns, instead of one host call per `set`, `add`, `append`, ...
+/// call. Writes keep their order, nested batches are flushed by the outermost one.
+///
+/// With the `batched-writes` feature the buffered writes are sent through a single
+/// `state.write_batch` host call, without it (for hosts not providing that call) they are
+/// sent with their individual host calls when `f` returns. Output store selections of
+/// `multi_writable` handlers are buffered with the writes, as `select_output` entries.
+///
+/// ```rust
+/// use substreams::store::{self, StoreAdd, StoreAddInt64, StoreNew};
+///
+/// fn store_counts(counts: StoreAddInt64, tokens: Vec<String>) {
+///     store::batch(|| {
+///         for token in tokens {
+///             counts.add(0, format!("token:{}", token), 1);
+///         }
+///     })
+/// }
+/// ```
+pub fn batch<R, F: FnOnce() -> R>(f: F) -> R {
+    crate::batch::run(f)
+}
+
 /// `StoreSet` is a trait which is implemented on any type of typed StoreSet
 pub trait StoreSet<V>: StoreNew + StoreDelete {
     /// Set a given key to a given value, if the key existed before, it will be replaced.
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &V);
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V);
     /// Set many keys to a given values, if the key existed before, it will be replaced.
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V);
+    // We accept &Vec<K> instead of &[K] to keep the trait implementations unchanged
+    #[allow(clippy::ptr_arg)]
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &V);
 }
 
 pub trait StoreDelete {
     /// Delete values in a store given prefixed string
-    fn delete_prefix(&self, ord: i64, prefix: &String) {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
         state::delete_prefix(ord, prefix);
     }
+
+    /// Deletes the value of `key` only, unlike [delete_prefix](StoreDelete::delete_prefix)
+    /// which also deletes sibling keys it prefixes (`pool:1` prefixing `pool:10`).
+    ///
+    /// Exact deletion requires the `state.delete_key` host function, used with the
+    /// `delete-key` feature, and panics without it. Servers without that host function only
+    /// delete by prefix: write the keys with [DELETE_KEY_SENTINEL] appended, which no other
+    /// key can extend, and delete them with `delete_prefix` instead.
+    ///
+    /// ```rust
+    /// use substreams::store::{StoreDelete, StoreSet, StoreSetInt64, DELETE_KEY_SENTINEL};
+    ///
+    /// // With the `delete-key` feature
+    /// fn close_pool(counts: StoreSetInt64, pool: &str) {
+    ///     counts.delete_key(2, format!("pool:{}", pool));
+    /// }
+    ///
+    /// // On servers without `state.delete_key`
+    /// fn close_pool_by_prefix(counts: StoreSetInt64, pool: &str) {
+    ///     let key = format!("pool:{}{}", pool, DELETE_KEY_SENTINEL);
+    ///     counts.set(1, &key, &0);
+    ///     counts.delete_prefix(2, &key);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the crate is built without the `delete-key` feature.
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::delete_key(ord, key);
+    }
 }
 
+/// Appended to keys deleted with [StoreDelete::delete_prefix] on servers without exact
+/// deletion, see [StoreDelete::delete_key].
+pub const DELETE_KEY_SENTINEL: char = '\0';
+
+/// Index passed by the host for a store input that isn't wired in the manifest, resolved to
+/// `None` by `Option<StoreGet*>` handler inputs, see [crate::handlers::map].
+pub const MISSING_STORE: u32 = u32::MAX;
+
 pub trait StoreNew {
     /// Create an instance of trait implementation
     fn new() -> Self;
 }
 
+/// `StoreNewOutput` creates a writable store bound to the output store at index
+/// `output_idx`, each write selecting it first with the `state.select_output` host call.
+/// Used by store handlers declared with `multi_writable`, see [crate::handlers::store].
+pub trait StoreNewOutput {
+    fn new_output(output_idx: u32) -> Self;
+}
+
+macro_rules! impl_store_new_output {
+    ($($store:ident),+ $(,)?) => {
+        $(
+            impl StoreNewOutput for $store {
+                fn new_output(output_idx: u32) -> Self {
+                    $store { output: output_idx }
+                }
+            }
+        )+
+    };
+}
+
+impl_store_new_output!(
+    StoreSetRaw,
+    StoreSetString,
+    StoreSetFloat64,
+    StoreSetInt32,
+    StoreSetUint64,
+    StoreSetBool,
+    StoreSetBigDecimal,
+    StoreSetBigInt,
+    StoreSetIfNotExistsRaw,
+    StoreSetIfNotExistsString,
+    StoreSetIfNotExistsBigDecimal,
+    StoreSetIfNotExistsBigInt,
+    StoreSetIfNotExistsInt64,
+    StoreSetIfNotExistsFloat64,
+    StoreSetIfNotExistsInt32,
+    StoreSetIfNotExistsUint64,
+    StoreSetIfNotExistsBool,
+    StoreSetOnceRaw,
+    StoreSetOnceString,
+    StoreSetOnceBigInt,
+    StoreAddInt64,
+    StoreAddFloat64,
+    StoreAddBigDecimal,
+    StoreAddBigInt,
+    StoreMaxInt64,
+    StoreMaxBigInt,
+    StoreMaxFloat64,
+    StoreMaxBigDecimal,
+    StoreMinInt64,
+    StoreMinBigInt,
+    StoreMinFloat64,
+    StoreMinBigDecimal,
+    StoreSetSumInt64,
+    StoreSetSumFloat64,
+    StoreSetSumBigInt,
+    StoreSetSumBigDecimal,
+);
+
+impl<V: Codec> StoreNewOutput for StoreSetProto<V> {
+    fn new_output(output_idx: u32) -> Self {
+        StoreSetProto {
+            output: output_idx,
+            compression: Compression::None,
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<V: Codec> StoreNewOutput for StoreSetIfNotExistsProto<V> {
+    fn new_output(output_idx: u32) -> Self {
+        StoreSetIfNotExistsProto {
+            store: StoreSetIfNotExistsRaw::new_output(output_idx),
+            created: RefCell::new(HashMap::new()),
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<V: Codec> StoreNewOutput for StoreSetOnceProto<V> {
+    fn new_output(output_idx: u32) -> Self {
+        StoreSetOnceProto {
+            store: StoreSetOnceRaw::new_output(output_idx),
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<T, C> StoreNewOutput for StoreAppend<T, C> {
+    fn new_output(output_idx: u32) -> Self {
+        StoreAppend {
+            output: output_idx,
+            casper: PhantomData,
+        }
+    }
+}
+
 /// `StoreSetRaw` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `bytes`
 ///     `StoreSetRaw` implements AsRef<[u8]> to give the client the flexibility
 ///     to either use the API with &Vec[...] or Vec[...].
-pub struct StoreSetRaw {}
+pub struct StoreSetRaw {
+    output: u32,
+}
 impl StoreNew for StoreSetRaw {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetRaw {}
+impl StoreDelete for StoreSetRaw {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<[u8]>> StoreSet<V> for StoreSetRaw {
     /// Set a given key to a given value, if the key existed before, it will be replaced.
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
-        state::set(ord as i64, key, value);
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set(ord, key, value);
     }
 
     /// Set many keys to a given values, if the key existed before, it will be replaced.
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         for key in keys {
-            state::set(ord as i64, key, value);
+            state::set(ord, key, value);
         }
     }
 }
@@ -121,138 +340,418 @@ impl<V: AsRef<[u8]>> StoreSet<V> for StoreSetRaw {
 /// `StoreSetString` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `string`
 /// `StoreSetString` implements `AsRef<str>` to give the client the flexibility
 /// to either use the API with &String or String.
-pub struct StoreSetString {}
+pub struct StoreSetString {
+    output: u32,
+}
 impl StoreNew for StoreSetString {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetString {}
+impl StoreDelete for StoreSetString {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<str>> StoreSet<V> for StoreSetString {
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
-        state::set(ord as i64, key, value.as_ref());
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set(ord, key, value.as_ref());
     }
 
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let value = value.as_ref();
 
         for key in keys {
-            state::set(ord as i64, key, value);
+            state::set(ord, key, value);
         }
     }
 }
 
 /// `StoreSetInt64` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `int64`
-pub struct StoreSetInt64 {}
+///
+/// Values are written as decimal strings, or in the smaller binary form of
+/// [value::encode_i64_binary] by a store created with [StoreSetInt64::new_binary] or converted
+/// with [StoreSetInt64::binary]. [StoreGetInt64] and [DeltaInt64] decode both forms.
+pub struct StoreSetInt64 {
+    output: u32,
+    binary: bool,
+}
 impl StoreNew for StoreSetInt64 {
     fn new() -> Self {
-        Self {}
+        Self {
+            output: 0,
+            binary: false,
+        }
+    }
+}
+
+impl StoreNewOutput for StoreSetInt64 {
+    fn new_output(output_idx: u32) -> Self {
+        Self {
+            output: output_idx,
+            binary: false,
+        }
     }
 }
 
-impl StoreDelete for StoreSetInt64 {}
+impl StoreSetInt64 {
+    /// A store writing values in binary form, see [value::encode_i64_binary].
+    pub fn new_binary() -> Self {
+        Self::new().binary()
+    }
+
+    /// Writes the values in binary form from now on, for stores received as handler inputs:
+    ///
+    /// ```rust
+    /// use substreams::store::{StoreSet, StoreSetInt64};
+    ///
+    /// fn store_balances(balances: StoreSetInt64, updates: Vec<(String, i64)>) {
+    ///     let balances = balances.binary();
+    ///     for (account, balance) in updates {
+    ///         balances.set(0, account, &balance);
+    ///     }
+    /// }
+    /// ```
+    pub fn binary(mut self) -> Self {
+        self.binary = true;
+        self
+    }
+
+    fn encode(&self, value: i64) -> Vec<u8> {
+        match self.binary {
+            true => value::encode_i64_binary(value),
+            false => value::encode_i64(value),
+        }
+    }
+}
+
+impl StoreDelete for StoreSetInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSet<i64> for StoreSetInt64 {
     /// Set a given key to a given value, if the key existed before, it will be replaced.
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &i64) {
-        state::set(ord as i64, key, value.to_string().as_bytes());
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &i64) {
+        state::select_output(self.output);
+        state::set(ord, key, self.encode(*value));
     }
 
     /// Set many keys to a given values, if the key existed before, it will be replaced.
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &i64) {
-        let as_str = value.to_string();
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &i64) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let bytes = self.encode(*value);
 
         for key in keys {
-            state::set(ord as i64, key, &as_str);
+            state::set(ord, key, &bytes);
         }
     }
 }
 
 /// `StoreSetFloat64` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `float64`
-pub struct StoreSetFloat64 {}
+pub struct StoreSetFloat64 {
+    output: u32,
+}
 impl StoreNew for StoreSetFloat64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetFloat64 {}
+impl StoreDelete for StoreSetFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSet<f64> for StoreSetFloat64 {
     /// Set a given key to a given value, if the key existed before, it will be replaced.
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &f64) {
-        state::set(ord as i64, key, value.to_string().as_bytes());
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &f64) {
+        state::select_output(self.output);
+        guard_float64("set", *value);
+        state::set(ord, key, value::encode_f64(*value));
+    }
+
+    /// Set many keys to a given values, if the key existed before, it will be replaced.
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &f64) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        guard_float64("set", *value);
+        let as_str = value::format_f64(*value);
+
+        for key in keys {
+            state::set(ord, key, &as_str);
+        }
+    }
+}
+
+/// `StoreSetInt32` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `int32`
+pub struct StoreSetInt32 {
+    output: u32,
+}
+impl StoreNew for StoreSetInt32 {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreDelete for StoreSetInt32 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreSet<i32> for StoreSetInt32 {
+    /// Set a given key to a given value, if the key existed before, it will be replaced.
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &i32) {
+        state::select_output(self.output);
+        state::set(ord, key, value.to_string().as_bytes());
+    }
+
+    /// Set many keys to a given values, if the key existed before, it will be replaced.
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &i32) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_str = value.to_string();
+
+        for key in keys {
+            state::set(ord, key, &as_str);
+        }
+    }
+}
+
+/// `StoreSetUint64` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `uint64`, stored as a decimal string like `int64` values
+pub struct StoreSetUint64 {
+    output: u32,
+}
+impl StoreNew for StoreSetUint64 {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreDelete for StoreSetUint64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreSet<u64> for StoreSetUint64 {
+    /// Set a given key to a given value, if the key existed before, it will be replaced.
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &u64) {
+        state::select_output(self.output);
+        state::set(ord, key, value.to_string().as_bytes());
     }
 
     /// Set many keys to a given values, if the key existed before, it will be replaced.
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &f64) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &u64) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.to_string();
 
         for key in keys {
-            state::set(ord as i64, key, &as_str);
+            state::set(ord, key, &as_str);
+        }
+    }
+}
+
+/// `StoreSetBool` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `bool`, stored as a single `0` or `1` byte
+pub struct StoreSetBool {
+    output: u32,
+}
+impl StoreNew for StoreSetBool {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreDelete for StoreSetBool {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreSet<bool> for StoreSetBool {
+    /// Set a given key to a given value, if the key existed before, it will be replaced.
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &bool) {
+        state::select_output(self.output);
+        state::set(ord, key, [*value as u8]);
+    }
+
+    /// Set many keys to a given values, if the key existed before, it will be replaced.
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &bool) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_bytes = [*value as u8];
+
+        for key in keys {
+            state::set(ord, key, as_bytes);
         }
     }
 }
 
 /// `StoreSetBigDecimal` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `bigdecimal`
-pub struct StoreSetBigDecimal {}
+pub struct StoreSetBigDecimal {
+    output: u32,
+}
 impl StoreNew for StoreSetBigDecimal {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetBigDecimal {}
+impl StoreDelete for StoreSetBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSet<BigDecimal> for StoreSetBigDecimal {
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigDecimal) {
-        state::set(ord as i64, key, value.to_string().as_bytes())
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigDecimal) {
+        state::select_output(self.output);
+        state::set(ord, key, value.to_string().as_bytes())
     }
 
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigDecimal) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &BigDecimal) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.to_string();
 
         for key in keys {
-            state::set(ord as i64, key, &as_str)
+            state::set(ord, key, &as_str)
         }
     }
 }
 
 /// `StoreSetBigInt` is a struct representing a `store` with `updatePolicy` equal to `set` on a `valueType` equal to `bigint`
-pub struct StoreSetBigInt {}
+pub struct StoreSetBigInt {
+    output: u32,
+}
 impl StoreNew for StoreSetBigInt {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetBigInt {}
+impl StoreDelete for StoreSetBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSet<BigInt> for StoreSetBigInt {
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigInt) {
-        state::set(ord as i64, key, value.as_ref().to_string().as_bytes());
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::select_output(self.output);
+        state::set(ord, key, value.as_ref().to_string().as_bytes());
     }
 
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigInt) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &BigInt) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.as_ref().to_string();
 
         for key in keys {
-            state::set(ord as i64, key, &as_str);
+            state::set(ord, key, &as_str);
         }
     }
 }
 
 /// `StoreSetProto` is a struct representing a `store` with `updatePolicy` equal to `set` and a `valueType` equal to `proto:{your_proto_type}`
 #[allow(dead_code)]
-pub struct StoreSetProto<V: Default + prost::Message> {
+pub struct StoreSetProto<V: Codec> {
+    output: u32,
+    compression: Compression,
     casper: PhantomData<V>,
 }
 
-impl<V: Default + prost::Message> StoreDelete for StoreSetProto<V> {}
+impl<V: Codec> StoreSetProto<V> {
+    /// A store writing its values compressed with `compression`, values that compression
+    /// doesn't make smaller are written as is. [StoreGetProto] and the proto deltas detect
+    /// compressed values, see [Compression].
+    ///
+    /// ```rust
+    /// use substreams::store::{Compression, StoreSetProto};
+    /// use prost_types::Timestamp;
+    ///
+    /// let snapshots: StoreSetProto<Timestamp> = StoreSetProto::new_with_compression(Compression::None);
+    /// ```
+    pub fn new_with_compression(compression: Compression) -> Self {
+        StoreSetProto {
+            output: 0,
+            compression,
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<V: Codec> StoreDelete for StoreSetProto<V> {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
-impl<V: Default + prost::Message> StoreNew for StoreSetProto<V> {
+impl<V: Codec> StoreNew for StoreSetProto<V> {
     fn new() -> Self {
         Self {
             // Adding a PhantomData<T> field to your type tells the compiler that
@@ -261,24 +760,30 @@ impl<V: Default + prost::Message> StoreNew for StoreSetProto<V> {
             // safety properties. For a more in-depth explanation of how to use
             // PhantomData<T>
             casper: PhantomData,
+            output: 0,
+            compression: Compression::None,
         }
     }
 }
 
-impl<V: Default + prost::Message> StoreSet<V> for StoreSetProto<V> {
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
+impl<V: Codec> StoreSet<V> for StoreSetProto<V> {
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
         let bytes = proto::encode(value)
             .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
 
-        state::set(ord as i64, key, &bytes)
+        state::set(ord, key, self.compression.compress(bytes))
     }
 
-    fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let bytes = proto::encode(value)
             .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+        let bytes = self.compression.compress(bytes);
 
         for key in keys {
-            state::set(ord as i64, key, &bytes)
+            state::set(ord, key, &bytes)
         }
     }
 }
@@ -286,33 +791,60 @@ impl<V: Default + prost::Message> StoreSet<V> for StoreSetProto<V> {
 /// `StoreSetIfNotExists` is a trait which is implemented on any type of typed StoreSetIfNotExists
 pub trait StoreSetIfNotExists<V>: StoreDelete + StoreNew {
     /// Set a given key to a given value, if the key existed before, it will be ignored and not set.
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &V);
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V);
     /// Set given keys to given values, if the key existed before, it will be ignored and not set.
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V);
+    // We accept &Vec<K> instead of &[K] to keep the trait implementations unchanged
+    #[allow(clippy::ptr_arg)]
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &V,
+    );
 }
 
 /// `StoreSetIfNotExistsRaw` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `string`
 ///     `StoreSetIfNotExistsRaw` implements AsRef<[u8]> to give the client the flexibility
 ///     to either use the API with &Vec[...] or Vec[...].
-pub struct StoreSetIfNotExistsRaw {}
+pub struct StoreSetIfNotExistsRaw {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsRaw {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsRaw {}
+impl StoreDelete for StoreSetIfNotExistsRaw {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl<V: AsRef<[u8]>> StoreSetIfNotExists<V> for StoreSetIfNotExistsRaw {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
-        state::set_if_not_exists(ord as i64, key, value.as_ref());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
+impl<V: AsRef<[u8]>> StoreSetIfNotExists<V> for StoreSetIfNotExistsRaw {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.as_ref());
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &V,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let value = value.as_ref();
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, value);
+            state::set_if_not_exists(ord, key, value);
         }
     }
 }
@@ -320,240 +852,806 @@ impl<V: AsRef<[u8]>> StoreSetIfNotExists<V> for StoreSetIfNotExistsRaw {
 /// `StoreSetIfNotExistsString` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `string`
 /// `StoreSetIfNotExistsString` implements `AsRef<str>` to give the client the flexibility
 /// to either use the API with &String or String.
-pub struct StoreSetIfNotExistsString {}
+pub struct StoreSetIfNotExistsString {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsString {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsString {}
+impl StoreDelete for StoreSetIfNotExistsString {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl<V: AsRef<str>> StoreSetIfNotExists<V> for StoreSetIfNotExistsString {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
-        state::set_if_not_exists(ord as i64, key, value.as_ref().as_bytes());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
+impl<V: AsRef<str>> StoreSetIfNotExists<V> for StoreSetIfNotExistsString {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.as_ref().as_bytes());
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &V,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let value = value.as_ref();
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, value);
+            state::set_if_not_exists(ord, key, value);
         }
     }
 }
 
 /// `StoreSetIfNotExistsBigDecimal` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `bigdecimal`
-pub struct StoreSetIfNotExistsBigDecimal {}
+pub struct StoreSetIfNotExistsBigDecimal {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsBigDecimal {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsBigDecimal {}
+impl StoreDelete for StoreSetIfNotExistsBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl StoreSetIfNotExists<BigDecimal> for StoreSetIfNotExistsBigDecimal {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigDecimal) {
-        state::set_if_not_exists(ord as i64, key, value.as_ref().to_string().as_bytes());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigDecimal) {
+impl StoreSetIfNotExists<BigDecimal> for StoreSetIfNotExistsBigDecimal {
+    fn set_if_not_exists<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &BigDecimal,
+    ) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.as_ref().to_string().as_bytes());
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigDecimal,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.to_string();
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, &as_str);
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
 /// `StoreSetIfNotExistsBigInt` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `bigint`
-pub struct StoreSetIfNotExistsBigInt {}
+pub struct StoreSetIfNotExistsBigInt {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsBigInt {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsBigInt {}
+impl StoreDelete for StoreSetIfNotExistsBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl StoreSetIfNotExists<BigInt> for StoreSetIfNotExistsBigInt {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigInt) {
-        state::set_if_not_exists(ord as i64, key, value.to_string().as_bytes());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigInt) {
+impl StoreSetIfNotExists<BigInt> for StoreSetIfNotExistsBigInt {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.to_string().as_bytes());
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigInt,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.to_string();
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, &as_str);
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
 /// `StoreSetIfNotExistsInt64` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `int64`
-pub struct StoreSetIfNotExistsInt64 {}
+pub struct StoreSetIfNotExistsInt64 {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsInt64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsInt64 {}
+impl StoreDelete for StoreSetIfNotExistsInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl StoreSetIfNotExists<i64> for StoreSetIfNotExistsInt64 {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &i64) {
-        state::set_if_not_exists(ord as i64, key, value.to_string().as_bytes());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &i64) {
+impl StoreSetIfNotExists<i64> for StoreSetIfNotExistsInt64 {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &i64) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.to_string().as_bytes());
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &i64,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let as_str = value.to_string();
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, &as_str);
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
 /// `StoreSetIfNotExistsFloat64` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `float64`
-pub struct StoreSetIfNotExistsFloat64 {}
+pub struct StoreSetIfNotExistsFloat64 {
+    output: u32,
+}
 impl StoreNew for StoreSetIfNotExistsFloat64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreSetIfNotExistsFloat64 {}
+impl StoreDelete for StoreSetIfNotExistsFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl StoreSetIfNotExists<f64> for StoreSetIfNotExistsFloat64 {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &f64) {
-        state::set_if_not_exists(ord as i64, key, value.to_string().as_bytes());
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
     }
+}
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &f64) {
-        let as_str = value.to_string();
+impl StoreSetIfNotExists<f64> for StoreSetIfNotExistsFloat64 {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &f64) {
+        state::select_output(self.output);
+        guard_float64("set_if_not_exists", *value);
+        state::set_if_not_exists(ord, key, value::encode_f64(*value));
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &f64,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        guard_float64("set_if_not_exists", *value);
+        let as_str = value::format_f64(*value);
 
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, &as_str);
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
-/// `StoreSetIfNotExistsProto` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `proto:{your_proto_type}`
-#[allow(dead_code)]
-pub struct StoreSetIfNotExistsProto<T> {
-    store: StoreSetIfNotExistsRaw,
-    casper: PhantomData<T>,
+/// `StoreSetIfNotExistsInt32` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `int32`
+pub struct StoreSetIfNotExistsInt32 {
+    output: u32,
 }
-
-impl<V: Default + prost::Message> StoreNew for StoreSetIfNotExistsProto<V> {
+impl StoreNew for StoreSetIfNotExistsInt32 {
     fn new() -> Self {
-        StoreSetIfNotExistsProto {
-            store: StoreSetIfNotExistsRaw {},
-            // Adding a PhantomData<T> field to your type tells the compiler that
-            // your type acts as though it stores a value of type T, even though
-            // it doesn't really. This information is used when computing certain
-            // safety properties. For a more in-depth explanation of how to use
-            // PhantomData<T>
-            casper: PhantomData,
-        }
+        Self { output: 0 }
     }
 }
 
-impl<V: Default + prost::Message> StoreDelete for StoreSetIfNotExistsProto<V> {}
+impl StoreDelete for StoreSetIfNotExistsInt32 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl<V: Default + prost::Message> StoreSetIfNotExists<V> for StoreSetIfNotExistsProto<V> {
-    fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &V) {
-        let bytes = proto::encode(value)
-            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
-        self.store.set_if_not_exists(ord, key, &bytes)
+impl StoreSetIfNotExists<i32> for StoreSetIfNotExistsInt32 {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &i32) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.to_string().as_bytes());
     }
 
-    fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &V) {
-        let bytes = proto::encode(value)
-            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &i32,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_str = value.to_string();
 
         for key in keys {
-            self.store.set_if_not_exists(ord, key, &bytes)
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
-/// `StoreAdd` is a trait which is implemented on any type of types StoreAdd
-pub trait StoreAdd<V>: StoreDelete + StoreNew {
-    /// Add a given value to an already existing key
-    fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: V);
-    /// Add multiple values to an already existing key
-    fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: V);
+/// `StoreSetIfNotExistsUint64` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `uint64`, stored as a decimal string like `int64` values
+pub struct StoreSetIfNotExistsUint64 {
+    output: u32,
 }
-
-/// `StoreAddInt64` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `int64`
-pub struct StoreAddInt64 {}
-impl StoreNew for StoreAddInt64 {
+impl StoreNew for StoreSetIfNotExistsUint64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreAddInt64 {}
+impl StoreDelete for StoreSetIfNotExistsUint64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
 
-impl StoreAdd<i64> for StoreAddInt64 {
-    fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::add_int64(ord as i64, key, value);
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreSetIfNotExists<u64> for StoreSetIfNotExistsUint64 {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &u64) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, value.to_string().as_bytes());
     }
 
-    fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: i64) {
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &u64,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_str = value.to_string();
+
         for key in keys {
-            state::add_int64(ord as i64, key, value);
+            state::set_if_not_exists(ord, key, &as_str);
         }
     }
 }
 
-/// `StoreAddFloat64` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `float64`
-pub struct StoreAddFloat64 {}
-impl StoreNew for StoreAddFloat64 {
+/// `StoreSetIfNotExistsBool` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `bool`, stored as a single `0` or `1` byte
+pub struct StoreSetIfNotExistsBool {
+    output: u32,
+}
+impl StoreNew for StoreSetIfNotExistsBool {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreAddFloat64 {}
+impl StoreDelete for StoreSetIfNotExistsBool {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
-impl StoreAdd<f64> for StoreAddFloat64 {
-    fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::add_float64(ord as i64, key, value);
+impl StoreSetIfNotExists<bool> for StoreSetIfNotExistsBool {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &bool) {
+        state::select_output(self.output);
+        state::set_if_not_exists(ord, key, [*value as u8]);
     }
 
-    fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: f64) {
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &bool,
+    ) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_bytes = [*value as u8];
+
         for key in keys {
-            state::add_float64(ord as i64, key, value);
+            state::set_if_not_exists(ord, key, as_bytes);
         }
     }
 }
 
-/// `StoreAddBigDecimal` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `bigdecimal`
-/// `StoreAddBigDecimal` implements `AsRef<BigInt>` to give the client the flexibility
-/// to either use the API with &BigDecimal or BigDecimal.
-pub struct StoreAddBigDecimal {}
+/// `StoreSetIfNotExistsProto` is a struct representing a `store` module with `updatePolicy` equal to `set_if_not_exists` and a `valueType` equal to `proto:{your_proto_type}`
+#[allow(dead_code)]
+pub struct StoreSetIfNotExistsProto<T> {
+    store: StoreSetIfNotExistsRaw,
+    /// Values written by [StoreSetIfNotExistsProto::create_if_absent], by key.
+    created: RefCell<HashMap<String, Vec<u8>>>,
+    casper: PhantomData<T>,
+}
+
+impl<V: Codec> StoreNew for StoreSetIfNotExistsProto<V> {
+    fn new() -> Self {
+        StoreSetIfNotExistsProto {
+            store: StoreSetIfNotExistsRaw { output: 0 },
+            created: RefCell::new(HashMap::new()),
+            // Adding a PhantomData<T> field to your type tells the compiler that
+            // your type acts as though it stores a value of type T, even though
+            // it doesn't really. This information is used when computing certain
+            // safety properties. For a more in-depth explanation of how to use
+            // PhantomData<T>
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<V: Codec> StoreDelete for StoreSetIfNotExistsProto<V> {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        self.store.delete_prefix(ord, prefix)
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        self.store.delete_key(ord, key)
+    }
+}
+
+impl<V: Codec> StoreSetIfNotExists<V> for StoreSetIfNotExistsProto<V> {
+    fn set_if_not_exists<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        let bytes = proto::encode(value)
+            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+
+        self.store.set_if_not_exists(ord, key, &bytes)
+    }
+
+    fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &V,
+    ) {
+        let ord: Ordinal = ord.into();
+        let bytes = proto::encode(value)
+            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+
+        for key in keys {
+            self.store.set_if_not_exists(ord, key, &bytes)
+        }
+    }
+}
+
+impl<V: Codec> StoreSetIfNotExistsProto<V> {
+    /// Registers the entity `key` with the value built by `make`, unless it already exists,
+    /// returning which value is stored.
+    ///
+    /// A store can't read its own values: entities registered on previous blocks are looked
+    /// up in `existing`, the same store consumed in `get` mode, usually through a previous
+    /// stage of the module graph. Entities registered earlier in the same block through this
+    /// method are recognized without reading `existing`:
+    ///
+    /// ```rust
+    /// use substreams::store::{Created, StoreGetProto, StoreSetIfNotExistsProto};
+    /// # mod pb {
+    /// #   #[derive(Clone, PartialEq, ::prost::Message)]
+    /// #   pub struct Token { #[prost(string, tag = "1")] pub address: String }
+    /// # }
+    ///
+    /// fn store_tokens(
+    ///     addresses: Vec<String>,
+    ///     known: StoreGetProto<pb::Token>,
+    ///     store: StoreSetIfNotExistsProto<pb::Token>,
+    /// ) {
+    ///     for address in addresses {
+    ///         let key = format!("token:{}", address);
+    ///         if let Created::New(token) = store.create_if_absent(0, key, &known, || pb::Token {
+    ///             address: address.clone(),
+    ///         }) {
+    ///             substreams::log::info!("registered token {}", token.address);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// `make` is only called when the entity is created.
+    pub fn create_if_absent<K, S, F>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        existing: &S,
+        make: F,
+    ) -> Created<V>
+    where
+        K: AsRef<str>,
+        S: StoreGet<V>,
+        F: FnOnce() -> V,
+    {
+        let key = key.as_ref();
+        if let Some(bytes) = self.created.borrow().get(key) {
+            return Created::Existing(proto::decode(bytes).unwrap_or_else(|_| {
+                panic!("Unable to decode store message's struct from Protobuf data")
+            }));
+        }
+
+        if let Some(value) = existing.get_last(key) {
+            return Created::Existing(value);
+        }
+
+        let value = make();
+        let bytes = proto::encode(&value)
+            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+        self.store.set_if_not_exists(ord, key, &bytes);
+        self.created.borrow_mut().insert(key.to_string(), bytes);
+
+        Created::New(value)
+    }
+}
+
+/// The value of an entity registered by [StoreSetIfNotExistsProto::create_if_absent].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Created<T> {
+    /// The entity was created with this value.
+    New(T),
+    /// The entity already existed with this value, it was left untouched.
+    Existing(T),
+}
+
+impl<T> Created<T> {
+    pub fn is_new(&self) -> bool {
+        matches!(self, Created::New(_))
+    }
+
+    pub fn value(&self) -> &T {
+        match self {
+            Created::New(value) | Created::Existing(value) => value,
+        }
+    }
+
+    pub fn into_value(self) -> T {
+        match self {
+            Created::New(value) | Created::Existing(value) => value,
+        }
+    }
+}
+
+/// `StoreSetOnce` is a trait which is implemented on any type of typed StoreSetOnce
+pub trait StoreSetOnce<V>: StoreNew {
+    /// Set a given key to a given value, the module fails if the key was already set.
+    fn set_once<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V);
+    /// Set given keys to given values, the module fails if any of the keys was already set.
+    fn set_once_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: &V);
+}
+
+/// `StoreSetOnceRaw` is a struct representing a `store` module with `updatePolicy` equal to `set_once` and a `valueType` equal to `bytes`
+///     `StoreSetOnceRaw` implements AsRef<[u8]> to give the client the flexibility
+///     to either use the API with &Vec[...] or Vec[...].
+pub struct StoreSetOnceRaw {
+    output: u32,
+}
+impl StoreNew for StoreSetOnceRaw {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl<V: AsRef<[u8]>> StoreSetOnce<V> for StoreSetOnceRaw {
+    fn set_once<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set_once(ord, key, value.as_ref());
+    }
+
+    fn set_once_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: &V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let value = value.as_ref();
+
+        for key in keys {
+            state::set_once(ord, key, value);
+        }
+    }
+}
+
+/// `StoreSetOnceString` is a struct representing a `store` module with `updatePolicy` equal to `set_once` and a `valueType` equal to `string`
+/// `StoreSetOnceString` implements `AsRef<str>` to give the client the flexibility
+/// to either use the API with &String or String.
+pub struct StoreSetOnceString {
+    output: u32,
+}
+impl StoreNew for StoreSetOnceString {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl<V: AsRef<str>> StoreSetOnce<V> for StoreSetOnceString {
+    fn set_once<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        state::select_output(self.output);
+        state::set_once(ord, key, value.as_ref().as_bytes());
+    }
+
+    fn set_once_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: &V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let value = value.as_ref();
+
+        for key in keys {
+            state::set_once(ord, key, value);
+        }
+    }
+}
+
+/// `StoreSetOnceBigInt` is a struct representing a `store` module with `updatePolicy` equal to `set_once` and a `valueType` equal to `bigint`
+pub struct StoreSetOnceBigInt {
+    output: u32,
+}
+impl StoreNew for StoreSetOnceBigInt {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreSetOnce<BigInt> for StoreSetOnceBigInt {
+    fn set_once<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::select_output(self.output);
+        state::set_once(ord, key, value.to_string().as_bytes());
+    }
+
+    fn set_once_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: &BigInt) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let as_str = value.to_string();
+
+        for key in keys {
+            state::set_once(ord, key, &as_str);
+        }
+    }
+}
+
+/// `StoreSetOnceProto` is a struct representing a `store` module with `updatePolicy` equal to `set_once` and a `valueType` equal to `proto:{your_proto_type}`
+#[allow(dead_code)]
+pub struct StoreSetOnceProto<T> {
+    store: StoreSetOnceRaw,
+    casper: PhantomData<T>,
+}
+
+impl<V: Codec> StoreNew for StoreSetOnceProto<V> {
+    fn new() -> Self {
+        StoreSetOnceProto {
+            store: StoreSetOnceRaw { output: 0 },
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<V: Codec> StoreSetOnce<V> for StoreSetOnceProto<V> {
+    fn set_once<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &V) {
+        let bytes = proto::encode(value)
+            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+
+        self.store.set_once(ord, key, &bytes)
+    }
+
+    fn set_once_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: &V) {
+        let ord: Ordinal = ord.into();
+        let bytes = proto::encode(value)
+            .unwrap_or_else(|_| panic!("Unable to encode store message's struct to Protobuf data"));
+
+        for key in keys {
+            self.store.set_once(ord, key, &bytes)
+        }
+    }
+}
+
+/// `StoreAdd` is a trait which is implemented on any type of types StoreAdd
+pub trait StoreAdd<V>: StoreDelete + StoreNew {
+    /// Add a given value to an already existing key
+    fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V);
+    /// Add multiple values to an already existing key
+    // We accept &Vec<K> instead of &[K] to keep the trait implementations unchanged
+    #[allow(clippy::ptr_arg)]
+    fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: V);
+}
+
+/// `StoreAddInt64` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `int64`
+///
+/// The host accumulates the values and stores the sums as decimal strings, there's no binary
+/// form like [StoreSetInt64::new_binary] for `add` stores.
+pub struct StoreAddInt64 {
+    output: u32,
+}
+impl StoreNew for StoreAddInt64 {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreDelete for StoreAddInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreAdd<i64> for StoreAddInt64 {
+    fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::select_output(self.output);
+        state::add_int64(ord, key, value);
+    }
+
+    fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: i64) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        for key in keys {
+            state::add_int64(ord, key, value);
+        }
+    }
+}
+
+/// `StoreAddFloat64` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `float64`
+pub struct StoreAddFloat64 {
+    output: u32,
+}
+impl StoreNew for StoreAddFloat64 {
+    fn new() -> Self {
+        Self { output: 0 }
+    }
+}
+
+impl StoreDelete for StoreAddFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl StoreAdd<f64> for StoreAddFloat64 {
+    fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        state::select_output(self.output);
+        guard_float64("add", value);
+        state::add_float64(ord, key, value);
+    }
+
+    fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: f64) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        guard_float64("add", value);
+        for key in keys {
+            state::add_float64(ord, key, value);
+        }
+    }
+}
+
+impl StoreAddFloat64 {
+    /// Same as [StoreAdd::add] but panics if `value` is NaN or infinite instead of letting it
+    /// propagate to the accumulated value. Enable the `checked-floats` feature to check
+    /// every float64 store write.
+    pub fn add_checked<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        check_float64("add", value);
+        state::add_float64(ord, key, value);
+    }
+
+    /// Same as [StoreAdd::add_many] but panics if `value` is NaN or infinite.
+    pub fn add_many_checked<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &[K], value: f64) {
+        let ord: Ordinal = ord.into();
+        check_float64("add", value);
+        for key in keys {
+            state::add_float64(ord, key, value);
+        }
+    }
+}
+
+/// Panics if `value` is NaN or infinite, such values would otherwise silently propagate to
+/// the accumulated float64 value.
+fn check_float64(op: &str, value: f64) {
+    if !value.is_finite() {
+        panic!(
+            "invalid float64 {} value {}, float64 store values must be finite",
+            op, value
+        );
+    }
+}
+
+/// Checks `value` with [check_float64] when the `checked-floats` feature is enabled.
+fn guard_float64(op: &str, value: f64) {
+    if cfg!(feature = "checked-floats") {
+        check_float64(op, value);
+    }
+}
+
+/// `StoreAddBigDecimal` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `bigdecimal`
+/// `StoreAddBigDecimal` implements `AsRef<BigInt>` to give the client the flexibility
+/// to either use the API with &BigDecimal or BigDecimal.
+pub struct StoreAddBigDecimal {
+    output: u32,
+}
 impl StoreNew for StoreAddBigDecimal {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreAddBigDecimal {}
+impl StoreDelete for StoreAddBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigDecimal>> StoreAdd<V> for StoreAddBigDecimal {
-    fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::add_bigdecimal(ord as i64, key, value.as_ref());
+    fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::add_bigdecimal(ord, key, value.as_ref());
     }
 
-    fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: V) {
+    fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let value = value.as_ref();
 
         for key in keys {
-            state::add_bigdecimal(ord as i64, key, value);
+            state::add_bigdecimal(ord, key, value);
         }
     }
 }
@@ -561,25 +1659,40 @@ impl<V: AsRef<BigDecimal>> StoreAdd<V> for StoreAddBigDecimal {
 /// `StoreAddBigInt` is a struct representing a `store` module with `updatePolicy` equal to `add` and a valueType of `bigint`
 /// `StoreAddBigInt` implements `AsRef<BigInt>` to give the client the flexibility
 /// to either use the API with &BigInt or BigInt.
-pub struct StoreAddBigInt {}
+pub struct StoreAddBigInt {
+    output: u32,
+}
 impl StoreNew for StoreAddBigInt {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreAddBigInt {}
+impl StoreDelete for StoreAddBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigInt>> StoreAdd<V> for StoreAddBigInt {
-    fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::add_bigint(ord as i64, key, value.as_ref());
+    fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::add_bigint(ord, key, value.as_ref());
     }
 
-    fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: V) {
+    fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: V) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
         let value = value.as_ref();
 
         for key in keys {
-            state::add_bigint(ord as i64, key, value);
+            state::add_bigint(ord, key, value);
         }
     }
 }
@@ -589,74 +1702,127 @@ pub trait StoreMax<V>: StoreNew + StoreDelete {
     /// max will set the provided key in the store only if the value received in
     /// parameter is bigger than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: V);
+    fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V);
 }
 
 /// `StoreMaxInt64` is a struct representing a `store` module with `updatePolicy` equal to `max` and a valueType of `int64`
-pub struct StoreMaxInt64 {}
+pub struct StoreMaxInt64 {
+    output: u32,
+}
 impl StoreNew for StoreMaxInt64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMaxInt64 {}
+impl StoreDelete for StoreMaxInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreMax<i64> for StoreMaxInt64 {
-    fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::set_max_int64(ord as i64, key, value);
+    fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::select_output(self.output);
+        state::set_max_int64(ord, key, value);
     }
 }
 
 /// `StoreMaxBigInt` is a struct representing a `store` module with `updatePolicy` equal to `max` and a valueType of `bigint`
 /// `StoreMaxBigInt` implements `AsRef<BigInt>` to give the client the flexibility
 /// to either use the API with &BigInt or BigInt.
-pub struct StoreMaxBigInt {}
+pub struct StoreMaxBigInt {
+    output: u32,
+}
 impl StoreNew for StoreMaxBigInt {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMaxBigInt {}
+impl StoreDelete for StoreMaxBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigInt>> StoreMax<V> for StoreMaxBigInt {
-    fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::set_max_bigint(ord as i64, key, value.as_ref());
+    fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::set_max_bigint(ord, key, value.as_ref());
     }
 }
 
 /// `StoreMaxFloat64` is a struct representing a `store` module with `updatePolicy` equal to `max` and a valueType of `float64`
-pub struct StoreMaxFloat64 {}
+pub struct StoreMaxFloat64 {
+    output: u32,
+}
 impl StoreNew for StoreMaxFloat64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMaxFloat64 {}
+impl StoreDelete for StoreMaxFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreMax<f64> for StoreMaxFloat64 {
-    fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::set_max_float64(ord as i64, key, value);
+    fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        state::select_output(self.output);
+        guard_float64("max", value);
+        state::set_max_float64(ord, key, value);
     }
 }
 
 /// `StoreMaxBigDecimal` is a struct representing a `store` module with `updatePolicy` equal to `max` and a valueType of `bigdecimal`
 /// `StoreMaxBigDecimal` implements `AsRef<BigDecimal>` to give the client the flexibility
 /// to either use the API with &BigDecimal or BigDecimal.
-pub struct StoreMaxBigDecimal {}
+pub struct StoreMaxBigDecimal {
+    output: u32,
+}
 impl StoreNew for StoreMaxBigDecimal {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMaxBigDecimal {}
+impl StoreDelete for StoreMaxBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigDecimal>> StoreMax<V> for StoreMaxBigDecimal {
-    fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::set_max_bigdecimal(ord as i64, key, value.as_ref());
+    fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::set_max_bigdecimal(ord, key, value.as_ref());
     }
 }
 
@@ -665,189 +1831,507 @@ pub trait StoreMin<V>: StoreNew + StoreDelete {
     /// Will set the provided key in the store only if the value received in
     /// parameter is smaller than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: V);
+    fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V);
 }
 
 /// `StoreMinInt64` is a struct representing a `store` module with `updatePolicy` equal to `min` and a valueType of `int64`
-pub struct StoreMinInt64 {}
+pub struct StoreMinInt64 {
+    output: u32,
+}
 impl StoreNew for StoreMinInt64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMinInt64 {}
+impl StoreDelete for StoreMinInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreMin<i64> for StoreMinInt64 {
-    fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::set_min_int64(ord as i64, key, value);
+    fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::select_output(self.output);
+        state::set_min_int64(ord, key, value);
     }
 }
 
 /// `StoreMinBigInt` is a struct representing a `store` module with `updatePolicy` equal to `min` and a valueType of `bigint`
 /// `StoreMinBigInt` implements `AsRef<BigInt>` to give the client the flexibility
 /// to either use the API with &BigInt or BigInt.
-pub struct StoreMinBigInt {}
+pub struct StoreMinBigInt {
+    output: u32,
+}
 impl StoreNew for StoreMinBigInt {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMinBigInt {}
+impl StoreDelete for StoreMinBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigInt>> StoreMin<V> for StoreMinBigInt {
-    fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::set_min_bigint(ord as i64, key, value.as_ref());
+    fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::set_min_bigint(ord, key, value.as_ref());
     }
 }
 
 /// `StoreMinFloat64` is a struct representing a `store` module with `updatePolicy` equal to `min` and a valueType of `float64`
-pub struct StoreMinFloat64 {}
+pub struct StoreMinFloat64 {
+    output: u32,
+}
 impl StoreNew for StoreMinFloat64 {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMinFloat64 {}
+impl StoreDelete for StoreMinFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreMin<f64> for StoreMinFloat64 {
-    fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::set_min_float64(ord as i64, key, value);
+    fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        state::select_output(self.output);
+        guard_float64("min", value);
+        state::set_min_float64(ord, key, value);
     }
 }
 
 /// `StoreMinBigDecimal` is a struct representing a `store` module with `updatePolicy` equal to `min` and a valueType of `bigdecimal`
 /// `StoreMinBigDecimal` implements `AsRef<BigDecimal>` to give the client the flexibility to either use
 /// the API with &BigDecimal or BigDecimal.
-pub struct StoreMinBigDecimal {}
+pub struct StoreMinBigDecimal {
+    output: u32,
+}
 impl StoreNew for StoreMinBigDecimal {
     fn new() -> Self {
-        Self {}
+        Self { output: 0 }
     }
 }
 
-impl StoreDelete for StoreMinBigDecimal {}
+impl StoreDelete for StoreMinBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl<V: AsRef<BigDecimal>> StoreMin<V> for StoreMinBigDecimal {
-    fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: V) {
-        state::set_min_bigdecimal(ord as i64, key, value.as_ref());
+    fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::select_output(self.output);
+        state::set_min_bigdecimal(ord, key, value.as_ref());
     }
 }
 
 // -------------------- Appender -------------------- //
 pub trait Appender<T> {
     fn new() -> Self;
-    fn append<K: AsRef<str>>(&self, ord: u64, key: K, item: T);
-    fn append_all<K: AsRef<str>>(&self, ord: u64, key: K, items: Vec<T>);
+    fn append<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, item: T);
+    fn append_all<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, items: Vec<T>);
 }
 
 /// StoreAppend is a struct representing a `store` with
-/// `updatePolicy` equal to `append`
-pub struct StoreAppend<T> {
-    casper: PhantomData<T>,
+/// `updatePolicy` equal to `append`, its elements are encoded with the
+/// [AppendCodec] `C` (`;`-joined strings by default).
+///
+/// The store must be read back (through [StoreGetArray] or [DeltaArray]) with the same codec:
+///
+/// ```rust
+/// use substreams::store::{Appender, FixedWidthCodec, StoreAppend, StoreGetArray};
+///
+/// #[substreams::handlers::store]
+/// fn store_prices(prices: StoreAppend<i64, FixedWidthCodec>) {
+///     prices.append_all(1, "pool:0x01", vec![1_000, 1_010]);
+/// }
+///
+/// fn read_prices(prices: StoreGetArray<i64, FixedWidthCodec>) -> Option<Vec<i64>> {
+///     use substreams::store::StoreGet;
+///
+///     prices.get_last("pool:0x01")
+/// }
+/// ```
+pub struct StoreAppend<T, C = StringCodec> {
+    output: u32,
+    casper: PhantomData<(T, C)>,
 }
 
-impl<T> Appender<T> for StoreAppend<T>
+impl<T, C> Appender<T> for StoreAppend<T, C>
 where
-    T: Into<String>,
+    C: AppendCodec<T>,
 {
     fn new() -> Self {
         StoreAppend {
             casper: PhantomData,
+            output: 0,
         }
     }
 
     /// Concatenates a given value at the end of the key's current value
-    fn append<K: AsRef<str>>(&self, ord: u64, key: K, item: T) {
-        let item: String = item.into();
-        state::append(ord as i64, &key, &format!("{};", &item).as_bytes());
+    fn append<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, item: T) {
+        state::select_output(self.output);
+        let mut buf = Vec::new();
+        C::encode(item, &mut buf);
+        state::append(ord, &key, &buf);
     }
 
-    fn append_all<K: AsRef<str>>(&self, ord: u64, key: K, items: Vec<T>) {
+    fn append_all<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, items: Vec<T>) {
+        let ord: Ordinal = ord.into();
         for item in items {
             self.append(ord, &key, item);
         }
     }
 }
 
+impl<T, C> StoreDelete for StoreAppend<T, C> {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+/// StoreAppendProto is a `store` with `updatePolicy` equal to `append` on `proto` elements,
+/// each message is length-prefixed so it can be read back with [StoreGetProtoArray].
+pub type StoreAppendProto<T> = StoreAppend<T, ProtoCodec>;
+
+/// StoreAppendBytes is a `store` with `updatePolicy` equal to `append` on `bytes` elements,
+/// each element is length-prefixed so binary data (like encoded events) can be read back
+/// with [StoreGetBytesArray], unlike the `;` separated elements of [StoreAppend].
+pub type StoreAppendBytes = StoreAppend<Vec<u8>, LengthPrefixedCodec>;
+
+// -------------------- AppendCodec -------------------- //
+/// Encoding of the elements of an `append` store, each appended element is encoded on its own
+/// and the store value is the concatenation of the encoded elements.
+pub trait AppendCodec<T> {
+    /// Writes the encoding of `item` at the end of `buf`.
+    fn encode(item: T, buf: &mut Vec<u8>);
+
+    /// Decodes the concatenated elements of `bytes`, returns `None` when there are none.
+    fn decode(bytes: Vec<u8>) -> Option<Vec<T>>;
+}
+
+/// Encodes elements as strings terminated by `;`, the default codec of append stores.
+///
+/// Elements must not contain `;`, use [LengthPrefixedCodec] for arbitrary values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringCodec;
+
+impl<T: Into<String> + From<String>> AppendCodec<T> for StringCodec {
+    fn encode(item: T, buf: &mut Vec<u8>) {
+        let item: String = item.into();
+        buf.extend_from_slice(item.as_bytes());
+        buf.push(b';');
+    }
+
+    fn decode(bytes: Vec<u8>) -> Option<Vec<T>> {
+        split_array(bytes)
+    }
+}
+
+/// Encodes elements as bytes prefixed by their varint encoded length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthPrefixedCodec;
+
+impl<T: Into<Vec<u8>> + From<Vec<u8>>> AppendCodec<T> for LengthPrefixedCodec {
+    fn encode(item: T, buf: &mut Vec<u8>) {
+        let item: Vec<u8> = item.into();
+        prost::encoding::encode_varint(item.len() as u64, buf);
+        buf.extend_from_slice(&item);
+    }
+
+    fn decode(bytes: Vec<u8>) -> Option<Vec<T>> {
+        split_length_prefixed(&bytes)
+            .map(|part| part.to_vec().into())
+            .collect_non_empty()
+    }
+}
+
+/// Encodes elements as [Codec] messages prefixed by their varint encoded length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtoCodec;
+
+impl<T: Codec> AppendCodec<T> for ProtoCodec {
+    fn encode(item: T, buf: &mut Vec<u8>) {
+        let item = item
+            .to_bytes()
+            .unwrap_or_else(|_| panic!("Unable to encode store message"));
+        prost::encoding::encode_varint(item.len() as u64, buf);
+        buf.extend_from_slice(&item);
+    }
+
+    fn decode(bytes: Vec<u8>) -> Option<Vec<T>> {
+        split_length_prefixed(&bytes)
+            .map(|part| {
+                T::from_bytes(part).unwrap_or_else(|_| panic!("Unable to decode store message"))
+            })
+            .collect_non_empty()
+    }
+}
+
+/// Encodes integer elements on their fixed width in big-endian order, see [FixedWidth].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedWidthCodec;
+
+/// Integers supported by [FixedWidthCodec].
+pub trait FixedWidth: Sized {
+    const WIDTH: usize;
+
+    fn write_be(self, buf: &mut Vec<u8>);
+    fn read_be(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_fixed_width {
+    ($($int:ty),+ $(,)?) => {
+        $(
+            impl FixedWidth for $int {
+                const WIDTH: usize = std::mem::size_of::<$int>();
+
+                fn write_be(self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn read_be(bytes: &[u8]) -> Self {
+                    let mut be = [0u8; std::mem::size_of::<$int>()];
+                    be.copy_from_slice(bytes);
+                    <$int>::from_be_bytes(be)
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed_width!(i32, i64, u32, u64);
+
+impl<T: FixedWidth> AppendCodec<T> for FixedWidthCodec {
+    fn encode(item: T, buf: &mut Vec<u8>) {
+        item.write_be(buf);
+    }
+
+    fn decode(bytes: Vec<u8>) -> Option<Vec<T>> {
+        if bytes.len() % T::WIDTH != 0 {
+            panic!(
+                "Invalid fixed width store value, {} bytes is not a multiple of {}",
+                bytes.len(),
+                T::WIDTH
+            );
+        }
+
+        bytes.chunks(T::WIDTH).map(T::read_be).collect_non_empty()
+    }
+}
+
+/// Iterates over the varint length prefixed parts of `bytes`.
+fn split_length_prefixed(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        if bytes.is_empty() {
+            return None;
+        }
+
+        let len = prost::encoding::decode_varint(&mut bytes)
+            .unwrap_or_else(|_| panic!("Invalid length prefix in store value"))
+            as usize;
+        if len > bytes.len() {
+            panic!("Truncated element in store value");
+        }
+
+        let (part, rest) = bytes.split_at(len);
+        bytes = rest;
+        Some(part)
+    })
+}
+
+trait CollectNonEmpty<T> {
+    fn collect_non_empty(self) -> Option<Vec<T>>;
+}
+
+impl<T, I: Iterator<Item = T>> CollectNonEmpty<T> for I {
+    fn collect_non_empty(self) -> Option<Vec<T>> {
+        let items: Vec<T> = self.collect();
+        match items.len() {
+            0 => None,
+            _ => Some(items),
+        }
+    }
+}
+
 // -------------------- StoreSetSum -------------------- //
 pub trait StoreSetSum<T> {
     fn new() -> Self;
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: T);
-    fn sum<K: AsRef<str>>(&self, ord: u64, key: K, value: T);
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: T);
+    fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: T);
+}
+
+pub struct StoreSetSumInt64 {
+    output: u32,
 }
 
-pub struct StoreSetSumInt64 {}
+impl StoreDelete for StoreSetSumInt64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSetSum<i64> for StoreSetSumInt64 {
     fn new() -> Self {
-        StoreSetSumInt64 {}
+        StoreSetSumInt64 { output: 0 }
     }
 
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::select_output(self.output);
         let v = format!("set:{}", value.to_string());
-        state::set_sum_int64(ord as i64, key, v);
+        state::set_sum_int64(ord, key, v);
     }
 
-    fn sum<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
+    fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::select_output(self.output);
         let v = format!("sum:{}", value.to_string());
-        state::set_sum_int64(ord as i64, key, v);
+        state::set_sum_int64(ord, key, v);
     }
 }
 
-pub struct StoreSetSumFloat64 {}
+pub struct StoreSetSumFloat64 {
+    output: u32,
+}
+
+impl StoreDelete for StoreSetSumFloat64 {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSetSum<f64> for StoreSetSumFloat64 {
     fn new() -> Self {
-        StoreSetSumFloat64 {}
+        StoreSetSumFloat64 { output: 0 }
     }
 
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        let v = format!("set:{}", value.to_string());
-        state::set_sum_float64(ord as i64, key, v);
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        state::select_output(self.output);
+        guard_float64("set", value);
+        let v = format!("set:{}", value::format_f64(value));
+        state::set_sum_float64(ord, key, v);
     }
 
-    fn sum<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        let v = format!("sum:{}", value.to_string());
-        state::set_sum_float64(ord as i64, key, v);
+    fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        state::select_output(self.output);
+        guard_float64("sum", value);
+        let v = format!("sum:{}", value::format_f64(value));
+        state::set_sum_float64(ord, key, v);
     }
 }
 
-pub struct StoreSetSumBigInt {}
+pub struct StoreSetSumBigInt {
+    output: u32,
+}
+
+impl StoreDelete for StoreSetSumBigInt {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSetSum<BigInt> for StoreSetSumBigInt {
     fn new() -> Self {
-        StoreSetSumBigInt {}
+        StoreSetSumBigInt { output: 0 }
     }
 
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: BigInt) {
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: BigInt) {
+        state::select_output(self.output);
         let v = format!("set:{}", value.to_string());
-        state::set_sum_bigint(ord as i64, key, v);
+        state::set_sum_bigint(ord, key, v);
     }
 
-    fn sum<K: AsRef<str>>(&self, ord: u64, key: K, value: BigInt) {
+    fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: BigInt) {
+        state::select_output(self.output);
         let v = format!("sum:{}", value.to_string());
-        state::set_sum_bigint(ord as i64, key, v);
+        state::set_sum_bigint(ord, key, v);
     }
 }
 
-pub struct StoreSetSumBigDecimal {}
+pub struct StoreSetSumBigDecimal {
+    output: u32,
+}
+
+impl StoreDelete for StoreSetSumBigDecimal {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
 
 impl StoreSetSum<BigDecimal> for StoreSetSumBigDecimal {
     fn new() -> Self {
-        StoreSetSumBigDecimal {}
+        StoreSetSumBigDecimal { output: 0 }
     }
 
-    fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: BigDecimal) {
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: BigDecimal) {
+        state::select_output(self.output);
         let v = format!("set:{}", value.to_string());
-        state::set_sum_bigdecimal(ord as i64, key, v);
+        state::set_sum_bigdecimal(ord, key, v);
     }
 
-    fn sum<K: AsRef<str>>(&self, ord: u64, key: K, value: BigDecimal) {
+    fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: BigDecimal) {
+        state::select_output(self.output);
         let v = format!("sum:{}", value.to_string());
-        state::set_sum_bigdecimal(ord as i64, key, v);
+        state::set_sum_bigdecimal(ord, key, v);
     }
 }
 
@@ -855,12 +2339,124 @@ impl StoreSetSum<BigDecimal> for StoreSetSumBigDecimal {
 /// StoreGet is a trait which is implemented on any type of typed StoreGet
 pub trait StoreGet<T> {
     fn new(idx: u32) -> Self;
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<T>;
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<T>;
     fn get_last<K: AsRef<str>>(&self, key: K) -> Option<T>;
     fn get_first<K: AsRef<str>>(&self, key: K) -> Option<T>;
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool;
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool;
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool;
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool;
+
+    /// Retrieves a key like `get_last`, returning `default` if it's not set. Use
+    /// [StoreGetOrDefault] to set the default once for the whole store.
+    fn get_last_or<K: AsRef<str>>(&self, key: K, default: T) -> T {
+        self.get_last(key).unwrap_or(default)
+    }
+
+    /// Retrieves a key like `get_last`, returning `T::default()` (zero for numeric stores)
+    /// if it's not set.
+    fn get_last_or_default<K: AsRef<str>>(&self, key: K) -> T
+    where
+        T: Default,
+    {
+        self.get_last(key).unwrap_or_default()
+    }
+
+    /// Retrieves many keys at once like `get_last`, values are returned in the order
+    /// of `keys`. Stores backed by the host perform a single batched host call.
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<T>> {
+        keys.into_iter().map(|key| self.get_last(key)).collect()
+    }
+
+    /// Retrieves many keys at once like `get_at`, values are returned in the order
+    /// of `keys`. Stores backed by the host perform a single batched host call.
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<T>> {
+        let ord: Ordinal = ord.into();
+        keys.into_iter().map(|key| self.get_at(ord, key)).collect()
+    }
+
+    /// Retrieves a key like `get_last` with a single host call, telling a missing key apart
+    /// from a value that can't be decoded instead of panicking, so joins don't need a
+    /// `has_last` call before each `get_last`.
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<T> {
+        self.get_last(key).into()
+    }
+
+    /// Checks many keys at once like `has_last`, bit `i` telling whether `keys[i]` exists.
+    /// Stores backed by the host perform a single batched host call.
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        keys.into_iter().map(|key| self.has_last(key)).collect()
+    }
+}
+
+/// The value of a key read with [StoreGet::get_last_checked].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GetResult<T> {
+    Missing,
+    Present(T),
+    /// The key exists but its value can't be decoded, with the decoding error.
+    DecodeError(String),
+}
+
+impl<T> GetResult<T> {
+    pub fn is_missing(&self) -> bool {
+        matches!(self, GetResult::Missing)
+    }
+
+    pub fn is_present(&self) -> bool {
+        matches!(self, GetResult::Present(_))
+    }
+
+    /// The value if present, `None` if it's missing or can't be decoded.
+    pub fn present(self) -> Option<T> {
+        match self {
+            GetResult::Present(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value, `None` if it's missing, or the decoding error.
+    pub fn into_result(self) -> Result<Option<T>, String> {
+        match self {
+            GetResult::Missing => Ok(None),
+            GetResult::Present(value) => Ok(Some(value)),
+            GetResult::DecodeError(err) => Err(err),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for GetResult<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => GetResult::Present(value),
+            None => GetResult::Missing,
+        }
+    }
+}
+
+/// Decodes the value read by [StoreGet::get_last_checked] with `decode`.
+fn checked_value<V, E, F>(bytes: Option<Vec<u8>>, decode: F) -> GetResult<V>
+where
+    E: std::fmt::Display,
+    F: FnOnce(&[u8]) -> Result<V, E>,
+{
+    match bytes.map(|bytes| decode(&bytes)) {
+        None => GetResult::Missing,
+        Some(Ok(value)) => GetResult::Present(value),
+        Some(Err(err)) => GetResult::DecodeError(err.to_string()),
+    }
+}
+
+/// Checks the existence of `keys` in the store `idx` with a single batched host call.
+fn exists_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(idx: u32, keys: I) -> BitVec {
+    let keys: Vec<K> = keys.into_iter().collect();
+    state::get_many_last(idx, &keys)
+        .iter()
+        .map(Option::is_some)
+        .collect()
 }
 
 /// RawStoreGet is a struct representing a read only store `store`
@@ -879,8 +2475,8 @@ impl StoreGet<Vec<u8>> for StoreGetRaw {
     /// the output section of the manifest. The ordinal is used here
     /// to go query a key that might have changed mid-block by
     /// the store module that built it.
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<Vec<u8>> {
-        state::get_at(self.idx, ord as i64, key)
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<Vec<u8>> {
+        state::get_at(self.idx, ord, key)
     }
 
     /// Retrieves a key from the store, like `get_at`, but querying the state of
@@ -902,8 +2498,8 @@ impl StoreGet<Vec<u8>> for StoreGetRaw {
     /// Checks if a key exists in the store. The ordinal is used here
     /// to check if a key that might have changed mid-block by
     /// the store module that built it exists.
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.idx, ord, key)
     }
 
     /// Checks if a key exists in the store, like `has_at`, but querying the state of
@@ -921,6 +2517,88 @@ impl StoreGet<Vec<u8>> for StoreGetRaw {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.idx, key)
     }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<Vec<u8>>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.idx, &keys)
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<Vec<u8>>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.idx, ord, &keys)
+    }
+}
+
+impl StoreGetRaw {
+    /// Same as `get_last` but reading the value into `buf`, replacing its content and reusing
+    /// its allocation across calls. Returns the length of the value, `None` if the key is
+    /// not found.
+    pub fn get_last_into<K: AsRef<str>>(&self, key: K, buf: &mut Vec<u8>) -> Option<usize> {
+        state::get_last_into(self.idx, key, buf)
+    }
+
+    /// Iterates over the keys starting with `prefix` and their values, in lexicographical
+    /// order, as of the last state of the store. Entries are fetched lazily from the host by
+    /// pages of [PREFIX_ITER_PAGE_SIZE].
+    pub fn prefix_iter<P: Into<String>>(&self, prefix: P) -> PrefixIter {
+        PrefixIter::new(self.idx, prefix.into())
+    }
+}
+
+/// Number of entries fetched per host call by [StoreGetRaw::prefix_iter].
+pub const PREFIX_ITER_PAGE_SIZE: u32 = 100;
+
+/// Iterator over the entries of a store under a prefix, see [StoreGetRaw::prefix_iter].
+pub struct PrefixIter {
+    idx: u32,
+    prefix: String,
+    page: std::vec::IntoIter<(String, Vec<u8>)>,
+    last_key: Option<String>,
+    done: bool,
+}
+
+impl PrefixIter {
+    fn new(idx: u32, prefix: String) -> Self {
+        PrefixIter {
+            idx,
+            prefix,
+            page: Vec::new().into_iter(),
+            last_key: None,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for PrefixIter {
+    type Item = (String, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.len() == 0 && !self.done {
+            let page = state::scan_prefix(
+                self.idx,
+                &self.prefix,
+                self.last_key.as_deref(),
+                PREFIX_ITER_PAGE_SIZE,
+            );
+            self.done = page.len() < PREFIX_ITER_PAGE_SIZE as usize;
+            self.page = page.into_iter();
+        }
+
+        let entry = self.page.next()?;
+        self.last_key = Some(entry.0.clone());
+        Some(entry)
+    }
 }
 
 /// StoreGetString is as struct representing a read only store `store`
@@ -928,15 +2606,30 @@ pub struct StoreGetString {
     idx: u32,
 }
 
+impl StoreGetString {
+    /// Same as [StoreGetRaw::prefix_iter] with values decoded as `String`.
+    pub fn prefix_iter<P: Into<String>>(
+        &self,
+        prefix: P,
+    ) -> impl Iterator<Item = (String, String)> {
+        PrefixIter::new(self.idx, prefix.into()).map(|(key, bytes)| {
+            let value = String::from_utf8(bytes).unwrap_or_else(|_| {
+                panic!("Invalid UTF-8 sequence in store value for key: {}", key)
+            });
+            (key, value)
+        })
+    }
+}
+
 impl StoreGet<String> for StoreGetString {
     fn new(idx: u32) -> Self {
         StoreGetString { idx }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<String> {
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<String> {
         let key_ref = key.as_ref();
 
-        state::get_at(self.idx, ord as i64, key_ref).map(|bytes| {
+        state::get_at(self.idx, ord, key_ref).map(|bytes| {
             String::from_utf8(bytes).unwrap_or_else(|_| {
                 panic!("Invalid UTF-8 sequence in store value for key: {}", key_ref)
             })
@@ -963,8 +2656,8 @@ impl StoreGet<String> for StoreGetString {
         })
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.idx, ord, key)
     }
 
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
@@ -974,6 +2667,59 @@ impl StoreGet<String> for StoreGetString {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.idx, key)
     }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<String> {
+        checked_value(state::get_last(self.idx, key), |bytes| {
+            str::from_utf8(bytes).map(String::from)
+        })
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<String>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.idx, &keys)
+            .into_iter()
+            .zip(keys.iter())
+            .map(|(bytes, key)| {
+                bytes.map(|bytes| {
+                    String::from_utf8(bytes).unwrap_or_else(|_| {
+                        panic!(
+                            "Invalid UTF-8 sequence in store value for key: {}",
+                            key.as_ref()
+                        )
+                    })
+                })
+            })
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<String>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.idx, ord, &keys)
+            .into_iter()
+            .zip(keys.iter())
+            .map(|(bytes, key)| {
+                bytes.map(|bytes| {
+                    String::from_utf8(bytes).unwrap_or_else(|_| {
+                        panic!(
+                            "Invalid UTF-8 sequence in store value for key: {}",
+                            key.as_ref()
+                        )
+                    })
+                })
+            })
+            .collect()
+    }
 }
 
 pub struct StoreGetInt64(StoreGetRaw);
@@ -984,8 +2730,8 @@ impl StoreGet<i64> for StoreGetInt64 {
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<i64> {
-        state::get_at(self.0.idx, ord as i64, key)
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<i64> {
+        state::get_at(self.0.idx, ord, key)
             .as_ref()
             .map(decode_bytes_to_i64)
     }
@@ -1002,8 +2748,8 @@ impl StoreGet<i64> for StoreGetInt64 {
             .map(decode_bytes_to_i64)
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.0.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
     }
 
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
@@ -1013,6 +2759,34 @@ impl StoreGet<i64> for StoreGetInt64 {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.0.idx, key)
     }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<i64> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_i64)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<i64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_i64))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<i64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_i64))
+            .collect()
+    }
 }
 
 pub struct StoreGetFloat64(StoreGetRaw);
@@ -1023,8 +2797,8 @@ impl StoreGet<f64> for StoreGetFloat64 {
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<f64> {
-        state::get_at(self.0.idx, ord as i64, key)
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<f64> {
+        state::get_at(self.0.idx, ord, key)
             .as_ref()
             .map(decode_bytes_to_f64)
     }
@@ -1041,8 +2815,203 @@ impl StoreGet<f64> for StoreGetFloat64 {
             .map(decode_bytes_to_f64)
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.0.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
+    }
+
+    fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_last(self.0.idx, key)
+    }
+
+    fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_first(self.0.idx, key)
+    }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<f64> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_f64)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<f64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_f64))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<f64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_f64))
+            .collect()
+    }
+}
+
+pub struct StoreGetInt32(StoreGetRaw);
+impl StoreGet<i32> for StoreGetInt32 {
+    fn new(idx: u32) -> Self {
+        Self(StoreGetRaw { idx })
+    }
+
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<i32> {
+        state::get_at(self.0.idx, ord, key)
+            .as_ref()
+            .map(decode_bytes_to_i32)
+    }
+
+    fn get_last<K: AsRef<str>>(&self, key: K) -> Option<i32> {
+        state::get_last(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_i32)
+    }
+
+    fn get_first<K: AsRef<str>>(&self, key: K) -> Option<i32> {
+        state::get_first(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_i32)
+    }
+
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
+    }
+
+    fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_last(self.0.idx, key)
+    }
+
+    fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_first(self.0.idx, key)
+    }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<i32> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_i32)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<i32>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_i32))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<i32>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_i32))
+            .collect()
+    }
+}
+
+pub struct StoreGetUint64(StoreGetRaw);
+impl StoreGet<u64> for StoreGetUint64 {
+    fn new(idx: u32) -> Self {
+        Self(StoreGetRaw { idx })
+    }
+
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<u64> {
+        state::get_at(self.0.idx, ord, key)
+            .as_ref()
+            .map(decode_bytes_to_u64)
+    }
+
+    fn get_last<K: AsRef<str>>(&self, key: K) -> Option<u64> {
+        state::get_last(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_u64)
+    }
+
+    fn get_first<K: AsRef<str>>(&self, key: K) -> Option<u64> {
+        state::get_first(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_u64)
+    }
+
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
+    }
+
+    fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_last(self.0.idx, key)
+    }
+
+    fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_first(self.0.idx, key)
+    }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<u64> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_u64)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<u64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_u64))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<u64>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_u64))
+            .collect()
+    }
+}
+
+pub struct StoreGetBool(StoreGetRaw);
+impl StoreGet<bool> for StoreGetBool {
+    fn new(idx: u32) -> Self {
+        Self(StoreGetRaw { idx })
+    }
+
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<bool> {
+        state::get_at(self.0.idx, ord, key)
+            .as_ref()
+            .map(decode_bytes_to_bool)
+    }
+
+    fn get_last<K: AsRef<str>>(&self, key: K) -> Option<bool> {
+        state::get_last(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_bool)
+    }
+
+    fn get_first<K: AsRef<str>>(&self, key: K) -> Option<bool> {
+        state::get_first(self.0.idx, key)
+            .as_ref()
+            .map(decode_bytes_to_bool)
+    }
+
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
     }
 
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
@@ -1052,6 +3021,33 @@ impl StoreGet<f64> for StoreGetFloat64 {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.0.idx, key)
     }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<bool>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_bool))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<bool>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.as_ref().map(decode_bytes_to_bool))
+            .collect()
+    }
 }
 
 pub struct StoreGetBigDecimal(StoreGetRaw);
@@ -1062,8 +3058,8 @@ impl StoreGet<BigDecimal> for StoreGetBigDecimal {
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<BigDecimal> {
-        state::get_at(self.0.idx, ord as i64, key).map(|bytes| BigDecimal::from_store_bytes(&bytes))
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<BigDecimal> {
+        state::get_at(self.0.idx, ord, key).map(|bytes| BigDecimal::from_store_bytes(&bytes))
     }
 
     fn get_last<K: AsRef<str>>(&self, key: K) -> Option<BigDecimal> {
@@ -1074,8 +3070,8 @@ impl StoreGet<BigDecimal> for StoreGetBigDecimal {
         state::get_first(self.0.idx, key).map(|bytes| BigDecimal::from_store_bytes(&bytes))
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.0.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
     }
 
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
@@ -1085,9 +3081,52 @@ impl StoreGet<BigDecimal> for StoreGetBigDecimal {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.0.idx, key)
     }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<BigDecimal> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_bigdecimal)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<BigDecimal>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.map(|bytes| BigDecimal::from_store_bytes(&bytes)))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<BigDecimal>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.map(|bytes| BigDecimal::from_store_bytes(&bytes)))
+            .collect()
+    }
 }
 
 pub struct StoreGetBigInt(StoreGetRaw);
+impl StoreGetBigInt {
+    /// Same as [StoreGetRaw::prefix_iter] with values decoded as [BigInt].
+    pub fn prefix_iter<P: Into<String>>(
+        &self,
+        prefix: P,
+    ) -> impl Iterator<Item = (String, BigInt)> {
+        self.0
+            .prefix_iter(prefix)
+            .map(|(key, bytes)| (key, BigInt::from_store_bytes(&bytes)))
+    }
+}
+
 impl StoreGet<BigInt> for StoreGetBigInt {
     fn new(idx: u32) -> Self {
         Self {
@@ -1095,8 +3134,8 @@ impl StoreGet<BigInt> for StoreGetBigInt {
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<BigInt> {
-        state::get_at(self.0.idx, ord as i64, key).map(|bytes| BigInt::from_store_bytes(&bytes))
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<BigInt> {
+        state::get_at(self.0.idx, ord, key).map(|bytes| BigInt::from_store_bytes(&bytes))
     }
 
     fn get_last<K: AsRef<str>>(&self, key: K) -> Option<BigInt> {
@@ -1107,8 +3146,8 @@ impl StoreGet<BigInt> for StoreGetBigInt {
         state::get_first(self.0.idx, key).map(|bytes| BigInt::from_store_bytes(&bytes))
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
-        state::has_at(self.0.idx, ord as i64, key)
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        state::has_at(self.0.idx, ord, key)
     }
 
     fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
@@ -1118,15 +3157,48 @@ impl StoreGet<BigInt> for StoreGetBigInt {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         state::has_first(self.0.idx, key)
     }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<BigInt> {
+        checked_value(state::get_last(self.0.idx, key), value::parse_bigint)
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.0.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<BigInt>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.0.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.map(|bytes| BigInt::from_store_bytes(&bytes)))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<BigInt>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.0.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.map(|bytes| BigInt::from_store_bytes(&bytes)))
+            .collect()
+    }
 }
 
+/// StoreGetArray reads the elements of an `append` store, decoded with the [AppendCodec] `C`
+/// the store was written with.
 #[allow(dead_code)]
-pub struct StoreGetArray<T> {
+pub struct StoreGetArray<T, C = StringCodec> {
     store: StoreGetRaw,
-    casper: PhantomData<T>,
+    casper: PhantomData<(T, C)>,
 }
 
-impl<T: Into<String> + From<String>> StoreGet<Vec<T>> for StoreGetArray<T> {
+impl<T, C: AppendCodec<T>> StoreGet<Vec<T>> for StoreGetArray<T, C> {
     fn new(idx: u32) -> Self {
         Self {
             store: StoreGetRaw { idx },
@@ -1134,19 +3206,19 @@ impl<T: Into<String> + From<String>> StoreGet<Vec<T>> for StoreGetArray<T> {
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<Vec<T>> {
-        self.store.get_at(ord, key).and_then(split_array)
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<Vec<T>> {
+        self.store.get_at(ord, key).and_then(C::decode)
     }
 
     fn get_last<K: AsRef<str>>(&self, key: K) -> Option<Vec<T>> {
-        self.store.get_last(key).and_then(split_array)
+        self.store.get_last(key).and_then(C::decode)
     }
 
     fn get_first<K: AsRef<str>>(&self, key: K) -> Option<Vec<T>> {
-        self.store.get_first(key).and_then(split_array)
+        self.store.get_first(key).and_then(C::decode)
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
         self.store.has_at(ord, key)
     }
 
@@ -1157,8 +3229,41 @@ impl<T: Into<String> + From<String>> StoreGet<Vec<T>> for StoreGetArray<T> {
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         self.store.has_first(key)
     }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.store.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> Vec<Option<Vec<T>>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.store.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(C::decode))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<Vec<T>>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.store.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(C::decode))
+            .collect()
+    }
 }
 
+/// StoreGetProtoArray reads the messages of a [StoreAppendProto] store.
+pub type StoreGetProtoArray<T> = StoreGetArray<T, ProtoCodec>;
+
+/// StoreGetBytesArray reads the elements of a [StoreAppendBytes] store.
+pub type StoreGetBytesArray = StoreGetArray<Vec<u8>, LengthPrefixedCodec>;
+
 fn split_array<T: Into<String> + From<String>>(bytes: Vec<u8>) -> Option<Vec<T>> {
     let parts = std::io::Cursor::new(bytes).split(b';');
     let chunks: Vec<_> = parts
@@ -1183,16 +3288,60 @@ pub struct StoreGetProto<T> {
     casper: PhantomData<T>,
 }
 
-impl<T: Default + prost::Message> StoreGetProto<T> {
+impl<T: Codec> StoreGetProto<T> {
     pub fn must_get_last<K: AsRef<str>>(&self, key: K) -> T {
         self.get_last(&key)
             .unwrap_or_else(|| panic!("cannot get_last value: key {} not found", key.as_ref()))
     }
+
+    /// Same as [StoreGetRaw::prefix_iter] with values decoded as `T`, entries that cannot
+    /// be decoded are skipped like with `get_last`.
+    pub fn prefix_iter<P: Into<String>>(&self, prefix: P) -> impl Iterator<Item = (String, T)> {
+        self.store.prefix_iter(prefix).filter_map(|(key, bytes)| {
+            compression::decode::<T>(&bytes)
+                .ok()
+                .map(|value| (key, value))
+        })
+    }
+}
+
+impl<T: Codec + Clone> StoreGetProto<T> {
+    /// Same as `get_last` with the decoded value kept in `cache`, so later lookups of `key`
+    /// during the block don't read and decode it again. Missing keys are cached too, the
+    /// cache must only be used with this store.
+    pub fn get_last_cached<K: AsRef<str>>(
+        &self,
+        cache: &BlockCache<String, Option<T>>,
+        key: K,
+    ) -> Option<T> {
+        if let Some(value) = cache.get(key.as_ref()) {
+            return value;
+        }
+
+        cache.get_or_insert_with(key.as_ref().to_string(), || self.get_last(&key))
+    }
+}
+
+impl<T: Codec + prost::Message> StoreGetProto<T> {
+    /// Same as `get_last` but reading the value through `buf` and decoding it in place into
+    /// `msg`, so hot paths can reuse both the buffer and the message allocations across calls.
+    /// `msg` is cleared first, returns `false` if the key is not found or cannot be decoded.
+    pub fn get_last_into<K: AsRef<str>>(&self, key: K, buf: &mut Vec<u8>, msg: &mut T) -> bool {
+        msg.clear();
+        if self.store.get_last_into(key, buf).is_none() {
+            return false;
+        }
+
+        match compression::decompress(buf) {
+            Ok(bytes) => msg.merge(&*bytes).is_ok(),
+            Err(_) => false,
+        }
+    }
 }
 
 impl<T> StoreGet<T> for StoreGetProto<T>
 where
-    T: Default + prost::Message,
+    T: Codec,
 {
     /// Return a StoreGet object with a store index set
     fn new(idx: u32) -> StoreGetProto<T> {
@@ -1202,25 +3351,25 @@ where
         }
     }
 
-    fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<T> {
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<T> {
         self.store
             .get_at(ord, key)
-            .and_then(|bytes| proto::decode::<T>(&bytes).ok())
+            .and_then(|bytes| compression::decode::<T>(&bytes).ok())
     }
 
     fn get_last<K: AsRef<str>>(&self, key: K) -> Option<T> {
         self.store
             .get_last(key)
-            .and_then(|bytes| proto::decode::<T>(&bytes).ok())
+            .and_then(|bytes| compression::decode::<T>(&bytes).ok())
     }
 
     fn get_first<K: AsRef<str>>(&self, key: K) -> Option<T> {
         self.store
             .get_first(key)
-            .and_then(|bytes| proto::decode::<T>(&bytes).ok())
+            .and_then(|bytes| compression::decode::<T>(&bytes).ok())
     }
 
-    fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
         self.store.has_at(ord, key)
     }
 
@@ -1231,11 +3380,42 @@ where
     fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
         self.store.has_first(key)
     }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<T> {
+        checked_value(
+            state::get_last(self.store.idx, key),
+            compression::decode::<T>,
+        )
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        exists_many_last(self.store.idx, keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<T>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_last(self.store.idx, &keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(|bytes| compression::decode::<T>(&bytes).ok()))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<T>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        state::get_many_at(self.store.idx, ord, &keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(|bytes| compression::decode::<T>(&bytes).ok()))
+            .collect()
+    }
 }
 
 pub trait Delta: PartialEq {
     fn get_key(&self) -> &String;
-    fn get_operation(&self) -> pb::substreams::store_delta::Operation;
+    fn get_operation(&self) -> DeltaOperation;
 }
 
 pub trait DeltaExt: Iterator {
@@ -1245,72 +3425,198 @@ pub trait DeltaExt: Iterator {
         Self::Item: Delta,
         Self: Sized,
     {
-        key::SegmentAtEq::new(value, Some(index), self)
+        key::SegmentAtEq::new(value, Some(index), self)
+    }
+
+    /// Equivalent to `filter(|x| first_segment(x.get_key(), index) == value)`.
+    fn key_first_segment_eq<S: AsRef<str>>(self, value: S) -> key::SegmentAtEq<Self, S>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::SegmentAtEq::new(value, Some(0), self)
+    }
+
+    /// Equivalent to `filter(|x| last_segment(x.get_key(), index) == value)`.
+    fn key_last_segment_eq<S: AsRef<str>>(self, value: S) -> key::SegmentAtEq<Self, S>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::SegmentAtEq::new(value, None, self)
+    }
+
+    /// Equivalent to `filter(|x| values.contains(first_segment(x.get_key(), index)))`.
+    fn key_first_segment_in<S: AsRef<str>, V: AsRef<[S]>>(
+        self,
+        values: V,
+    ) -> key::SegmentAtIn<Self, S, V>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::SegmentAtIn::new(values, Some(0), self)
+    }
+
+    /// Equivalent to `filter(|x| values.contains(last_segment(x.get_key(), index)))`.
+    fn key_last_segment_in<S: AsRef<str>, V: AsRef<[S]>>(
+        self,
+        values: V,
+    ) -> key::SegmentAtIn<Self, S, V>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::SegmentAtIn::new(values, None, self)
+    }
+
+    /// Equivalent to `filter(|x| x.get_key().starts_with(prefix))`.
+    fn key_starts_with<S: AsRef<str>>(self, prefix: S) -> key::KeyStartsWith<Self, S>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::KeyStartsWith::new(prefix, self)
+    }
+
+    /// Equivalent to `filter(|x| x.get_key().ends_with(suffix))`.
+    fn key_ends_with<S: AsRef<str>>(self, suffix: S) -> key::KeyEndsWith<Self, S>
+    where
+        Self::Item: Delta,
+        Self: Sized,
+    {
+        key::KeyEndsWith::new(suffix, self)
     }
 
-    /// Equivalent to `filter(|x| first_segment(x.get_key(), index) == value)`.
-    fn key_first_segment_eq<S: AsRef<str>>(self, value: S) -> key::SegmentAtEq<Self, S>
+    /// Equivalent to `filter(|x| regex.is_match(x.get_key()))`, `regex` can be owned or
+    /// borrowed so it's compiled once and reused across blocks. Requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    fn key_matches<R: std::borrow::Borrow<regex::Regex>>(self, regex: R) -> key::KeyMatches<Self, R>
     where
         Self::Item: Delta,
         Self: Sized,
     {
-        key::SegmentAtEq::new(value, Some(0), self)
+        key::KeyMatches::new(regex, self)
     }
 
-    /// Equivalent to `filter(|x| last_segment(x.get_key(), index) == value)`.
-    fn key_last_segment_eq<S: AsRef<str>>(self, value: S) -> key::SegmentAtEq<Self, S>
+    /// Equivalent to `filter(|x| x.get_operation() == operation)`.
+    fn operation_eq<O: Into<DeltaOperation>>(self, operation: O) -> operation::OperationIs<Self>
     where
         Self::Item: Delta,
         Self: Sized,
     {
-        key::SegmentAtEq::new(value, None, self)
+        operation::OperationIs::new(operation.into(), false, self)
     }
 
-    /// Equivalent to `filter(|x| values.contains(first_segment(x.get_key(), index)))`.
-    fn key_first_segment_in<S: AsRef<str>, V: AsRef<[S]>>(
+    /// Equivalent to `filter(|x| x.get_operation() == DeltaOperation::Create &&
+    /// first_segment(x.get_key()) == prefix)`, the entities registered under `prefix`, for
+    /// example by [StoreSetIfNotExistsProto::create_if_absent].
+    fn operation_is_create_for_prefix<S: AsRef<str>>(
         self,
-        values: V,
-    ) -> key::SegmentAtIn<Self, S, V>
+        prefix: S,
+    ) -> key::SegmentAtEq<operation::OperationIs<Self>, S>
     where
         Self::Item: Delta,
         Self: Sized,
     {
-        key::SegmentAtIn::new(values, Some(0), self)
+        self.operation_eq(DeltaOperation::Create)
+            .key_first_segment_eq(prefix)
     }
 
-    /// Equivalent to `filter(|x| values.contains(last_segment(x.get_key(), index)))`.
-    fn key_last_segment_in<S: AsRef<str>, V: AsRef<[S]>>(
-        self,
-        values: V,
-    ) -> key::SegmentAtIn<Self, S, V>
+    /// Equivalent to `filter(|x| x.get_operation() != operation)`.
+    fn operation_not_eq<O: Into<DeltaOperation>>(self, operation: O) -> operation::OperationIs<Self>
     where
         Self::Item: Delta,
         Self: Sized,
     {
-        key::SegmentAtIn::new(values, None, self)
+        operation::OperationIs::new(operation.into(), true, self)
     }
 
-    /// Equivalent to `filter(|x| x.get_operation() == operation)`.
-    fn operation_eq(self, operation: Operation) -> operation::OperationIs<Self>
+    /// Equivalent to `filter(|x| x.new_value != x.old_value)`, for [NumericDelta] items.
+    fn value_changed(self) -> change::ValueChange<Self>
     where
-        Self::Item: Delta,
+        Self::Item: NumericDelta,
         Self: Sized,
     {
-        operation::OperationIs::new(operation, false, self)
+        change::ValueChange::new(change::Change::Changed, self)
     }
 
-    /// Equivalent to `filter(|x| x.get_operation() != operation)`.
-    fn operation_not_eq(self, operation: Operation) -> operation::OperationIs<Self>
+    /// Equivalent to `filter(|x| x.new_value > x.old_value)`, for [NumericDelta] items. A
+    /// created key increases when its value is positive, its old value being zero.
+    fn value_increased(self) -> change::ValueChange<Self>
     where
-        Self::Item: Delta,
+        Self::Item: NumericDelta,
         Self: Sized,
     {
-        operation::OperationIs::new(operation, true, self)
+        change::ValueChange::new(change::Change::Increased, self)
+    }
+
+    /// Equivalent to `filter(|x| x.new_value < x.old_value)`, for [NumericDelta] items. A
+    /// deleted key decreases when its value was positive, its new value being zero.
+    fn value_decreased(self) -> change::ValueChange<Self>
+    where
+        Self::Item: NumericDelta,
+        Self: Sized,
+    {
+        change::ValueChange::new(change::Change::Decreased, self)
     }
 }
 
 impl<I: Iterator> DeltaExt for I {}
 
+/// Deltas of numeric stores, giving access to their old and new values for the
+/// [DeltaExt::value_changed], [DeltaExt::value_increased] and [DeltaExt::value_decreased]
+/// filters.
+pub trait NumericDelta: Delta {
+    type Value: PartialOrd;
+
+    fn old_value(&self) -> &Self::Value;
+    fn new_value(&self) -> &Self::Value;
+}
+
+macro_rules! impl_numeric_delta {
+    ($($name:ty => $value:ty),+ $(,)?) => {
+        $(
+            impl NumericDelta for $name {
+                type Value = $value;
+
+                fn old_value(&self) -> &$value {
+                    &self.old_value
+                }
+
+                fn new_value(&self) -> &$value {
+                    &self.new_value
+                }
+            }
+
+            impl NumericDelta for &$name {
+                type Value = $value;
+
+                fn old_value(&self) -> &$value {
+                    &self.old_value
+                }
+
+                fn new_value(&self) -> &$value {
+                    &self.new_value
+                }
+            }
+        )+
+    };
+}
+
+impl_numeric_delta!(
+    DeltaBigDecimal => BigDecimal,
+    DeltaBigInt => BigInt,
+    DeltaInt32 => i32,
+    DeltaInt64 => i64,
+    DeltaUint64 => u64,
+    DeltaFloat64 => f64,
+    DeltaSetSumInt64 => i64,
+    DeltaSetSumFloat64 => f64,
+    DeltaSetSumBigInt => BigInt,
+    DeltaSetSumBigDecimal => BigDecimal,
+);
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Deltas<T: Delta> {
     pub deltas: Vec<T>,
@@ -1318,25 +3624,225 @@ pub struct Deltas<T: Delta> {
 
 impl<T: Delta + From<StoreDelta>> Deltas<T> {
     pub fn new(store_deltas: Vec<StoreDelta>) -> Self {
+        guardrails::on_deltas(store_deltas.iter().map(|delta| delta.key.as_str()));
+
         Deltas {
             deltas: store_deltas.into_iter().map(Into::into).collect(),
         }
     }
 
+    /// Same as [Deltas::new] keeping only the deltas matching `predicate`, the others are
+    /// never decoded.
+    pub fn filtered<I, F>(store_deltas: I, mut predicate: F) -> Self
+    where
+        I: IntoIterator<Item = StoreDelta>,
+        F: FnMut(&StoreDelta) -> bool,
+    {
+        Self::new(
+            store_deltas
+                .into_iter()
+                .filter(|delta| predicate(delta))
+                .collect(),
+        )
+    }
+
     /// Shortcut for `self.deltas.iter()`.
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.deltas.iter()
     }
 
-    /// Shortcut for `self.deltas.into_iter()`.
-    pub fn into_iter(self) -> impl Iterator<Item = T> {
+    /// Groups the deltas by the segment at `index` of their key, deltas of a group keep the
+    /// order in which they were received, which is their ordinal order.
+    ///
+    /// Panics if a key has no segment at `index`, like [key::segment_at].
+    pub fn group_by_segment(&self, index: usize) -> HashMap<String, Vec<&T>> {
+        let mut groups: HashMap<String, Vec<&T>> = HashMap::new();
+        for delta in self.deltas.iter() {
+            let segment = key::segment_at(delta.get_key(), index);
+            groups.entry(segment.to_string()).or_default().push(delta);
+        }
+        groups
+    }
+
+    /// Same as [Deltas::group_by_segment] but groups are returned in the order of their first
+    /// delta, so entities are processed in ordinal order as well.
+    pub fn group_by_segment_ordered(&self, index: usize) -> Vec<(String, Vec<&T>)> {
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        let mut groups: Vec<(String, Vec<&T>)> = Vec::new();
+        for delta in self.deltas.iter() {
+            let segment = key::segment_at(delta.get_key(), index);
+            match positions.get(segment) {
+                Some(position) => groups[*position].1.push(delta),
+                None => {
+                    positions.insert(segment, groups.len());
+                    groups.push((segment.to_string(), vec![delta]));
+                }
+            }
+        }
+        groups
+    }
+}
+
+impl<T: Delta + TryFromStoreDelta> Deltas<T> {
+    /// Decodes each delta like [Deltas::new] but returns the ones with invalid values as
+    /// errors instead of panicking, in the order of `store_deltas`.
+    pub fn try_decode(store_deltas: Vec<StoreDelta>) -> Vec<Result<T, DeltaDecodeError>> {
+        guardrails::on_deltas(store_deltas.iter().map(|delta| delta.key.as_str()));
+
+        store_deltas.into_iter().map(T::try_from_delta).collect()
+    }
+
+    /// Same as [Deltas::new] but skips the deltas with invalid values instead of panicking,
+    /// each skipped delta is logged with its key:
+    ///
+    /// ```rust
+    /// use substreams::pb::substreams::StoreDelta;
+    /// use substreams::store::{DeltaString, Deltas};
+    ///
+    /// let deltas = Deltas::<DeltaString>::new_lossy(vec![
+    ///     StoreDelta { key: "a".into(), new_value: b"valid".to_vec(), ..Default::default() },
+    ///     StoreDelta { key: "b".into(), new_value: vec![0xff], ..Default::default() },
+    /// ]);
+    /// assert_eq!(deltas.deltas.len(), 1);
+    /// ```
+    pub fn new_lossy(store_deltas: Vec<StoreDelta>) -> Self {
+        Self::try_decode(store_deltas)
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Ok(delta) => Some(delta),
+                Err(err) => {
+                    crate::log::info!("skipping {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// A [StoreDelta] with an old or new value that couldn't be decoded, see
+/// [Deltas::try_decode].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeltaDecodeError {
+    pub key: String,
+    pub ordinal: u64,
+    pub message: String,
+}
+
+impl std::fmt::Display for DeltaDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "delta {:?} at ordinal {}: {}",
+            self.key, self.ordinal, self.message
+        )
+    }
+}
+
+impl std::error::Error for DeltaDecodeError {}
+
+/// Decodes a [StoreDelta] like the `From<StoreDelta>` conversion of the delta type, returning
+/// an error instead of panicking when a value is invalid.
+pub trait TryFromStoreDelta: Sized {
+    fn try_from_delta(delta: StoreDelta) -> Result<Self, DeltaDecodeError>;
+}
+
+/// Decodes the old and new values of `delta` with `decode`.
+fn try_decode_values<V, E, F>(delta: &StoreDelta, decode: F) -> Result<(V, V), DeltaDecodeError>
+where
+    E: std::fmt::Display,
+    F: Fn(&[u8]) -> Result<V, E>,
+{
+    let error = |which: &str, err: E| DeltaDecodeError {
+        key: delta.key.clone(),
+        ordinal: delta.ordinal,
+        message: format!("invalid {} value: {}", which, err),
+    };
+
+    let old_value = decode(&delta.old_value).map_err(|err| error("old", err))?;
+    let new_value = decode(&delta.new_value).map_err(|err| error("new", err))?;
+    Ok((old_value, new_value))
+}
+
+macro_rules! impl_try_from_store_delta {
+    ($($delta:ty => $decode:expr),+ $(,)?) => {
+        $(
+            impl TryFromStoreDelta for $delta {
+                fn try_from_delta(d: StoreDelta) -> Result<Self, DeltaDecodeError> {
+                    let (old_value, new_value) = try_decode_values(&d, $decode)?;
+
+                    Ok(Self {
+                        operation: DeltaOperation::from(d.operation),
+                        ordinal: d.ordinal,
+                        key: d.key,
+                        old_value,
+                        new_value,
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_try_from_store_delta!(
+    DeltaString => |bytes: &[u8]| str::from_utf8(bytes).map(String::from),
+    DeltaInt32 => value::parse_i32,
+    DeltaInt64 => value::parse_i64,
+    DeltaUint64 => value::parse_u64,
+    DeltaFloat64 => value::parse_f64,
+    DeltaBigInt => value::parse_bigint,
+    DeltaBigDecimal => value::parse_bigdecimal,
+);
+
+impl<T: Codec + PartialEq> TryFromStoreDelta for DeltaProto<T> {
+    fn try_from_delta(d: StoreDelta) -> Result<Self, DeltaDecodeError> {
+        let (old_value, new_value) = try_decode_values(&d, compression::decode::<T>)?;
+
+        Ok(Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value,
+            new_value,
+        })
+    }
+}
+
+impl<T: Delta + From<StoreDelta>> FromIterator<StoreDelta> for Deltas<T> {
+    fn from_iter<I: IntoIterator<Item = StoreDelta>>(store_deltas: I) -> Self {
+        Self::new(store_deltas.into_iter().collect())
+    }
+}
+
+/// Collects decoded deltas, so filtered or mapped deltas round-trip through `collect()`.
+impl<T: Delta> FromIterator<T> for Deltas<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(deltas: I) -> Self {
+        Deltas {
+            deltas: deltas.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: Delta> IntoIterator for Deltas<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
         self.deltas.into_iter()
     }
 }
 
+impl<'a, T: Delta> IntoIterator for &'a Deltas<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.deltas.iter()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaBigDecimal {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: BigDecimal,
@@ -1346,7 +3852,7 @@ pub struct DeltaBigDecimal {
 impl From<StoreDelta> for DeltaBigDecimal {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: BigDecimal::from_store_bytes(&d.old_value),
@@ -1357,7 +3863,7 @@ impl From<StoreDelta> for DeltaBigDecimal {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaBigInt {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: BigInt,
@@ -1367,7 +3873,7 @@ pub struct DeltaBigInt {
 impl From<StoreDelta> for DeltaBigInt {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: BigInt::from_store_bytes(&d.old_value),
@@ -1378,7 +3884,7 @@ impl From<StoreDelta> for DeltaBigInt {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaInt32 {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: i32,
@@ -1388,7 +3894,7 @@ pub struct DeltaInt32 {
 impl From<StoreDelta> for DeltaInt32 {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: decode_bytes_to_i32(&d.old_value),
@@ -1399,7 +3905,7 @@ impl From<StoreDelta> for DeltaInt32 {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaInt64 {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: i64,
@@ -1409,7 +3915,7 @@ pub struct DeltaInt64 {
 impl From<StoreDelta> for DeltaInt64 {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: decode_bytes_to_i64(&d.old_value),
@@ -1418,9 +3924,30 @@ impl From<StoreDelta> for DeltaInt64 {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaUint64 {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: u64,
+    pub new_value: u64,
+}
+
+impl From<StoreDelta> for DeltaUint64 {
+    fn from(d: StoreDelta) -> Self {
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: decode_bytes_to_u64(&d.old_value),
+            new_value: decode_bytes_to_u64(&d.new_value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaFloat64 {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: f64,
@@ -1430,7 +3957,7 @@ pub struct DeltaFloat64 {
 impl From<StoreDelta> for DeltaFloat64 {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: decode_bytes_to_f64(&d.old_value),
@@ -1439,9 +3966,25 @@ impl From<StoreDelta> for DeltaFloat64 {
     }
 }
 
+impl DeltaFloat64 {
+    /// Decodes `d` like [DeltaFloat64::from] but returns an error instead of panicking when
+    /// one of its values isn't a valid float64.
+    pub fn try_from_delta(d: StoreDelta) -> Result<Self, crate::errors::Error> {
+        Ok(Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            old_value: value::parse_f64(&d.old_value)
+                .map_err(|e| e.context(format!("decoding old value of key {}", d.key)))?,
+            new_value: value::parse_f64(&d.new_value)
+                .map_err(|e| e.context(format!("decoding new value of key {}", d.key)))?,
+            key: d.key,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaBool {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: bool,
@@ -1451,18 +3994,27 @@ pub struct DeltaBool {
 impl From<StoreDelta> for DeltaBool {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
-            old_value: !d.old_value.contains(&0),
-            new_value: !d.new_value.contains(&0),
+            old_value: decode_bytes_to_bool(&d.old_value),
+            new_value: decode_bytes_to_bool(&d.new_value),
         }
     }
 }
 
+/// Deltas of a `StoreSetOnceRaw` store, only ever of [DeltaOperation::Create] as keys can't be overwritten.
+pub type DeltaSetOnceRaw = DeltaBytes;
+/// Deltas of a `StoreSetOnceString` store, see [DeltaSetOnceRaw].
+pub type DeltaSetOnceString = DeltaString;
+/// Deltas of a `StoreSetOnceBigInt` store, see [DeltaSetOnceRaw].
+pub type DeltaSetOnceBigInt = DeltaBigInt;
+/// Deltas of a `StoreSetOnceProto` store, see [DeltaSetOnceRaw].
+pub type DeltaSetOnceProto<T> = DeltaProto<T>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaBytes {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: Vec<u8>,
@@ -1472,7 +4024,7 @@ pub struct DeltaBytes {
 impl From<StoreDelta> for DeltaBytes {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: d.old_value,
@@ -1483,7 +4035,7 @@ impl From<StoreDelta> for DeltaBytes {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaString {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: String,
@@ -1493,7 +4045,7 @@ pub struct DeltaString {
 impl From<StoreDelta> for DeltaString {
     fn from(d: StoreDelta) -> Self {
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: String::from_utf8(d.old_value).unwrap_or_else(|_| {
@@ -1508,22 +4060,22 @@ impl From<StoreDelta> for DeltaString {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeltaProto<T> {
-    pub operation: pb::substreams::store_delta::Operation,
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: T,
     pub new_value: T,
 }
 
-impl<T: Default + prost::Message + PartialEq> From<StoreDelta> for DeltaProto<T> {
+impl<T: Codec + PartialEq> From<StoreDelta> for DeltaProto<T> {
     fn from(d: StoreDelta) -> Self {
-        let nv: T = prost::Message::decode(d.new_value.as_ref())
+        let nv = compression::decode::<T>(d.new_value.as_ref())
             .unwrap_or_else(|_| panic!("Unable to decode Store DeltaProto for new value"));
-        let ov: T = prost::Message::decode(d.old_value.as_ref())
+        let ov = compression::decode::<T>(d.old_value.as_ref())
             .unwrap_or_else(|_| panic!("Unable to decode Store DeltaProto for old value"));
 
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: ov,
@@ -1532,63 +4084,373 @@ impl<T: Default + prost::Message + PartialEq> From<StoreDelta> for DeltaProto<T>
     }
 }
 
-impl<T: Default + prost::Message + PartialEq> Delta for DeltaProto<T> {
+impl<T: Codec + PartialEq> Delta for DeltaProto<T> {
     fn get_key(&self) -> &String {
         &self.key
     }
-    fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+    fn get_operation(&self) -> DeltaOperation {
         return self.operation;
     }
 }
 
-impl<T: Default + prost::Message + PartialEq> Delta for &DeltaProto<T> {
+impl<T: Codec + PartialEq> Delta for &DeltaProto<T> {
     fn get_key(&self) -> &String {
         &self.key
     }
-    fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+    fn get_operation(&self) -> DeltaOperation {
         return self.operation;
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct DeltaArray<T> {
-    pub operation: pb::substreams::store_delta::Operation,
+/// Same as [DeltaProto] but decoding the old and new values on first access, consumers
+/// only reading `new_value` never pay for decoding `old_value`.
+///
+/// ```rust
+/// use substreams::store::{Deltas, LazyDeltaProto};
+/// # mod pb {
+/// #   #[derive(Clone, PartialEq, ::prost::Message)]
+/// #   pub struct Pool { #[prost(string, tag = "1")] pub name: String }
+/// # }
+///
+/// fn db_out(pools: Deltas<LazyDeltaProto<pb::Pool>>) {
+///     for delta in pools.iter() {
+///         let name = &delta.new_value().name;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazyDeltaProto<T> {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    raw_old_value: Vec<u8>,
+    raw_new_value: Vec<u8>,
+    old_value: OnceCell<T>,
+    new_value: OnceCell<T>,
+}
+
+impl<T: Codec> LazyDeltaProto<T> {
+    /// Decodes the old value on first call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the old value cannot be decoded as `T`.
+    pub fn old_value(&self) -> &T {
+        self.old_value.get_or_init(|| {
+            compression::decode::<T>(&self.raw_old_value)
+                .unwrap_or_else(|_| panic!("Unable to decode Store DeltaProto for old value"))
+        })
+    }
+
+    /// Decodes the new value on first call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new value cannot be decoded as `T`.
+    pub fn new_value(&self) -> &T {
+        self.new_value.get_or_init(|| {
+            compression::decode::<T>(&self.raw_new_value)
+                .unwrap_or_else(|_| panic!("Unable to decode Store DeltaProto for new value"))
+        })
+    }
+
+    pub fn raw_old_value(&self) -> &[u8] {
+        &self.raw_old_value
+    }
+
+    pub fn raw_new_value(&self) -> &[u8] {
+        &self.raw_new_value
+    }
+}
+
+/// Deltas are equal when their raw values are, whether they were decoded or not.
+impl<T> PartialEq for LazyDeltaProto<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.operation == other.operation
+            && self.ordinal == other.ordinal
+            && self.key == other.key
+            && self.raw_old_value == other.raw_old_value
+            && self.raw_new_value == other.raw_new_value
+    }
+}
+
+impl<T: Codec> From<StoreDelta> for LazyDeltaProto<T> {
+    fn from(d: StoreDelta) -> Self {
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            raw_old_value: d.old_value,
+            raw_new_value: d.new_value,
+            old_value: OnceCell::new(),
+            new_value: OnceCell::new(),
+        }
+    }
+}
+
+impl<T: Codec> From<LazyDeltaProto<T>> for DeltaProto<T> {
+    fn from(d: LazyDeltaProto<T>) -> Self {
+        d.new_value();
+        d.old_value();
+
+        Self {
+            operation: d.operation,
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: d.old_value.into_inner().expect("old value decoded above"),
+            new_value: d.new_value.into_inner().expect("new value decoded above"),
+        }
+    }
+}
+
+impl<T: Codec> Delta for LazyDeltaProto<T> {
+    fn get_key(&self) -> &String {
+        &self.key
+    }
+    fn get_operation(&self) -> DeltaOperation {
+        self.operation
+    }
+}
+
+impl<T: Codec> Delta for &LazyDeltaProto<T> {
+    fn get_key(&self) -> &String {
+        &self.key
+    }
+    fn get_operation(&self) -> DeltaOperation {
+        self.operation
+    }
+}
+
+/// DeltaArray is a delta of an `append` store, decoded with the [AppendCodec] `C` the store
+/// was written with.
+#[derive(Debug, Clone)]
+pub struct DeltaArray<T, C = StringCodec> {
+    pub operation: DeltaOperation,
     pub ordinal: u64,
     pub key: String,
     pub old_value: Vec<T>,
     pub new_value: Vec<T>,
+    casper: PhantomData<C>,
+}
+
+impl<T: PartialEq, C> PartialEq for DeltaArray<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.operation == other.operation
+            && self.ordinal == other.ordinal
+            && self.key == other.key
+            && self.old_value == other.old_value
+            && self.new_value == other.new_value
+    }
 }
 
-impl<T: Into<String> + From<String> + PartialEq> From<StoreDelta> for DeltaArray<T> {
+impl<T: PartialEq, C: AppendCodec<T>> From<StoreDelta> for DeltaArray<T, C> {
     fn from(d: StoreDelta) -> Self {
-        let old = split_array::<T>(d.old_value).unwrap_or_default();
-        let new = split_array::<T>(d.new_value).unwrap_or_default();
+        let old = C::decode(d.old_value).unwrap_or_default();
+        let new = C::decode(d.new_value).unwrap_or_default();
 
         Self {
-            operation: convert_i32_to_operation(d.operation),
+            operation: DeltaOperation::from(d.operation),
             ordinal: d.ordinal,
             key: d.key,
             old_value: old,
             new_value: new,
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<T: PartialEq, C> DeltaArray<T, C> {
+    /// Whether the previous elements aren't a prefix of the new ones, the key was deleted (and
+    /// possibly appended again) instead of only receiving new elements.
+    pub fn is_reset(&self) -> bool {
+        !self.new_value.starts_with(&self.old_value)
+    }
+
+    /// The elements added by this delta: the suffix of `new_value` after `old_value`, or all of
+    /// `new_value` when the delta [is a reset](DeltaArray::is_reset).
+    pub fn appended(&self) -> &[T] {
+        match self.is_reset() {
+            true => &self.new_value,
+            false => &self.new_value[self.old_value.len()..],
         }
     }
 }
 
-impl<T: Into<String> + From<String> + PartialEq> Delta for DeltaArray<T> {
+impl<T: PartialEq, C: AppendCodec<T>> Deltas<DeltaArray<T, C>> {
+    /// The elements appended by the deltas as `(key, element)` pairs, in delta order, see
+    /// [DeltaArray::appended].
+    pub fn appended(&self) -> impl Iterator<Item = (&str, &T)> + '_ {
+        self.deltas.iter().flat_map(|delta| {
+            delta
+                .appended()
+                .iter()
+                .map(move |element| (delta.key.as_str(), element))
+        })
+    }
+
+    /// The keys and elements appended to them, like [Deltas::appended] grouped per delta and
+    /// skipping the deltas appending nothing.
+    pub fn appended_by_key(&self) -> impl Iterator<Item = (&str, &[T])> + '_ {
+        self.deltas
+            .iter()
+            .map(|delta| (delta.key.as_str(), delta.appended()))
+            .filter(|(_, appended)| !appended.is_empty())
+    }
+}
+
+impl<T: PartialEq, C: AppendCodec<T>> Delta for DeltaArray<T, C> {
+    fn get_key(&self) -> &String {
+        &self.key
+    }
+    fn get_operation(&self) -> DeltaOperation {
+        return self.operation;
+    }
+}
+
+impl<T: PartialEq, C: AppendCodec<T>> Delta for &DeltaArray<T, C> {
     fn get_key(&self) -> &String {
         &self.key
     }
-    fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+    fn get_operation(&self) -> DeltaOperation {
         return self.operation;
     }
 }
 
-impl<T: Into<String> + From<String> + PartialEq> Delta for &DeltaArray<T> {
-    fn get_key(&self) -> &String {
-        &self.key
-    }
-    fn get_operation(&self) -> pb::substreams::store_delta::Operation {
-        return self.operation;
+/// DeltaProtoArray is a delta of a [StoreAppendProto] store.
+pub type DeltaProtoArray<T> = DeltaArray<T, ProtoCodec>;
+
+/// DeltaBytesArray is a delta of a [StoreAppendBytes] store.
+pub type DeltaBytesArray = DeltaArray<Vec<u8>, LengthPrefixedCodec>;
+
+/// Policy of a value written by a `StoreSetSum*` store, either replacing the previous value
+/// or accumulated into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetSumPolicy {
+    Set,
+    Sum,
+}
+
+/// Splits a `set:<value>` or `sum:<value>` store value, empty bytes have no policy.
+fn split_set_sum(bytes: &[u8]) -> (Option<SetSumPolicy>, &[u8]) {
+    if bytes.is_empty() {
+        return (None, bytes);
+    }
+
+    match bytes.split_at(bytes.len().min(4)) {
+        (b"set:", value) => (Some(SetSumPolicy::Set), value),
+        (b"sum:", value) => (Some(SetSumPolicy::Sum), value),
+        _ => panic!(
+            "Invalid set_sum store value '{}', expected 'set:' or 'sum:' prefix",
+            String::from_utf8_lossy(bytes)
+        ),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaSetSumInt64 {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: i64,
+    pub new_value: i64,
+    /// Policy of `new_value`, `None` when the key was deleted.
+    pub policy: Option<SetSumPolicy>,
+}
+
+impl From<StoreDelta> for DeltaSetSumInt64 {
+    fn from(d: StoreDelta) -> Self {
+        let (_, old_value) = split_set_sum(&d.old_value);
+        let (policy, new_value) = split_set_sum(&d.new_value);
+
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: decode_bytes_to_i64(&old_value.to_vec()),
+            new_value: decode_bytes_to_i64(&new_value.to_vec()),
+            policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaSetSumFloat64 {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: f64,
+    pub new_value: f64,
+    /// Policy of `new_value`, `None` when the key was deleted.
+    pub policy: Option<SetSumPolicy>,
+}
+
+impl From<StoreDelta> for DeltaSetSumFloat64 {
+    fn from(d: StoreDelta) -> Self {
+        let (_, old_value) = split_set_sum(&d.old_value);
+        let (policy, new_value) = split_set_sum(&d.new_value);
+
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: decode_bytes_to_f64(&old_value.to_vec()),
+            new_value: decode_bytes_to_f64(&new_value.to_vec()),
+            policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaSetSumBigInt {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: BigInt,
+    pub new_value: BigInt,
+    /// Policy of `new_value`, `None` when the key was deleted.
+    pub policy: Option<SetSumPolicy>,
+}
+
+impl From<StoreDelta> for DeltaSetSumBigInt {
+    fn from(d: StoreDelta) -> Self {
+        let (_, old_value) = split_set_sum(&d.old_value);
+        let (policy, new_value) = split_set_sum(&d.new_value);
+
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: BigInt::from_store_bytes(old_value),
+            new_value: BigInt::from_store_bytes(new_value),
+            policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaSetSumBigDecimal {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: BigDecimal,
+    pub new_value: BigDecimal,
+    /// Policy of `new_value`, `None` when the key was deleted.
+    pub policy: Option<SetSumPolicy>,
+}
+
+impl From<StoreDelta> for DeltaSetSumBigDecimal {
+    fn from(d: StoreDelta) -> Self {
+        let (_, old_value) = split_set_sum(&d.old_value);
+        let (policy, new_value) = split_set_sum(&d.new_value);
+
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value: BigDecimal::from_store_bytes(old_value),
+            new_value: BigDecimal::from_store_bytes(new_value),
+            policy,
+        }
     }
 }
 
@@ -1598,7 +4460,7 @@ macro_rules! impl_delta_ref {
             fn get_key(&self) -> &String {
                 &self.key
             }
-            fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+            fn get_operation(&self) -> DeltaOperation {
                 self.operation
             }
         }
@@ -1611,7 +4473,7 @@ macro_rules! impl_delta {
             fn get_key(&self) -> &String {
                 &self.key
             }
-            fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+            fn get_operation(&self) -> DeltaOperation {
                 self.operation
             }
         }
@@ -1619,7 +4481,7 @@ macro_rules! impl_delta {
             pub fn get_key(&self) -> &String {
                 &self.key
             }
-            pub fn get_operation(&self) -> pb::substreams::store_delta::Operation {
+            pub fn get_operation(&self) -> DeltaOperation {
                 self.operation
             }
         }
@@ -1630,82 +4492,225 @@ impl_delta!(DeltaBigDecimal);
 impl_delta!(DeltaBigInt);
 impl_delta!(DeltaInt32);
 impl_delta!(DeltaInt64);
+impl_delta!(DeltaUint64);
 impl_delta!(DeltaFloat64);
 impl_delta!(DeltaBool);
 impl_delta!(DeltaBytes);
 impl_delta!(DeltaString);
+impl_delta!(DeltaSetSumInt64);
+impl_delta!(DeltaSetSumFloat64);
+impl_delta!(DeltaSetSumBigInt);
+impl_delta!(DeltaSetSumBigDecimal);
 
 impl_delta_ref!(&DeltaBigDecimal);
 impl_delta_ref!(&DeltaBigInt);
 impl_delta_ref!(&DeltaInt32);
 impl_delta_ref!(&DeltaInt64);
+impl_delta_ref!(&DeltaUint64);
 impl_delta_ref!(&DeltaFloat64);
 impl_delta_ref!(&DeltaBool);
 impl_delta_ref!(&DeltaBytes);
 impl_delta_ref!(&DeltaString);
+impl_delta_ref!(&DeltaSetSumInt64);
+impl_delta_ref!(&DeltaSetSumFloat64);
+impl_delta_ref!(&DeltaSetSumBigInt);
+impl_delta_ref!(&DeltaSetSumBigDecimal);
+
+/// Conversions of the old and new values of a delta, implemented by every `Delta*` struct:
+///
+/// ```rust
+/// use substreams::scalar::BigDecimal;
+/// use substreams::store::{DeltaBigDecimal, DeltaValues, Deltas};
+///
+/// fn volume_changes(deltas: Deltas<DeltaBigDecimal>) -> Vec<(String, f64)> {
+///     deltas
+///         .into_iter()
+///         .map(|delta| delta.map_values(|value| value.to_string().parse::<f64>().unwrap()))
+///         .map(|delta| (delta.key, delta.new_value - delta.old_value))
+///         .collect()
+/// }
+///
+/// fn last_change(delta: DeltaBigDecimal) -> BigDecimal {
+///     let (old, new): (BigDecimal, BigDecimal) = delta.into_values();
+///     new - old
+/// }
+/// ```
+pub trait DeltaValues<V>: Delta + Sized {
+    /// The delta as a [MappedDelta], dropping the fields specific to its type (like the
+    /// `policy` of `DeltaSetSum*` deltas).
+    fn into_mapped(self) -> MappedDelta<V>;
+
+    /// The `(old_value, new_value)` of the delta.
+    fn into_values(self) -> (V, V) {
+        let delta = self.into_mapped();
+        (delta.old_value, delta.new_value)
+    }
+
+    /// The delta with `f` applied to its old and new values, in this order.
+    fn map_values<U, F: FnMut(V) -> U>(self, mut f: F) -> MappedDelta<U> {
+        let delta = self.into_mapped();
+        MappedDelta {
+            operation: delta.operation,
+            ordinal: delta.ordinal,
+            key: delta.key,
+            old_value: f(delta.old_value),
+            new_value: f(delta.new_value),
+        }
+    }
+}
 
-fn convert_i32_to_operation(operation: i32) -> pb::substreams::store_delta::Operation {
-    Operation::from_i32(operation).unwrap_or_else(|| panic!("unhandled operation: {}", operation))
+/// A delta with values of any type, as returned by [DeltaValues::map_values].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedDelta<V> {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: V,
+    pub new_value: V,
 }
 
-// We accept &Vec<u8> instead of &[u8] because use internally and makes it easier to chain
-fn decode_bytes_to_i32(bytes: &Vec<u8>) -> i32 {
-    if bytes.is_empty() {
-        return 0;
+impl<V: PartialEq> Delta for MappedDelta<V> {
+    fn get_key(&self) -> &String {
+        &self.key
+    }
+    fn get_operation(&self) -> DeltaOperation {
+        self.operation
+    }
+}
+
+impl<V: PartialEq> DeltaValues<V> for MappedDelta<V> {
+    fn into_mapped(self) -> MappedDelta<V> {
+        self
     }
+}
 
-    // FIXME: If we are ready to accept the fact that `bytes` is always valid UTF-8, we could even use
-    //        the unsafe `from_utf8_unchecked` version, we would need first to measure the impact and
-    //        better understand implication of an invalid UTF-8 &str with `from_str` call.
-    let int_as_str =
-        std::str::from_utf8(bytes).expect("received bytes expected to be valid UTF-8 string");
+macro_rules! impl_delta_values {
+    ($($name:ty => $value:ty),+ $(,)?) => {
+        $(
+            impl DeltaValues<$value> for $name {
+                fn into_mapped(self) -> MappedDelta<$value> {
+                    MappedDelta {
+                        operation: self.operation,
+                        ordinal: self.ordinal,
+                        key: self.key,
+                        old_value: self.old_value,
+                        new_value: self.new_value,
+                    }
+                }
+            }
+        )+
+    };
+}
 
-    i32::from_str(int_as_str).unwrap_or_else(|_| {
-        panic!(
-            "value {} is not a valid representation of an i32",
-            int_as_str
-        )
-    })
+impl_delta_values!(
+    DeltaBigDecimal => BigDecimal,
+    DeltaBigInt => BigInt,
+    DeltaInt32 => i32,
+    DeltaInt64 => i64,
+    DeltaUint64 => u64,
+    DeltaFloat64 => f64,
+    DeltaBool => bool,
+    DeltaBytes => Vec<u8>,
+    DeltaString => String,
+    DeltaSetSumInt64 => i64,
+    DeltaSetSumFloat64 => f64,
+    DeltaSetSumBigInt => BigInt,
+    DeltaSetSumBigDecimal => BigDecimal,
+);
+
+impl<T: Codec + PartialEq> DeltaValues<T> for DeltaProto<T> {
+    fn into_mapped(self) -> MappedDelta<T> {
+        MappedDelta {
+            operation: self.operation,
+            ordinal: self.ordinal,
+            key: self.key,
+            old_value: self.old_value,
+            new_value: self.new_value,
+        }
+    }
+}
+
+impl<T: PartialEq, C: AppendCodec<T>> DeltaValues<Vec<T>> for DeltaArray<T, C> {
+    fn into_mapped(self) -> MappedDelta<Vec<T>> {
+        MappedDelta {
+            operation: self.operation,
+            ordinal: self.ordinal,
+            key: self.key,
+            old_value: self.old_value,
+            new_value: self.new_value,
+        }
+    }
+}
+
+// We accept &Vec<u8> instead of &[u8] because use internally and makes it easier to chain
+#[allow(clippy::ptr_arg)]
+fn decode_bytes_to_i32(bytes: &Vec<u8>) -> i32 {
+    value::parse_i32(bytes).unwrap_or_else(|e| panic!("{}", e))
 }
 
 // We accept &Vec<u8> instead of &[u8] because use internally and makes it easier to chain
+#[allow(clippy::ptr_arg)]
 fn decode_bytes_to_i64(bytes: &Vec<u8>) -> i64 {
-    if bytes.is_empty() {
-        return 0;
-    }
+    value::parse_i64(bytes).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    // FIXME: If we are ready to accept the fact that `bytes` is always valid UTF-8, we could even use
-    //        the unsafe `from_utf8_unchecked` version, we would need first to measure the impact and
-    //        better understand implication of an invalid UTF-8 &str with `from_str` call.
-    let int_as_str =
-        std::str::from_utf8(bytes).expect("received bytes expected to be valid UTF-8 string");
+// We accept &Vec<u8> instead of &[u8] because use internally and makes it easier to chain
+#[allow(clippy::ptr_arg)]
+fn decode_bytes_to_u64(bytes: &Vec<u8>) -> u64 {
+    value::parse_u64(bytes).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    i64::from_str(int_as_str).unwrap_or_else(|_| {
-        panic!(
-            "value {} is not a valid representation of an i64",
-            int_as_str
-        )
-    })
+#[allow(clippy::ptr_arg)]
+fn decode_bytes_to_bool(bytes: &Vec<u8>) -> bool {
+    value::parse_bool(bytes)
 }
 
 // We accept &Vec<u8> instead of &[u8] because use internally and makes it easier to chain
+#[allow(clippy::ptr_arg)]
 fn decode_bytes_to_f64(bytes: &Vec<u8>) -> f64 {
-    if bytes.is_empty() {
-        return 0.0;
-    }
+    value::parse_f64(bytes).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    // FIXME: If we are ready to accept the fact that `bytes` is always valid UTF-8, we could even use
-    //        the unsafe `from_utf8_unchecked` version, we would need first to measure the impact and
-    //        better understand implication of an invalid UTF-8 &str with `from_str` call.
-    let float64_as_str =
-        std::str::from_utf8(bytes).expect("received bytes expected to be valid UTF-8 string");
+/// Readable stores, `use substreams::store::get;` then `get::StoreGetInt64` to keep store
+/// types out of the importing module's namespace.
+pub mod get {
+    pub use super::{
+        BitVec, GetResult, PrefixIter, StoreGetArray, StoreGetBigDecimal, StoreGetBigInt,
+        StoreGetBool, StoreGetBytesArray, StoreGetEnum, StoreGetFloat64, StoreGetInt32,
+        StoreGetInt64, StoreGetOrDefault, StoreGetProto, StoreGetProtoArray, StoreGetRaw,
+        StoreGetString, StoreGetUint64,
+    };
+}
 
-    f64::from_str(float64_as_str).unwrap_or_else(|_| {
-        panic!(
-            "value {} is not a valid representation of an f64",
-            float64_as_str
-        )
-    })
+/// Writable stores, `use substreams::store::set;` then `set::StoreSetInt64`, see [get].
+pub mod set {
+    pub use super::{
+        StoreAddBigDecimal, StoreAddBigInt, StoreAddFloat64, StoreAddInt64, StoreAppend,
+        StoreAppendBytes, StoreAppendProto, StoreMaxBigDecimal, StoreMaxBigInt, StoreMaxFloat64,
+        StoreMaxInt64, StoreMinBigDecimal, StoreMinBigInt, StoreMinFloat64, StoreMinInt64,
+        StoreSetBigDecimal, StoreSetBigInt, StoreSetBool, StoreSetEnum, StoreSetFloat64,
+        StoreSetIfNotExistsBigDecimal, StoreSetIfNotExistsBigInt, StoreSetIfNotExistsBool,
+        StoreSetIfNotExistsFloat64, StoreSetIfNotExistsInt32, StoreSetIfNotExistsInt64,
+        StoreSetIfNotExistsProto, StoreSetIfNotExistsRaw, StoreSetIfNotExistsString,
+        StoreSetIfNotExistsUint64, StoreSetInt32, StoreSetInt64, StoreSetOnceBigInt,
+        StoreSetOnceProto, StoreSetOnceRaw, StoreSetOnceString, StoreSetProto, StoreSetRaw,
+        StoreSetString, StoreSetSumBigDecimal, StoreSetSumBigInt, StoreSetSumFloat64,
+        StoreSetSumInt64, StoreSetUint64,
+    };
+    #[cfg(feature = "lexicographic-min-max")]
+    pub use super::{StoreMaxBytes, StoreMaxString, StoreMinBytes, StoreMinString};
+}
+
+/// Store deltas, `use substreams::store::delta;` then `delta::DeltaInt64`, see [get].
+pub mod delta {
+    pub use super::{
+        DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaBool, DeltaBytes, DeltaBytesArray,
+        DeltaDecodeError, DeltaEnum, DeltaFloat64, DeltaInt32, DeltaInt64, DeltaOperation,
+        DeltaProto, DeltaProtoArray, DeltaSetOnceBigInt, DeltaSetOnceProto, DeltaSetOnceRaw,
+        DeltaSetOnceString, DeltaSetSumBigDecimal, DeltaSetSumBigInt, DeltaSetSumFloat64,
+        DeltaSetSumInt64, DeltaString, DeltaUint64, DeltaValues, Deltas, LazyDeltaProto,
+        MappedDelta, TryFromStoreDelta,
+    };
 }
 
 #[cfg(test)]
@@ -1714,9 +4719,10 @@ mod tests {
         pb::substreams::{store_delta::Operation, StoreDelta},
         store::{
             decode_bytes_to_f64, decode_bytes_to_i32, decode_bytes_to_i64, split_array, DeltaArray,
-            Deltas,
+            DeltaOperation, DeltaSetSumBigInt, Deltas, SetSumPolicy,
         },
     };
+    use std::marker::PhantomData;
 
     #[test]
     fn valid_int64_decode_bytes_to_i32() {
@@ -1806,17 +4812,90 @@ mod tests {
         assert_eq!(
             Deltas::<DeltaArray<String>> {
                 deltas: vec![DeltaArray::<String> {
-                    operation: Operation::Create,
+                    operation: DeltaOperation::Create,
                     ordinal: 0,
                     key: "".to_string(),
                     old_value: vec![],
-                    new_value: vec!["1.1".to_string(), "2.2".to_string(), "3.3".to_string(),]
+                    new_value: vec!["1.1".to_string(), "2.2".to_string(), "3.3".to_string(),],
+                    casper: PhantomData,
                 }]
             },
             deltas
         );
     }
 
+    #[test]
+    fn delta_values_conversions() {
+        use crate::store::{DeltaInt64, DeltaSetSumInt64, DeltaValues, MappedDelta};
+
+        let delta = DeltaInt64 {
+            operation: DeltaOperation::Update,
+            ordinal: 3,
+            key: "volume".to_string(),
+            old_value: 2,
+            new_value: 5,
+        };
+        assert_eq!(delta.clone().into_values(), (2, 5));
+        assert_eq!(
+            delta.map_values(|v| v.to_string()),
+            MappedDelta {
+                operation: DeltaOperation::Update,
+                ordinal: 3,
+                key: "volume".to_string(),
+                old_value: "2".to_string(),
+                new_value: "5".to_string(),
+            }
+        );
+
+        let sum = DeltaSetSumInt64 {
+            operation: DeltaOperation::Create,
+            ordinal: 0,
+            key: "sum".to_string(),
+            old_value: 0,
+            new_value: 10,
+            policy: Some(SetSumPolicy::Sum),
+        };
+        let (old, new) = sum.map_values(|v| v * 2).into_values();
+        assert_eq!((old, new), (0, 20));
+    }
+
+    #[test]
+    fn delta_array_appended() {
+        let delta = |key: &str, old: &str, new: &str| StoreDelta {
+            operation: 2,
+            ordinal: 0,
+            key: key.to_string(),
+            old_value: old.as_bytes().to_vec(),
+            new_value: new.as_bytes().to_vec(),
+        };
+        let deltas = Deltas::<DeltaArray<String>>::new(vec![
+            delta("a", "1;", "1;2;3;"),
+            delta("b", "1;2;", "4;"),
+            delta("c", "5;", "5;"),
+        ]);
+
+        assert!(!deltas.deltas[0].is_reset());
+        assert_eq!(deltas.deltas[0].appended(), ["2", "3"]);
+        assert!(deltas.deltas[1].is_reset());
+        assert_eq!(deltas.deltas[1].appended(), ["4"]);
+        assert!(deltas.deltas[2].appended().is_empty());
+
+        assert_eq!(
+            deltas
+                .appended()
+                .map(|(key, element)| format!("{}={}", key, element))
+                .collect::<Vec<_>>(),
+            vec!["a=2", "a=3", "b=4"]
+        );
+        assert_eq!(
+            deltas
+                .appended_by_key()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+    }
+
     #[test]
     fn split_arrays_no_elements() {
         let value = "";
@@ -1849,4 +4928,744 @@ mod tests {
 
         assert_eq!(expected_value, actual_value)
     }
+
+    #[test]
+    fn delta_set_sum_decodes_policy() {
+        let deltas = Deltas::<DeltaSetSumBigInt>::new(vec![
+            StoreDelta {
+                operation: Operation::Create as i32,
+                ordinal: 1,
+                key: "a".to_string(),
+                old_value: vec![],
+                new_value: "sum:10".as_bytes().to_vec(),
+            },
+            StoreDelta {
+                operation: Operation::Update as i32,
+                ordinal: 2,
+                key: "a".to_string(),
+                old_value: "sum:10".as_bytes().to_vec(),
+                new_value: "set:-3".as_bytes().to_vec(),
+            },
+        ]);
+
+        let summary: Vec<_> = deltas
+            .iter()
+            .map(|d| (d.old_value.to_string(), d.new_value.to_string(), d.policy))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("0".to_string(), "10".to_string(), Some(SetSumPolicy::Sum)),
+                ("10".to_string(), "-3".to_string(), Some(SetSumPolicy::Set)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reads_last_value_into_buffer() {
+        use crate::prelude::*;
+        use prost::Message;
+        use prost_types::Timestamp;
+
+        crate::testing::reset();
+        crate::testing::set_store_value(0, "raw", "abc");
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 2,
+        };
+        crate::testing::set_store_value(0, "proto", timestamp.encode_to_vec());
+
+        let mut buf = Vec::with_capacity(16);
+        let raw = StoreGetRaw::new(0);
+        assert_eq!(raw.get_last_into("raw", &mut buf), Some(3));
+        assert_eq!(buf, b"abc");
+        assert_eq!(raw.get_last_into("missing", &mut buf), None);
+        assert!(buf.is_empty());
+
+        let mut msg = Timestamp {
+            seconds: 1,
+            nanos: 1,
+        };
+        let protos = StoreGetProto::<Timestamp>::new(0);
+        assert!(protos.get_last_into("proto", &mut buf, &mut msg));
+        assert_eq!(msg, timestamp);
+        assert!(!protos.get_last_into("missing", &mut buf, &mut msg));
+    }
+
+    #[test]
+    fn it_checks_existence_and_decoding_in_one_read() {
+        use crate::prelude::*;
+        use crate::store::GetResult;
+        use prost_types::Timestamp;
+
+        crate::testing::reset();
+        crate::testing::set_store_value(0, "count", "42");
+        crate::testing::set_store_value(0, "invalid", "4.2");
+        crate::testing::set_store_value(0, "proto", vec![0xff]);
+
+        let counts = StoreGetInt64::new(0);
+        assert_eq!(counts.get_last_checked("count"), GetResult::Present(42));
+        assert!(counts.get_last_checked("missing").is_missing());
+        assert_eq!(
+            counts.get_last_checked("invalid"),
+            GetResult::DecodeError("value 4.2 is not a valid representation of an i64".to_string())
+        );
+
+        let protos = StoreGetProto::<Timestamp>::new(0);
+        assert!(protos.get_last_checked("proto").into_result().is_err());
+        assert_eq!(protos.get_last_checked("missing").into_result(), Ok(None));
+
+        let exists = counts.exists_many(vec!["count", "missing", "proto", "other"]);
+        assert_eq!(exists.len(), 4);
+        assert_eq!(exists.iter_ones().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn it_filters_deltas_by_key_affixes() {
+        use crate::store::{DeltaExt, DeltaInt64};
+
+        let deltas = Deltas::<DeltaInt64>::new(
+            [
+                "pool:0xab:volume",
+                "pool:0xab:fees",
+                "pool:0xcd:volume",
+                "token:0xab",
+            ]
+            .iter()
+            .map(|key| StoreDelta {
+                operation: Operation::Create as i32,
+                ordinal: 1,
+                key: key.to_string(),
+                old_value: vec![],
+                new_value: "1".as_bytes().to_vec(),
+            })
+            .collect(),
+        );
+
+        let keys = |iter: &mut dyn Iterator<Item = &DeltaInt64>| -> Vec<String> {
+            iter.map(|d| d.key.clone()).collect()
+        };
+
+        assert_eq!(
+            keys(&mut deltas.iter().key_starts_with("pool:0xab")),
+            vec!["pool:0xab:volume", "pool:0xab:fees"]
+        );
+        assert_eq!(
+            keys(&mut deltas.iter().key_ends_with(":volume")),
+            vec!["pool:0xab:volume", "pool:0xcd:volume"]
+        );
+
+        #[cfg(feature = "regex")]
+        {
+            let regex = regex::Regex::new("^(pool|token):0xab").unwrap();
+            assert_eq!(
+                keys(&mut deltas.iter().key_matches(&regex)),
+                vec!["pool:0xab:volume", "pool:0xab:fees", "token:0xab"]
+            );
+        }
+    }
+
+    #[test]
+    fn it_decodes_unknown_operations() {
+        use crate::store::{DeltaExt, DeltaInt64};
+
+        let deltas = Deltas::<DeltaInt64>::new(vec![StoreDelta {
+            operation: 42,
+            ordinal: 1,
+            key: "a".to_string(),
+            old_value: "1".as_bytes().to_vec(),
+            new_value: "2".as_bytes().to_vec(),
+        }]);
+
+        assert_eq!(deltas.deltas[0].operation, DeltaOperation::Unknown(42));
+        assert_eq!(deltas.deltas[0].operation.as_i32(), 42);
+        assert_eq!(deltas.iter().operation_eq(Operation::Update).count(), 0);
+        assert_eq!(deltas.iter().operation_not_eq(Operation::Update).count(), 1);
+        assert_eq!(
+            DeltaOperation::from(Operation::Delete as i32),
+            Operation::Delete
+        );
+    }
+
+    #[test]
+    fn it_builds_deltas_from_iterators() {
+        use crate::store::DeltaInt64;
+
+        let store_deltas = || {
+            (1..=4).map(|i| StoreDelta {
+                operation: Operation::Update as i32,
+                ordinal: i,
+                key: format!("key:{}", i),
+                old_value: vec![],
+                new_value: i.to_string().into_bytes(),
+            })
+        };
+
+        let deltas: Deltas<DeltaInt64> = store_deltas().collect();
+        assert_eq!(deltas.deltas.len(), 4);
+
+        let even = Deltas::<DeltaInt64>::filtered(store_deltas(), |delta| delta.ordinal % 2 == 0);
+        assert_eq!(
+            (&even).into_iter().map(|d| d.new_value).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+
+        let odd: Deltas<DeltaInt64> = deltas
+            .into_iter()
+            .filter(|d| d.new_value % 2 == 1)
+            .collect();
+        assert_eq!(
+            odd.deltas.iter().map(|d| d.ordinal).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+    }
+
+    #[test]
+    fn it_iterates_over_prefix_by_pages() {
+        use crate::prelude::*;
+        use crate::store::PREFIX_ITER_PAGE_SIZE;
+
+        crate::testing::reset();
+        let count = PREFIX_ITER_PAGE_SIZE as usize * 2 + 5;
+        for i in 0..count {
+            crate::testing::set_store_value(0, format!("pool:{:04}", i), i.to_string());
+        }
+        crate::testing::set_store_value(0, "token:0001", "x");
+        crate::testing::set_store_value(0, "pool", "x");
+
+        let keys: Vec<String> = StoreGetRaw::new(0)
+            .prefix_iter("pool:")
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys.len(), count);
+        assert_eq!(keys[0], "pool:0000");
+        assert_eq!(keys[count - 1], format!("pool:{:04}", count - 1));
+
+        let values: Vec<(String, BigInt)> =
+            StoreGetBigInt::new(0).prefix_iter("pool:000").collect();
+        assert_eq!(values.len(), 10);
+        assert_eq!(values[3], ("pool:0003".to_string(), BigInt::from(3)));
+
+        assert_eq!(StoreGetString::new(0).prefix_iter("missing:").count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is already set")]
+    fn it_rejects_second_set_once() {
+        use crate::prelude::*;
+
+        crate::testing::reset();
+        let store = StoreSetOnceBigInt::new();
+        store.set_once(1, "a", &BigInt::from(1));
+        assert_eq!(crate::testing::store_value("a"), Some(b"1".to_vec()));
+
+        store.set_once(2, "a", &BigInt::from(2));
+    }
+
+    #[test]
+    fn it_flushes_batched_writes_on_return() {
+        use crate::prelude::*;
+
+        crate::testing::reset();
+        let store = StoreAddInt64::new();
+        super::batch(|| {
+            store.add(1, "a", 1);
+            super::batch(|| store.add(2, "a", 2));
+            assert_eq!(crate::testing::store_value("a"), None);
+        });
+        assert_eq!(crate::testing::store_value("a"), Some(b"3".to_vec()));
+
+        store.add(3, "a", 1);
+        assert_eq!(crate::testing::store_value("a"), Some(b"4".to_vec()));
+    }
+
+    #[test]
+    fn it_decodes_deltas_lossily() {
+        use super::{DeltaDecodeError, DeltaInt64, DeltaProto, DeltaString, Deltas};
+        use prost_types::Timestamp;
+
+        let store_deltas = || {
+            vec![
+                StoreDelta {
+                    key: "a".to_string(),
+                    ordinal: 1,
+                    new_value: b"1".to_vec(),
+                    ..Default::default()
+                },
+                StoreDelta {
+                    key: "b".to_string(),
+                    ordinal: 2,
+                    old_value: vec![0xff],
+                    new_value: b"2".to_vec(),
+                    ..Default::default()
+                },
+            ]
+        };
+
+        let strings = Deltas::<DeltaString>::try_decode(store_deltas());
+        assert_eq!(strings[0].as_ref().unwrap().new_value, "1");
+        assert_eq!(
+            strings[1].as_ref().unwrap_err().to_string(),
+            "delta \"b\" at ordinal 2: invalid old value: invalid utf-8 sequence of 1 bytes from index 0"
+        );
+
+        let ints = Deltas::<DeltaInt64>::new_lossy(store_deltas());
+        assert_eq!(ints.deltas.len(), 1);
+        assert_eq!(ints.deltas[0].key, "a");
+
+        let timestamps = Deltas::<DeltaProto<Timestamp>>::try_decode(store_deltas());
+        assert!(matches!(
+            &timestamps[1],
+            Err(DeltaDecodeError { key, ordinal: 2, .. }) if key == "b"
+        ));
+    }
+
+    #[test]
+    fn it_reads_binary_int64_values() {
+        use super::{
+            value, DeltaInt64, StoreGet, StoreGetInt64, StoreNew, StoreSet, StoreSetInt64,
+        };
+        use crate::pb::substreams::StoreDelta;
+        use crate::testing;
+
+        testing::reset();
+        StoreSetInt64::new_binary().set(1, "binary", &-42);
+        StoreSetInt64::new().set(1, "decimal", &42);
+        let binary = testing::store_value("binary").unwrap();
+        assert_eq!(binary, value::encode_i64_binary(-42));
+        assert_eq!(testing::store_value("decimal"), Some(b"42".to_vec()));
+
+        testing::set_store_value(1, "binary", binary.clone());
+        testing::set_store_value(1, "decimal", "42");
+        let store = StoreGetInt64::new(1);
+        assert_eq!(store.get_last("binary"), Some(-42));
+        assert_eq!(store.get_last("decimal"), Some(42));
+
+        let delta = DeltaInt64::from(StoreDelta {
+            operation: 2,
+            ordinal: 1,
+            key: "binary".to_string(),
+            old_value: b"7".to_vec(),
+            new_value: binary,
+        });
+        assert_eq!((delta.old_value, delta.new_value), (7, -42));
+    }
+
+    #[test]
+    fn it_writes_to_selected_output_stores() {
+        use crate::prelude::*;
+        use crate::testing;
+
+        testing::reset();
+        let counts: StoreAddInt64 = super::StoreNewOutput::new_output(0);
+        let names: StoreSetString = super::StoreNewOutput::new_output(1);
+
+        counts.add(1, "a", 1);
+        names.set(1, "a", &"first".to_string());
+        super::batch(|| {
+            counts.add(2, "a", 2);
+            names.set(2, "b", &"second".to_string());
+            counts.add(3, "b", 1);
+        });
+
+        assert_eq!(testing::store_value("a"), Some(b"3".to_vec()));
+        assert_eq!(testing::store_value("b"), Some(b"1".to_vec()));
+        assert_eq!(testing::store_value_at(1, "a"), Some(b"first".to_vec()));
+        assert_eq!(testing::output_store_at(1).len(), 2);
+    }
+
+    #[test]
+    fn it_appends_with_element_codecs() {
+        use super::{
+            AppendCodec, Appender, FixedWidthCodec, LengthPrefixedCodec, ProtoCodec, StoreAppend,
+        };
+        use crate::testing;
+        use prost_types::Timestamp;
+
+        testing::reset();
+        let names: StoreAppend<Vec<u8>, LengthPrefixedCodec> = Appender::new();
+        names.append_all(1, "names", vec![b"a;b".to_vec(), vec![], b"c".to_vec()]);
+        let prices: StoreAppend<i64, FixedWidthCodec> = Appender::new();
+        prices.append_all(1, "prices", vec![-1, 256]);
+
+        let names_value = testing::store_value("names").unwrap();
+        assert_eq!(
+            LengthPrefixedCodec::decode(names_value),
+            Some(vec![b"a;b".to_vec(), vec![], b"c".to_vec()])
+        );
+        let prices_value = testing::store_value("prices").unwrap();
+        assert_eq!(prices_value.len(), 16);
+        assert_eq!(
+            FixedWidthCodec::decode(prices_value),
+            Some(vec![-1i64, 256])
+        );
+
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 1,
+        };
+        let mut bytes = Vec::new();
+        ProtoCodec::encode(timestamp, &mut bytes);
+        ProtoCodec::encode(Timestamp::default(), &mut bytes);
+        let delta = DeltaArray::<Timestamp, ProtoCodec>::from(StoreDelta {
+            operation: Operation::Update as i32,
+            ordinal: 1,
+            key: "timestamps".to_string(),
+            old_value: vec![],
+            new_value: bytes,
+        });
+        assert_eq!(delta.old_value, vec![]);
+        assert_eq!(delta.new_value, vec![timestamp, Timestamp::default()]);
+    }
+
+    #[test]
+    fn it_round_trips_proto_arrays() {
+        use super::{DeltaProtoArray, StoreAppendProto, StoreGetProtoArray};
+        use crate::prelude::*;
+        use crate::testing;
+        use prost_types::Timestamp;
+
+        let first = Timestamp {
+            seconds: 10,
+            nanos: 0,
+        };
+        let second = Timestamp {
+            seconds: 20,
+            nanos: 5,
+        };
+
+        testing::reset();
+        let store: StoreAppendProto<Timestamp> = Appender::new();
+        store.append(1, "timestamps", first);
+        store.append(2, "timestamps", second);
+
+        let value = testing::store_value("timestamps").unwrap();
+        testing::set_store_value(0, "timestamps", value.clone());
+        assert_eq!(
+            StoreGetProtoArray::<Timestamp>::new(0).get_last("timestamps"),
+            Some(vec![first, second])
+        );
+
+        let delta = DeltaProtoArray::<Timestamp>::from(StoreDelta {
+            operation: Operation::Create as i32,
+            ordinal: 2,
+            key: "timestamps".to_string(),
+            old_value: vec![],
+            new_value: value,
+        });
+        assert_eq!(delta.new_value, vec![first, second]);
+    }
+
+    #[test]
+    fn it_round_trips_bytes_arrays() {
+        use super::{DeltaBytesArray, StoreAppendBytes, StoreGetBytesArray};
+        use crate::prelude::*;
+        use crate::testing;
+
+        let events = vec![b"\x00;\xff".to_vec(), vec![], vec![b';'; 200]];
+
+        testing::reset();
+        let store: StoreAppendBytes = Appender::new();
+        store.append(1, "events", events[0].clone());
+        store.append_all(2, "events", events[1..].to_vec());
+
+        let value = testing::store_value("events").unwrap();
+        testing::set_store_value(0, "events", value.clone());
+        assert_eq!(
+            StoreGetBytesArray::new(0).get_last("events"),
+            Some(events.clone())
+        );
+
+        let delta = DeltaBytesArray::from(StoreDelta {
+            operation: Operation::Create as i32,
+            ordinal: 2,
+            key: "events".to_string(),
+            old_value: vec![],
+            new_value: value,
+        });
+        assert_eq!(delta.new_value, events);
+    }
+
+    #[test]
+    fn it_caches_proto_lookups_for_the_block() {
+        use crate::cache::BlockCache;
+        use crate::prelude::*;
+        use crate::testing;
+        use prost_types::Timestamp;
+
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 0,
+        };
+
+        testing::reset();
+        testing::set_store_value(0, "a", crate::proto::encode(&timestamp).unwrap());
+        let store = StoreGetProto::<Timestamp>::new(0);
+        let cache = BlockCache::new();
+
+        assert_eq!(store.get_last_cached(&cache, "a"), Some(timestamp));
+        assert_eq!(store.get_last_cached(&cache, "missing"), None);
+
+        testing::set_store_value(0, "a", vec![]);
+        testing::set_store_value(0, "missing", vec![]);
+        assert_eq!(store.get_last_cached(&cache, "a"), Some(timestamp));
+        assert_eq!(store.get_last_cached(&cache, "missing"), None);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn it_filters_deltas_on_value_changes() {
+        use super::{DeltaExt, DeltaInt64};
+        use crate::testing::MockDeltas;
+
+        let deltas = MockDeltas::new()
+            .create("a", "3")
+            .update("b", "5", "5")
+            .update("c", "5", "2")
+            .delete("d", "-1")
+            .build::<DeltaInt64>();
+
+        let keys = |deltas: Vec<&DeltaInt64>| -> Vec<String> {
+            deltas.into_iter().map(|d| d.key.clone()).collect()
+        };
+        assert_eq!(
+            keys(deltas.iter().value_changed().collect()),
+            ["a", "c", "d"]
+        );
+        assert_eq!(keys(deltas.iter().value_increased().collect()), ["a", "d"]);
+        assert_eq!(keys(deltas.iter().value_decreased().collect()), ["c"]);
+
+        let increased: Vec<DeltaInt64> = deltas.into_iter().value_increased().collect();
+        assert_eq!(increased.len(), 2);
+    }
+
+    #[test]
+    fn it_decodes_lazy_delta_proto_on_access() {
+        use prost_types::Timestamp;
+
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 0,
+        };
+        let delta = super::LazyDeltaProto::<Timestamp>::from(StoreDelta {
+            operation: Operation::Update as i32,
+            ordinal: 1,
+            key: "a".to_string(),
+            old_value: vec![0xff],
+            new_value: crate::proto::encode(&timestamp).unwrap(),
+        });
+
+        // The invalid old value is never decoded
+        assert_eq!(delta.new_value(), &timestamp);
+        assert_eq!(delta.new_value(), &timestamp);
+        assert_eq!(delta.raw_old_value(), &[0xff]);
+    }
+
+    #[test]
+    fn it_round_trips_primitive_stores() {
+        use crate::prelude::*;
+
+        crate::testing::reset();
+        StoreSetUint64::new().set(1, "supply", &u64::MAX);
+        StoreSetInt32::new().set(1, "decimals", &-6);
+        StoreSetBool::new().set(1, "paused", &false);
+        assert_eq!(
+            crate::testing::store_value("supply"),
+            Some(u64::MAX.to_string().into_bytes())
+        );
+
+        for key in ["supply", "decimals", "paused"] {
+            crate::testing::set_store_value(0, key, crate::testing::store_value(key).unwrap());
+        }
+        assert_eq!(StoreGetUint64::new(0).get_last("supply"), Some(u64::MAX));
+        assert_eq!(StoreGetInt32::new(0).get_last("decimals"), Some(-6));
+        assert_eq!(StoreGetBool::new(0).get_last("paused"), Some(false));
+
+        let deltas = crate::testing::MockDeltas::new()
+            .update("supply", "1", "2")
+            .build::<DeltaUint64>();
+        assert_eq!(deltas.deltas[0].old_value, 1);
+        assert_eq!(deltas.deltas[0].new_value, 2);
+    }
+
+    #[test]
+    fn it_decodes_missing_bool_delta_values_as_false() {
+        use crate::store::DeltaBool;
+
+        let deltas = crate::testing::MockDeltas::new()
+            .create("paused", vec![1])
+            .delete("halted", vec![1])
+            .build::<DeltaBool>();
+        assert_eq!(deltas.deltas[0].operation, Operation::Create);
+        assert!(!deltas.deltas[0].old_value);
+        assert!(deltas.deltas[0].new_value);
+        assert_eq!(deltas.deltas[1].operation, Operation::Delete);
+        assert!(deltas.deltas[1].old_value);
+        assert!(!deltas.deltas[1].new_value);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid float64 add value NaN, float64 store values must be finite")]
+    fn it_rejects_nan_on_checked_add() {
+        use crate::prelude::*;
+
+        StoreAddFloat64::new().add_checked(1, "a", f64::NAN);
+    }
+
+    #[test]
+    fn it_writes_float64_values_in_canonical_form() {
+        use super::{StoreSetSum, StoreSetSumFloat64};
+        use crate::prelude::*;
+        use crate::testing::store_value;
+
+        crate::testing::reset();
+        StoreSetFloat64::new().set(1, "set", &1e21);
+        StoreSetFloat64::new().set_many(1, &vec!["set_many"], &-0.0);
+        StoreSetIfNotExistsFloat64::new().set_if_not_exists(1, "if_not_exists", &1.5e-7);
+        StoreAddFloat64::new().add(1, "add", 0.1);
+        StoreAddFloat64::new().add(2, "add", 0.2);
+        StoreMaxFloat64::new().max(1, "max", 2.0);
+        StoreMaxFloat64::new().max(2, "max", 1e-5);
+        StoreMinFloat64::new().min(1, "min", -1.25e-5);
+        StoreSetSumFloat64::new().set(1, "set_sum", 100.0);
+        StoreSetSumFloat64::new().sum(2, "set_sum", 1e16);
+
+        let expected: &[(&str, &str)] = &[
+            ("set", "1000000000000000000000"),
+            ("set_many", "0"),
+            ("if_not_exists", "0.00000015"),
+            ("add", "0.30000000000000004"),
+            ("max", "2"),
+            ("min", "-0.0000125"),
+            ("set_sum", "sum:10000000000000100"),
+        ];
+        for (key, value) in expected {
+            assert_eq!(
+                store_value(key).map(String::from_utf8),
+                Some(Ok(value.to_string())),
+                "value of {}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn it_surfaces_float64_delta_decoding_errors() {
+        use crate::store::DeltaFloat64;
+
+        let delta = |new_value: &[u8]| StoreDelta {
+            operation: Operation::Update as i32,
+            ordinal: 1,
+            key: "a".to_string(),
+            old_value: b"1.5".to_vec(),
+            new_value: new_value.to_vec(),
+        };
+
+        let decoded = DeltaFloat64::try_from_delta(delta(b"2.5")).unwrap();
+        assert_eq!((decoded.old_value, decoded.new_value), (1.5, 2.5));
+
+        let err = DeltaFloat64::try_from_delta(delta(b"abc")).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "decoding new value of key a: value abc is not a valid representation of an f64"
+        );
+    }
+
+    #[test]
+    fn it_groups_deltas_by_segment() {
+        use crate::store::DeltaInt64;
+
+        let deltas = Deltas::<DeltaInt64>::new(
+            [
+                "pool:b:volume",
+                "pool:a:volume",
+                "pool:b:fees",
+                "pool:a:fees",
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| StoreDelta {
+                operation: Operation::Create as i32,
+                ordinal: i as u64,
+                key: key.to_string(),
+                old_value: vec![],
+                new_value: i.to_string().into_bytes(),
+            })
+            .collect(),
+        );
+
+        let ordinals =
+            |group: &Vec<&DeltaInt64>| -> Vec<u64> { group.iter().map(|d| d.ordinal).collect() };
+
+        let groups = deltas.group_by_segment(1);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(ordinals(&groups["a"]), vec![1, 3]);
+        assert_eq!(ordinals(&groups["b"]), vec![0, 2]);
+
+        let ordered: Vec<_> = deltas
+            .group_by_segment_ordered(1)
+            .iter()
+            .map(|(segment, group)| (segment.clone(), ordinals(group)))
+            .collect();
+        assert_eq!(
+            ordered,
+            vec![("b".to_string(), vec![0, 2]), ("a".to_string(), vec![1, 3])]
+        );
+    }
+
+    #[test]
+    fn it_creates_entities_if_absent() {
+        use crate::prelude::*;
+        use crate::store::{Created, DeltaExt, DeltaString};
+        use crate::testing;
+        use prost_types::Timestamp;
+
+        let at = |seconds| Timestamp { seconds, nanos: 0 };
+
+        testing::reset();
+        testing::set_store_proto(0, "token:a", &at(1));
+        let known = StoreGetProto::<Timestamp>::new(0);
+        let store = StoreSetIfNotExistsProto::<Timestamp>::new();
+
+        let created = store.create_if_absent(1, "token:a", &known, || unreachable!());
+        assert_eq!(created, Created::Existing(at(1)));
+        assert_eq!(
+            store.create_if_absent(2, "token:b", &known, || at(2)),
+            Created::New(at(2))
+        );
+        assert_eq!(
+            store.create_if_absent(3, "token:b", &known, || at(3)),
+            Created::Existing(at(2))
+        );
+        assert_eq!(testing::store_value("token:a"), None);
+        assert_eq!(
+            testing::store_value("token:b"),
+            Some(crate::proto::encode(&at(2)).unwrap())
+        );
+
+        let deltas = Deltas::<DeltaString>::new(
+            [
+                (Operation::Create, "token:b"),
+                (Operation::Update, "token:a"),
+                (Operation::Create, "tokens:c"),
+                (Operation::Create, "pool:token"),
+            ]
+            .iter()
+            .map(|(operation, key)| StoreDelta {
+                operation: *operation as i32,
+                ordinal: 1,
+                key: key.to_string(),
+                old_value: vec![],
+                new_value: vec![],
+            })
+            .collect(),
+        );
+        let created: Vec<&str> = deltas
+            .iter()
+            .operation_is_create_for_prefix("token")
+            .map(|delta| delta.key.as_str())
+            .collect();
+        assert_eq!(created, vec!["token:b"]);
+    }
 }
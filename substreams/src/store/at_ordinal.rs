@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use super::{
+    AppendCodec, Delta, DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaBool, DeltaBytes,
+    DeltaFloat64, DeltaInt32, DeltaInt64, DeltaOperation, DeltaProto, DeltaSetSumBigDecimal,
+    DeltaSetSumBigInt, DeltaSetSumFloat64, DeltaSetSumInt64, DeltaString, DeltaUint64, Deltas,
+    MappedDelta, Ordinal,
+};
+use crate::codec::Codec;
+use crate::scalar::{BigDecimal, BigInt};
+
+/// The ordinal and values of a delta, read by [Deltas::state_at] and [Deltas::value_at]
+/// to fold the deltas of a block up to an ordinal.
+pub trait OrdinalDelta<V>: Delta {
+    fn get_ordinal(&self) -> u64;
+    fn get_old_value(&self) -> &V;
+    fn get_new_value(&self) -> &V;
+}
+
+macro_rules! impl_ordinal_delta {
+    ($($name:ty => $value:ty),+ $(,)?) => {
+        $(
+            impl OrdinalDelta<$value> for $name {
+                fn get_ordinal(&self) -> u64 {
+                    self.ordinal
+                }
+
+                fn get_old_value(&self) -> &$value {
+                    &self.old_value
+                }
+
+                fn get_new_value(&self) -> &$value {
+                    &self.new_value
+                }
+            }
+        )+
+    };
+}
+
+impl_ordinal_delta!(
+    DeltaBigDecimal => BigDecimal,
+    DeltaBigInt => BigInt,
+    DeltaInt32 => i32,
+    DeltaInt64 => i64,
+    DeltaUint64 => u64,
+    DeltaFloat64 => f64,
+    DeltaBool => bool,
+    DeltaBytes => Vec<u8>,
+    DeltaString => String,
+    DeltaSetSumInt64 => i64,
+    DeltaSetSumFloat64 => f64,
+    DeltaSetSumBigInt => BigInt,
+    DeltaSetSumBigDecimal => BigDecimal,
+);
+
+impl<V: PartialEq> OrdinalDelta<V> for MappedDelta<V> {
+    fn get_ordinal(&self) -> u64 {
+        self.ordinal
+    }
+
+    fn get_old_value(&self) -> &V {
+        &self.old_value
+    }
+
+    fn get_new_value(&self) -> &V {
+        &self.new_value
+    }
+}
+
+impl<T: Codec + PartialEq> OrdinalDelta<T> for DeltaProto<T> {
+    fn get_ordinal(&self) -> u64 {
+        self.ordinal
+    }
+
+    fn get_old_value(&self) -> &T {
+        &self.old_value
+    }
+
+    fn get_new_value(&self) -> &T {
+        &self.new_value
+    }
+}
+
+impl<T: PartialEq, C: AppendCodec<T>> OrdinalDelta<Vec<T>> for DeltaArray<T, C> {
+    fn get_ordinal(&self) -> u64 {
+        self.ordinal
+    }
+
+    fn get_old_value(&self) -> &Vec<T> {
+        &self.old_value
+    }
+
+    fn get_new_value(&self) -> &Vec<T> {
+        &self.new_value
+    }
+}
+
+/// The value of the key of `delta` before it was applied, `None` if it didn't exist yet.
+fn value_before<V, T: OrdinalDelta<V>>(delta: &T) -> Option<&V> {
+    match delta.get_operation() {
+        DeltaOperation::Create => None,
+        _ => Some(delta.get_old_value()),
+    }
+}
+
+/// The value of the key of `delta` once it was applied, `None` if it was deleted.
+fn value_after<V, T: OrdinalDelta<V>>(delta: &T) -> Option<&V> {
+    match delta.get_operation() {
+        DeltaOperation::Delete => None,
+        _ => Some(delta.get_new_value()),
+    }
+}
+
+impl<T: Delta> Deltas<T> {
+    /// The values of the keys written in the block as they were at ordinal `ord`, folding
+    /// the deltas with an ordinal lower than or equal to `ord`, for mid-block joins with the
+    /// output of another module. A key written only after `ord` has its value from before
+    /// the block, keys absent at `ord` (created later or deleted) map to `None`.
+    ///
+    /// Keys the block doesn't write are not part of the state, their value is the one read
+    /// from the store. Raw values are read with `Deltas<DeltaBytes>`:
+    ///
+    /// ```rust
+    /// use substreams::store::{DeltaBigInt, DeltaBytes, Deltas};
+    /// use substreams::scalar::BigInt;
+    ///
+    /// fn reserve_at(reserves: &Deltas<DeltaBigInt>, pool: &str, ord: u64) -> Option<BigInt> {
+    ///     reserves.value_at(pool, ord).flatten().cloned()
+    /// }
+    ///
+    /// fn raw_state(deltas: &Deltas<DeltaBytes>, ord: u64) -> usize {
+    ///     deltas.state_at(ord).values().flatten().map(|value| value.len()).sum()
+    /// }
+    /// ```
+    pub fn state_at<V>(&self, ord: impl Into<Ordinal>) -> HashMap<&str, Option<&V>>
+    where
+        T: OrdinalDelta<V>,
+    {
+        let ord = ord.into().get();
+        let mut state: HashMap<&str, Option<&V>> = HashMap::new();
+        for delta in self.deltas.iter() {
+            let key = delta.get_key().as_str();
+            if delta.get_ordinal() <= ord {
+                state.insert(key, value_after(delta));
+            } else {
+                state.entry(key).or_insert_with(|| value_before(delta));
+            }
+        }
+        state
+    }
+
+    /// The value of `key` at ordinal `ord` as computed by [Deltas::state_at], `None` if the
+    /// block doesn't write `key` and `Some(None)` if it's absent at `ord`.
+    pub fn value_at<V>(&self, key: &str, ord: impl Into<Ordinal>) -> Option<Option<&V>>
+    where
+        T: OrdinalDelta<V>,
+    {
+        let ord = ord.into().get();
+        let mut value = None;
+        for delta in self.deltas.iter().filter(|delta| delta.get_key() == key) {
+            if delta.get_ordinal() > ord {
+                return Some(value.unwrap_or_else(|| value_before(delta)));
+            }
+            value = Some(value_after(delta));
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{DeltaInt64, DeltaString};
+    use crate::testing::MockDeltas;
+
+    #[test]
+    fn it_folds_deltas_up_to_an_ordinal() {
+        let deltas = MockDeltas::new()
+            .update("a", "1", "2")
+            .create("b", "5")
+            .update("a", "2", "3")
+            .delete("c", "7")
+            .build::<DeltaInt64>();
+
+        let state = deltas.state_at(0);
+        assert_eq!(state["a"], Some(&1));
+        assert_eq!(state["b"], None);
+        assert_eq!(state["c"], Some(&7));
+
+        let state = deltas.state_at(3);
+        assert_eq!(state["a"], Some(&3));
+        assert_eq!(state["b"], Some(&5));
+        assert_eq!(state["c"], Some(&7));
+
+        assert_eq!(deltas.state_at(4)["c"], None);
+        assert_eq!(deltas.value_at("a", 1), Some(Some(&2)));
+        assert_eq!(deltas.value_at("c", 4), Some(None));
+        assert_eq!(deltas.value_at("d", 4), None);
+    }
+
+    #[test]
+    fn it_reads_typed_values_at_an_ordinal() {
+        let deltas = MockDeltas::new()
+            .create("token", "dai")
+            .update("token", "dai", "usdc")
+            .build::<DeltaString>();
+
+        assert_eq!(
+            deltas.value_at("token", 1).flatten(),
+            Some(&"dai".to_string())
+        );
+        assert_eq!(deltas.state_at(2)["token"], Some(&"usdc".to_string()));
+    }
+}
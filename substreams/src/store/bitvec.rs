@@ -0,0 +1,103 @@
+use std::iter::FromIterator;
+
+/// A compact vector of bits, one per key of [StoreGet::exists_many](super::StoreGet::exists_many)
+/// telling whether the key exists:
+///
+/// ```rust
+/// use substreams::store::BitVec;
+///
+/// let bits: BitVec = vec![true, false, true].into_iter().collect();
+/// assert_eq!(bits.len(), 3);
+/// assert!(bits.get(0) && !bits.get(1));
+/// assert_eq!(bits.count_ones(), 2);
+/// assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![0, 2]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        BitVec {
+            words: Vec::with_capacity((bits + 63) / 64),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len % 64 == 0 {
+            self.words.push(0);
+        }
+
+        if bit {
+            self.words[self.len / 64] |= 1 << (self.len % 64);
+        }
+        self.len += 1;
+    }
+
+    /// The bit at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> bool {
+        assert!(
+            index < self.len,
+            "bit index {} out of bounds for length {}",
+            index,
+            self.len
+        );
+
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of bits set.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn all(&self) -> bool {
+        self.count_ones() == self.len
+    }
+
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|word| *word != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |index| self.get(index))
+    }
+
+    /// The indexes of the bits set, in order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(move |index| self.get(*index))
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(bits: I) -> Self {
+        let bits = bits.into_iter();
+        let mut bit_vec = BitVec::with_capacity(bits.size_hint().0);
+        for bit in bits {
+            bit_vec.push(bit);
+        }
+        bit_vec
+    }
+}
@@ -0,0 +1,258 @@
+//! Transparent compression of [StoreSetProto](super::StoreSetProto) values.
+//!
+//! A store written with [StoreSetProto::new_with_compression](super::StoreSetProto::new_with_compression)
+//! stores each value compressed in an envelope, [COMPRESSION_MARKER] followed by a byte naming
+//! the algorithm, unless compressing doesn't make it smaller. The marker starts with a `0` byte,
+//! a field number no encoded message starts with: [StoreGetProto](super::StoreGetProto) and the
+//! proto deltas detect compressed values on read, and stores mixing compressed and uncompressed
+//! values, like stores that enabled compression later, are read without configuration.
+//!
+//! Compression pays off for large values written repeatedly, like pool snapshots, and costs
+//! CPU on every write and read: run `cargo bench --bench store_compression --features zstd,lz4`
+//! to compare the algorithms on representative values. Each algorithm is behind a feature,
+//! `zstd` and `lz4`, both pure Rust so they build for `wasm32`.
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+use crate::codec::Codec;
+
+/// Starts the envelope of compressed values, followed by the algorithm byte and the
+/// compressed value.
+pub const COMPRESSION_MARKER: &[u8; 4] = b"\0spc";
+
+/// Algorithm byte of values compressed with [Compression::Zstd].
+const ZSTD: u8 = 1;
+/// Algorithm byte of values compressed with [Compression::Lz4].
+const LZ4: u8 = 2;
+
+/// The compression of the values written by a [StoreSetProto](super::StoreSetProto).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    None,
+    /// Zstandard at its fastest level, the best ratio, requires the `zstd` feature and Rust 1.81+.
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// LZ4, faster to compress and decompress, requires the `lz4` feature.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::None
+    }
+}
+
+impl Compression {
+    /// Compresses the encoded value `bytes` in its envelope, keeping it as is when
+    /// compression doesn't make it smaller.
+    pub fn compress(self, bytes: Vec<u8>) -> Vec<u8> {
+        let compressed: Option<Vec<u8>> = match self {
+            Compression::None => None,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some(envelope(
+                ZSTD,
+                ruzstd::encoding::compress_to_vec(
+                    bytes.as_slice(),
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                ),
+            )),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Some(envelope(
+                LZ4,
+                lz4_flex::block::compress_prepend_size(&bytes),
+            )),
+        };
+
+        match compressed {
+            Some(compressed) if compressed.len() < bytes.len() => compressed,
+            _ => bytes,
+        }
+    }
+}
+
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+fn envelope(algorithm: u8, compressed: Vec<u8>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(COMPRESSION_MARKER.len() + 1 + compressed.len());
+    bytes.extend_from_slice(COMPRESSION_MARKER);
+    bytes.push(algorithm);
+    bytes.extend_from_slice(&compressed);
+    bytes
+}
+
+/// A stored value that can't be decompressed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecompressError {
+    #[error("value is compressed with {0}, enable the `{0}` feature of substreams to read it")]
+    Disabled(&'static str),
+    #[error("invalid {0} compressed value: {1}")]
+    Invalid(&'static str, String),
+    #[error("unknown compression algorithm {0}")]
+    Unknown(u8),
+    #[error("compressed value is missing its algorithm")]
+    Truncated,
+}
+
+/// The encoded value of a stored value, decompressed when it was written compressed and
+/// borrowed otherwise. Sinks reading raw store deltas decode them the same way.
+pub fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>, DecompressError> {
+    let envelope = match bytes.strip_prefix(&COMPRESSION_MARKER[..]) {
+        Some(envelope) => envelope,
+        None => return Ok(Cow::Borrowed(bytes)),
+    };
+
+    match envelope.split_first() {
+        Some((&ZSTD, compressed)) => decompress_zstd(compressed).map(Cow::Owned),
+        Some((&LZ4, compressed)) => decompress_lz4(compressed).map(Cow::Owned),
+        Some((&algorithm, _)) => Err(DecompressError::Unknown(algorithm)),
+        None => Err(DecompressError::Truncated),
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(compressed: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    ruzstd::decoding::StreamingDecoder::new(compressed)
+        .map_err(|e| DecompressError::Invalid("zstd", e.to_string()))?
+        .read_to_end(&mut bytes)
+        .map_err(|e| DecompressError::Invalid("zstd", e.to_string()))?;
+    Ok(bytes)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_compressed: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    Err(DecompressError::Disabled("zstd"))
+}
+
+#[cfg(feature = "lz4")]
+fn decompress_lz4(compressed: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    lz4_flex::block::decompress_size_prepended(compressed)
+        .map_err(|e| DecompressError::Invalid("lz4", e.to_string()))
+}
+
+#[cfg(not(feature = "lz4"))]
+fn decompress_lz4(_compressed: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    Err(DecompressError::Disabled("lz4"))
+}
+
+/// Decodes a proto store value, decompressing it first when it was written compressed.
+pub(crate) fn decode<T: Codec>(bytes: &[u8]) -> Result<T, String> {
+    let bytes = decompress(bytes).map_err(|e| e.to_string())?;
+    crate::metrics::on_decode(bytes.len());
+    T::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decompress, Compression, DecompressError, COMPRESSION_MARKER};
+    use prost_types::Timestamp;
+
+    fn value() -> Vec<u8> {
+        "pool:0x1f98431c8ad98523631ae4a59f267346ea31f984;"
+            .repeat(20)
+            .into_bytes()
+    }
+
+    #[test]
+    fn it_keeps_small_and_uncompressed_values() {
+        let bytes = crate::proto::encode(&Timestamp {
+            seconds: 1,
+            nanos: 2,
+        })
+        .unwrap();
+
+        assert_eq!(Compression::None.compress(value()), value());
+        assert_eq!(decompress(&bytes).unwrap(), &bytes[..]);
+        assert_eq!(
+            decode::<Timestamp>(&bytes),
+            Ok(Timestamp {
+                seconds: 1,
+                nanos: 2
+            })
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn it_round_trips_zstd_values() {
+        let compressed = Compression::Zstd.compress(value());
+        assert!(compressed.starts_with(COMPRESSION_MARKER));
+        assert!(compressed.len() < value().len());
+        assert_eq!(decompress(&compressed).unwrap(), &value()[..]);
+
+        let small = Compression::Zstd.compress(b"ab".to_vec());
+        assert_eq!(small, b"ab");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn it_reads_compressed_store_values() {
+        use crate::store::{StoreGet, StoreGetProto, StoreSet, StoreSetProto};
+        use prost_types::Duration;
+
+        crate::testing::reset();
+        let snapshot = Duration {
+            seconds: 0x0101_0101_0101_0101,
+            nanos: 0x0101_0101,
+        };
+        let repeated = prost_types::Struct {
+            fields: (0..40)
+                .map(|i| (format!("pool:{:04}", i), prost_types::Value::default()))
+                .collect(),
+        };
+
+        StoreSetProto::new_with_compression(Compression::Zstd).set(1, "small", &snapshot);
+        StoreSetProto::new_with_compression(Compression::Zstd).set(2, "large", &repeated);
+        assert!(crate::testing::store_value("large")
+            .unwrap()
+            .starts_with(COMPRESSION_MARKER));
+
+        // Reads go through the readable stores, fed with the values written
+        for key in ["small", "large"] {
+            let value = crate::testing::store_value(key).unwrap();
+            crate::testing::set_store_value(0, key, value);
+        }
+
+        assert_eq!(
+            StoreGetProto::<Duration>::new(0).get_last("small"),
+            Some(snapshot)
+        );
+        assert_eq!(
+            StoreGetProto::<prost_types::Struct>::new(0).get_last("large"),
+            Some(repeated)
+        );
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn it_round_trips_lz4_values() {
+        let compressed = Compression::Lz4.compress(value());
+        assert!(compressed.starts_with(COMPRESSION_MARKER));
+        assert!(compressed.len() < value().len());
+        assert_eq!(decompress(&compressed).unwrap(), &value()[..]);
+    }
+
+    #[cfg(not(feature = "lz4"))]
+    #[test]
+    fn it_rejects_values_compressed_with_a_disabled_algorithm() {
+        let enveloped = [&COMPRESSION_MARKER[..], &[super::LZ4, 0x01]].concat();
+        assert_eq!(
+            decompress(&enveloped),
+            Err(DecompressError::Disabled("lz4"))
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_envelopes() {
+        let enveloped = [&COMPRESSION_MARKER[..], &[9, 0x01]].concat();
+        assert_eq!(decompress(&enveloped), Err(DecompressError::Unknown(9)));
+        assert_eq!(
+            decompress(COMPRESSION_MARKER),
+            Err(DecompressError::Truncated)
+        );
+    }
+}
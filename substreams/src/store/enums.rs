@@ -0,0 +1,352 @@
+use std::marker::PhantomData;
+use std::str::{self, FromStr};
+
+use super::{
+    BitVec, Delta, DeltaDecodeError, DeltaOperation, DeltaValues, GetResult, MappedDelta, Ordinal,
+    OrdinalDelta, StoreDelete, StoreGet, StoreGetRaw, StoreNew, StoreNewOutput, StoreSet,
+    TryFromStoreDelta,
+};
+use crate::pb::substreams::StoreDelta;
+use crate::state;
+
+/// An enum kept in a store by its canonical name: every type converting to a `&'static str`
+/// and parsed back with [FromStr], like enums deriving strum's `IntoStaticStr` and
+/// `EnumString`. Renaming a variant changes its stored value.
+pub trait StoreEnum: Clone + Into<&'static str> + FromStr {}
+
+impl<E: Clone + Into<&'static str> + FromStr> StoreEnum for E {}
+
+fn encode_enum<E: StoreEnum>(value: &E) -> &'static str {
+    value.clone().into()
+}
+
+fn try_decode_enum<E: StoreEnum>(bytes: &[u8]) -> Result<Option<E>, String> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+
+    str::from_utf8(bytes)
+        .ok()
+        .and_then(|name| E::from_str(name).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            format!(
+                "{:?} is not a variant of {}",
+                String::from_utf8_lossy(bytes),
+                std::any::type_name::<E>()
+            )
+        })
+}
+
+fn decode_enum<E: StoreEnum>(bytes: &[u8]) -> Option<E> {
+    try_decode_enum(bytes).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// `StoreSetEnum` is a struct representing a `store` with `updatePolicy` equal to `set` on a
+/// `valueType` equal to `string`, holding the canonical name of a [StoreEnum] so statuses and
+/// states are written and read back as the enum instead of free-form strings:
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use substreams::store::{StoreGet, StoreGetEnum, StoreNew, StoreSet, StoreSetEnum};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum Status {
+///     Open,
+///     Closed,
+/// }
+/// # impl From<Status> for &'static str {
+/// #     fn from(status: Status) -> Self {
+/// #         match status { Status::Open => "open", Status::Closed => "closed" }
+/// #     }
+/// # }
+/// # impl FromStr for Status {
+/// #     type Err = ();
+/// #     fn from_str(s: &str) -> Result<Self, ()> {
+/// #         match s { "open" => Ok(Status::Open), "closed" => Ok(Status::Closed), _ => Err(()) }
+/// #     }
+/// # }
+///
+/// fn store_statuses(closed: Vec<String>, statuses: StoreSetEnum<Status>) {
+///     for position in closed {
+///         statuses.set(0, position, &Status::Closed);
+///     }
+/// }
+///
+/// fn is_open(statuses: &StoreGetEnum<Status>, position: &str) -> bool {
+///     statuses.get_last(position) == Some(Status::Open)
+/// }
+/// ```
+pub struct StoreSetEnum<E> {
+    output: u32,
+    casper: PhantomData<E>,
+}
+
+impl<E: StoreEnum> StoreNew for StoreSetEnum<E> {
+    fn new() -> Self {
+        Self::new_output(0)
+    }
+}
+
+impl<E: StoreEnum> StoreNewOutput for StoreSetEnum<E> {
+    fn new_output(output_idx: u32) -> Self {
+        StoreSetEnum {
+            output: output_idx,
+            casper: PhantomData,
+        }
+    }
+}
+
+impl<E: StoreEnum> StoreDelete for StoreSetEnum<E> {
+    fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+        state::select_output(self.output);
+        state::delete_prefix(ord, prefix);
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::select_output(self.output);
+        state::delete_key(ord, key);
+    }
+}
+
+impl<E: StoreEnum> StoreSet<E> for StoreSetEnum<E> {
+    fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &E) {
+        state::select_output(self.output);
+        state::set(ord, key, encode_enum(value));
+    }
+
+    fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &E) {
+        let ord: Ordinal = ord.into();
+        state::select_output(self.output);
+        let name = encode_enum(value);
+
+        for key in keys {
+            state::set(ord, key, name);
+        }
+    }
+}
+
+/// Reads the values of a [StoreSetEnum] store, panics on names that aren't a variant of `E`.
+pub struct StoreGetEnum<E> {
+    store: StoreGetRaw,
+    casper: PhantomData<E>,
+}
+
+impl<E: StoreEnum> StoreGet<E> for StoreGetEnum<E> {
+    fn new(idx: u32) -> Self {
+        StoreGetEnum {
+            store: StoreGetRaw::new(idx),
+            casper: PhantomData,
+        }
+    }
+
+    fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> Option<E> {
+        self.store
+            .get_at(ord, key)
+            .and_then(|bytes| decode_enum(&bytes))
+    }
+
+    fn get_last<K: AsRef<str>>(&self, key: K) -> Option<E> {
+        self.store
+            .get_last(key)
+            .and_then(|bytes| decode_enum(&bytes))
+    }
+
+    fn get_first<K: AsRef<str>>(&self, key: K) -> Option<E> {
+        self.store
+            .get_first(key)
+            .and_then(|bytes| decode_enum(&bytes))
+    }
+
+    fn has_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> bool {
+        self.store.has_at(ord, key)
+    }
+
+    fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
+        self.store.has_last(key)
+    }
+
+    fn has_first<K: AsRef<str>>(&self, key: K) -> bool {
+        self.store.has_first(key)
+    }
+
+    fn get_last_checked<K: AsRef<str>>(&self, key: K) -> GetResult<E> {
+        match self
+            .store
+            .get_last(key)
+            .map(|bytes| try_decode_enum(&bytes))
+        {
+            None | Some(Ok(None)) => GetResult::Missing,
+            Some(Ok(Some(value))) => GetResult::Present(value),
+            Some(Err(err)) => GetResult::DecodeError(err),
+        }
+    }
+
+    fn exists_many<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> BitVec {
+        self.store.exists_many(keys)
+    }
+
+    fn get_many_last<K: AsRef<str>, I: IntoIterator<Item = K>>(&self, keys: I) -> Vec<Option<E>> {
+        self.store
+            .get_many_last(keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(|bytes| decode_enum(&bytes)))
+            .collect()
+    }
+
+    fn get_many_at<K: AsRef<str>, I: IntoIterator<Item = K>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: I,
+    ) -> Vec<Option<E>> {
+        self.store
+            .get_many_at(ord, keys)
+            .into_iter()
+            .map(|bytes| bytes.and_then(|bytes| decode_enum(&bytes)))
+            .collect()
+    }
+}
+
+/// Deltas of a [StoreSetEnum] store, the old value is `None` when the key is created and the
+/// new value is `None` when it's deleted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaEnum<E> {
+    pub operation: DeltaOperation,
+    pub ordinal: u64,
+    pub key: String,
+    pub old_value: Option<E>,
+    pub new_value: Option<E>,
+}
+
+impl<E: StoreEnum> From<StoreDelta> for DeltaEnum<E> {
+    fn from(d: StoreDelta) -> Self {
+        Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            old_value: decode_enum(&d.old_value),
+            new_value: decode_enum(&d.new_value),
+            key: d.key,
+        }
+    }
+}
+
+impl<E: StoreEnum> TryFromStoreDelta for DeltaEnum<E> {
+    fn try_from_delta(d: StoreDelta) -> Result<Self, DeltaDecodeError> {
+        let error = |which: &str, message: String| DeltaDecodeError {
+            key: d.key.clone(),
+            ordinal: d.ordinal,
+            message: format!("invalid {} value: {}", which, message),
+        };
+        let old_value = try_decode_enum(&d.old_value).map_err(|e| error("old", e))?;
+        let new_value = try_decode_enum(&d.new_value).map_err(|e| error("new", e))?;
+
+        Ok(Self {
+            operation: DeltaOperation::from(d.operation),
+            ordinal: d.ordinal,
+            key: d.key,
+            old_value,
+            new_value,
+        })
+    }
+}
+
+impl<E: PartialEq> Delta for DeltaEnum<E> {
+    fn get_key(&self) -> &String {
+        &self.key
+    }
+    fn get_operation(&self) -> DeltaOperation {
+        self.operation
+    }
+}
+
+impl<E: PartialEq> DeltaValues<Option<E>> for DeltaEnum<E> {
+    fn into_mapped(self) -> MappedDelta<Option<E>> {
+        MappedDelta {
+            operation: self.operation,
+            ordinal: self.ordinal,
+            key: self.key,
+            old_value: self.old_value,
+            new_value: self.new_value,
+        }
+    }
+}
+
+impl<E: PartialEq> OrdinalDelta<Option<E>> for DeltaEnum<E> {
+    fn get_ordinal(&self) -> u64 {
+        self.ordinal
+    }
+
+    fn get_old_value(&self) -> &Option<E> {
+        &self.old_value
+    }
+
+    fn get_new_value(&self) -> &Option<E> {
+        &self.new_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeltaEnum, StoreGetEnum, StoreSetEnum};
+    use crate::store::{Deltas, StoreGet, StoreNew, StoreSet};
+    use crate::testing::{self, MockDeltas};
+    use std::str::FromStr;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Status {
+        Open,
+        Closed,
+    }
+
+    impl From<Status> for &'static str {
+        fn from(status: Status) -> Self {
+            match status {
+                Status::Open => "open",
+                Status::Closed => "closed",
+            }
+        }
+    }
+
+    impl FromStr for Status {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, ()> {
+            match s {
+                "open" => Ok(Status::Open),
+                "closed" => Ok(Status::Closed),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[test]
+    fn it_stores_enums_by_name() {
+        testing::reset();
+
+        StoreSetEnum::<Status>::new().set(1, "position:1", &Status::Closed);
+        assert_eq!(testing::store_value("position:1"), Some(b"closed".to_vec()));
+
+        testing::set_store_value(0, "position:2", "open");
+        let statuses = StoreGetEnum::<Status>::new(0);
+        assert_eq!(statuses.get_last("position:2"), Some(Status::Open));
+        assert_eq!(statuses.get_last("position:3"), None);
+    }
+
+    #[test]
+    fn it_decodes_enum_deltas() {
+        let deltas = MockDeltas::new()
+            .create("position:1", "open")
+            .update("position:1", "open", "closed")
+            .build::<DeltaEnum<Status>>();
+
+        assert_eq!(deltas.deltas[0].old_value, None);
+        assert_eq!(deltas.deltas[0].new_value, Some(Status::Open));
+        assert_eq!(deltas.deltas[1].new_value, Some(Status::Closed));
+
+        let invalid = MockDeltas::new()
+            .update("position:1", "open", "pending")
+            .into_pb();
+        let decoded = Deltas::<DeltaEnum<Status>>::try_decode(invalid.deltas);
+        assert!(decoded[0].is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+
+use crate::hashing;
+use crate::Hex;
+
+use super::KeyMappedStore;
+
+/// Hash applied by [HashedKeyStore] to long keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyHash {
+    /// [hashing::xxhash64], 16 hex characters. Fast but not collision resistant: with `n`
+    /// hashed keys sharing a readable prefix, the odds of a collision are about `n² / 2^65`
+    /// (one in 37 million for a million keys).
+    Xxhash64,
+    /// [hashing::keccak256], 64 hex characters, collisions are not a practical concern.
+    /// Requires the `keccak` feature.
+    #[cfg(feature = "keccak")]
+    Keccak256,
+}
+
+/// A store whose keys longer than a threshold are replaced by a readable prefix followed by
+/// the hash of the whole key, `<prefix>#<hash>`, to bound the size of stores keyed by
+/// combinations of addresses. Shorter keys are left unchanged.
+///
+/// Every read and write of the key must go through a [HashedKeyStore] configured the same way,
+/// writers and readers included:
+///
+/// ```rust
+/// use substreams::store::{HashedKeyStore, KeyMappedStore, StoreGetInt64, StoreSetInt64};
+///
+/// fn store_routes(store: StoreSetInt64, routes: Vec<(String, String, String)>) {
+///     let store = HashedKeyStore::new(store);
+///     for (token_in, pool, token_out) in routes {
+///         store.set(0, format!("route:{}:{}:{}", token_in, pool, token_out), &1);
+///     }
+/// }
+///
+/// fn route_count(routes: StoreGetInt64, token_in: &str, pool: &str, token_out: &str) -> i64 {
+///     let routes = HashedKeyStore::new(routes);
+///     routes
+///         .get_last(format!("route:{}:{}:{}", token_in, pool, token_out))
+///         .unwrap_or_default()
+/// }
+/// ```
+///
+/// Hashing can't be reversed, deltas of hashed keys only give back the readable prefix. A
+/// shorter key already shaped like a hashed one (`<prefix>#<hash>`) would share its entry.
+pub struct HashedKeyStore<S> {
+    store: S,
+    max_len: usize,
+    readable_prefix: usize,
+    hash: KeyHash,
+}
+
+impl<S> HashedKeyStore<S> {
+    /// Hashes keys longer than 64 bytes with [KeyHash::Xxhash64], keeping their first 32 bytes.
+    pub fn new(store: S) -> Self {
+        HashedKeyStore {
+            store,
+            max_len: 64,
+            readable_prefix: 32,
+            hash: KeyHash::Xxhash64,
+        }
+    }
+
+    /// Hashes the keys longer than `max_len` bytes.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Keeps the first `len` bytes of hashed keys, less when that would split a character.
+    pub fn with_readable_prefix(mut self, len: usize) -> Self {
+        self.readable_prefix = len;
+        self
+    }
+
+    pub fn with_hash(mut self, hash: KeyHash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// The wrapped store, to access keys without hashing.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Returns the key of `key` in the underlying store.
+    pub fn key<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        if key.len() <= self.max_len {
+            return Cow::Borrowed(key);
+        }
+
+        let mut prefix_len = self.readable_prefix.min(key.len());
+        while !key.is_char_boundary(prefix_len) {
+            prefix_len -= 1;
+        }
+
+        let hash = match self.hash {
+            KeyHash::Xxhash64 => Hex(hashing::xxhash64(key, 0).to_be_bytes()).to_string(),
+            #[cfg(feature = "keccak")]
+            KeyHash::Keccak256 => Hex(hashing::keccak256(key)).to_string(),
+        };
+
+        Cow::Owned(format!("{}#{}", &key[..prefix_len], hash))
+    }
+}
+
+impl<S> KeyMappedStore for HashedKeyStore<S> {
+    type Store = S;
+
+    fn mapped_store(&self) -> &S {
+        &self.store
+    }
+
+    fn map_key<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        self.key(key)
+    }
+
+    /// Prefixes aren't hashed: hashed keys only match prefixes within their readable prefix.
+    fn map_prefix<'p>(&self, prefix: &'p str) -> Cow<'p, str> {
+        Cow::Borrowed(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashedKeyStore;
+    use crate::store::{KeyMappedStore, StoreGet, StoreGetInt64, StoreNew, StoreSetInt64};
+    use crate::testing;
+
+    #[test]
+    fn it_hashes_long_keys_only() {
+        testing::reset();
+
+        let long = format!("route:{}:{}", "a".repeat(40), "b".repeat(40));
+        let store = HashedKeyStore::new(StoreSetInt64::new())
+            .with_max_len(32)
+            .with_readable_prefix(10);
+        assert_eq!(store.key("route:short"), "route:short");
+        let hashed = store.key(&long).into_owned();
+        assert_eq!(hashed.len(), 10 + 1 + 16);
+        assert!(hashed.starts_with("route:aaaa#"));
+        assert_ne!(store.key(&format!("{}c", long)), hashed);
+
+        store.set(1, "route:short", &1);
+        store.set(1, &long, &2);
+        assert_eq!(testing::store_value("route:short"), Some(b"1".to_vec()));
+        assert_eq!(testing::store_value(&hashed), Some(b"2".to_vec()));
+
+        testing::set_store_value(1, &hashed, "2");
+        let reader = HashedKeyStore::new(StoreGetInt64::new(1))
+            .with_max_len(32)
+            .with_readable_prefix(10);
+        assert_eq!(reader.get_last(&long), Some(2));
+        assert!(reader.has_last(&long));
+
+        store.delete_prefix(2, "route:a");
+        assert_eq!(testing::store_value(&hashed), None);
+        assert_eq!(testing::store_value("route:short"), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    fn it_keeps_the_prefix_on_char_boundaries() {
+        let store = HashedKeyStore::new(())
+            .with_max_len(2)
+            .with_readable_prefix(2);
+        assert!(store.key("éé").starts_with("é#"));
+        assert!(store.key("aéé").starts_with("a#"));
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn it_hashes_with_keccak() {
+        let store = HashedKeyStore::new(())
+            .with_max_len(0)
+            .with_readable_prefix(0)
+            .with_hash(super::KeyHash::Keccak256);
+        assert_eq!(
+            store.key("abc"),
+            "#4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::marker::PhantomData;
+
+use super::{Ordinal, StoreGet};
+
+/// Reads the values related to an identifier from several stores in a single call, each store
+/// with its own key derived from the identifier by a [JoinOn]:
+///
+/// ```rust
+/// use substreams::scalar::BigDecimal;
+/// use substreams::store::{JoinOn, StoreGetBigDecimal, StoreGetInt64, StoreGetString, StoreJoin};
+///
+/// fn pools(
+///     prices: StoreGetBigDecimal,
+///     names: StoreGetString,
+///     swaps: StoreGetInt64,
+///     pool_ids: Vec<String>,
+/// ) {
+///     let join = StoreJoin::new((
+///         JoinOn::new(&prices, |pool: &str| format!("pool:{}:price", pool)),
+///         JoinOn::new(&names, |pool: &str| format!("pool:{}", pool)),
+///         JoinOn::new(&swaps, |pool: &str| pool.to_string()),
+///     ));
+///
+///     let (price, name, swap_count): (Option<BigDecimal>, Option<String>, Option<i64>) =
+///         join.get_last("0x1234");
+///
+///     // A single batched read per store
+///     for (pool, (price, name, swap_count)) in pool_ids.iter().zip(join.get_many_last(&pool_ids)) {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// Joins are made of tuples of up to 8 stores.
+pub struct StoreJoin<P> {
+    parts: P,
+}
+
+impl<P: JoinParts> StoreJoin<P> {
+    pub fn new(parts: P) -> Self {
+        StoreJoin { parts }
+    }
+
+    /// Reads the values of `id` like [StoreGet::get_last].
+    pub fn get_last<I: AsRef<str>>(&self, id: I) -> P::Row {
+        self.parts.get_last(id.as_ref())
+    }
+
+    /// Reads the values of `id` like [StoreGet::get_at].
+    pub fn get_at<I: AsRef<str>>(&self, ord: impl Into<Ordinal>, id: I) -> P::Row {
+        self.parts.get_at(ord.into(), id.as_ref())
+    }
+
+    /// Reads the values of many identifiers like [StoreGet::get_many_last], with a single
+    /// batched read per store. Rows are returned in the order of `ids`.
+    pub fn get_many_last<I: AsRef<str>>(&self, ids: &[I]) -> Vec<P::Row> {
+        let ids: Vec<&str> = ids.iter().map(AsRef::as_ref).collect();
+        self.parts.get_many_last(&ids)
+    }
+}
+
+/// A store of a [StoreJoin] and the function deriving its key from the joined identifier.
+pub struct JoinOn<'a, S, V, F> {
+    store: &'a S,
+    key: F,
+    value: PhantomData<V>,
+}
+
+impl<'a, S, V, F, K> JoinOn<'a, S, V, F>
+where
+    S: StoreGet<V>,
+    F: Fn(&str) -> K,
+    K: AsRef<str>,
+{
+    pub fn new(store: &'a S, key: F) -> Self {
+        JoinOn {
+            store,
+            key,
+            value: PhantomData,
+        }
+    }
+
+    fn get_last(&self, id: &str) -> Option<V> {
+        self.store.get_last((self.key)(id))
+    }
+
+    fn get_at(&self, ord: Ordinal, id: &str) -> Option<V> {
+        self.store.get_at(ord, (self.key)(id))
+    }
+
+    fn get_many_last(&self, ids: &[&str]) -> Vec<Option<V>> {
+        self.store
+            .get_many_last(ids.iter().map(|id| (self.key)(id)))
+    }
+}
+
+/// Tuples of [JoinOn] read together by a [StoreJoin].
+pub trait JoinParts {
+    /// The values of an identifier, a tuple of `Option`s in the order of the stores.
+    type Row;
+
+    fn get_last(&self, id: &str) -> Self::Row;
+    fn get_at(&self, ord: Ordinal, id: &str) -> Self::Row;
+    fn get_many_last(&self, ids: &[&str]) -> Vec<Self::Row>;
+}
+
+macro_rules! impl_join_parts {
+    ($(($part:ident, $store:ident, $value:ident, $key_fn:ident, $key:ident, $index:tt)),+) => {
+        impl<'a, $($store, $value, $key_fn, $key),+> JoinParts
+            for ($(JoinOn<'a, $store, $value, $key_fn>,)+)
+        where
+            $(
+                $store: StoreGet<$value>,
+                $key_fn: Fn(&str) -> $key,
+                $key: AsRef<str>,
+            )+
+        {
+            type Row = ($(Option<$value>,)+);
+
+            fn get_last(&self, id: &str) -> Self::Row {
+                ($(self.$index.get_last(id),)+)
+            }
+
+            fn get_at(&self, ord: Ordinal, id: &str) -> Self::Row {
+                ($(self.$index.get_at(ord, id),)+)
+            }
+
+            fn get_many_last(&self, ids: &[&str]) -> Vec<Self::Row> {
+                $(let mut $part = self.$index.get_many_last(ids).into_iter();)+
+
+                ids.iter()
+                    .map(|_| ($($part.next().flatten(),)+))
+                    .collect()
+            }
+        }
+    };
+}
+
+impl_join_parts!((a, SA, VA, FA, KA, 0));
+impl_join_parts!((a, SA, VA, FA, KA, 0), (b, SB, VB, FB, KB, 1));
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2)
+);
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2),
+    (d, SD, VD, FD, KD, 3)
+);
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2),
+    (d, SD, VD, FD, KD, 3),
+    (e, SE, VE, FE, KE, 4)
+);
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2),
+    (d, SD, VD, FD, KD, 3),
+    (e, SE, VE, FE, KE, 4),
+    (f, SF, VF, FF, KF, 5)
+);
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2),
+    (d, SD, VD, FD, KD, 3),
+    (e, SE, VE, FE, KE, 4),
+    (f, SF, VF, FF, KF, 5),
+    (g, SG, VG, FG, KG, 6)
+);
+impl_join_parts!(
+    (a, SA, VA, FA, KA, 0),
+    (b, SB, VB, FB, KB, 1),
+    (c, SC, VC, FC, KC, 2),
+    (d, SD, VD, FD, KD, 3),
+    (e, SE, VE, FE, KE, 4),
+    (f, SF, VF, FF, KF, 5),
+    (g, SG, VG, FG, KG, 6),
+    (h, SH, VH, FH, KH, 7)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{JoinOn, StoreJoin};
+    use crate::store::{StoreGet, StoreGetInt64, StoreGetString};
+    use crate::testing;
+
+    #[test]
+    fn it_joins_stores_on_an_identifier() {
+        testing::reset();
+        testing::set_store_value(1, "pool:a:name", "USDC/ETH");
+        testing::set_store_value(2, "a", "12");
+        testing::set_store_value(2, "b", "3");
+
+        let names = StoreGetString::new(1);
+        let swaps = StoreGetInt64::new(2);
+        let join = StoreJoin::new((
+            JoinOn::new(&names, |pool: &str| format!("pool:{}:name", pool)),
+            JoinOn::new(&swaps, |pool: &str| pool.to_string()),
+        ));
+
+        assert_eq!(join.get_last("a"), (Some("USDC/ETH".to_string()), Some(12)));
+        assert_eq!(join.get_at(0, "b"), (None, Some(3)));
+        assert_eq!(
+            join.get_many_last(&["b", "c", "a"]),
+            vec![
+                (None, Some(3)),
+                (None, None),
+                (Some("USDC/ETH".to_string()), Some(12))
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+use super::{
+    Appender, Ordinal, StoreAdd, StoreDelete, StoreGet, StoreMax, StoreMin, StoreSet,
+    StoreSetIfNotExists, StoreSetOnce, StoreSetSum,
+};
+
+/// A view of a store rewriting every key before it reaches the wrapped store, implemented by
+/// [super::Scoped] and [super::HashedKeyStore]. Its provided methods read and write the
+/// wrapped store under the rewritten key, for any operation the wrapped store supports.
+pub trait KeyMappedStore {
+    type Store;
+
+    /// The wrapped store.
+    fn mapped_store(&self) -> &Self::Store;
+
+    /// Returns the key of `key` in the wrapped store.
+    fn map_key<'k>(&self, key: &'k str) -> Cow<'k, str>;
+
+    /// Returns the prefix deleted from the wrapped store by [KeyMappedStore::delete_prefix].
+    fn map_prefix<'p>(&self, prefix: &'p str) -> Cow<'p, str>;
+
+    fn set<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: &V)
+    where
+        Self::Store: StoreSet<V>,
+    {
+        self.mapped_store()
+            .set(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn set_if_not_exists<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: &V)
+    where
+        Self::Store: StoreSetIfNotExists<V>,
+    {
+        self.mapped_store()
+            .set_if_not_exists(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn set_once<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: &V)
+    where
+        Self::Store: StoreSetOnce<V>,
+    {
+        self.mapped_store()
+            .set_once(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn add<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: V)
+    where
+        Self::Store: StoreAdd<V>,
+    {
+        self.mapped_store()
+            .add(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn max<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: V)
+    where
+        Self::Store: StoreMax<V>,
+    {
+        self.mapped_store()
+            .max(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn min<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: V)
+    where
+        Self::Store: StoreMin<V>,
+    {
+        self.mapped_store()
+            .min(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn append<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, item: V)
+    where
+        Self::Store: Appender<V>,
+    {
+        self.mapped_store()
+            .append(ord, self.map_key(key.as_ref()), item)
+    }
+
+    /// [StoreSetSum::set], named apart from [KeyMappedStore::set].
+    fn set_sum<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: V)
+    where
+        Self::Store: StoreSetSum<V>,
+    {
+        StoreSetSum::set(self.mapped_store(), ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn sum<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, value: V)
+    where
+        Self::Store: StoreSetSum<V>,
+    {
+        self.mapped_store()
+            .sum(ord, self.map_key(key.as_ref()), value)
+    }
+
+    fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K)
+    where
+        Self::Store: StoreDelete,
+    {
+        self.mapped_store()
+            .delete_key(ord, self.map_key(key.as_ref()))
+    }
+
+    fn delete_prefix<P: AsRef<str>>(&self, ord: impl Into<Ordinal>, prefix: P)
+    where
+        Self::Store: StoreDelete,
+    {
+        self.mapped_store()
+            .delete_prefix(ord, &self.map_prefix(prefix.as_ref()).into_owned())
+    }
+
+    fn get_at<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K) -> Option<V>
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().get_at(ord, self.map_key(key.as_ref()))
+    }
+
+    fn get_last<K: AsRef<str>, V>(&self, key: K) -> Option<V>
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().get_last(self.map_key(key.as_ref()))
+    }
+
+    fn get_first<K: AsRef<str>, V>(&self, key: K) -> Option<V>
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().get_first(self.map_key(key.as_ref()))
+    }
+
+    fn has_at<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K) -> bool
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().has_at(ord, self.map_key(key.as_ref()))
+    }
+
+    fn has_last<K: AsRef<str>, V>(&self, key: K) -> bool
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().has_last(self.map_key(key.as_ref()))
+    }
+
+    fn has_first<K: AsRef<str>, V>(&self, key: K) -> bool
+    where
+        Self::Store: StoreGet<V>,
+    {
+        self.mapped_store().has_first(self.map_key(key.as_ref()))
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Ordinal, StoreDelete, StoreMax, StoreMin, StoreNew, StoreNewOutput};
+use crate::state;
+
+macro_rules! impl_lexicographic_store {
+    ($(#[$meta:meta])* $name:ident, $trait:ident, $method:ident, $value:ty, $set:path) => {
+        $(#[$meta])*
+        pub struct $name {
+            output: u32,
+        }
+
+        impl StoreNew for $name {
+            fn new() -> Self {
+                Self { output: 0 }
+            }
+        }
+
+        impl StoreNewOutput for $name {
+            fn new_output(output_idx: u32) -> Self {
+                Self { output: output_idx }
+            }
+        }
+
+        impl StoreDelete for $name {
+            fn delete_prefix(&self, ord: impl Into<Ordinal>, prefix: &String) {
+                state::select_output(self.output);
+                state::delete_prefix(ord, prefix);
+            }
+
+            fn delete_key<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+                state::select_output(self.output);
+                state::delete_key(ord, key);
+            }
+        }
+
+        impl<V: AsRef<$value>> $trait<V> for $name {
+            fn $method<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+                state::select_output(self.output);
+                $set(ord, key, value.as_ref());
+            }
+        }
+    };
+}
+
+impl_lexicographic_store!(
+    /// `StoreMaxString` is a struct representing a `store` module with `updatePolicy` equal to
+    /// `max` and a valueType of `string`, keeping the lexicographically largest value, for
+    /// example the latest of zero-padded ids seen. Values are compared byte by byte, which for
+    /// UTF-8 is the order of their code points, and an absent key takes the value as is.
+    ///
+    /// Requires the `lexicographic-min-max` feature and a server providing the
+    /// `state.set_max_bytes` and `state.set_min_bytes` host functions: like the numeric `max`
+    /// and `min` policies the comparison is done by the host, a module can't read its own store
+    /// to emulate it.
+    ///
+    /// ```rust
+    /// use substreams::store::{StoreMax, StoreMaxString};
+    ///
+    /// fn track_latest_pool(latest: &StoreMaxString, ord: u64, pool_id: u64) {
+    ///     // Zero-padded so the lexicographic order is the numeric one
+    ///     latest.max(ord, "latest_pool", format!("{:020}", pool_id));
+    /// }
+    /// ```
+    StoreMaxString,
+    StoreMax,
+    max,
+    str,
+    state::set_max_bytes
+);
+impl_lexicographic_store!(
+    /// `StoreMinString` is a struct representing a `store` module with `updatePolicy` equal to
+    /// `min` and a valueType of `string`, keeping the lexicographically smallest value, see
+    /// [StoreMaxString].
+    StoreMinString,
+    StoreMin,
+    min,
+    str,
+    state::set_min_bytes
+);
+impl_lexicographic_store!(
+    /// `StoreMaxBytes` is a struct representing a `store` module with `updatePolicy` equal to
+    /// `max` and a valueType of `bytes`, keeping the largest value compared byte by byte (a
+    /// shorter value sorts before the values it prefixes), see [StoreMaxString].
+    StoreMaxBytes,
+    StoreMax,
+    max,
+    [u8],
+    state::set_max_bytes
+);
+impl_lexicographic_store!(
+    /// `StoreMinBytes` is a struct representing a `store` module with `updatePolicy` equal to
+    /// `min` and a valueType of `bytes`, keeping the smallest value compared byte by byte, see
+    /// [StoreMaxBytes].
+    StoreMinBytes,
+    StoreMin,
+    min,
+    [u8],
+    state::set_min_bytes
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{StoreMaxBytes, StoreMaxString, StoreMinString};
+    use crate::store::{StoreMax, StoreMin, StoreNew};
+    use crate::testing;
+
+    #[test]
+    fn it_keeps_the_lexicographic_max_and_min() {
+        testing::reset();
+
+        let latest = StoreMaxString::new();
+        latest.max(1, "latest", "pool:0009");
+        latest.max(2, "latest", "pool:0010");
+        latest.max(3, "latest", "pool:0002");
+        assert_eq!(testing::store_value("latest"), Some(b"pool:0010".to_vec()));
+
+        let first = StoreMinString::new();
+        first.min(4, "first", "b");
+        first.min(5, "first", "ab");
+        first.min(6, "first", "abc");
+        assert_eq!(testing::store_value("first"), Some(b"ab".to_vec()));
+
+        let hashes = StoreMaxBytes::new();
+        hashes.max(7, "hash", vec![0x01, 0xff]);
+        hashes.max(8, "hash", vec![0x02]);
+        hashes.max(9, "hash", vec![0x01, 0xff, 0x00]);
+        assert_eq!(testing::store_value("hash"), Some(vec![0x02]));
+    }
+}
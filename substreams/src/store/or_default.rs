@@ -0,0 +1,137 @@
+use std::cell::{Cell, RefCell};
+
+use super::{Ordinal, StoreGet};
+
+/// A readable store returning a default value for keys that are not set, so the default
+/// policy is declared once instead of at each `get_last(key).unwrap_or(...)` call site.
+///
+/// The lookups that fell back to the default are counted, [with_missing_keys] also
+/// records their keys to find out which ones are unexpectedly missing:
+///
+/// ```rust
+/// use substreams::scalar::BigInt;
+/// use substreams::store::{StoreGet, StoreGetBigInt, StoreGetOrDefault};
+///
+/// fn total_supply(supplies: StoreGetBigInt, tokens: &[String]) -> BigInt {
+///     let supplies = StoreGetOrDefault::new(supplies, BigInt::zero()).with_missing_keys();
+///     let total = tokens
+///         .iter()
+///         .fold(BigInt::zero(), |total, token| total + supplies.get_last(token));
+///
+///     if supplies.missing_count() > 0 {
+///         substreams::log::info!("tokens without supply: {:?}", supplies.missing_keys());
+///     }
+///     total
+/// }
+/// ```
+///
+/// [with_missing_keys]: StoreGetOrDefault::with_missing_keys
+pub struct StoreGetOrDefault<T, S> {
+    store: S,
+    default: T,
+    missing_count: Cell<u64>,
+    missing_keys: Option<RefCell<Vec<String>>>,
+}
+
+impl<T: Clone, S: StoreGet<T>> StoreGetOrDefault<T, S> {
+    pub fn new(store: S, default: T) -> Self {
+        StoreGetOrDefault {
+            store,
+            default,
+            missing_count: Cell::new(0),
+            missing_keys: None,
+        }
+    }
+
+    /// Records the keys of the lookups that fell back to the default, see
+    /// [missing_keys](StoreGetOrDefault::missing_keys).
+    pub fn with_missing_keys(mut self) -> Self {
+        self.missing_keys = Some(RefCell::new(Vec::new()));
+        self
+    }
+
+    /// Retrieves a key like [StoreGet::get_last], the default if it's not set.
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> T {
+        let value = self.store.get_last(&key);
+        self.or_default(key, value)
+    }
+
+    /// Retrieves a key like [StoreGet::get_at], the default if it's not set.
+    pub fn get_at<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) -> T {
+        let value = self.store.get_at(ord, &key);
+        self.or_default(key, value)
+    }
+
+    /// Retrieves a key like [StoreGet::get_first], the default if it's not set.
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> T {
+        let value = self.store.get_first(&key);
+        self.or_default(key, value)
+    }
+
+    /// The number of lookups that fell back to the default.
+    pub fn missing_count(&self) -> u64 {
+        self.missing_count.get()
+    }
+
+    /// The keys of the lookups that fell back to the default in lookup order, always empty
+    /// unless created [with_missing_keys](StoreGetOrDefault::with_missing_keys).
+    pub fn missing_keys(&self) -> Vec<String> {
+        self.missing_keys
+            .as_ref()
+            .map_or_else(Vec::new, |keys| keys.borrow().clone())
+    }
+
+    pub fn default_value(&self) -> &T {
+        &self.default
+    }
+
+    /// The wrapped store, to tell keys that are not set apart.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    fn or_default<K: AsRef<str>>(&self, key: K, value: Option<T>) -> T {
+        match value {
+            Some(value) => value,
+            None => {
+                self.missing_count.set(self.missing_count.get() + 1);
+                if let Some(keys) = &self.missing_keys {
+                    keys.borrow_mut().push(key.as_ref().to_string());
+                }
+                self.default.clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoreGetOrDefault;
+    use crate::store::{StoreGet, StoreGetInt64};
+    use crate::testing;
+
+    #[test]
+    fn it_falls_back_to_the_default() {
+        testing::reset();
+        testing::set_store_value(1, "a", "10");
+
+        let store = StoreGetInt64::new(1);
+        assert_eq!(store.get_last_or("a", 5), 10);
+        assert_eq!(store.get_last_or("b", 5), 5);
+        assert_eq!(store.get_last_or_default("b"), 0);
+
+        let counts = StoreGetOrDefault::new(StoreGetInt64::new(1), 0);
+        assert_eq!(counts.get_last("a"), 10);
+        assert_eq!(counts.get_last("b"), 0);
+        assert_eq!(counts.missing_count(), 1);
+        assert!(counts.missing_keys().is_empty());
+
+        let counts = StoreGetOrDefault::new(StoreGetInt64::new(1), -1).with_missing_keys();
+        assert_eq!(counts.get_at(3, "b"), -1);
+        assert_eq!(counts.get_first("c"), -1);
+        assert_eq!(
+            counts.missing_keys(),
+            vec!["b".to_string(), "c".to_string()]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use thiserror::Error;
+
+/// The ordinal of a store operation, ordering it within the block. Ordinals are unsigned
+/// while the host functions take an `i64`, every store operation goes through `Ordinal` so
+/// the conversion is checked instead of silently wrapping.
+///
+/// Store traits and state functions take any `impl Into<Ordinal>`, so existing calls passing
+/// a `u64`, a `u32`, an `i64` or an integer literal keep compiling: unsigned ordinals convert
+/// as is while signed ones panic when they're negative. Signed ordinals computed by the
+/// module are checked with [Ordinal::checked] instead:
+///
+/// ```rust
+/// use substreams::store::{Ordinal, StoreSet, StoreSetInt64};
+///
+/// fn set_balance(balances: &StoreSetInt64, ord: i64, balance: i64) -> Result<(), substreams::errors::Error> {
+///     balances.set(Ordinal::checked(ord)?, "balance", &balance);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ordinal(u64);
+
+impl Ordinal {
+    pub const fn new(ord: u64) -> Self {
+        Ordinal(ord)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The ordinal as the `i64` taken by the host functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ordinal is larger than `i64::MAX`.
+    pub fn to_host(self) -> i64 {
+        i64::try_from(self.0).unwrap_or_else(|_| {
+            panic!(
+                "ordinal {} is larger than the maximum ordinal {}",
+                self.0,
+                i64::MAX
+            )
+        })
+    }
+
+    /// The ordinal `ord`, [NegativeOrdinal] if it's negative.
+    pub fn checked(ord: i64) -> Result<Self, NegativeOrdinal> {
+        u64::try_from(ord)
+            .map(Ordinal)
+            .map_err(|_| NegativeOrdinal(ord))
+    }
+}
+
+/// A negative signed ordinal, rejected by [Ordinal::checked].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("ordinal {0} is negative")]
+pub struct NegativeOrdinal(pub i64);
+
+impl From<u64> for Ordinal {
+    fn from(ord: u64) -> Self {
+        Ordinal(ord)
+    }
+}
+
+impl From<u32> for Ordinal {
+    fn from(ord: u32) -> Self {
+        Ordinal(ord as u64)
+    }
+}
+
+/// # Panics
+///
+/// Panics if `ord` is negative, see [Ordinal::checked].
+impl From<i64> for Ordinal {
+    fn from(ord: i64) -> Self {
+        Ordinal::checked(ord).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// Integer literals default to `i32`, this keeps `store.set(0, ...)` compiling.
+///
+/// # Panics
+///
+/// Panics if `ord` is negative, see [Ordinal::checked].
+impl From<i32> for Ordinal {
+    fn from(ord: i32) -> Self {
+        Ordinal::from(ord as i64)
+    }
+}
+
+impl From<Ordinal> for u64 {
+    fn from(ord: Ordinal) -> Self {
+        ord.0
+    }
+}
+
+impl fmt::Display for Ordinal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NegativeOrdinal, Ordinal};
+    use crate::store::{StoreDelete, StoreGet, StoreGetInt64, StoreNew, StoreSet, StoreSetInt64};
+    use crate::testing;
+
+    #[test]
+    fn it_checks_signed_ordinals() {
+        assert_eq!(Ordinal::checked(7), Ok(Ordinal::new(7)));
+        assert_eq!(Ordinal::checked(-1), Err(NegativeOrdinal(-1)));
+        assert_eq!(Ordinal::from(7u32).to_host(), 7);
+        assert_eq!(Ordinal::from(7).to_host(), 7);
+    }
+
+    #[test]
+    fn it_takes_any_integer_ordinal() {
+        testing::reset();
+        let store = StoreSetInt64::new();
+        store.set(1, "a", &1);
+        store.set(2u32, "b", &2);
+        store.set(3u64, "c", &3);
+        store.set(4i64, "d", &4);
+        store.set(Ordinal::new(5), "e", &5);
+        store.delete_prefix(6u64, &"a".to_string());
+        assert_eq!(testing::output_store().len(), 4);
+
+        let get = StoreGetInt64::new(0);
+        testing::set_store_value(0, "b", "2");
+        assert_eq!(get.get_at(7u64, "b"), Some(2));
+        assert_eq!(get.get_at(7, "b"), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "larger than the maximum ordinal")]
+    fn it_rejects_ordinals_overflowing_the_host() {
+        Ordinal::new(u64::MAX).to_host();
+    }
+
+    #[test]
+    #[should_panic(expected = "ordinal -1 is negative")]
+    fn it_rejects_negative_delete_ordinals() {
+        StoreSetInt64::new().delete_prefix(-1, &"pool:".to_string());
+    }
+}
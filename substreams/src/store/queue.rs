@@ -0,0 +1,327 @@
+use super::{
+    AppendCodec, Appender, DeltaArray, DeltaOperation, Deltas, Ordinal, StoreDelete, StoreGet,
+    DELETE_KEY_SENTINEL,
+};
+
+/// The store key of the sequence `name`, terminated by [DELETE_KEY_SENTINEL] so clearing it
+/// with `delete_prefix` never reaches a sequence it prefixes (`pool:1` and `pool:10`).
+fn sequence_key(name: &str) -> String {
+    format!("{}{}", name, DELETE_KEY_SENTINEL)
+}
+
+macro_rules! impl_sequence {
+    ($sequence:ident, $next:literal) => {
+        impl<S> $sequence<S> {
+            pub fn new(store: S) -> Self {
+                $sequence { store }
+            }
+
+            /// The wrapped store, to access keys outside of sequences.
+            pub fn inner(&self) -> &S {
+                &self.store
+            }
+
+            pub fn into_inner(self) -> S {
+                self.store
+            }
+
+            /// Returns the key of the sequence `name` in the underlying store.
+            pub fn key(&self, name: &str) -> String {
+                sequence_key(name)
+            }
+
+            pub fn push<T>(&self, ord: impl Into<Ordinal>, name: &str, item: T)
+            where
+                S: Appender<T>,
+            {
+                self.store.append(ord, sequence_key(name), item)
+            }
+
+            pub fn push_all<T>(&self, ord: impl Into<Ordinal>, name: &str, items: Vec<T>)
+            where
+                S: Appender<T>,
+            {
+                self.store.append_all(ord, sequence_key(name), items)
+            }
+
+            /// Removes every item of the sequence `name`.
+            pub fn clear(&self, ord: impl Into<Ordinal>, name: &str)
+            where
+                S: StoreDelete,
+            {
+                self.store.delete_prefix(ord, &sequence_key(name))
+            }
+
+            /// Replaces the items of the sequence `name` by `items`, cleared and written again
+            /// at `ord`.
+            pub fn replace<T>(&self, ord: impl Into<Ordinal>, name: &str, items: Vec<T>)
+            where
+                S: Appender<T> + StoreDelete,
+            {
+                let ord: Ordinal = ord.into();
+                self.clear(ord, name);
+                if !items.is_empty() {
+                    self.push_all(ord, name, items);
+                }
+            }
+
+            #[doc = concat!("Removes the ", $next, " item of `items`, the current items of the")]
+            /// sequence `name` as known by the caller, and writes the remaining ones back at
+            /// `ord`. Store handlers can't read their own store, `items` comes from the items
+            /// pushed earlier in the block or from a copy of the sequence read from another store.
+            pub fn pop<T: Clone>(
+                &self,
+                ord: impl Into<Ordinal>,
+                name: &str,
+                items: &mut Vec<T>,
+            ) -> Option<T>
+            where
+                S: Appender<T> + StoreDelete,
+            {
+                let item = Self::take_next(items)?;
+                self.replace(ord, name, items.clone());
+                Some(item)
+            }
+
+            #[doc = concat!("The ", $next, " item of the sequence `name`.")]
+            pub fn peek<T>(&self, name: &str) -> Option<T>
+            where
+                S: StoreGet<Vec<T>>,
+            {
+                self.items(name).into_iter().next()
+            }
+
+            #[doc = concat!("The ", $next, " item of the sequence `name` at ordinal `ord`,")]
+            /// ignoring the writes of the block after it.
+            pub fn peek_at<T>(&self, ord: impl Into<Ordinal>, name: &str) -> Option<T>
+            where
+                S: StoreGet<Vec<T>>,
+            {
+                self.items_at(ord, name).into_iter().next()
+            }
+
+            pub fn len<T>(&self, name: &str) -> usize
+            where
+                S: StoreGet<Vec<T>>,
+            {
+                self.store
+                    .get_last(sequence_key(name))
+                    .map_or(0, |items| items.len())
+            }
+
+            pub fn is_empty<T>(&self, name: &str) -> bool
+            where
+                S: StoreGet<Vec<T>>,
+            {
+                self.len::<T>(name) == 0
+            }
+        }
+    };
+}
+
+/// A first-in first-out queue of items kept in an `append` store, for work sequenced across
+/// blocks like positions waiting for a price: the store handler pushes items and clears the
+/// queue once they are handled, later modules peek at the pending items.
+///
+/// Each queue is a key of the store, so one store holds any number of named queues. Writes
+/// and reads go through a [StoreQueue] wrapping the writable or the readable store:
+///
+/// ```rust
+/// use substreams::store::{DeltaArray, Deltas, StoreAppend, StoreGetArray, StoreQueue};
+///
+/// fn store_pending(pending: StoreAppend<String>, opened: Vec<(String, String)>, priced: Vec<String>) {
+///     let pending = StoreQueue::new(pending);
+///     for (pool, position) in opened {
+///         pending.push(1, &pool, position);
+///     }
+///     for pool in priced {
+///         pending.clear(2, &pool);
+///     }
+/// }
+///
+/// fn oldest_pending(pending: StoreGetArray<String>, pool: &str) -> Option<String> {
+///     StoreQueue::new(pending).peek(pool)
+/// }
+///
+/// fn newly_pending(deltas: Deltas<DeltaArray<String>>) -> usize {
+///     deltas.sequence_changes().map(|change| change.appended.len()).sum()
+/// }
+/// ```
+pub struct StoreQueue<S> {
+    store: S,
+}
+
+impl_sequence!(StoreQueue, "first");
+
+impl<S> StoreQueue<S> {
+    /// The items of the queue `name`, oldest first.
+    pub fn items<T>(&self, name: &str) -> Vec<T>
+    where
+        S: StoreGet<Vec<T>>,
+    {
+        self.store.get_last(sequence_key(name)).unwrap_or_default()
+    }
+
+    /// The items of the queue `name` at ordinal `ord`, oldest first.
+    pub fn items_at<T>(&self, ord: impl Into<Ordinal>, name: &str) -> Vec<T>
+    where
+        S: StoreGet<Vec<T>>,
+    {
+        self.store
+            .get_at(ord, sequence_key(name))
+            .unwrap_or_default()
+    }
+
+    fn take_next<T>(items: &mut Vec<T>) -> Option<T> {
+        match items.is_empty() {
+            true => None,
+            false => Some(items.remove(0)),
+        }
+    }
+}
+
+/// A last-in first-out stack of items kept in an `append` store, the counterpart of
+/// [StoreQueue] where [peek](StoreStack::peek) and [pop](StoreStack::pop) return the last
+/// pushed item.
+pub struct StoreStack<S> {
+    store: S,
+}
+
+impl_sequence!(StoreStack, "last");
+
+impl<S> StoreStack<S> {
+    /// The items of the stack `name`, newest first.
+    pub fn items<T>(&self, name: &str) -> Vec<T>
+    where
+        S: StoreGet<Vec<T>>,
+    {
+        let mut items = self.store.get_last(sequence_key(name)).unwrap_or_default();
+        items.reverse();
+        items
+    }
+
+    /// The items of the stack `name` at ordinal `ord`, newest first.
+    pub fn items_at<T>(&self, ord: impl Into<Ordinal>, name: &str) -> Vec<T>
+    where
+        S: StoreGet<Vec<T>>,
+    {
+        let mut items = self
+            .store
+            .get_at(ord, sequence_key(name))
+            .unwrap_or_default();
+        items.reverse();
+        items
+    }
+
+    fn take_next<T>(items: &mut Vec<T>) -> Option<T> {
+        items.pop()
+    }
+}
+
+/// The change of a [StoreQueue] or [StoreStack] sequence in a block, see
+/// [Deltas::sequence_changes].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceChange<'a, T> {
+    /// The name of the sequence.
+    pub name: &'a str,
+    /// Whether the previous items were removed, by a `clear`, `replace` or `pop`.
+    pub reset: bool,
+    /// The items added, in push order. After a reset they are all the items of the sequence.
+    pub appended: &'a [T],
+}
+
+impl<T: PartialEq, C: AppendCodec<T>> Deltas<DeltaArray<T, C>> {
+    /// The changes of the [StoreQueue] and [StoreStack] sequences of the store, in delta
+    /// order. Keys not written through a sequence are skipped.
+    pub fn sequence_changes(&self) -> impl Iterator<Item = SequenceChange<'_, T>> + '_ {
+        self.deltas.iter().filter_map(|delta| {
+            let name = delta.key.strip_suffix(DELETE_KEY_SENTINEL)?;
+            let deleted = delta.operation == DeltaOperation::Delete;
+
+            Some(SequenceChange {
+                name,
+                reset: deleted || delta.is_reset(),
+                appended: match deleted {
+                    true => &[],
+                    false => delta.appended(),
+                },
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sequence_key, SequenceChange, StoreQueue, StoreStack};
+    use crate::store::{Appender, DeltaArray, StoreAppend, StoreGet, StoreGetArray};
+    use crate::testing::{self, MockDeltas};
+
+    #[test]
+    fn it_writes_and_reads_sequences() {
+        testing::reset();
+
+        let queue = StoreQueue::new(StoreAppend::<String>::new());
+        queue.push(1, "pool:1", "a".to_string());
+        queue.push_all(1, "pool:1", vec!["b".to_string(), "c".to_string()]);
+        queue.push(1, "pool:10", "z".to_string());
+        queue.clear(2, "pool:1");
+        assert_eq!(testing::store_value(&sequence_key("pool:1")), None);
+        assert_eq!(
+            testing::store_value(&sequence_key("pool:10")),
+            Some(b"z;".to_vec())
+        );
+
+        let mut items = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(queue.pop(3, "pool:1", &mut items), Some("a".to_string()));
+        assert_eq!(
+            testing::store_value(&sequence_key("pool:1")),
+            Some(b"b;c;".to_vec())
+        );
+
+        testing::set_store_value(1, sequence_key("pool:1"), "a;b;c;");
+        let queue = StoreQueue::new(StoreGetArray::<String>::new(1));
+        assert_eq!(queue.peek::<String>("pool:1"), Some("a".to_string()));
+        assert_eq!(queue.len::<String>("pool:1"), 3);
+        assert!(queue.is_empty::<String>("pool:2"));
+
+        let stack = StoreStack::new(StoreGetArray::<String>::new(1));
+        assert_eq!(stack.peek::<String>("pool:1"), Some("c".to_string()));
+        assert_eq!(stack.items::<String>("pool:1"), vec!["c", "b", "a"]);
+        let mut items = vec!["a".to_string(), "b".to_string()];
+        let stack = StoreStack::new(StoreAppend::<String>::new());
+        assert_eq!(stack.pop(4, "pool:1", &mut items), Some("b".to_string()));
+        assert_eq!(items, vec!["a"]);
+    }
+
+    #[test]
+    fn it_reads_sequence_changes() {
+        let deltas = MockDeltas::new()
+            .update(sequence_key("pool:1"), "a;", "a;b;")
+            .update(sequence_key("pool:2"), "a;b;", "b;")
+            .delete(sequence_key("pool:3"), "a;")
+            .create("other", "x;")
+            .build::<DeltaArray<String>>();
+
+        let changes: Vec<SequenceChange<String>> = deltas.sequence_changes().collect();
+        assert_eq!(
+            changes,
+            vec![
+                SequenceChange {
+                    name: "pool:1",
+                    reset: false,
+                    appended: &["b".to_string()],
+                },
+                SequenceChange {
+                    name: "pool:2",
+                    reset: true,
+                    appended: &["b".to_string()],
+                },
+                SequenceChange {
+                    name: "pool:3",
+                    reset: true,
+                    appended: &[],
+                },
+            ]
+        );
+    }
+}
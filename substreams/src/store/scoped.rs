@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+
+use crate::key;
+
+use super::{
+    KeyMappedStore, Ordinal, StoreDelete, StoreGetArray, StoreGetBigDecimal, StoreGetBigInt,
+    StoreGetBool, StoreGetFloat64, StoreGetInt32, StoreGetInt64, StoreGetProto, StoreGetRaw,
+    StoreGetString, StoreGetUint64, StoreNew,
+};
+
+/// A view of a store where every key is prefixed by a namespace segment, for stores shared
+/// between logical domains. Keys are written as `<namespace>:<key>`, deltas read back with
+/// [key::strip_namespace]:
+///
+/// ```rust
+/// use substreams::key;
+/// use substreams::store::{Delta, Deltas, DeltaInt64, KeyMappedStore, StoreScope, StoreSetInt64};
+///
+/// fn store_counts(store: StoreSetInt64, users: Vec<String>, tokens: Vec<String>) {
+///     let scoped_users = store.scoped("user");
+///     for user in users {
+///         scoped_users.set(0, &user, &1);
+///     }
+///
+///     let scoped_tokens = store.scoped("token");
+///     for token in tokens {
+///         scoped_tokens.set(0, &token, &1);
+///     }
+/// }
+///
+/// fn user_changes(deltas: Deltas<DeltaInt64>) -> Vec<String> {
+///     deltas
+///         .iter()
+///         .filter_map(|delta| key::strip_namespace(delta.get_key(), "user"))
+///         .map(String::from)
+///         .collect()
+/// }
+/// ```
+pub struct Scoped<'a, S> {
+    store: &'a S,
+    namespace: String,
+}
+
+/// Scopes any store to a namespace, see [Scoped].
+pub trait StoreScope: Sized {
+    fn scoped<N: Into<String>>(&self, namespace: N) -> Scoped<'_, Self> {
+        Scoped::new(self, namespace)
+    }
+}
+
+impl<S: StoreNew> StoreScope for S {}
+
+macro_rules! impl_store_scope {
+    ($($store:ty),+ $(,)?) => {
+        $(impl StoreScope for $store {})+
+    };
+}
+
+impl_store_scope!(
+    StoreGetRaw,
+    StoreGetString,
+    StoreGetInt64,
+    StoreGetFloat64,
+    StoreGetInt32,
+    StoreGetUint64,
+    StoreGetBool,
+    StoreGetBigDecimal,
+    StoreGetBigInt,
+);
+
+impl<T, C> StoreScope for StoreGetArray<T, C> {}
+
+impl<T> StoreScope for StoreGetProto<T> {}
+
+impl<'a, S> StoreScope for Scoped<'a, S> {}
+
+impl<'a, S> Scoped<'a, S> {
+    pub fn new<N: Into<String>>(store: &'a S, namespace: N) -> Self {
+        Scoped {
+            store,
+            namespace: namespace.into(),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The wrapped store, to access keys outside of the namespace.
+    pub fn inner(&self) -> &'a S {
+        self.store
+    }
+
+    /// A nested namespace, `<namespace>:<nested>`.
+    pub fn scoped<N: AsRef<str>>(&self, nested: N) -> Scoped<'a, S> {
+        Scoped::new(self.store, self.key(nested))
+    }
+
+    /// Returns the key of `key` in the underlying store.
+    pub fn key<K: AsRef<str>>(&self, key: K) -> String {
+        format!("{}:{}", self.namespace, key.as_ref())
+    }
+
+    /// Returns `key` without the namespace, `None` if it's not in the namespace.
+    pub fn strip<'k>(&self, key: &'k str) -> Option<&'k str> {
+        key::strip_namespace(key, &self.namespace)
+    }
+
+    /// Deletes every key of the namespace.
+    pub fn delete_all(&self, ord: impl Into<Ordinal>)
+    where
+        S: StoreDelete,
+    {
+        self.delete_prefix(ord, "")
+    }
+}
+
+impl<'a, S> KeyMappedStore for Scoped<'a, S> {
+    type Store = S;
+
+    fn mapped_store(&self) -> &S {
+        self.store
+    }
+
+    fn map_key<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        Cow::Owned(self.key(key))
+    }
+
+    /// Deleted prefixes are within the namespace.
+    fn map_prefix<'p>(&self, prefix: &'p str) -> Cow<'p, str> {
+        Cow::Owned(self.key(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoreScope;
+    use crate::key;
+    use crate::store::{KeyMappedStore, StoreAddInt64, StoreGet, StoreGetInt64, StoreNew};
+    use crate::testing;
+
+    #[test]
+    fn it_prefixes_keys_with_the_namespace() {
+        testing::reset();
+
+        let store = StoreAddInt64::new();
+        let users = store.scoped("user");
+        users.add(0, "0xab", 1);
+        users.scoped("0xab").add(0, "count", 2);
+        store.scoped("token").add(0, "0xab", 3);
+        assert_eq!(testing::store_value("user:0xab"), Some(b"1".to_vec()));
+        assert_eq!(testing::store_value("user:0xab:count"), Some(b"2".to_vec()));
+
+        users.delete_all(1);
+        assert_eq!(testing::store_value("user:0xab"), None);
+        assert_eq!(testing::store_value("token:0xab"), Some(b"3".to_vec()));
+
+        testing::set_store_value(1, "token:0xcd", "4");
+        let tokens = StoreGetInt64::new(1);
+        assert_eq!(tokens.scoped("token").get_last("0xcd"), Some(4));
+        assert_eq!(tokens.scoped("token").strip("token:0xcd"), Some("0xcd"));
+        assert_eq!(key::strip_namespace("user:0xcd", "token"), None);
+        assert_eq!(key::strip_namespace("tokens:0xcd", "token"), None);
+    }
+}
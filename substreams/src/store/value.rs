@@ -0,0 +1,287 @@
+//! Canonical encoding of store values.
+//!
+//! Numeric stores hold their values as UTF-8 decimal strings, an empty value being zero, and
+//! boolean stores as a single byte. The functions of this module are the ones used by the
+//! store types of this crate, so custom store wrappers and sink code reading raw store bytes
+//! (through [StoreGetRaw](super::StoreGetRaw) or [DeltaBytes](super::DeltaBytes)) decode them
+//! exactly the same way:
+//!
+//! ```rust
+//! use substreams::store::value;
+//!
+//! let bytes = value::encode_i64(-42);
+//! assert_eq!(bytes, b"-42");
+//! assert_eq!(value::parse_i64(&bytes).unwrap(), -42);
+//! assert_eq!(value::parse_i64(b"").unwrap(), 0);
+//! assert!(value::parse_i64(b"4.2").is_err());
+//! ```
+//!
+//! `i64` values can also be written in a binary form, [BINARY_I64_TAG] followed by the value as
+//! 8 little-endian bytes, which is smaller and faster to decode. The tag byte can't start a
+//! decimal string, so [parse_i64] reads both forms and readers don't need to know which one the
+//! writer used:
+//!
+//! ```rust
+//! use substreams::store::value;
+//!
+//! let bytes = value::encode_i64_binary(-42);
+//! assert_eq!(bytes.len(), 9);
+//! assert!(value::is_binary_i64(&bytes));
+//! assert_eq!(value::parse_i64(&bytes).unwrap(), -42);
+//! ```
+//!
+//! `f64` values are written in a canonical decimal form, see [format_f64]: the shortest digits
+//! parsing back to the same value, in plain notation without exponent, so downstream parsers
+//! (and the Go `strconv.ParseFloat` of the engine) read them the same way on every platform:
+//!
+//! ```rust
+//! use substreams::store::value;
+//!
+//! assert_eq!(value::encode_f64(0.1 + 0.2), b"0.30000000000000004");
+//! assert_eq!(value::encode_f64(1.5e-7), b"0.00000015");
+//! assert_eq!(value::encode_f64(-0.0), b"0");
+//! ```
+
+use std::convert::TryInto;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::errors::Error;
+use crate::scalar::{BigDecimal, BigInt};
+
+/// First byte of binary encoded `i64` values, see [encode_i64_binary].
+pub const BINARY_I64_TAG: u8 = 0x01;
+
+fn parse_str(bytes: &[u8]) -> Result<&str, Error> {
+    std::str::from_utf8(bytes).map_err(|_| anyhow!("value {:?} is not a valid UTF-8 string", bytes))
+}
+
+fn parse_number<T: FromStr + Default>(bytes: &[u8], name: &str) -> Result<T, Error> {
+    if bytes.is_empty() {
+        return Ok(T::default());
+    }
+
+    let value = parse_str(bytes)?;
+    T::from_str(value)
+        .map_err(|_| anyhow!("value {} is not a valid representation of {}", value, name))
+}
+
+fn encode_number<T: Display>(value: T) -> Vec<u8> {
+    value.to_string().into_bytes()
+}
+
+pub fn parse_i32(bytes: &[u8]) -> Result<i32, Error> {
+    parse_number(bytes, "an i32")
+}
+
+/// Decodes decimal and binary encoded values, see [is_binary_i64].
+pub fn parse_i64(bytes: &[u8]) -> Result<i64, Error> {
+    if is_binary_i64(bytes) {
+        let le_bytes = bytes[1..].try_into().expect("binary i64 has 8 value bytes");
+        return Ok(i64::from_le_bytes(le_bytes));
+    }
+
+    parse_number(bytes, "an i64")
+}
+
+/// Whether `bytes` is a value written by [encode_i64_binary].
+pub fn is_binary_i64(bytes: &[u8]) -> bool {
+    bytes.len() == 9 && bytes[0] == BINARY_I64_TAG
+}
+
+pub fn parse_u64(bytes: &[u8]) -> Result<u64, Error> {
+    parse_number(bytes, "a u64")
+}
+
+pub fn parse_f64(bytes: &[u8]) -> Result<f64, Error> {
+    parse_number(bytes, "an f64")
+}
+
+pub fn parse_bigint(bytes: &[u8]) -> Result<BigInt, Error> {
+    parse_number(bytes, "a BigInt")
+}
+
+pub fn parse_bigdecimal(bytes: &[u8]) -> Result<BigDecimal, Error> {
+    parse_number(bytes, "a BigDecimal")
+}
+
+/// Any non-empty value without a `0` byte is `true`. The empty value, the missing side of
+/// `Create` and `Delete` deltas, is `false`.
+pub fn parse_bool(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && !bytes.contains(&0)
+}
+
+pub fn encode_i32(value: i32) -> Vec<u8> {
+    encode_number(value)
+}
+
+pub fn encode_i64(value: i64) -> Vec<u8> {
+    encode_number(value)
+}
+
+/// Encodes `value` as [BINARY_I64_TAG] followed by its 8 little-endian bytes.
+pub fn encode_i64_binary(value: i64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(9);
+    bytes.push(BINARY_I64_TAG);
+    bytes.extend_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    encode_number(value)
+}
+
+/// Encodes `value` in its canonical form, see [format_f64].
+pub fn encode_f64(value: f64) -> Vec<u8> {
+    format_f64(value).into_bytes()
+}
+
+/// The canonical decimal form of `value` written by float64 stores:
+///
+/// * the shortest digits parsing back to `value`, as computed by Ryū, a tie on the last digit
+///   being rounded to even;
+/// * in plain notation, never with an exponent: `1e21` is `1000000000000000000000` and
+///   `1.5e-7` is `0.00000015`;
+/// * without fractional part for integral values: `2.0` is `2`;
+/// * `-0.0` is `0`, and non-finite values are `NaN`, `inf` and `-inf`.
+///
+/// This is the output of `f64`'s `Display` except for `-0.0` and the rare last digit ties it
+/// rounds up, values written before this form parse back to the same `f64` either way.
+pub fn format_f64(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    if value == 0.0 {
+        return "0".to_string();
+    }
+
+    let mut buffer = ryu::Buffer::new();
+    let shortest = buffer.format_finite(value);
+    let (negative, shortest) = match shortest.strip_prefix('-') {
+        Some(shortest) => (true, shortest),
+        None => (false, shortest),
+    };
+    let (mantissa, exponent) = match shortest.split_once('e') {
+        Some((mantissa, exponent)) => (
+            mantissa,
+            exponent
+                .parse::<i32>()
+                .expect("ryu writes integer exponents"),
+        ),
+        None => (shortest, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    // Significant digits and the position of the decimal point relative to them
+    let digits = format!("{}{}", integer, fraction);
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0');
+    let point = integer.len() as i32 + exponent - leading_zeros as i32;
+
+    let mut formatted = String::with_capacity(digits.len() + 24);
+    if negative {
+        formatted.push('-');
+    }
+    if point <= 0 {
+        formatted.push_str("0.");
+        formatted.extend(std::iter::repeat('0').take(-point as usize));
+        formatted.push_str(digits);
+    } else if point as usize >= digits.len() {
+        formatted.push_str(digits);
+        formatted.extend(std::iter::repeat('0').take(point as usize - digits.len()));
+    } else {
+        formatted.push_str(&digits[..point as usize]);
+        formatted.push('.');
+        formatted.push_str(&digits[point as usize..]);
+    }
+    formatted
+}
+
+pub fn encode_bigint(value: &BigInt) -> Vec<u8> {
+    encode_number(value)
+}
+
+pub fn encode_bigdecimal(value: &BigDecimal) -> Vec<u8> {
+    encode_number(value)
+}
+
+pub fn encode_bool(value: bool) -> Vec<u8> {
+    vec![value as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_values() {
+        assert_eq!(parse_i32(&encode_i32(i32::MIN)).unwrap(), i32::MIN);
+        assert_eq!(parse_u64(&encode_u64(u64::MAX)).unwrap(), u64::MAX);
+        assert_eq!(parse_f64(&encode_f64(1.5)).unwrap(), 1.5);
+        assert!(!parse_bool(&encode_bool(false)));
+        assert!(parse_bool(&encode_bool(true)));
+
+        let big = BigInt::from_str("-123456789012345678901234567890").unwrap();
+        assert_eq!(parse_bigint(&encode_bigint(&big)).unwrap(), big);
+        let decimal = BigDecimal::from_str("12.0034").unwrap();
+        assert_eq!(encode_bigdecimal(&decimal), b"12.0034");
+        assert_eq!(
+            parse_bigdecimal(&encode_bigdecimal(&decimal)).unwrap(),
+            decimal
+        );
+
+        assert_eq!(parse_bigint(b"").unwrap(), BigInt::zero());
+        assert_eq!(
+            parse_u64(b"-1").unwrap_err().to_string(),
+            "value -1 is not a valid representation of a u64"
+        );
+        assert!(parse_f64(&[0xff]).is_err());
+
+        for value in [0, -1, i64::MIN, i64::MAX] {
+            assert_eq!(parse_i64(&encode_i64_binary(value)).unwrap(), value);
+        }
+        assert!(!is_binary_i64(&encode_i64(i64::MIN)));
+        assert!(parse_i64(&[BINARY_I64_TAG, 1]).is_err());
+    }
+
+    #[test]
+    fn it_formats_f64_in_canonical_form() {
+        let cases: &[(f64, &str)] = &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-2.5, "-2.5"),
+            (0.1, "0.1"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (100.0, "100"),
+            (123456.789, "123456.789"),
+            (1e15, "1000000000000000"),
+            (1e16, "10000000000000000"),
+            (1e21, "1000000000000000000000"),
+            (1.5e-7, "0.00000015"),
+            (-1.25e-5, "-0.0000125"),
+            (1e-4, "0.0001"),
+            // Exactly between ...562.2 and ...562.3, `Display` writes `...562.3`. Written as
+            // a sum, `...562.25` has more digits than an f64 keeps.
+            (1658206780088562.0 + 0.25, "1658206780088562.2"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "inf"),
+            (f64::NEG_INFINITY, "-inf"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(format_f64(*value), *expected, "formatting {:?}", value);
+            if value.is_finite() {
+                assert_eq!(parse_f64(expected.as_bytes()).unwrap(), *value);
+            }
+        }
+
+        assert_eq!(format_f64(5e-324), format!("0.{}5", "0".repeat(323)));
+        assert_eq!(
+            format_f64(f64::MAX),
+            format!("17976931348623157{}", "0".repeat(292))
+        );
+    }
+}
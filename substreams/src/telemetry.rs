@@ -0,0 +1,430 @@
+//! Counters, gauges and histograms recorded by a handler and output as the standard
+//! `sf.substreams.telemetry.v1.Telemetry` message.
+//!
+//! Metrics are declared once, usually as statics, and recorded while processing a block. The
+//! values accumulated since the last [take] form the [Telemetry] message, a dedicated map
+//! module returning it gives dashboards the same data shape for every Substreams:
+//!
+//! ```rust
+//! use substreams::telemetry::{self, Counter, Gauge, Histogram, Telemetry};
+//! # mod eth {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transaction { #[prost(uint64, tag = "1")] pub gas_used: u64, #[prost(string, tag = "2")] pub to: String }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Block { #[prost(message, repeated, tag = "1")] pub transactions: Vec<Transaction> }
+//! # }
+//!
+//! static TRANSACTIONS: Counter = Counter::new("transactions_total");
+//! static BLOCK_SIZE: Gauge = Gauge::new("block_transactions");
+//! static GAS_USED: Histogram = Histogram::new("gas_used", &[21_000.0, 100_000.0, 1_000_000.0]);
+//!
+//! #[substreams::handlers::map]
+//! fn map_telemetry(blk: eth::Block) -> Telemetry {
+//!     BLOCK_SIZE.set(blk.transactions.len() as f64);
+//!     for trx in blk.transactions.iter() {
+//!         TRANSACTIONS.with_label("to", &trx.to).inc();
+//!         GAS_USED.observe(trx.gas_used as f64);
+//!     }
+//!
+//!     telemetry::take()
+//! }
+//! ```
+//!
+//! Handlers producing no other output can send the message with [flush_to_output] instead.
+//!
+//! Metrics are identified by their name and labels, recording a name as two different kinds
+//! of metrics panics. The values live in the module instance and start over after [take],
+//! counters and histograms therefore count the events of the blocks since the last flush.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+pub use crate::pb::sf::substreams::telemetry::v1::{
+    metric::Value, Bucket, Histogram as HistogramValue, Label, Metric, Telemetry,
+};
+
+/// Upper bounds of the buckets of Prometheus' default histogram.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+type Labels = Vec<(String, String)>;
+
+enum Recorded {
+    Counter(f64),
+    Gauge(f64),
+    Histogram {
+        bounds: &'static [f64],
+        counts: Vec<u64>,
+        count: u64,
+        sum: f64,
+    },
+}
+
+impl Recorded {
+    fn kind(&self) -> &'static str {
+        match self {
+            Recorded::Counter(_) => "counter",
+            Recorded::Gauge(_) => "gauge",
+            Recorded::Histogram { .. } => "histogram",
+        }
+    }
+}
+
+thread_local! {
+    static METRICS: RefCell<BTreeMap<(&'static str, Labels), Recorded>> = RefCell::new(BTreeMap::new());
+}
+
+fn record<I, F>(kind: &'static str, name: &'static str, labels: &Labels, init: I, update: F)
+where
+    I: FnOnce() -> Recorded,
+    F: FnOnce(&mut Recorded),
+{
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+
+        // All the series of a name have the same kind, checking the first one is enough
+        let first = metrics
+            .range((name, Vec::new())..)
+            .next()
+            .filter(|((existing, _), _)| *existing == name);
+        if let Some((_, recorded)) = first {
+            if recorded.kind() != kind {
+                panic!(
+                    "telemetry metric '{}' is already recorded as a {}",
+                    name,
+                    recorded.kind()
+                );
+            }
+        }
+
+        update(metrics.entry((name, labels.clone())).or_insert_with(init));
+    })
+}
+
+fn with_label(labels: &Labels, name: &str, value: &str) -> Labels {
+    let mut labels = labels.clone();
+    match labels.binary_search_by(|(existing, _)| existing.as_str().cmp(name)) {
+        Ok(i) => labels[i].1 = value.to_string(),
+        Err(i) => labels.insert(i, (name.to_string(), value.to_string())),
+    }
+    labels
+}
+
+/// A value only going up, like the number of events seen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counter {
+    name: &'static str,
+    labels: Labels,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str) -> Self {
+        Counter {
+            name,
+            labels: Vec::new(),
+        }
+    }
+
+    /// The same counter with the label `name` set to `value`, recorded as its own series.
+    pub fn with_label<V: AsRef<str>>(&self, name: &str, value: V) -> Self {
+        Counter {
+            name: self.name,
+            labels: with_label(&self.labels, name, value.as_ref()),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.add(1.0)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `value` is negative, use a [Gauge] for values going down.
+    pub fn add(&self, value: f64) {
+        if value < 0.0 {
+            panic!("telemetry counter '{}' can't decrease", self.name);
+        }
+
+        record(
+            "counter",
+            self.name,
+            &self.labels,
+            || Recorded::Counter(0.0),
+            |recorded| {
+                if let Recorded::Counter(current) = recorded {
+                    *current += value
+                }
+            },
+        )
+    }
+}
+
+/// A value going up and down, like the size of a queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    name: &'static str,
+    labels: Labels,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str) -> Self {
+        Gauge {
+            name,
+            labels: Vec::new(),
+        }
+    }
+
+    /// The same gauge with the label `name` set to `value`, recorded as its own series.
+    pub fn with_label<V: AsRef<str>>(&self, name: &str, value: V) -> Self {
+        Gauge {
+            name: self.name,
+            labels: with_label(&self.labels, name, value.as_ref()),
+        }
+    }
+
+    pub fn set(&self, value: f64) {
+        self.update(|current| *current = value)
+    }
+
+    pub fn add(&self, value: f64) {
+        self.update(|current| *current += value)
+    }
+
+    pub fn sub(&self, value: f64) {
+        self.update(|current| *current -= value)
+    }
+
+    fn update<F: FnOnce(&mut f64)>(&self, f: F) {
+        record(
+            "gauge",
+            self.name,
+            &self.labels,
+            || Recorded::Gauge(0.0),
+            |recorded| {
+                if let Recorded::Gauge(current) = recorded {
+                    f(current)
+                }
+            },
+        )
+    }
+}
+
+/// The distribution of observed values over buckets of fixed upper bounds, see
+/// [DEFAULT_BUCKETS].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    name: &'static str,
+    labels: Labels,
+    bounds: &'static [f64],
+}
+
+impl Histogram {
+    /// A histogram with buckets of the given upper bounds, sorted in ascending order.
+    pub const fn new(name: &'static str, bounds: &'static [f64]) -> Self {
+        Histogram {
+            name,
+            labels: Vec::new(),
+            bounds,
+        }
+    }
+
+    /// The same histogram with the label `name` set to `value`, recorded as its own series.
+    pub fn with_label<V: AsRef<str>>(&self, name: &str, value: V) -> Self {
+        Histogram {
+            name: self.name,
+            labels: with_label(&self.labels, name, value.as_ref()),
+            bounds: self.bounds,
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let bounds = self.bounds;
+        record(
+            "histogram",
+            self.name,
+            &self.labels,
+            || Recorded::Histogram {
+                bounds,
+                counts: vec![0; bounds.len()],
+                count: 0,
+                sum: 0.0,
+            },
+            |recorded| {
+                if let Recorded::Histogram {
+                    bounds,
+                    counts,
+                    count,
+                    sum,
+                } = recorded
+                {
+                    if let Some(i) = bounds.iter().position(|bound| value <= *bound) {
+                        counts[i] += 1;
+                    }
+                    *count += 1;
+                    *sum += value;
+                }
+            },
+        )
+    }
+}
+
+/// Returns the metrics recorded since the last [take] or [reset], ordered by name and labels.
+pub fn snapshot() -> Telemetry {
+    METRICS.with(|metrics| to_message(&metrics.borrow()))
+}
+
+/// Returns the metrics recorded since the last [take] or [reset] and resets them.
+pub fn take() -> Telemetry {
+    METRICS.with(|metrics| to_message(&std::mem::take(&mut *metrics.borrow_mut())))
+}
+
+/// Resets the recorded metrics.
+pub fn reset() {
+    METRICS.with(|metrics| metrics.borrow_mut().clear())
+}
+
+/// Outputs the metrics returned by [take] as the module's output, the handler must not
+/// produce another output, for example by returning `None`.
+pub fn flush_to_output() {
+    crate::output(take())
+}
+
+fn to_message(metrics: &BTreeMap<(&'static str, Labels), Recorded>) -> Telemetry {
+    let metrics = metrics
+        .iter()
+        .map(|((name, labels), recorded)| Metric {
+            name: name.to_string(),
+            labels: labels
+                .iter()
+                .map(|(name, value)| Label {
+                    name: name.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            value: Some(match recorded {
+                Recorded::Counter(value) => Value::Counter(*value),
+                Recorded::Gauge(value) => Value::Gauge(*value),
+                Recorded::Histogram {
+                    bounds,
+                    counts,
+                    count,
+                    sum,
+                } => Value::Histogram(HistogramValue {
+                    buckets: bounds
+                        .iter()
+                        .zip(counts.iter())
+                        .scan(0, |cumulative, (bound, count)| {
+                            *cumulative += count;
+                            Some(Bucket {
+                                upper_bound: *bound,
+                                count: *cumulative,
+                            })
+                        })
+                        .collect(),
+                    count: *count,
+                    sum: *sum,
+                }),
+            }),
+        })
+        .collect();
+
+    Telemetry { metrics }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        flush_to_output, reset, snapshot, take, Bucket, Counter, Gauge, Histogram, HistogramValue,
+        Label, Metric, Telemetry, Value,
+    };
+    use crate::testing;
+
+    static SWAPS: Counter = Counter::new("swaps");
+    static POOLS: Gauge = Gauge::new("pools");
+    static AMOUNTS: Histogram = Histogram::new("amounts", &[10.0, 100.0]);
+
+    #[test]
+    fn it_accumulates_metrics_until_taken() {
+        reset();
+
+        SWAPS.inc();
+        SWAPS.with_label("pool", "0xb").add(2.0);
+        SWAPS.with_label("pool", "0xa").inc();
+        SWAPS.with_label("pool", "0xa").inc();
+        POOLS.set(10.0);
+        POOLS.sub(3.0);
+        for amount in [5.0, 50.0, 500.0] {
+            AMOUNTS.observe(amount);
+        }
+
+        let label = |value: &str| Label {
+            name: "pool".to_string(),
+            value: value.to_string(),
+        };
+        let metric = |name: &str, labels, value| Metric {
+            name: name.to_string(),
+            labels,
+            value: Some(value),
+        };
+        assert_eq!(
+            take(),
+            Telemetry {
+                metrics: vec![
+                    metric(
+                        "amounts",
+                        vec![],
+                        Value::Histogram(HistogramValue {
+                            buckets: vec![
+                                Bucket {
+                                    upper_bound: 10.0,
+                                    count: 1
+                                },
+                                Bucket {
+                                    upper_bound: 100.0,
+                                    count: 2
+                                },
+                            ],
+                            count: 3,
+                            sum: 555.0,
+                        })
+                    ),
+                    metric("pools", vec![], Value::Gauge(7.0)),
+                    metric("swaps", vec![], Value::Counter(1.0)),
+                    metric("swaps", vec![label("0xa")], Value::Counter(2.0)),
+                    metric("swaps", vec![label("0xb")], Value::Counter(2.0)),
+                ]
+            }
+        );
+        assert_eq!(snapshot(), Telemetry::default());
+    }
+
+    #[test]
+    fn it_flushes_to_output() {
+        testing::reset();
+        reset();
+
+        SWAPS.add(3.0);
+        flush_to_output();
+
+        let outputs = testing::take_outputs();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(
+            crate::proto::decode::<Telemetry>(&outputs[0])
+                .unwrap()
+                .metrics[0]
+                .value,
+            Some(Value::Counter(3.0))
+        );
+        assert!(take().metrics.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "telemetry metric 'swaps' is already recorded as a counter")]
+    fn it_rejects_kind_changes() {
+        reset();
+
+        SWAPS.with_label("pool", "0xa").inc();
+        Gauge::new("swaps").set(1.0);
+    }
+}
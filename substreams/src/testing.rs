@@ -0,0 +1,565 @@
+//! Native test harness for handlers, available when not compiling to `wasm32`.
+//!
+//! Outside of `wasm32` the store types and [crate::output] are backed by an in-memory
+//! host: readable stores (`StoreGet*`) answer from fixtures registered with
+//! [set_store_value], writable stores (`StoreSet*`, `StoreAdd*`, `StoreMax*`, ...) apply
+//! their operation to an in-memory output store and handler outputs are captured.
+//!
+//! Handlers generated by [crate::handlers::map] and [crate::handlers::store] can then be
+//! called directly from a `cargo test` suite, [input] turns a fixture message into the
+//! pointer and length the handler expects and readable stores are referred to by their index:
+//!
+//! ```rust
+//! use substreams::errors::Error;
+//! use substreams::store::{StoreGet, StoreGetInt64};
+//! use substreams::testing;
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transfer { #[prost(string, tag = "1")] pub token: String }
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Volume { #[prost(int64, tag = "1")] pub total: i64 }
+//! # }
+//!
+//! #[substreams::handlers::map]
+//! fn map_volume(transfer: pb::Transfer, totals: StoreGetInt64) -> Result<pb::Volume, Error> {
+//!     let total = totals.get_last(format!("token:{}", transfer.token)).unwrap_or(0);
+//!     Ok(pb::Volume { total })
+//! }
+//!
+//! testing::reset();
+//! testing::set_store_value(0, "token:dai", "42");
+//!
+//! let (ptr, len) = testing::input(&pb::Transfer { token: "dai".to_string() });
+//! map_volume(ptr, len, 0);
+//!
+//! assert_eq!(testing::take_output::<pb::Volume>(), Some(pb::Volume { total: 42 }));
+//! ```
+//!
+//! The backend is thread local, tests running in parallel don't interfere with each other.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::{Add, Bound};
+use std::str::FromStr;
+
+use crate::codec::Codec;
+use crate::pb::substreams::store_delta::Operation;
+use crate::pb::substreams::{StoreDelta, StoreDeltas};
+use crate::scalar::{BigDecimal, BigInt};
+use crate::store::{value, Delta, Deltas};
+
+#[cfg(feature = "record")]
+pub use crate::journal::{take_journal, JournalEntry};
+
+#[derive(Default)]
+struct Backend {
+    stores: BTreeMap<u32, BTreeMap<String, Vec<u8>>>,
+    output_stores: BTreeMap<u32, BTreeMap<String, Vec<u8>>>,
+    output: u32,
+    outputs: Vec<Vec<u8>>,
+    emitted: Vec<Vec<u8>>,
+    skip_empty_output: bool,
+    runtime_config: BTreeMap<String, String>,
+}
+
+thread_local! {
+    static BACKEND: RefCell<Backend> = RefCell::new(Backend::default());
+}
+
+/// Clears store fixtures, the output stores, captured outputs and emitted messages, the
+/// recorded clock, the runtime config, the skip empty output flag and the store journal.
+pub fn reset() {
+    BACKEND.with(|backend| *backend.borrow_mut() = Backend::default());
+    crate::runtime_config::reset();
+    crate::state::reset_output();
+    crate::clock::reset();
+    #[cfg(feature = "debug-assertions")]
+    crate::write_checks::reset();
+    #[cfg(feature = "record")]
+    crate::journal::take_journal();
+}
+
+/// Registers `value` as the value of `key` in the readable store at index `store_idx`.
+///
+/// Values are raw store bytes, numeric stores hold their value as a string, for example
+/// `"42"` for a `StoreGetInt64`.
+pub fn set_store_value<K: Into<String>, V: Into<Vec<u8>>>(store_idx: u32, key: K, value: V) {
+    BACKEND.with(|backend| {
+        backend
+            .borrow_mut()
+            .stores
+            .entry(store_idx)
+            .or_default()
+            .insert(key.into(), value.into());
+    })
+}
+
+/// Provides `value` as the runtime config value of `key`, returned by [crate::runtime_config].
+pub fn set_runtime_config<K: Into<String>, V: Into<String>>(key: K, value: V) {
+    BACKEND.with(|backend| {
+        backend
+            .borrow_mut()
+            .runtime_config
+            .insert(key.into(), value.into());
+    });
+    crate::runtime_config::reset();
+}
+
+/// Same as [set_store_value] but encoding `msg` for a `StoreGetProto`.
+pub fn set_store_proto<K: Into<String>, M: Codec>(store_idx: u32, key: K, msg: &M) {
+    set_store_value(store_idx, key, encode(msg))
+}
+
+/// Returns the value of `key` in the output store, as written by the handler.
+pub fn store_value(key: &str) -> Option<Vec<u8>> {
+    store_value_at(0, key)
+}
+
+/// Returns all the keys and values of the output store.
+pub fn output_store() -> BTreeMap<String, Vec<u8>> {
+    output_store_at(0)
+}
+
+/// Same as [store_value] for the output store at index `output_idx` of a `multi_writable`
+/// handler.
+pub fn store_value_at(output_idx: u32, key: &str) -> Option<Vec<u8>> {
+    BACKEND.with(|backend| {
+        backend
+            .borrow()
+            .output_stores
+            .get(&output_idx)
+            .and_then(|store| store.get(key).cloned())
+    })
+}
+
+/// Same as [output_store] for the output store at index `output_idx` of a `multi_writable`
+/// handler.
+pub fn output_store_at(output_idx: u32) -> BTreeMap<String, Vec<u8>> {
+    BACKEND.with(|backend| {
+        let backend = backend.borrow();
+        backend
+            .output_stores
+            .get(&output_idx)
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+/// Returns and clears the raw outputs emitted since the last call.
+pub fn take_outputs() -> Vec<Vec<u8>> {
+    BACKEND.with(|backend| std::mem::take(&mut backend.borrow_mut().outputs))
+}
+
+/// Returns and clears the outputs, decoding the last one as `M`. `None` if no output
+/// was emitted.
+///
+/// # Panics
+///
+/// Panics if the output cannot be decoded as `M`.
+pub fn take_output<M: Codec>() -> Option<M> {
+    take_outputs().pop().map(|data| {
+        M::from_bytes(data.as_slice()).unwrap_or_else(|e| {
+            panic!(
+                "Unable to decode output as '{}': {}",
+                std::any::type_name::<M>(),
+                e
+            )
+        })
+    })
+}
+
+/// Returns and clears the messages sent with [crate::emit()], decoding the last one as `M`.
+/// `None` if no message was emitted.
+///
+/// # Panics
+///
+/// Panics if the message cannot be decoded as `M`.
+pub fn take_emitted<M: Codec>() -> Option<M> {
+    let emitted = BACKEND.with(|backend| std::mem::take(&mut backend.borrow_mut().emitted));
+    emitted.last().map(|data| {
+        M::from_bytes(data.as_slice()).unwrap_or_else(|e| {
+            panic!(
+                "Unable to decode emitted message as '{}': {}",
+                std::any::type_name::<M>(),
+                e
+            )
+        })
+    })
+}
+
+/// Returns whether the handler told the engine to skip its output when empty, see
+/// [crate::OutputPolicy].
+pub fn skips_empty_output() -> bool {
+    BACKEND.with(|backend| backend.borrow().skip_empty_output)
+}
+
+/// Encodes `msg` and returns the pointer and length to pass to a handler for that input.
+///
+/// The buffer is leaked, handlers don't free their inputs.
+pub fn input<M: Codec>(msg: &M) -> (*mut u8, usize) {
+    leak(encode(msg))
+}
+
+/// Same as [input] for `String` and `Params` inputs.
+pub fn string_input<S: Into<String>>(value: S) -> (*mut u8, usize) {
+    leak(value.into().into_bytes())
+}
+
+fn encode<M: Codec>(msg: &M) -> Vec<u8> {
+    msg.to_bytes()
+        .unwrap_or_else(|e| panic!("Unable to encode '{}': {}", std::any::type_name::<M>(), e))
+}
+
+fn leak(data: Vec<u8>) -> (*mut u8, usize) {
+    // Handlers rebuild the input with a capacity equal to its length.
+    let data = Box::leak(data.into_boxed_slice());
+    (data.as_mut_ptr(), data.len())
+}
+
+/// Builds store deltas, used to feed `Deltas<T>` handler inputs. Ordinals are assigned in
+/// the order deltas are added.
+///
+/// ```rust
+/// use substreams::store::DeltaInt64;
+/// use substreams::testing::MockDeltas;
+///
+/// let deltas = MockDeltas::new()
+///     .create("token:dai", "10")
+///     .update("token:dai", "10", "12")
+///     .build::<DeltaInt64>();
+///
+/// assert_eq!(deltas.deltas[1].new_value, 12);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockDeltas {
+    deltas: Vec<StoreDelta>,
+}
+
+impl MockDeltas {
+    pub fn new() -> Self {
+        MockDeltas::default()
+    }
+
+    pub fn create<K: Into<String>, V: Into<Vec<u8>>>(self, key: K, new_value: V) -> Self {
+        self.push(Operation::Create, key.into(), Vec::new(), new_value.into())
+    }
+
+    pub fn update<K, V>(self, key: K, old_value: V, new_value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        self.push(
+            Operation::Update,
+            key.into(),
+            old_value.into(),
+            new_value.into(),
+        )
+    }
+
+    pub fn delete<K: Into<String>, V: Into<Vec<u8>>>(self, key: K, old_value: V) -> Self {
+        self.push(Operation::Delete, key.into(), old_value.into(), Vec::new())
+    }
+
+    fn push(mut self, operation: Operation, key: String, old: Vec<u8>, new: Vec<u8>) -> Self {
+        self.deltas.push(StoreDelta {
+            operation: operation as i32,
+            ordinal: self.deltas.len() as u64 + 1,
+            key,
+            old_value: old,
+            new_value: new,
+        });
+        self
+    }
+
+    /// Decodes the deltas, as received by a `Deltas<T>` handler input.
+    pub fn build<T: Delta + From<StoreDelta>>(&self) -> Deltas<T> {
+        Deltas::new(self.deltas.clone())
+    }
+
+    pub fn into_pb(self) -> StoreDeltas {
+        StoreDeltas {
+            deltas: self.deltas,
+        }
+    }
+
+    /// Same as [input] for a `Deltas<T>` handler input.
+    pub fn input(&self) -> (*mut u8, usize) {
+        input(&self.clone().into_pb())
+    }
+}
+
+/// Reads `key` from the fixtures of the readable store `store_idx`.
+pub(crate) fn get(store_idx: u32, key: &str) -> Option<Vec<u8>> {
+    BACKEND.with(|backend| {
+        backend
+            .borrow()
+            .stores
+            .get(&store_idx)
+            .and_then(|store| store.get(key))
+            .cloned()
+    })
+}
+
+/// Lists up to `limit` fixtures of the readable store `store_idx` starting with `prefix` and
+/// sorting after `after`.
+pub(crate) fn scan(
+    store_idx: u32,
+    prefix: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> Vec<(String, Vec<u8>)> {
+    BACKEND.with(|backend| {
+        let backend = backend.borrow();
+        let store = match backend.stores.get(&store_idx) {
+            Some(store) => store,
+            None => return Vec::new(),
+        };
+
+        store
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .filter(|(key, _)| after.map_or(true, |after| key.as_str() > after))
+            .take(limit)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    })
+}
+
+/// Captures a handler output.
+pub(crate) fn runtime_config(key: &str) -> Option<String> {
+    BACKEND.with(|backend| backend.borrow().runtime_config.get(key).cloned())
+}
+
+pub(crate) fn output(data: Vec<u8>) {
+    BACKEND.with(|backend| backend.borrow_mut().outputs.push(data))
+}
+
+/// Captures a message sent with [crate::emit()].
+pub(crate) fn emit(data: Vec<u8>) {
+    BACKEND.with(|backend| backend.borrow_mut().emitted.push(data))
+}
+
+/// Records that the handler skips its output when empty.
+pub(crate) fn skip_empty_output() {
+    BACKEND.with(|backend| backend.borrow_mut().skip_empty_output = true)
+}
+
+/// Applies the store write `name` to the output store.
+pub(crate) fn write(name: &str, key: &str, value: &[u8]) {
+    BACKEND.with(|backend| {
+        let mut backend = backend.borrow_mut();
+        if name == "select_output" {
+            backend.output = parse::<u32>(value);
+            return;
+        }
+
+        let output = backend.output;
+        let store = backend.output_stores.entry(output).or_default();
+        let current = store.get(key).map(Vec::as_slice);
+
+        let new_value = match name {
+            "set" => value.to_vec(),
+            "set_if_not_exists" if current.is_some() => return,
+            "set_if_not_exists" => value.to_vec(),
+            "set_once" if current.is_some() => {
+                panic!("set_once store key {:?} is already set", key)
+            }
+            "set_once" => value.to_vec(),
+            "append" => [current.unwrap_or_default(), value].concat(),
+            "delete_prefix" => {
+                store.retain(|k, _| !k.starts_with(key));
+                return;
+            }
+            "delete_key" => {
+                store.remove(key);
+                return;
+            }
+            "set_max_bytes" if current.map_or(false, |current| current >= value) => return,
+            "set_min_bytes" if current.map_or(false, |current| current <= value) => return,
+            "set_max_bytes" | "set_min_bytes" => value.to_vec(),
+            _ => {
+                let (operation, kind) = name
+                    .rsplit_once('_')
+                    .unwrap_or_else(|| panic!("unknown store operation {}", name));
+
+                match kind {
+                    "int64" => combine::<i64>(operation, current, value),
+                    "float64" => combine::<f64>(operation, current, value),
+                    "bigint" => combine::<BigInt>(operation, current, value),
+                    "bigdecimal" => combine::<BigDecimal>(operation, current, value),
+                    _ => panic!("unknown store operation {}", name),
+                }
+            }
+        };
+
+        store.insert(key.to_string(), new_value);
+    })
+}
+
+/// A numeric store value, written back in the form the store types write it.
+trait Number: FromStr + PartialOrd + Add<Output = Self> {
+    fn encode(&self) -> String;
+}
+
+macro_rules! impl_number_display {
+    ($($t:ty),*) => {
+        $(impl Number for $t {
+            fn encode(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+impl_number_display!(i64, BigInt, BigDecimal);
+
+impl Number for f64 {
+    fn encode(&self) -> String {
+        value::format_f64(*self)
+    }
+}
+
+fn combine<T: Number>(operation: &str, current: Option<&[u8]>, value: &[u8]) -> Vec<u8> {
+    if operation == "set_sum" {
+        return set_sum::<T>(current, value);
+    }
+
+    let value = parse::<T>(value);
+    let current = match current {
+        Some(current) => parse::<T>(current),
+        None => return value.encode().into_bytes(),
+    };
+
+    let result = match operation {
+        "add" => current + value,
+        "set_min" if value < current => value,
+        "set_max" if value > current => value,
+        "set_min" | "set_max" => current,
+        _ => panic!("unknown store operation {}", operation),
+    };
+
+    result.encode().into_bytes()
+}
+
+fn set_sum<T: Number>(current: Option<&[u8]>, value: &[u8]) -> Vec<u8> {
+    match (value.get(..4), current) {
+        (Some(b"set:"), _) | (Some(b"sum:"), None) => value.to_vec(),
+        (Some(b"sum:"), Some(current)) => {
+            let total = parse::<T>(&current[4..]) + parse::<T>(&value[4..]);
+            format!("sum:{}", total.encode()).into_bytes()
+        }
+        _ => panic!(
+            "Invalid set_sum value '{}', expected 'set:' or 'sum:' prefix",
+            String::from_utf8_lossy(value)
+        ),
+    }
+}
+
+fn parse<T: FromStr>(bytes: &[u8]) -> T {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|value| T::from_str(value).ok())
+        .unwrap_or_else(|| {
+            panic!(
+                "Invalid {} store value '{}'",
+                std::any::type_name::<T>(),
+                String::from_utf8_lossy(bytes)
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn it_reads_store_fixtures() {
+        reset();
+        set_store_value(1, "a", "10");
+
+        let store = StoreGetInt64::new(1);
+        assert_eq!(store.get_last("a"), Some(10));
+        assert_eq!(store.get_at(5, "a"), Some(10));
+        assert!(store.has_last("a"));
+        assert_eq!(store.get_last("b"), None);
+        assert_eq!(StoreGetInt64::new(0).get_last("a"), None);
+    }
+
+    #[test]
+    fn it_applies_store_writes() {
+        reset();
+
+        let adds = StoreAddInt64::new();
+        adds.add(1, "count", 2);
+        adds.add(2, "count", 3);
+
+        let max = StoreMaxBigInt::new();
+        max.max(1, "max", BigInt::from(5));
+        max.max(2, "max", BigInt::from(3));
+
+        let sets = StoreSetIfNotExistsString::new();
+        sets.set_if_not_exists(1, "name", &"a".to_string());
+        sets.set_if_not_exists(2, "name", &"b".to_string());
+        sets.set_if_not_exists(3, "other:name", &"c".to_string());
+        sets.delete_prefix(4, &"other:".to_string());
+
+        assert_eq!(
+            output_store(),
+            vec![
+                ("count".to_string(), b"5".to_vec()),
+                ("max".to_string(), b"5".to_vec()),
+                ("name".to_string(), b"a".to_vec()),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "delete-key")]
+    fn it_deletes_single_keys() {
+        reset();
+
+        let sets = StoreSetString::new();
+        sets.set(1, "pool:1", &"a".to_string());
+        sets.set(1, "pool:10", &"b".to_string());
+        sets.delete_key(2, "pool:1");
+
+        assert_eq!(
+            output_store(),
+            vec![("pool:10".to_string(), b"b".to_vec())]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "delete-key"))]
+    #[should_panic(expected = "requires the `delete-key` feature")]
+    fn it_requires_the_delete_key_feature() {
+        StoreSetString::new().delete_key(2, "pool:1");
+    }
+
+    #[test]
+    fn it_applies_set_sum() {
+        assert_eq!(set_sum::<i64>(None, b"sum:2"), b"sum:2".to_vec());
+        assert_eq!(set_sum::<i64>(Some(b"set:2"), b"sum:3"), b"sum:5".to_vec());
+        assert_eq!(set_sum::<i64>(Some(b"sum:5"), b"set:1"), b"set:1".to_vec());
+    }
+
+    #[test]
+    fn it_builds_deltas() {
+        let deltas: Deltas<DeltaInt64> =
+            MockDeltas::new().create("a", "1").delete("b", "2").build();
+
+        assert_eq!(deltas.deltas[0].new_value, 1);
+        assert_eq!(deltas.deltas[1].ordinal, 2);
+        assert_eq!(deltas.deltas[1].old_value, 2);
+        assert_eq!(
+            deltas.deltas[1].operation,
+            crate::pb::substreams::store_delta::Operation::Delete
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Time-series rollups over fixed-size time buckets.
+//!
+//! A [TimeWindowStore] wraps a `StoreAdd`, `StoreMax` or `StoreMin` store and writes each
+//! value under the key of the bucket its timestamp falls in, `<key>:<bucket>` where
+//! `<bucket>` is the number of buckets between the Unix epoch and the timestamp (the same
+//! as [crate::clock::Clock::day] for daily buckets). A [TimeWindowGet] reads the buckets
+//! back from a store in `get` mode:
+//!
+//! ```rust
+//! use substreams::clock::Clock;
+//! use substreams::store::{StoreAddInt64, StoreGetInt64, StoreNew};
+//! use substreams::windows::{TimeWindowGet, TimeWindowStore, Window};
+//! # mod pb {
+//! #   #[derive(Clone, PartialEq, ::prost::Message)]
+//! #   pub struct Transfers { #[prost(string, repeated, tag = "1")] pub tokens: Vec<String> }
+//! # }
+//!
+//! #[substreams::handlers::store]
+//! fn store_daily_transfers(clock: Clock, transfers: pb::Transfers, counts: StoreAddInt64) {
+//!     let counts = TimeWindowStore::new(counts, Window::DAY);
+//!     for token in transfers.tokens.iter() {
+//!         counts.add(0, format!("transfers:{}", token), clock.timestamp_seconds(), 1);
+//!     }
+//! }
+//!
+//! fn weekly_transfers(clock: &Clock, counts: StoreGetInt64, token: &str) -> i64 {
+//!     TimeWindowGet::new(counts, Window::DAY)
+//!         .last(format!("transfers:{}", token), clock.timestamp_seconds(), 7)
+//!         .into_iter()
+//!         .filter_map(|(_, count)| count)
+//!         .sum()
+//! }
+//! ```
+//!
+//! Bucket keys don't include the bucket size, a store must only hold buckets of one size.
+
+use crate::store::{Ordinal, StoreAdd, StoreGet, StoreMax, StoreMin};
+
+/// The size of the buckets of a time series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    seconds: i64,
+}
+
+impl Window {
+    pub const MINUTE: Window = Window { seconds: 60 };
+    pub const HOUR: Window = Window { seconds: 3600 };
+    pub const DAY: Window = Window { seconds: 86400 };
+    pub const WEEK: Window = Window { seconds: 7 * 86400 };
+
+    /// Buckets of `seconds` seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seconds` is not positive.
+    pub fn seconds(seconds: i64) -> Self {
+        if seconds <= 0 {
+            panic!("window size must be positive, got {} seconds", seconds);
+        }
+
+        Window { seconds }
+    }
+
+    pub fn as_seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Returns the bucket `timestamp` (in seconds) falls in.
+    pub fn bucket(&self, timestamp: i64) -> i64 {
+        timestamp.div_euclid(self.seconds)
+    }
+
+    /// Returns the timestamp of the start of `bucket`.
+    pub fn bucket_start(&self, bucket: i64) -> i64 {
+        bucket * self.seconds
+    }
+
+    /// Returns the key holding the value of `key` for the bucket `timestamp` falls in.
+    pub fn key<K: AsRef<str>>(&self, key: K, timestamp: i64) -> String {
+        bucket_key(key.as_ref(), self.bucket(timestamp))
+    }
+}
+
+fn bucket_key(key: &str, bucket: i64) -> String {
+    format!("{}:{}", key, bucket)
+}
+
+/// A writable store whose values are bucketed by timestamp, see the
+/// [module](crate::windows) documentation.
+pub struct TimeWindowStore<S> {
+    store: S,
+    window: Window,
+}
+
+impl<S> TimeWindowStore<S> {
+    pub fn new(store: S, window: Window) -> Self {
+        TimeWindowStore { store, window }
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// The wrapped store, to write keys that are not bucketed.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Adds `value` to the bucket of `key` that `timestamp` falls in.
+    pub fn add<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreAdd<V>,
+    {
+        self.store.add(ord, self.window.key(key, timestamp), value)
+    }
+
+    /// Keeps the greatest of `value` and the value of the bucket of `key` that `timestamp`
+    /// falls in.
+    pub fn max<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreMax<V>,
+    {
+        self.store.max(ord, self.window.key(key, timestamp), value)
+    }
+
+    /// Keeps the smallest of `value` and the value of the bucket of `key` that `timestamp`
+    /// falls in.
+    pub fn min<K: AsRef<str>, V>(&self, ord: impl Into<Ordinal>, key: K, timestamp: i64, value: V)
+    where
+        S: StoreMin<V>,
+    {
+        self.store.min(ord, self.window.key(key, timestamp), value)
+    }
+}
+
+/// Reads the buckets written by a [TimeWindowStore] from a store in `get` mode.
+pub struct TimeWindowGet<S> {
+    store: S,
+    window: Window,
+}
+
+impl<S> TimeWindowGet<S> {
+    pub fn new(store: S, window: Window) -> Self {
+        TimeWindowGet { store, window }
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the value of the bucket of `key` that `timestamp` falls in.
+    pub fn get<K: AsRef<str>, V>(&self, key: K, timestamp: i64) -> Option<V>
+    where
+        S: StoreGet<V>,
+    {
+        self.store.get_last(self.window.key(key, timestamp))
+    }
+
+    /// Returns the last `count` buckets of `key` up to the one `timestamp` falls in, oldest
+    /// first, as the timestamp of the start of the bucket and its value (`None` for buckets
+    /// without writes).
+    pub fn last<K: AsRef<str>, V>(
+        &self,
+        key: K,
+        timestamp: i64,
+        count: usize,
+    ) -> Vec<(i64, Option<V>)>
+    where
+        S: StoreGet<V>,
+    {
+        let current = self.window.bucket(timestamp);
+        (0..count as i64)
+            .rev()
+            .map(|offset| {
+                let bucket = current - offset;
+                let value = self.store.get_last(bucket_key(key.as_ref(), bucket));
+                (self.window.bucket_start(bucket), value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeWindowGet, TimeWindowStore, Window};
+    use crate::store::{StoreAddInt64, StoreGet, StoreGetInt64, StoreMaxInt64, StoreNew};
+    use crate::testing;
+
+    const DAY: i64 = 86400;
+
+    #[test]
+    fn it_buckets_writes_by_timestamp() {
+        testing::reset();
+
+        let counts = TimeWindowStore::new(StoreAddInt64::new(), Window::DAY);
+        counts.add(0, "transfers", 3 * DAY + 10, 1);
+        counts.add(0, "transfers", 3 * DAY + 20, 2);
+        counts.add(0, "transfers", 4 * DAY, 5);
+        assert_eq!(testing::store_value("transfers:3"), Some(b"3".to_vec()));
+        assert_eq!(testing::store_value("transfers:4"), Some(b"5".to_vec()));
+
+        let highs = TimeWindowStore::new(StoreMaxInt64::new(), Window::HOUR);
+        highs.max(0, "price", 7200, 10);
+        highs.max(0, "price", 7300, 4);
+        assert_eq!(testing::store_value("price:2"), Some(b"10".to_vec()));
+    }
+
+    #[test]
+    fn it_reads_the_last_buckets() {
+        testing::reset();
+        testing::set_store_value(1, "transfers:3", "3");
+        testing::set_store_value(1, "transfers:5", "5");
+
+        let counts = TimeWindowGet::new(StoreGetInt64::new(1), Window::DAY);
+        assert_eq!(counts.get("transfers", 5 * DAY + 1), Some(5));
+        assert_eq!(
+            counts.last("transfers", 5 * DAY + 1, 3),
+            vec![(3 * DAY, Some(3)), (4 * DAY, None), (5 * DAY, Some(5))]
+        );
+        assert_eq!(Window::seconds(60).bucket(-1), -1);
+    }
+}
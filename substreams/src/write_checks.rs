@@ -0,0 +1,144 @@
+//! Development checks of the writes made to writable stores, enabled by the
+//! `debug-assertions` feature.
+//!
+//! Every store write is validated before being forwarded to the host, and suspicious inputs
+//! are reported through [crate::log] instead of failing the module:
+//!
+//! - empty keys,
+//! - NaN and infinite float64 values,
+//! - values larger than [LARGE_VALUE_BYTES],
+//! - ordinals lower than the one of a previous write of the same block.
+//!
+//! Without the feature the checks are not compiled at all. Natively, the reported warnings are
+//! also kept until [crate::testing::reset] so tests can assert on them with [warnings].
+
+use std::cell::RefCell;
+
+/// Values larger than this are reported, they usually come from unbounded aggregations.
+pub const LARGE_VALUE_BYTES: usize = 1024 * 1024;
+
+#[derive(Default)]
+struct State {
+    /// Block of the last write, when the handler takes a `Clock` input.
+    block: Option<u64>,
+    last_ordinal: Option<i64>,
+    #[cfg(not(target_arch = "wasm32"))]
+    warnings: Vec<String>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Checks a write of `value_len` bytes to `key`.
+pub(crate) fn on_write(op: &str, ord: i64, key: &str, value_len: usize) {
+    if key.is_empty() {
+        warn(op, key, "empty key".to_string());
+    }
+
+    if value_len > LARGE_VALUE_BYTES {
+        warn(
+            op,
+            key,
+            format!(
+                "value of {} bytes exceeds {} bytes",
+                value_len, LARGE_VALUE_BYTES
+            ),
+        );
+    }
+
+    on_ordinal(op, ord, key);
+}
+
+/// Checks the ordinal of a write, ordinals going backward within a block are reported.
+pub(crate) fn on_ordinal(op: &str, ord: i64, key: &str) {
+    let block = crate::clock::current().map(|clock| clock.number);
+    let previous = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.block != block {
+            state.block = block;
+            state.last_ordinal = None;
+        }
+
+        let previous = state.last_ordinal;
+        state.last_ordinal = Some(previous.map_or(ord, |previous| previous.max(ord)));
+        previous
+    });
+
+    if let Some(previous) = previous {
+        if ord < previous {
+            warn(
+                op,
+                key,
+                format!(
+                    "ordinal {} is lower than previous write ordinal {}",
+                    ord, previous
+                ),
+            );
+        }
+    }
+}
+
+/// Checks a float64 value written to `key`.
+pub(crate) fn on_float(op: &str, key: &str, value: f64) {
+    if !value.is_finite() {
+        warn(op, key, format!("non-finite float64 value {}", value));
+    }
+}
+
+fn warn(op: &str, key: &str, problem: String) {
+    let message = format!("store write check: {} {:?}: {}", op, key, problem);
+    crate::log::println(&message);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    STATE.with(|state| state.borrow_mut().warnings.push(message));
+}
+
+/// Returns the warnings reported since the last [crate::testing::reset].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn warnings() -> Vec<String> {
+    STATE.with(|state| state.borrow().warnings.clone())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reset() {
+    STATE.with(|state| *state.borrow_mut() = State::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{warnings, LARGE_VALUE_BYTES};
+    use crate::store::{StoreNew, StoreSet, StoreSetRaw};
+    use crate::testing;
+
+    #[test]
+    fn it_reports_suspicious_writes() {
+        testing::reset();
+
+        let store = StoreSetRaw::new();
+        store.set(5, "a", &"1");
+        store.set(6, "", &"1");
+        store.set(4, "b", &"1");
+        store.set(7, "c", &vec![0u8; LARGE_VALUE_BYTES + 1]);
+        // Store wrappers reject NaN with the `checked-floats` feature
+        crate::state::add_float64(8, "d", f64::NAN);
+
+        assert_eq!(
+            warnings(),
+            vec![
+                "store write check: set \"\": empty key".to_string(),
+                "store write check: set \"b\": ordinal 4 is lower than previous write ordinal 6"
+                    .to_string(),
+                format!(
+                    "store write check: set \"c\": value of {} bytes exceeds {} bytes",
+                    LARGE_VALUE_BYTES + 1,
+                    LARGE_VALUE_BYTES
+                ),
+                "store write check: add_float64 \"d\": non-finite float64 value NaN".to_string(),
+            ]
+        );
+
+        testing::reset();
+        assert!(warnings().is_empty());
+    }
+}
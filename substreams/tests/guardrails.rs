@@ -0,0 +1,31 @@
+use substreams::pb::substreams::Clock;
+use substreams::prelude::*;
+
+#[substreams::handlers::store(max_keys_written = 1)]
+fn store_limited(clock: Clock, store: StoreAddInt64) {
+    store.add(0, format!("block:{}", clock.number), 1);
+}
+
+#[substreams::handlers::store]
+fn store_unlimited(clock: Clock, store: StoreAddInt64) {
+    store.add(0, format!("block:{}", clock.number), 1);
+    store.add(0, "total", 1);
+}
+
+fn run(handler: extern "C" fn(*mut u8, usize)) {
+    let mut input = substreams::proto::encode(&Clock {
+        number: 1,
+        ..Default::default()
+    })
+    .unwrap();
+
+    handler(input.as_mut_ptr(), input.len());
+}
+
+#[test]
+fn it_does_not_leak_limits_to_the_next_handler() {
+    run(store_limited);
+    run(store_unlimited);
+
+    assert_eq!(substreams::guardrails::limits(), Default::default());
+}
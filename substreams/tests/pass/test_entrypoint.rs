@@ -0,0 +1,18 @@
+use prost_types::Timestamp;
+
+#[substreams::handlers::map(test_entrypoint)]
+fn map_timestamp(params: String, clock: Timestamp) -> Result<Timestamp, substreams::errors::Error> {
+    Ok(Timestamp {
+        seconds: clock.seconds + params.parse::<i64>()?,
+        nanos: 0,
+    })
+}
+
+fn main() {
+    let clock = Timestamp {
+        seconds: 10,
+        nanos: 5,
+    };
+    let shifted = map_timestamp::entrypoint("5".to_string(), clock).unwrap();
+    assert_eq!(shifted.seconds, 15);
+}
@@ -0,0 +1,55 @@
+// Records a handler generated by the handler macros and replays it from its trace, run with
+// `cargo test --features replay --test replay`.
+#![cfg(feature = "replay")]
+
+use prost::Message;
+use prost_types::Timestamp;
+use substreams::errors::Error;
+use substreams::replay::{self, Replayer};
+use substreams::store::{StoreGet, StoreGetInt64};
+
+#[substreams::handlers::map]
+fn map_shifted(
+    params: String,
+    clock: Timestamp,
+    offsets: StoreGetInt64,
+) -> Result<Timestamp, Error> {
+    let offset = offsets.get_last(&params).unwrap_or(0);
+    Ok(Timestamp {
+        seconds: clock.seconds + offset,
+        nanos: 0,
+    })
+}
+
+fn run(params: &[u8], clock: &[u8]) {
+    let mut params = params.to_vec();
+    let mut clock = clock.to_vec();
+    map_shifted(
+        params.as_mut_ptr(),
+        params.len(),
+        clock.as_mut_ptr(),
+        clock.len(),
+        0,
+    );
+}
+
+#[test]
+fn it_records_and_replays_a_handler() {
+    let clock = Timestamp {
+        seconds: 10,
+        nanos: 0,
+    };
+    run(b"usd", &clock.encode_to_vec());
+
+    let trace = replay::last_trace().expect("handler run recorded");
+    assert_eq!(trace.inputs(), vec![b"usd".to_vec(), clock.encode_to_vec()]);
+    let writes = trace.writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].name, "output");
+    assert_eq!(writes[0].value, clock.encode_to_vec());
+
+    Replayer::new(trace)
+        .run_and_compare(|inputs| run(&inputs[0], &inputs[1]))
+        .unwrap();
+    assert_eq!(replay::last_trace(), None);
+}
@@ -0,0 +1,8 @@
+// Compile errors and native entrypoints of the handler macros, regenerate the expected outputs with
+// `TRYBUILD=overwrite cargo test --test ui`.
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    t.pass("tests/pass/*.rs");
+}
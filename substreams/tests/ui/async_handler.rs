@@ -0,0 +1,6 @@
+#[substreams::handlers::map]
+async fn map_timestamp(_params: String) -> prost_types::Timestamp {
+    unimplemented!()
+}
+
+fn main() {}
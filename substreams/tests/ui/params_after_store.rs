@@ -0,0 +1,6 @@
+#[substreams::handlers::map]
+fn map_counts(counts: StoreGetInt64, params: String) -> prost_types::Timestamp {
+    unimplemented!()
+}
+
+fn main() {}
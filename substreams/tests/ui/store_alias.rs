@@ -0,0 +1,8 @@
+use substreams::store::StoreAddInt64;
+
+type StoreAddCounters = StoreAddInt64;
+
+#[substreams::handlers::store]
+fn store_counts(_blk: prost_types::Timestamp, _store: StoreAddCounters) {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[substreams::handlers::store]
+fn store_counts(blk: prost_types::Timestamp, store: StoreSetInt65) {}
+
+fn main() {}